@@ -6,7 +6,7 @@ use crate::codec::{
     encode_entry_v0_parts_unsigned_for_signing, encode_entry_v0_payload_data_unsigned_for_signing,
     encode_meta_parts, encode_payload, encode_signature_with_key_parts,
     parse_plain_entry_v0_storage, parse_plain_signature_with_key_ref, parse_raw_entry_v0_meta,
-    parse_raw_entry_v0_payload, signable_entry_to_signed_storage,
+    parse_raw_entry_v0_payload, prehashed_signable, signable_entry_to_signed_storage,
     unsigned_entry_v0_storage_for_signing,
 };
 use crate::crypto::{cached_verifying_key, sign_ed25519_with_key, validate_signature_lengths};
@@ -1316,10 +1316,10 @@ pub fn prepare_raw_entry_v0_blocks_with_expected_cids_and_verify_profiled(
                 }
             }
             let signable_started = profile.as_ref().map(|_| now_ms());
-            parsed_messages.push(unsigned_entry_v0_storage_for_signing(
-                &bytes,
-                storage.signable_prefix_len,
-            )?);
+            parsed_messages.push(prehashed_signable(
+                parsed_signature.prehash,
+                unsigned_entry_v0_storage_for_signing(&bytes, storage.signable_prefix_len)?,
+            ));
             if let Some(started) = signable_started {
                 if let Some(profile) = profile.as_deref_mut() {
                     profile.signable_ms += now_ms() - started;
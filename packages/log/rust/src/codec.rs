@@ -1,4 +1,5 @@
 use crate::error::LogError;
+use peerbit_wire::wire::{is_known_prehash, prehash_digest};
 
 const SIGNED_ENTRY_EXTRA_CAPACITY: usize = 128;
 
@@ -20,6 +21,7 @@ pub(crate) struct SignatureInput {
 }
 
 pub(crate) struct ParsedEntryV0StorageSignature {
+    /// Already prehashed when the signature uses a prehash.
     pub(crate) signable: Vec<u8>,
     pub(crate) signature: Vec<u8>,
     pub(crate) public_key: Vec<u8>,
@@ -28,11 +30,13 @@ pub(crate) struct ParsedEntryV0StorageSignature {
 pub(crate) struct ParsedSignatureWithKey {
     pub(crate) signature: Vec<u8>,
     pub(crate) public_key: Vec<u8>,
+    pub(crate) prehash: u8,
 }
 
 pub(crate) struct ParsedSignatureWithKeyRef<'a> {
     pub(crate) signature: &'a [u8],
     pub(crate) public_key: &'a [u8],
+    pub(crate) prehash: u8,
 }
 
 pub(crate) struct ParsedPlainEntryV0Storage<'a> {
@@ -199,7 +203,10 @@ pub(crate) fn parse_plain_entry_v0_storage_signature(
     let parsed_signature = parse_plain_signature_with_key(storage.signature_with_key)?;
 
     Ok(ParsedEntryV0StorageSignature {
-        signable: unsigned_entry_v0_storage_for_signing(bytes, storage.signable_prefix_len)?,
+        signable: prehashed_signable(
+            parsed_signature.prehash,
+            unsigned_entry_v0_storage_for_signing(bytes, storage.signable_prefix_len)?,
+        ),
         signature: parsed_signature.signature,
         public_key: parsed_signature.public_key,
     })
@@ -222,8 +229,9 @@ pub(crate) fn parse_plain_signature_with_key_ref(
         return Err(LogError::OnlyEd25519Signatures);
     }
     let public_key = signature_reader.read_exact(32, "signature public key")?;
-    if signature_reader.read_u8("signature prehash")? != 0 {
-        return Err(LogError::OnlyNonPrehashedSignatures);
+    let prehash = signature_reader.read_u8("signature prehash")?;
+    if !is_known_prehash(prehash) {
+        return Err(LogError::UnsupportedSignaturePrehash);
     }
     if !signature_reader.is_done() {
         return Err(LogError::UnexpectedTrailingSignatureWithKeyBytes);
@@ -232,6 +240,7 @@ pub(crate) fn parse_plain_signature_with_key_ref(
     Ok(ParsedSignatureWithKeyRef {
         signature,
         public_key,
+        prehash,
     })
 }
 
@@ -242,9 +251,19 @@ pub(crate) fn parse_plain_signature_with_key(
     Ok(ParsedSignatureWithKey {
         signature: parsed.signature.to_vec(),
         public_key: parsed.public_key.to_vec(),
+        prehash: parsed.prehash,
     })
 }
 
+/// What a signature with `prehash` signs: the digest `prehashFn` computes
+/// from the signable bytes, or the bytes themselves for `PreHash.NONE`.
+pub(crate) fn prehashed_signable(prehash: u8, signable: Vec<u8>) -> Vec<u8> {
+    match prehash_digest(prehash, &signable) {
+        Some(digest) => digest.to_vec(),
+        None => signable,
+    }
+}
+
 pub(crate) fn read_string_vec(
     reader: &mut BorshReader<'_>,
     label: &'static str,
@@ -1,5 +1,5 @@
 use crate::codec::{
-    parse_plain_entry_v0_storage_signature, parse_plain_signature_with_key_ref,
+    parse_plain_entry_v0_storage_signature, parse_plain_signature_with_key_ref, prehashed_signable,
     unsigned_entry_v0_storage_for_signing,
 };
 use crate::error::LogError;
@@ -72,10 +72,10 @@ pub(crate) fn parse_prepared_entry_v0_ed25519_storage_slices(
         let verifying_key = cached_verifying_key(&mut verifying_key_cache, parsed.public_key)?;
         parsed_signatures.push(Signature::from_bytes(&signature_bytes));
         parsed_public_keys.push(verifying_key);
-        parsed_messages.push(unsigned_entry_v0_storage_for_signing(
-            entry.storage_bytes,
-            entry.signable_prefix_len,
-        )?);
+        parsed_messages.push(prehashed_signable(
+            parsed.prehash,
+            unsigned_entry_v0_storage_for_signing(entry.storage_bytes, entry.signable_prefix_len)?,
+        ));
     }
 
     Ok((parsed_signatures, parsed_public_keys, parsed_messages))
@@ -35,7 +35,7 @@ pub enum LogError {
     InvalidSignablePrefixLength,
     ExpectedSignatureWithKeyVariant,
     OnlyEd25519Signatures,
-    OnlyNonPrehashedSignatures,
+    UnsupportedSignaturePrehash,
     UnexpectedTrailingSignatureWithKeyBytes,
     ExpectedEntryV0MetaVariant,
     ExpectedLamportClockVariant,
@@ -110,8 +110,8 @@ impl std::fmt::Display for LogError {
             LogError::OnlyEd25519Signatures => {
                 f.write_str("Only Ed25519 EntryV0 signatures can be verified natively")
             }
-            LogError::OnlyNonPrehashedSignatures => {
-                f.write_str("Only non-prehashed EntryV0 signatures can be verified natively")
+            LogError::UnsupportedSignaturePrehash => {
+                f.write_str("Unsupported EntryV0 signature prehash")
             }
             LogError::UnexpectedTrailingSignatureWithKeyBytes => {
                 f.write_str("Unexpected trailing SignatureWithKey bytes")
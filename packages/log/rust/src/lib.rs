@@ -2762,18 +2762,22 @@ fn join_plan_to_row(plan: JoinPlan) -> Array {
 #[cfg(test)]
 mod tests {
     use super::{
-        verify_prepared_entry_v0_ed25519_storage_slices_all, JoinPlan, LogGraphIndex,
+        verify_entry_v0_ed25519_storage_slices,
+        verify_prepared_entry_v0_ed25519_storage_slices_all, JoinPlan, LogError, LogGraphIndex,
         LogIndexEntry, NativeLogBlockStore, PreparedEntryV0SignatureInput,
     };
     use crate::append::trim_oldest_log_entry_hashes_core;
     use crate::codec::{
         encode_entry_v0_parts_unsigned_for_signing, encode_entry_v0_parts_with_signature_bytes,
         encode_entry_v0_payload_data_unsigned_for_signing, encode_payload,
-        encode_signature_with_key_parts, parse_plain_entry_v0_storage,
+        encode_signature_with_key_parts, parse_plain_entry_v0_storage, prehashed_signable,
         signable_entry_to_signed_storage, unsigned_entry_v0_storage_for_signing,
     };
     use crate::crypto::{prepared_entry_v0_signature_with_key, sign_ed25519_with_key};
     use ed25519_dalek::SigningKey;
+    use peerbit_wire::wire::{
+        prehash_digest, PREHASH_BLAKE3, PREHASH_ETH_KECCAK_256, PREHASH_SHA_256,
+    };
 
     const APPEND: u8 = 0;
     const CUT: u8 = 1;
@@ -2832,6 +2836,59 @@ mod tests {
         assert!(verify_prepared_entry_v0_ed25519_storage_slices_all(&[input]).unwrap());
     }
 
+    #[test]
+    fn prehashed_entry_signatures_verify_over_their_digest() {
+        let meta = b"encoded-meta".to_vec();
+        let payload = b"encoded-payload".to_vec();
+        let signing_key = SigningKey::from_bytes(&[9u8; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+        let signable = encode_entry_v0_parts_unsigned_for_signing(&meta, &payload);
+
+        // Official BLAKE3 vector (1025 bytes `i % 251`), also checked against
+        // `@noble/hashes` by the `PreHash.BLAKE3` spec in packages/utils/crypto.
+        let vector_input = (0..1025).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        assert_eq!(
+            prehashed_signable(PREHASH_BLAKE3, vector_input),
+            (0..32)
+                .map(|i| {
+                    let hex = "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444";
+                    u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap()
+                })
+                .collect::<Vec<_>>()
+        );
+
+        let signed = |prehash: u8, signed_prehash: u8| {
+            let digest = prehash_digest(signed_prehash, &signable).unwrap();
+            let signature = sign_ed25519_with_key(&signing_key, &digest);
+            let signature_with_key =
+                encode_signature_with_key_parts(&signature, &public_key, prehash);
+            signable_entry_to_signed_storage(signable.clone(), &signature_with_key)
+        };
+
+        let valid = [
+            signed(PREHASH_SHA_256, PREHASH_SHA_256),
+            signed(PREHASH_BLAKE3, PREHASH_BLAKE3),
+            signed(PREHASH_ETH_KECCAK_256, PREHASH_ETH_KECCAK_256),
+        ];
+        let mismatched = signed(PREHASH_BLAKE3, PREHASH_ETH_KECCAK_256);
+        let blocks = valid
+            .iter()
+            .chain([&mismatched])
+            .map(|storage| storage.as_slice())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            verify_entry_v0_ed25519_storage_slices(&blocks).unwrap(),
+            vec![1, 1, 1, 0]
+        );
+
+        let unknown = encode_signature_with_key_parts(&[0u8; 64], &public_key, 4);
+        let storage = signable_entry_to_signed_storage(signable.clone(), &unknown);
+        assert_eq!(
+            verify_entry_v0_ed25519_storage_slices(&[storage.as_slice()]),
+            Err(LogError::UnsupportedSignaturePrehash)
+        );
+    }
+
     #[test]
     fn direct_payload_signable_encoding_matches_payload_parts_encoder() {
         let meta = b"encoded-meta".to_vec();
@@ -0,0 +1,420 @@
+//! Native `EncryptedThing` sealing and opening, byte-compatible with
+//! `packages/utils/crypto/src/encryption.ts`:
+//!
+//! - The serialized `DecryptedThing` is sealed with libsodium's
+//!   `crypto_secretbox_easy` (XSalsa20-Poly1305, `mac || cipher`) under a
+//!   fresh 32-byte key.
+//! - That key is sealed to every recipient with `crypto_box_easy` (X25519,
+//!   HSalsa20 key derivation, then the same secretbox) from the sender's
+//!   X25519 keypair.
+//! - Borsh layout: `MaybeEncrypted`/`EncryptedThing` variants `[0, 1]`,
+//!   `_encrypted: Uint8Array`, `_nonce: Uint8Array`, then the envelope.
+//!   `PublicKeyEnvelope` (variant `0`) holds `_senderPublicKey:
+//!   X25519PublicKey` (variant `0`, `[u8; 32]`) and `_ks: vec<K>`, where `K`
+//!   is variant `0`, `CipherWithNonce` (variant `0`, `nonce`, `cipher`) and
+//!   `_receiverPublicKey`. A `HashedKeyEnvelope` (variant `1`) needs the
+//!   symmetric key itself and is left to the TS path.
+//!
+//! XSalsa20-Poly1305 and the HSalsa20 key derivation come from
+//! `crypto_secretbox`. X25519 is curve25519-dalek's clamped Montgomery ladder
+//! rather than `crypto_box::SalsaBox`, which reduces the clamped scalar mod l
+//! first and so derives a different key than libsodium for a peer key with a
+//! torsion component. `JsValue`-free so host `cargo test` can exercise it.
+
+use crate::wire::{Reader, WireResult, Writer};
+use crypto_secretbox::aead::rand_core::CryptoRngCore;
+use crypto_secretbox::aead::{Aead, KeyInit};
+use crypto_secretbox::{Kdf, XSalsa20Poly1305};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+
+pub const X25519_KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 24;
+
+/// An X25519 keypair as `X25519Keypair` holds it in TS. The secret key is
+/// clamped on use, as libsodium's `crypto_scalarmult` does.
+#[derive(Clone)]
+pub struct X25519Keypair {
+    secret_key: [u8; 32],
+    public_key: [u8; 32],
+}
+
+impl X25519Keypair {
+    pub fn from_secret_key(secret_key: [u8; 32]) -> Self {
+        let public_key = MontgomeryPoint::mul_base_clamped(secret_key).to_bytes();
+        Self {
+            secret_key,
+            public_key,
+        }
+    }
+
+    /// Checks that `public_key` belongs to `secret_key`.
+    pub fn new(secret_key: &[u8], public_key: &[u8]) -> WireResult<Self> {
+        let secret_key: [u8; 32] = secret_key
+            .try_into()
+            .map_err(|_| "expected a 32-byte X25519 secret key".to_string())?;
+        let keypair = Self::from_secret_key(secret_key);
+        if keypair.public_key.as_slice() != public_key {
+            return Err("X25519 public key does not match the secret key".to_string());
+        }
+        Ok(keypair)
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+
+    /// `crypto_box_beforenm`: HSalsa20 over the X25519 shared secret.
+    /// libsodium refuses an all-zero shared secret (a low-order peer key).
+    fn box_key(&self, peer_public_key: &[u8; 32]) -> WireResult<[u8; 32]> {
+        let shared = MontgomeryPoint(*peer_public_key)
+            .mul_clamped(self.secret_key)
+            .to_bytes();
+        if shared == [0u8; 32] {
+            return Err("X25519 key agreement with a low-order public key".to_string());
+        }
+        Ok(XSalsa20Poly1305::kdf(&shared.into(), &Default::default()).into())
+    }
+}
+
+// --- XSalsa20-Poly1305 (libsodium `crypto_secretbox_easy`) ----------------
+
+/// `crypto_secretbox_easy`: `mac || cipher`.
+fn secretbox_seal(key: &[u8; 32], nonce: &[u8; NONCE_LENGTH], message: &[u8]) -> Vec<u8> {
+    XSalsa20Poly1305::new(key.into())
+        .encrypt(nonce.into(), message)
+        .expect("XSalsa20-Poly1305 accepts any message length")
+}
+
+/// `crypto_secretbox_open_easy`; `None` when the MAC does not verify.
+fn secretbox_open(key: &[u8; 32], nonce: &[u8; NONCE_LENGTH], sealed: &[u8]) -> Option<Vec<u8>> {
+    XSalsa20Poly1305::new(key.into())
+        .decrypt(nonce.into(), sealed)
+        .ok()
+}
+
+// --- EncryptedThing --------------------------------------------------------
+
+/// A parsed `PublicKeyEnvelope` entry: the payload key sealed to one
+/// recipient.
+struct SealedKey<'a> {
+    nonce: &'a [u8],
+    cipher: &'a [u8],
+    receiver_public_key: [u8; 32],
+}
+
+fn read_bytes<'a>(reader: &mut Reader<'a>) -> WireResult<&'a [u8]> {
+    let length = reader.u32_le()? as usize;
+    reader.take(length)
+}
+
+fn read_x25519_public_key(reader: &mut Reader) -> WireResult<[u8; 32]> {
+    let variant = reader.u8()?;
+    if variant != 0 {
+        return Err(format!("unsupported X25519PublicKey variant: {variant}"));
+    }
+    reader.fixed_32()
+}
+
+fn nonce_array(nonce: &[u8]) -> WireResult<&[u8; NONCE_LENGTH]> {
+    nonce
+        .try_into()
+        .map_err(|_| format!("expected a {NONCE_LENGTH}-byte nonce, got {}", nonce.len()))
+}
+
+/// Open a serialized `MaybeEncrypted` that must be an `EncryptedThing` with
+/// a `PublicKeyEnvelope`, the way `EncryptedThing.decrypt(keypair)` does, and
+/// return the `DecryptedThing` data. Like the TS resolver, only the first
+/// envelope entry addressed to `recipient` is tried.
+pub fn open_encrypted_thing(bytes: &[u8], recipient: &X25519Keypair) -> WireResult<Vec<u8>> {
+    let mut reader = Reader::new(bytes);
+    let variants = [reader.u8()?, reader.u8()?];
+    if variants != [0, 1] {
+        return Err(format!("not an EncryptedThing: variant {variants:?}"));
+    }
+    let encrypted = read_bytes(&mut reader)?;
+    let nonce = read_bytes(&mut reader)?;
+    let envelope_variant = reader.u8()?;
+    if envelope_variant != 0 {
+        return Err(format!(
+            "unsupported EncryptedThing envelope variant: {envelope_variant}"
+        ));
+    }
+    let sender_public_key = read_x25519_public_key(&mut reader)?;
+    let key_count = reader.u32_le()? as usize;
+    // Cheap sanity bound: every K needs two variant bytes, two length
+    // prefixes and the variant plus 32 bytes of its receiver key.
+    if key_count > reader.remaining() / 43 {
+        return Err("envelope key count exceeds payload".to_string());
+    }
+    let mut sealed_keys = Vec::with_capacity(key_count);
+    for _ in 0..key_count {
+        let variants = [reader.u8()?, reader.u8()?];
+        if variants != [0, 0] {
+            return Err(format!("unsupported envelope key variant: {variants:?}"));
+        }
+        let nonce = read_bytes(&mut reader)?;
+        let cipher = read_bytes(&mut reader)?;
+        let receiver_public_key = read_x25519_public_key(&mut reader)?;
+        sealed_keys.push(SealedKey {
+            nonce,
+            cipher,
+            receiver_public_key,
+        });
+    }
+    if reader.remaining() != 0 {
+        return Err(format!(
+            "unexpected {} trailing bytes after EncryptedThing",
+            reader.remaining()
+        ));
+    }
+
+    let sealed_key = sealed_keys
+        .iter()
+        .find(|key| key.receiver_public_key == recipient.public_key)
+        .ok_or_else(|| "no envelope key for this recipient".to_string())?;
+    let box_key = recipient.box_key(&sender_public_key)?;
+    let payload_key = secretbox_open(&box_key, nonce_array(sealed_key.nonce)?, sealed_key.cipher)
+        .ok_or_else(|| "failed to open the envelope key".to_string())?;
+    let payload_key: [u8; 32] = payload_key
+        .as_slice()
+        .try_into()
+        .map_err(|_| "envelope key is not 32 bytes".to_string())?;
+    let plaintext = secretbox_open(&payload_key, nonce_array(nonce)?, encrypted)
+        .ok_or_else(|| "failed to open the encrypted payload".to_string())?;
+
+    let mut reader = Reader::new(&plaintext);
+    let variants = [reader.u8()?, reader.u8()?];
+    if variants != [0, 0] {
+        return Err(format!("not a DecryptedThing: variant {variants:?}"));
+    }
+    let data = read_bytes(&mut reader)?.to_vec();
+    if reader.remaining() != 0 {
+        return Err(format!(
+            "unexpected {} trailing bytes after DecryptedThing",
+            reader.remaining()
+        ));
+    }
+    Ok(data)
+}
+
+/// Seal `data` as `DecryptedThing.encrypt(sender, recipients)` does and
+/// return the serialized `EncryptedThing`. `random` draws the payload key and
+/// the nonces; pass `OsRng` outside tests (the TS side uses
+/// `crypto.getRandomValues`).
+pub fn seal_encrypted_thing(
+    data: &[u8],
+    sender: &X25519Keypair,
+    recipients: &[[u8; 32]],
+    random: &mut impl CryptoRngCore,
+) -> WireResult<Vec<u8>> {
+    let mut plaintext = Writer::new();
+    plaintext.u8(0); // MaybeEncrypted variant
+    plaintext.u8(0); // DecryptedThing variant
+    plaintext.u32_le(data.len() as u32);
+    plaintext.raw(data);
+
+    let mut nonce = [0u8; NONCE_LENGTH];
+    random.fill_bytes(&mut nonce);
+    let mut payload_key = [0u8; 32];
+    random.fill_bytes(&mut payload_key);
+    let encrypted = secretbox_seal(&payload_key, &nonce, &plaintext.bytes);
+
+    let mut out = Writer::new();
+    out.u8(0); // MaybeEncrypted variant
+    out.u8(1); // EncryptedThing variant
+    out.u32_le(encrypted.len() as u32);
+    out.raw(&encrypted);
+    out.u32_le(NONCE_LENGTH as u32);
+    out.raw(&nonce);
+    out.u8(0); // PublicKeyEnvelope variant
+    out.u8(0); // X25519PublicKey variant
+    out.raw(&sender.public_key);
+    out.u32_le(recipients.len() as u32);
+    for recipient in recipients {
+        let mut key_nonce = [0u8; NONCE_LENGTH];
+        random.fill_bytes(&mut key_nonce);
+        let sealed_key = secretbox_seal(&sender.box_key(recipient)?, &key_nonce, &payload_key);
+        out.u8(0); // K variant
+        out.u8(0); // CipherWithNonce variant
+        out.u32_le(NONCE_LENGTH as u32);
+        out.raw(&key_nonce);
+        out.u32_le(sealed_key.len() as u32);
+        out.raw(&sealed_key);
+        out.u8(0); // X25519PublicKey variant
+        out.raw(recipient);
+    }
+    Ok(out.bytes)
+}
+
+/// Deterministic stand-in for `crypto.getRandomValues` in tests. Not random
+/// at all; the `CryptoRng` marker only lets it stand in for `OsRng`.
+#[cfg(test)]
+pub(crate) struct CounterRng(pub(crate) u8);
+
+#[cfg(test)]
+impl crypto_secretbox::aead::rand_core::RngCore for CounterRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            *byte = self.0;
+            self.0 = self.0.wrapping_mul(31).wrapping_add(7);
+        }
+    }
+
+    fn try_fill_bytes(
+        &mut self,
+        bytes: &mut [u8],
+    ) -> Result<(), crypto_secretbox::aead::rand_core::Error> {
+        self.fill_bytes(bytes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl crypto_secretbox::aead::rand_core::CryptoRng for CounterRng {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_secretbox::aead::OsRng;
+
+    fn hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn array<const N: usize>(hex_string: &str) -> [u8; N] {
+        hex(hex_string).try_into().unwrap()
+    }
+
+    #[test]
+    fn crypto_box_matches_the_nacl_test_vector() {
+        // "Cryptography in NaCl", section 9 (Alice sends to Bob).
+        let alice = X25519Keypair::from_secret_key(array(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+        ));
+        let bob = X25519Keypair::from_secret_key(array(
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+        ));
+        assert_eq!(
+            alice.public_key().to_vec(),
+            hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            bob.public_key().to_vec(),
+            hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+        );
+        let box_key = alice.box_key(&bob.public_key()).unwrap();
+        assert_eq!(
+            box_key.to_vec(),
+            hex("1b27556473e985d462cd51197a9a46c76009549eac6474f206c4ee0844f68389")
+        );
+        assert_eq!(bob.box_key(&alice.public_key()).unwrap(), box_key);
+
+        let nonce: [u8; NONCE_LENGTH] = array("69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37");
+        let message = hex(concat!(
+            "be075fc53c81f2d5cf141316ebeb0c7b5228c52a4c62cbd44b66849b64244ffc",
+            "e5ecbaaf33bd751a1ac728d45e6c61296cdc3c01233561f41db66cce314adb31",
+            "0e3be8250c46f06dceea3a7fa1348057e2f6556ad6b1318a024a838f21af1fde",
+            "048977eb48f59ffd4924ca1c60902e52f0a089bc76897040e082f93776384864",
+            "5e0705",
+        ));
+        let sealed = hex(concat!(
+            "f3ffc7703f9400e52a7dfb4b3d3305d98e993b9f48681273c29650ba32fc76ce",
+            "48332ea7164d96a4476fb8c531a1186ac0dfc17c98dce87b4da7f011ec48c972",
+            "71d2c20f9b928fe2270d6fb863d51738b48eeee314a7cc8ab932164548e526ae",
+            "90224368517acfeabd6bb3732bc0e9da99832b61ca01b6de56244a9e88d5f9b3",
+            "7973f622a43d14a6599b1f654cb45a74e355a5",
+        ));
+        assert_eq!(secretbox_seal(&box_key, &nonce, &message), sealed);
+        assert_eq!(secretbox_open(&box_key, &nonce, &sealed).unwrap(), message);
+
+        let mut tampered = sealed.clone();
+        tampered[40] ^= 1;
+        assert!(secretbox_open(&box_key, &nonce, &tampered).is_none());
+        assert!(secretbox_open(&box_key, &nonce, &sealed[..15]).is_none());
+
+        // RustCrypto's `crypto_box` agrees on honest keys.
+        let reference = crypto_box::SalsaBox::new(
+            &crypto_box::PublicKey::from(bob.public_key()),
+            &crypto_box::SecretKey::from(array::<32>(
+                "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+            )),
+        );
+        assert_eq!(
+            reference
+                .encrypt(&nonce.into(), message.as_slice())
+                .unwrap(),
+            sealed
+        );
+    }
+
+    #[test]
+    fn encrypted_things_open_for_each_recipient_only() {
+        let sender = X25519Keypair::from_secret_key([1; 32]);
+        let first = X25519Keypair::from_secret_key([2; 32]);
+        let second = X25519Keypair::from_secret_key([3; 32]);
+        let outsider = X25519Keypair::from_secret_key([4; 32]);
+        for length in [0, 1, 31, 32, 33, 64, 200] {
+            let data: Vec<u8> = (0..length).map(|byte| byte as u8).collect();
+            let sealed = seal_encrypted_thing(
+                &data,
+                &sender,
+                &[first.public_key(), second.public_key()],
+                &mut CounterRng(length as u8),
+            )
+            .unwrap();
+            assert_eq!(open_encrypted_thing(&sealed, &first).unwrap(), data);
+            assert_eq!(open_encrypted_thing(&sealed, &second).unwrap(), data);
+            assert!(open_encrypted_thing(&sealed, &outsider).is_err());
+            assert!(open_encrypted_thing(&sealed, &sender).is_err());
+
+            // The payload MAC sits right after the `_encrypted` length.
+            let mut tampered = sealed.clone();
+            tampered[6] ^= 1;
+            assert!(open_encrypted_thing(&tampered, &first).is_err());
+            let mut trailing = sealed.clone();
+            trailing.push(0);
+            assert!(open_encrypted_thing(&trailing, &first).is_err());
+            for end in 0..sealed.len() {
+                assert!(open_encrypted_thing(&sealed[..end], &first).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn os_random_seals_fresh_keys_and_nonces() {
+        let sender = X25519Keypair::from_secret_key([1; 32]);
+        let recipient = X25519Keypair::from_secret_key([2; 32]);
+        let first =
+            seal_encrypted_thing(b"data", &sender, &[recipient.public_key()], &mut OsRng).unwrap();
+        let second =
+            seal_encrypted_thing(b"data", &sender, &[recipient.public_key()], &mut OsRng).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(open_encrypted_thing(&first, &recipient).unwrap(), b"data");
+        assert_eq!(open_encrypted_thing(&second, &recipient).unwrap(), b"data");
+    }
+
+    #[test]
+    fn keypairs_reject_mismatched_and_low_order_keys() {
+        let keypair = X25519Keypair::from_secret_key([5; 32]);
+        assert!(X25519Keypair::new(&[5; 32], &keypair.public_key()).is_ok());
+        assert!(X25519Keypair::new(&[6; 32], &keypair.public_key()).is_err());
+        assert!(X25519Keypair::new(&[5; 31], &keypair.public_key()).is_err());
+        // u = 0 has order 2, so the shared secret is all zeros.
+        assert!(seal_encrypted_thing(b"x", &keypair, &[[0; 32]], &mut CounterRng(0)).is_err());
+    }
+}
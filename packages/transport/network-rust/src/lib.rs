@@ -6,6 +6,7 @@
 
 pub mod block_exchange;
 pub mod direct_stream;
+pub mod encryption;
 pub mod fanout_tree;
 pub mod sync_payload;
 pub mod topic_control;
@@ -70,8 +71,8 @@ pub fn record_to_words(record: &FrameRecord, out: &mut Vec<u32>) {
 }
 
 /// Decode a batch of direct-stream frames and verify their signatures
-/// (sha256-prehashed Ed25519, batched via ed25519-dalek). Returns
-/// [`RECORD_WORDS`] u32 words per input frame; see the layout above.
+/// (optionally prehashed Ed25519 or secp256k1, one signature at a time).
+/// Returns [`RECORD_WORDS`] u32 words per input frame; see the layout above.
 ///
 /// `now_ms` is the wall clock used for the header expiry check.
 #[wasm_bindgen]
@@ -1791,3 +1792,101 @@ pub fn ft_pu_evaluate_gate(
         },
     )
 }
+
+/// X25519 keypair handle for opening encrypted sync payloads. Holds the
+/// secret key on the wasm side so repeated decrypts don't re-send it.
+#[wasm_bindgen]
+pub struct SyncPayloadKeypair {
+    inner: encryption::X25519Keypair,
+}
+
+#[wasm_bindgen]
+impl SyncPayloadKeypair {
+    #[wasm_bindgen(constructor)]
+    pub fn new(secret_key: &[u8], public_key: &[u8]) -> Result<SyncPayloadKeypair, JsValue> {
+        encryption::X25519Keypair::new(secret_key, public_key)
+            .map(|inner| SyncPayloadKeypair { inner })
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        self.inner.public_key().to_vec()
+    }
+}
+
+/// Opens `PubSubData.data` whose `RequestV0` carries an `EncryptedThing` and
+/// returns the decrypted `RawExchangeHeadsMessage` bytes, after checking
+/// they parse as one.
+#[wasm_bindgen]
+pub fn sp_decrypt_raw_exchange_rpc_request(
+    data: &[u8],
+    keypair: &SyncPayloadKeypair,
+) -> Result<Vec<u8>, JsValue> {
+    sync_payload::decrypt_raw_exchange_rpc_request(data, &keypair.inner)
+        .map(|decrypted| decrypted.transport)
+        .map_err(|error| JsValue::from_str(&error))
+}
+
+/// Encodes the full `PubSubData` nesting of a raw exchange sync payload with
+/// the `RawExchangeHeadsMessage` sealed from `sender` for `recipients` (32-byte
+/// X25519 public keys). The payload key and nonces come from the platform
+/// CSPRNG (`crypto.getRandomValues` in wasm).
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sp_encode_encrypted_raw_exchange_sync_payload(
+    topics: Vec<String>,
+    strict: bool,
+    hashes: Vec<String>,
+    blocks: Array,
+    gid_refrences: Array,
+    reserved: &[u8],
+    sender: &SyncPayloadKeypair,
+    recipients: Array,
+) -> Result<Vec<u8>, JsValue> {
+    let blocks = array_to_byte_vecs(&blocks);
+    let gid_refrences: Vec<Vec<String>> = gid_refrences
+        .iter()
+        .map(|refs| {
+            refs.dyn_into::<Array>()
+                .map(|refs| refs.iter().filter_map(|value| value.as_string()).collect())
+                .unwrap_or_default()
+        })
+        .collect();
+    if blocks.len() != hashes.len() || gid_refrences.len() != hashes.len() {
+        return Err(JsValue::from_str(
+            "mismatched sync payload head input lengths",
+        ));
+    }
+    let reserved: [u8; 4] = reserved
+        .try_into()
+        .map_err(|_| JsValue::from_str("expected 4 reserved bytes"))?;
+    let recipients = array_to_byte_vecs(&recipients)
+        .into_iter()
+        .map(|key| <[u8; 32]>::try_from(key.as_slice()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| JsValue::from_str("expected 32-byte X25519 recipient keys"))?;
+    let heads: Vec<sync_payload::SyncPayloadHeadRef<'_>> = hashes
+        .iter()
+        .zip(&blocks)
+        .zip(&gid_refrences)
+        .map(
+            |((hash, bytes), gid_refrences)| sync_payload::SyncPayloadHeadRef {
+                hash,
+                bytes,
+                gid_refrences,
+            },
+        )
+        .collect();
+    sync_payload::encode_encrypted_raw_exchange_sync_payload_refs(
+        &topics,
+        strict,
+        &heads,
+        reserved,
+        sync_payload::SyncPayloadEncryption {
+            sender: &sender.inner,
+            recipients: &recipients,
+        },
+        &mut crypto_secretbox::aead::OsRng,
+    )
+    .map_err(|error| JsValue::from_str(&error))
+}
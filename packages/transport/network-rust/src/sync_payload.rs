@@ -8,7 +8,9 @@
 //!   shared-log sync uses fire-and-forget `rpc.send`), then `MaybeEncrypted`.
 //! - `MaybeEncrypted`/`DecryptedThing` (`packages/utils/crypto/src/
 //!   encryption.ts`): variants `[0, 0]`, `data: Uint8Array`. An
-//!   `EncryptedThing` (variant 1) is never fused — the TS path decrypts it.
+//!   `EncryptedThing` (variant 1) is opened by
+//!   [`decrypt_raw_exchange_rpc_request`] with the recipient's keypair (see
+//!   [`crate::encryption`]).
 //! - `TransportMessage`/`RawExchangeHeadsMessage` (`packages/programs/data/
 //!   shared-log/src/message.ts`, `exchange-heads.ts`): variants `[0] + [0, 7]`,
 //!   `heads: vec<RawEntryWithRefs>` (variant `1`, `hash: string`,
@@ -19,6 +21,9 @@
 //!
 //! `JsValue`-free so host `cargo test` can exercise it.
 
+use crypto_secretbox::aead::rand_core::CryptoRngCore;
+
+use crate::encryption::{open_encrypted_thing, seal_encrypted_thing, X25519Keypair};
 use crate::wire::{Reader, WireResult, Writer};
 
 /// One head inside a raw exchange payload. `bytes_offset`/`bytes_length`
@@ -49,6 +54,23 @@ pub struct RawExchangeSyncPayload {
     pub reserved: [u8; 4],
 }
 
+/// A decrypted raw exchange payload. The plaintext is not part of the frame,
+/// so head byte offsets are relative to `transport`, the decrypted
+/// `TransportMessage` bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecryptedRawExchangeSyncPayload {
+    pub transport: Vec<u8>,
+    pub payload: RawExchangeSyncPayload,
+}
+
+/// Who an outbound sync payload is sealed for, as `DecryptedThing.encrypt`'s
+/// keypair and `receiverPublicKeys` (already converted to X25519).
+#[derive(Clone, Copy)]
+pub struct SyncPayloadEncryption<'a> {
+    pub sender: &'a X25519Keypair,
+    pub recipients: &'a [[u8; 32]],
+}
+
 pub fn parse_pubsub_data(payload: &[u8]) -> WireResult<PubSubDataRef> {
     let mut reader = Reader::new(payload);
     let variant = reader.u8()?;
@@ -78,10 +100,9 @@ pub fn parse_pubsub_data(payload: &[u8]) -> WireResult<PubSubDataRef> {
     })
 }
 
-/// Parse `PubSubData.data` (an RPC `RequestV0` wrapping a plaintext
-/// `RawExchangeHeadsMessage`). Head byte offsets are relative to `data` —
-/// callers add the enclosing offsets to address into the original frame.
-pub fn parse_raw_exchange_rpc_request(data: &[u8]) -> WireResult<RawExchangeSyncPayload> {
+/// Reads the `RPCMessage`/`RequestV0` prefix up to its `MaybeEncrypted`
+/// body and returns the body's offset in `data`.
+fn read_rpc_request_prefix(data: &[u8]) -> WireResult<usize> {
     let mut reader = Reader::new(data);
     let rpc_variant = reader.u8()?;
     if rpc_variant != 0 {
@@ -101,9 +122,20 @@ pub fn parse_raw_exchange_rpc_request(data: &[u8]) -> WireResult<RawExchangeSync
             "unsupported MaybeEncrypted variant: {maybe_encrypted_variant}"
         ));
     }
+    Ok(reader.offset - 1)
+}
+
+/// Parse `PubSubData.data` (an RPC `RequestV0` wrapping a plaintext
+/// `RawExchangeHeadsMessage`). Head byte offsets are relative to `data` —
+/// callers add the enclosing offsets to address into the original frame.
+pub fn parse_raw_exchange_rpc_request(data: &[u8]) -> WireResult<RawExchangeSyncPayload> {
+    let mut reader = Reader::new(data);
+    reader.offset = read_rpc_request_prefix(data)? + 1;
     let decrypted_variant = reader.u8()?;
     if decrypted_variant != 0 {
-        return Err("encrypted sync payloads are not fused".to_string());
+        return Err(
+            "encrypted sync payload: open it with decrypt_raw_exchange_rpc_request".to_string(),
+        );
     }
     let inner_length = reader.u32_le()? as usize;
     let inner_offset = reader.offset;
@@ -115,7 +147,31 @@ pub fn parse_raw_exchange_rpc_request(data: &[u8]) -> WireResult<RawExchangeSync
         ));
     }
 
-    let inner = &data[inner_offset..inner_offset + inner_length];
+    parse_raw_exchange_heads(
+        &data[inner_offset..inner_offset + inner_length],
+        inner_offset,
+    )
+}
+
+/// Parse `PubSubData.data` whose `RequestV0` carries an `EncryptedThing`,
+/// opening it as `recipient`. Fails like the TS decrypt does when no
+/// envelope key is addressed to `recipient` or a MAC does not verify.
+pub fn decrypt_raw_exchange_rpc_request(
+    data: &[u8],
+    recipient: &X25519Keypair,
+) -> WireResult<DecryptedRawExchangeSyncPayload> {
+    let body_offset = read_rpc_request_prefix(data)?;
+    let transport = open_encrypted_thing(&data[body_offset..], recipient)?;
+    let payload = parse_raw_exchange_heads(&transport, 0)?;
+    Ok(DecryptedRawExchangeSyncPayload { transport, payload })
+}
+
+/// Parse a `TransportMessage` that must be a `RawExchangeHeadsMessage`.
+/// Head byte offsets are `inner` offsets plus `base_offset`.
+fn parse_raw_exchange_heads(
+    inner: &[u8],
+    base_offset: usize,
+) -> WireResult<RawExchangeSyncPayload> {
     let mut reader = Reader::new(inner);
     let transport_variant = reader.u8()?;
     if transport_variant != 0 {
@@ -145,7 +201,7 @@ pub fn parse_raw_exchange_rpc_request(data: &[u8]) -> WireResult<RawExchangeSync
         }
         let hash = reader.string()?;
         let bytes_length = reader.u32_le()? as usize;
-        let bytes_offset = inner_offset + reader.offset;
+        let bytes_offset = base_offset + reader.offset;
         reader.take(bytes_length)?;
         let gid_refrences = reader.string_vec()?;
         heads.push(SyncPayloadHead {
@@ -232,22 +288,63 @@ pub fn encode_raw_exchange_sync_payload_refs(
     payload.u8(0); // MaybeEncrypted variant
     payload.u8(0); // DecryptedThing variant
     payload.u32_le(transport_len as u32); // DecryptedThing.data length
-    payload.u8(0); // TransportMessage variant
-    payload.u8(0); // RawExchangeHeadsMessage variant [0, 7]
-    payload.u8(7);
-    payload.u32_le(heads.len() as u32);
-    for head in heads {
-        payload.u8(1); // RawEntryWithRefs variant
-        payload.string(head.hash);
-        payload.u32_le(head.bytes.len() as u32);
-        payload.raw(head.bytes);
-        payload.string_vec(head.gid_refrences);
-    }
-    payload.raw(&reserved);
+    write_transport_message(&mut payload, heads, reserved);
     debug_assert_eq!(payload.bytes.len(), total_len);
     payload.bytes
 }
 
+fn write_transport_message(
+    writer: &mut Writer,
+    heads: &[SyncPayloadHeadRef<'_>],
+    reserved: [u8; 4],
+) {
+    writer.u8(0); // TransportMessage variant
+    writer.u8(0); // RawExchangeHeadsMessage variant [0, 7]
+    writer.u8(7);
+    writer.u32_le(heads.len() as u32);
+    for head in heads {
+        writer.u8(1); // RawEntryWithRefs variant
+        writer.string(head.hash);
+        writer.u32_le(head.bytes.len() as u32);
+        writer.raw(head.bytes);
+        writer.string_vec(head.gid_refrences);
+    }
+    writer.raw(&reserved);
+}
+
+/// [`encode_raw_exchange_sync_payload_refs`] with the `RawExchangeHeadsMessage`
+/// sealed in an `EncryptedThing` for `encryption.recipients`. `random` fills
+/// the payload key and nonces; pass `OsRng` outside tests. Fails if a
+/// recipient key is low-order, as libsodium's `crypto_box_easy` does.
+pub fn encode_encrypted_raw_exchange_sync_payload_refs(
+    topics: &[String],
+    strict: bool,
+    heads: &[SyncPayloadHeadRef<'_>],
+    reserved: [u8; 4],
+    encryption: SyncPayloadEncryption<'_>,
+    random: &mut impl CryptoRngCore,
+) -> WireResult<Vec<u8>> {
+    let mut transport = Writer::new();
+    transport.bytes.reserve_exact(transport_message_len(heads));
+    write_transport_message(&mut transport, heads, reserved);
+    let sealed = seal_encrypted_thing(
+        &transport.bytes,
+        encryption.sender,
+        encryption.recipients,
+        random,
+    )?;
+    let mut payload = Writer::new();
+    payload.u8(0); // PubSubData variant
+    payload.string_vec(topics);
+    payload.u8(u8::from(strict));
+    payload.u32_le((3 + sealed.len()) as u32); // PubSubData.data length
+    payload.u8(0); // RPCMessage variant
+    payload.u8(0); // RequestV0 variant
+    payload.u8(0); // respondTo: None
+    payload.raw(&sealed);
+    Ok(payload.bytes)
+}
+
 /// Encode the full payload nesting from owned heads (test/corpus helper and
 /// the golden pin for [`encode_raw_exchange_sync_payload_refs`]).
 pub fn encode_raw_exchange_sync_payload(
@@ -267,9 +364,38 @@ pub fn encode_raw_exchange_sync_payload(
     encode_raw_exchange_sync_payload_refs(topics, strict, &head_refs, reserved)
 }
 
+/// Encode from owned heads, optionally sealed for `encryption`'s recipients
+/// (see [`encode_encrypted_raw_exchange_sync_payload_refs`]).
+pub fn encode_encrypted_raw_exchange_sync_payload(
+    topics: &[String],
+    strict: bool,
+    heads: &[(String, Vec<u8>, Vec<String>)],
+    reserved: [u8; 4],
+    encryption: Option<SyncPayloadEncryption<'_>>,
+    random: &mut impl CryptoRngCore,
+) -> WireResult<Vec<u8>> {
+    let head_refs: Vec<SyncPayloadHeadRef<'_>> = heads
+        .iter()
+        .map(|(hash, bytes, gid_refrences)| SyncPayloadHeadRef {
+            hash,
+            bytes,
+            gid_refrences,
+        })
+        .collect();
+    match encryption {
+        Some(encryption) => encode_encrypted_raw_exchange_sync_payload_refs(
+            topics, strict, &head_refs, reserved, encryption, random,
+        ),
+        None => Ok(encode_raw_exchange_sync_payload_refs(
+            topics, strict, &head_refs, reserved,
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::encryption::CounterRng;
 
     fn corpus_heads() -> Vec<(String, Vec<u8>, Vec<String>)> {
         vec![
@@ -464,6 +590,103 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encrypted_payloads_decrypt_for_recipients() {
+        let sender = X25519Keypair::from_secret_key([3; 32]);
+        let alice = X25519Keypair::from_secret_key([5; 32]);
+        let bob = X25519Keypair::from_secret_key([7; 32]);
+        let eve = X25519Keypair::from_secret_key([9; 32]);
+        let recipients = [alice.public_key(), bob.public_key()];
+        let heads = corpus_heads();
+        let payload = encode_encrypted_raw_exchange_sync_payload(
+            &["topicA".to_string()],
+            true,
+            &heads,
+            [1, 0, 0, 0],
+            Some(SyncPayloadEncryption {
+                sender: &sender,
+                recipients: &recipients,
+            }),
+            &mut CounterRng(1),
+        )
+        .unwrap();
+        let pubsub = parse_pubsub_data(&payload).unwrap();
+        assert_eq!(pubsub.topics, vec!["topicA".to_string()]);
+        let data = &payload[pubsub.data_offset..pubsub.data_offset + pubsub.data_length];
+        assert!(parse_raw_exchange_rpc_request(data).is_err());
+
+        // The plaintext matches what the unencrypted encoder nests.
+        let plain =
+            encode_raw_exchange_sync_payload(&["topicA".to_string()], true, &heads, [1, 0, 0, 0]);
+        let plain_transport = &plain[plain.len() - transport_message_len_of(&heads)..];
+        for recipient in [&alice, &bob] {
+            let decrypted = decrypt_raw_exchange_rpc_request(data, recipient).unwrap();
+            assert_eq!(decrypted.transport.as_slice(), plain_transport);
+            assert_eq!(decrypted.payload.reserved, [1, 0, 0, 0]);
+            assert_eq!(decrypted.payload.heads.len(), heads.len());
+            for (parsed_head, (hash, bytes, gid_refrences)) in
+                decrypted.payload.heads.iter().zip(&heads)
+            {
+                assert_eq!(&parsed_head.hash, hash);
+                assert_eq!(&parsed_head.gid_refrences, gid_refrences);
+                assert_eq!(
+                    &decrypted.transport[parsed_head.bytes_offset
+                        ..parsed_head.bytes_offset + parsed_head.bytes_length],
+                    bytes.as_slice()
+                );
+            }
+        }
+        assert!(decrypt_raw_exchange_rpc_request(data, &eve).is_err());
+
+        // With a single recipient every byte is read, so any flipped bit
+        // fails authentication (or parsing) instead of yielding heads.
+        let single = encode_encrypted_raw_exchange_sync_payload(
+            &["topicA".to_string()],
+            true,
+            &heads,
+            [1, 0, 0, 0],
+            Some(SyncPayloadEncryption {
+                sender: &sender,
+                recipients: &recipients[..1],
+            }),
+            &mut CounterRng(1),
+        )
+        .unwrap();
+        let pubsub = parse_pubsub_data(&single).unwrap();
+        let data = &single[pubsub.data_offset..pubsub.data_offset + pubsub.data_length];
+        assert!(decrypt_raw_exchange_rpc_request(data, &alice).is_ok());
+        assert!(decrypt_raw_exchange_rpc_request(data, &bob).is_err());
+        for index in 0..data.len() {
+            let mut tampered = data.to_vec();
+            tampered[index] ^= 0x01;
+            assert!(decrypt_raw_exchange_rpc_request(&tampered, &alice).is_err());
+        }
+
+        // Without encryption the helper matches the plaintext encoder.
+        let unencrypted = encode_encrypted_raw_exchange_sync_payload(
+            &["topicA".to_string()],
+            true,
+            &heads,
+            [1, 0, 0, 0],
+            None,
+            &mut CounterRng(1),
+        )
+        .unwrap();
+        assert_eq!(unencrypted, plain);
+    }
+
+    fn transport_message_len_of(heads: &[(String, Vec<u8>, Vec<String>)]) -> usize {
+        let head_refs: Vec<SyncPayloadHeadRef<'_>> = heads
+            .iter()
+            .map(|(hash, bytes, gid_refrences)| SyncPayloadHeadRef {
+                hash,
+                bytes,
+                gid_refrences,
+            })
+            .collect();
+        transport_message_len(&head_refs)
+    }
+
     #[test]
     fn rejects_trailing_bytes() {
         let mut payload = encode_raw_exchange_sync_payload(
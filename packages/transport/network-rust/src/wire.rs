@@ -16,13 +16,23 @@
 //! frame this is equivalent to replacing the byte range
 //! `[mode_flag_offset, signatures_end_offset)` with two zero bytes.
 //!
+//! [`decode_frame_ref`] is the borrowed decoder: its `*Ref` types point
+//! into the input buffer (string lists and signatures are walked lazily), and
+//! the owned [`WireMessage`] form is converted from it. The inbound batch
+//! path uses the borrowed form only.
+//!
 //! This module is intentionally `JsValue`-free so it can be exercised by host
 //! `cargo test` (constructing `JsValue`s aborts outside a JS runtime).
 
 #[cfg(test)]
 use ed25519_dalek::{verify_batch, Verifier};
 use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use k256::ecdsa::{
+    RecoveryId, Signature as Secp256k1Signature, SigningKey as Secp256k1SigningKey,
+    VerifyingKey as Secp256k1VerifyingKey,
+};
 use sha2::{Digest, Sha256};
+use sha3::Keccak256;
 
 pub const ID_LENGTH: usize = 32;
 
@@ -31,11 +41,37 @@ pub const VARIANT_ACK: u8 = 1;
 pub const VARIANT_HELLO: u8 = 2;
 pub const VARIANT_GOODBYE: u8 = 3;
 
-// PreHash values from packages/utils/crypto/src/prehash.ts. Value 2 is a gap
-// (reserved for BLAKE3), 3 is ETH_KECCAK_256 which this crate does not verify
-// natively (reported as unsupported so the TS fallback handles it).
+// PreHash values from packages/utils/crypto/src/prehash.ts. BLAKE3 is the
+// plain 32-byte hash of the signable bytes (`@noble/hashes` `blake3` there).
 pub const PREHASH_NONE: u8 = 0;
 pub const PREHASH_SHA_256: u8 = 1;
+pub const PREHASH_BLAKE3: u8 = 2;
+pub const PREHASH_ETH_KECCAK_256: u8 = 3;
+
+const ETH_MESSAGE_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n";
+
+pub fn is_known_prehash(prehash: u8) -> bool {
+    prehash <= PREHASH_ETH_KECCAK_256
+}
+
+/// The digest a signature with `prehash` signs instead of `data`, as
+/// `prehashFn` computes it; `None` for `PREHASH_NONE` and unknown values.
+/// ETH_KECCAK_256 is keccak-256 of the EIP-191 personal-message framing
+/// (`"\x19Ethereum Signed Message:\n" + decimal length + data`).
+pub fn prehash_digest(prehash: u8, data: &[u8]) -> Option<[u8; 32]> {
+    match prehash {
+        PREHASH_SHA_256 => Some(Sha256::digest(data).into()),
+        PREHASH_BLAKE3 => Some(*blake3::hash(data).as_bytes()),
+        PREHASH_ETH_KECCAK_256 => {
+            let mut hasher = Keccak256::new();
+            hasher.update(ETH_MESSAGE_PREFIX);
+            hasher.update(data.len().to_string().as_bytes());
+            hasher.update(data);
+            Some(hasher.finalize().into())
+        }
+        _ => None,
+    }
+}
 
 pub type WireResult<T> = Result<T, String>;
 
@@ -159,6 +195,324 @@ pub struct DecodedFrame {
     pub data_length: usize,
 }
 
+/// A borsh `Vec<String>` borrowed from a frame: the encoded elements and
+/// their count. The elements were checked to be UTF-8 when the frame was
+/// decoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WireStrings<'a> {
+    bytes: &'a [u8],
+    len: usize,
+}
+
+impl<'a> WireStrings<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> WireStringsIter<'a> {
+        WireStringsIter {
+            reader: Reader::new(self.bytes),
+            remaining: self.len,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<String> {
+        self.iter().map(str::to_string).collect()
+    }
+}
+
+impl<'a> IntoIterator for WireStrings<'a> {
+    type Item = &'a str;
+    type IntoIter = WireStringsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct WireStringsIter<'a> {
+    reader: Reader<'a>,
+    remaining: usize,
+}
+
+impl<'a> Iterator for WireStringsIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.reader.str().ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for WireStringsIter<'_> {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureWithKeyRef<'a> {
+    pub signature: &'a [u8],
+    pub public_key: PublicSignKey,
+    pub prehash: u8,
+}
+
+/// The `Signatures` list borrowed from a frame; every entry was parsed once
+/// when the frame was decoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SignaturesRef<'a> {
+    bytes: &'a [u8],
+    len: usize,
+}
+
+impl<'a> SignaturesRef<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> SignaturesIter<'a> {
+        SignaturesIter {
+            reader: Reader::new(self.bytes),
+            remaining: self.len,
+        }
+    }
+}
+
+impl<'a> IntoIterator for SignaturesRef<'a> {
+    type Item = SignatureWithKeyRef<'a>;
+    type IntoIter = SignaturesIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct SignaturesIter<'a> {
+    reader: Reader<'a>,
+    remaining: usize,
+}
+
+impl<'a> Iterator for SignaturesIter<'a> {
+    type Item = SignatureWithKeyRef<'a>;
+
+    fn next(&mut self) -> Option<SignatureWithKeyRef<'a>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        read_signature_with_key(&mut self.reader).ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for SignaturesIter<'_> {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryModeRef<'a> {
+    Silent {
+        to: WireStrings<'a>,
+        redundancy: u8,
+    },
+    Acknowledge {
+        to: WireStrings<'a>,
+        redundancy: u8,
+        hops: WireStrings<'a>,
+    },
+    Traced {
+        trace: WireStrings<'a>,
+    },
+    AnyWhere,
+    AcknowledgeAnyWhere {
+        redundancy: u8,
+        hops: WireStrings<'a>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageHeaderRef<'a> {
+    pub id: [u8; ID_LENGTH],
+    pub timestamp: u64,
+    pub session: u64,
+    pub expires: u64,
+    pub priority: Option<u32>,
+    pub response_priority: Option<u32>,
+    /// `origin.multiaddrs`.
+    pub origin: Option<WireStrings<'a>>,
+    pub mode: Option<DeliveryModeRef<'a>>,
+    pub signatures: Option<SignaturesRef<'a>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireMessageRef<'a> {
+    Data {
+        header: MessageHeaderRef<'a>,
+        data: Option<&'a [u8]>,
+    },
+    Ack {
+        header: MessageHeaderRef<'a>,
+        message_id_to_acknowledge: [u8; ID_LENGTH],
+        seen_counter: u8,
+    },
+    Hello {
+        header: MessageHeaderRef<'a>,
+        joined: WireStrings<'a>,
+    },
+    Goodbye {
+        header: MessageHeaderRef<'a>,
+        leaving: WireStrings<'a>,
+    },
+}
+
+impl<'a> WireMessageRef<'a> {
+    pub fn variant(&self) -> u8 {
+        match self {
+            WireMessageRef::Data { .. } => VARIANT_DATA,
+            WireMessageRef::Ack { .. } => VARIANT_ACK,
+            WireMessageRef::Hello { .. } => VARIANT_HELLO,
+            WireMessageRef::Goodbye { .. } => VARIANT_GOODBYE,
+        }
+    }
+
+    pub fn header(&self) -> &MessageHeaderRef<'a> {
+        match self {
+            WireMessageRef::Data { header, .. } => header,
+            WireMessageRef::Ack { header, .. } => header,
+            WireMessageRef::Hello { header, .. } => header,
+            WireMessageRef::Goodbye { header, .. } => header,
+        }
+    }
+}
+
+/// [`DecodedFrame`] borrowing from the frame it was decoded from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodedFrameRef<'a> {
+    pub message: WireMessageRef<'a>,
+    pub mode_flag_offset: usize,
+    pub signatures_end_offset: usize,
+    pub data_offset: usize,
+    pub data_length: usize,
+}
+
+impl From<WireStrings<'_>> for Vec<String> {
+    fn from(strings: WireStrings<'_>) -> Self {
+        strings.to_vec()
+    }
+}
+
+impl From<SignatureWithKeyRef<'_>> for SignatureWithKey {
+    fn from(signature: SignatureWithKeyRef<'_>) -> Self {
+        SignatureWithKey {
+            signature: signature.signature.to_vec(),
+            public_key: signature.public_key,
+            prehash: signature.prehash,
+        }
+    }
+}
+
+impl From<DeliveryModeRef<'_>> for DeliveryMode {
+    fn from(mode: DeliveryModeRef<'_>) -> Self {
+        match mode {
+            DeliveryModeRef::Silent { to, redundancy } => DeliveryMode::Silent {
+                to: to.to_vec(),
+                redundancy,
+            },
+            DeliveryModeRef::Acknowledge {
+                to,
+                redundancy,
+                hops,
+            } => DeliveryMode::Acknowledge {
+                to: to.to_vec(),
+                redundancy,
+                hops: hops.to_vec(),
+            },
+            DeliveryModeRef::Traced { trace } => DeliveryMode::Traced {
+                trace: trace.to_vec(),
+            },
+            DeliveryModeRef::AnyWhere => DeliveryMode::AnyWhere,
+            DeliveryModeRef::AcknowledgeAnyWhere { redundancy, hops } => {
+                DeliveryMode::AcknowledgeAnyWhere {
+                    redundancy,
+                    hops: hops.to_vec(),
+                }
+            }
+        }
+    }
+}
+
+impl From<MessageHeaderRef<'_>> for MessageHeader {
+    fn from(header: MessageHeaderRef<'_>) -> Self {
+        MessageHeader {
+            id: header.id,
+            timestamp: header.timestamp,
+            session: header.session,
+            expires: header.expires,
+            priority: header.priority,
+            response_priority: header.response_priority,
+            origin: header.origin.map(|multiaddrs| MultiAddrInfo {
+                multiaddrs: multiaddrs.to_vec(),
+            }),
+            mode: header.mode.map(DeliveryMode::from),
+            signatures: header
+                .signatures
+                .map(|signatures| signatures.iter().map(SignatureWithKey::from).collect()),
+        }
+    }
+}
+
+impl From<WireMessageRef<'_>> for WireMessage {
+    fn from(message: WireMessageRef<'_>) -> Self {
+        match message {
+            WireMessageRef::Data { header, data } => WireMessage::Data {
+                header: header.into(),
+                data: data.map(<[u8]>::to_vec),
+            },
+            WireMessageRef::Ack {
+                header,
+                message_id_to_acknowledge,
+                seen_counter,
+            } => WireMessage::Ack {
+                header: header.into(),
+                message_id_to_acknowledge,
+                seen_counter,
+            },
+            WireMessageRef::Hello { header, joined } => WireMessage::Hello {
+                header: header.into(),
+                joined: joined.to_vec(),
+            },
+            WireMessageRef::Goodbye { header, leaving } => WireMessage::Goodbye {
+                header: header.into(),
+                leaving: leaving.to_vec(),
+            },
+        }
+    }
+}
+
+impl From<DecodedFrameRef<'_>> for DecodedFrame {
+    fn from(decoded: DecodedFrameRef<'_>) -> Self {
+        DecodedFrame {
+            message: decoded.message.into(),
+            mode_flag_offset: decoded.mode_flag_offset,
+            signatures_end_offset: decoded.signatures_end_offset,
+            data_offset: decoded.data_offset,
+            data_length: decoded.data_length,
+        }
+    }
+}
+
 pub(crate) struct Reader<'a> {
     bytes: &'a [u8],
     pub(crate) offset: usize,
@@ -218,9 +572,13 @@ impl<'a> Reader<'a> {
     }
 
     pub(crate) fn string(&mut self) -> WireResult<String> {
+        self.str().map(str::to_string)
+    }
+
+    pub(crate) fn str(&mut self) -> WireResult<&'a str> {
         let length = self.u32_le()? as usize;
         let bytes = self.take(length)?;
-        String::from_utf8(bytes.to_vec()).map_err(|_| "invalid utf8 in string".to_string())
+        std::str::from_utf8(bytes).map_err(|_| "invalid utf8 in string".to_string())
     }
 
     pub(crate) fn string_vec(&mut self) -> WireResult<Vec<String>> {
@@ -235,35 +593,52 @@ impl<'a> Reader<'a> {
         }
         Ok(out)
     }
+
+    /// [`Reader::string_vec`] without copying: validates the elements and
+    /// returns them as a view into the input.
+    pub(crate) fn wire_strings(&mut self) -> WireResult<WireStrings<'a>> {
+        let length = self.u32_le()? as usize;
+        if length > self.remaining() / 4 {
+            return Err("string vec length exceeds frame".to_string());
+        }
+        let start = self.offset;
+        for _ in 0..length {
+            self.str()?;
+        }
+        Ok(WireStrings {
+            bytes: &self.bytes[start..self.offset],
+            len: length,
+        })
+    }
 }
 
-fn read_delivery_mode(reader: &mut Reader) -> WireResult<DeliveryMode> {
+fn read_delivery_mode<'a>(reader: &mut Reader<'a>) -> WireResult<DeliveryModeRef<'a>> {
     let variant = reader.u8()?;
     match variant {
         0 => {
-            let to = reader.string_vec()?;
+            let to = reader.wire_strings()?;
             let redundancy = reader.u8()?;
-            Ok(DeliveryMode::Silent { to, redundancy })
+            Ok(DeliveryModeRef::Silent { to, redundancy })
         }
         1 => {
-            let to = reader.string_vec()?;
+            let to = reader.wire_strings()?;
             let redundancy = reader.u8()?;
-            let hops = reader.string_vec()?;
-            Ok(DeliveryMode::Acknowledge {
+            let hops = reader.wire_strings()?;
+            Ok(DeliveryModeRef::Acknowledge {
                 to,
                 redundancy,
                 hops,
             })
         }
         3 => {
-            let trace = reader.string_vec()?;
-            Ok(DeliveryMode::Traced { trace })
+            let trace = reader.wire_strings()?;
+            Ok(DeliveryModeRef::Traced { trace })
         }
-        4 => Ok(DeliveryMode::AnyWhere),
+        4 => Ok(DeliveryModeRef::AnyWhere),
         5 => {
             let redundancy = reader.u8()?;
-            let hops = reader.string_vec()?;
-            Ok(DeliveryMode::AcknowledgeAnyWhere { redundancy, hops })
+            let hops = reader.wire_strings()?;
+            Ok(DeliveryModeRef::AcknowledgeAnyWhere { redundancy, hops })
         }
         other => Err(format!("unsupported delivery mode variant: {other}")),
     }
@@ -282,43 +657,46 @@ fn read_public_sign_key(reader: &mut Reader) -> WireResult<PublicSignKey> {
     }
 }
 
-fn read_signature_with_key(reader: &mut Reader) -> WireResult<SignatureWithKey> {
+fn read_signature_with_key<'a>(reader: &mut Reader<'a>) -> WireResult<SignatureWithKeyRef<'a>> {
     let variant = reader.u8()?;
     if variant != 0 {
         return Err(format!("unsupported signature variant: {variant}"));
     }
     let signature_length = reader.u32_le()? as usize;
-    let signature = reader.take(signature_length)?.to_vec();
+    let signature = reader.take(signature_length)?;
     let public_key = read_public_sign_key(reader)?;
     let prehash = reader.u8()?;
-    Ok(SignatureWithKey {
+    Ok(SignatureWithKeyRef {
         signature,
         public_key,
         prehash,
     })
 }
 
-fn read_signatures(reader: &mut Reader) -> WireResult<Vec<SignatureWithKey>> {
+fn read_signatures<'a>(reader: &mut Reader<'a>) -> WireResult<SignaturesRef<'a>> {
     let variant = reader.u8()?;
     if variant != 0 {
         return Err(format!("unsupported signatures variant: {variant}"));
     }
     // SIGNATURES_SIZE_ENCODING = "u8"
     let length = reader.u8()? as usize;
-    let mut out = Vec::with_capacity(length);
+    let start = reader.offset;
     for _ in 0..length {
-        out.push(read_signature_with_key(reader)?);
+        read_signature_with_key(reader)?;
     }
-    Ok(out)
+    Ok(SignaturesRef {
+        bytes: &reader.bytes[start..reader.offset],
+        len: length,
+    })
 }
 
-struct HeaderWithOffsets {
-    header: MessageHeader,
+struct HeaderWithOffsets<'a> {
+    header: MessageHeaderRef<'a>,
     mode_flag_offset: usize,
     signatures_end_offset: usize,
 }
 
-fn read_header(reader: &mut Reader) -> WireResult<HeaderWithOffsets> {
+fn read_header<'a>(reader: &mut Reader<'a>) -> WireResult<HeaderWithOffsets<'a>> {
     let header_variant = reader.u8()?;
     if header_variant != 0 {
         return Err(format!(
@@ -346,9 +724,7 @@ fn read_header(reader: &mut Reader) -> WireResult<HeaderWithOffsets> {
                 "unsupported peer info variant: {peer_info_variant}"
             ));
         }
-        Some(MultiAddrInfo {
-            multiaddrs: reader.string_vec()?,
-        })
+        Some(reader.wire_strings()?)
     } else {
         None
     };
@@ -365,7 +741,7 @@ fn read_header(reader: &mut Reader) -> WireResult<HeaderWithOffsets> {
     };
     let signatures_end_offset = reader.offset;
     Ok(HeaderWithOffsets {
-        header: MessageHeader {
+        header: MessageHeaderRef {
             id,
             timestamp,
             session,
@@ -386,6 +762,12 @@ fn read_header(reader: &mut Reader) -> WireResult<HeaderWithOffsets> {
 /// technically tolerates trailing garbage after the payload, but such frames
 /// simply fall back to the TS path when native decode rejects them.
 pub fn decode_frame(bytes: &[u8]) -> WireResult<DecodedFrame> {
+    decode_frame_ref(bytes).map(DecodedFrame::from)
+}
+
+/// [`decode_frame`] without copying anything out of `bytes`; accepts and
+/// rejects exactly the same frames.
+pub fn decode_frame_ref(bytes: &[u8]) -> WireResult<DecodedFrameRef<'_>> {
     let mut reader = Reader::new(bytes);
     let variant = reader.u8()?;
     let HeaderWithOffsets {
@@ -401,28 +783,28 @@ pub fn decode_frame(bytes: &[u8]) -> WireResult<DecodedFrame> {
                 let length = reader.u32_le()? as usize;
                 data_offset = reader.offset;
                 data_length = length;
-                Some(reader.take(length)?.to_vec())
+                Some(reader.take(length)?)
             } else {
                 None
             };
-            WireMessage::Data { header, data }
+            WireMessageRef::Data { header, data }
         }
         VARIANT_ACK => {
             let message_id_to_acknowledge = reader.fixed_32()?;
             let seen_counter = reader.u8()?;
-            WireMessage::Ack {
+            WireMessageRef::Ack {
                 header,
                 message_id_to_acknowledge,
                 seen_counter,
             }
         }
-        VARIANT_HELLO => WireMessage::Hello {
+        VARIANT_HELLO => WireMessageRef::Hello {
             header,
-            joined: reader.string_vec()?,
+            joined: reader.wire_strings()?,
         },
-        VARIANT_GOODBYE => WireMessage::Goodbye {
+        VARIANT_GOODBYE => WireMessageRef::Goodbye {
             header,
-            leaving: reader.string_vec()?,
+            leaving: reader.wire_strings()?,
         },
         other => Err(format!("unsupported message variant: {other}"))?,
     };
@@ -432,7 +814,7 @@ pub fn decode_frame(bytes: &[u8]) -> WireResult<DecodedFrame> {
             reader.remaining()
         ));
     }
-    Ok(DecodedFrame {
+    Ok(DecodedFrameRef {
         message,
         mode_flag_offset,
         signatures_end_offset,
@@ -452,7 +834,24 @@ pub struct FrameDeliveryMeta {
     pub mode: Option<DeliveryMode>,
 }
 
+/// [`FrameDeliveryMeta`] borrowing the mode's peer lists from the frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameDeliveryMetaRef<'a> {
+    pub variant: u8,
+    pub id: [u8; ID_LENGTH],
+    pub mode: Option<DeliveryModeRef<'a>>,
+}
+
 pub fn decode_frame_delivery_meta(frame: &[u8]) -> WireResult<FrameDeliveryMeta> {
+    let meta = decode_frame_delivery_meta_ref(frame)?;
+    Ok(FrameDeliveryMeta {
+        variant: meta.variant,
+        id: meta.id,
+        mode: meta.mode.map(DeliveryMode::from),
+    })
+}
+
+pub fn decode_frame_delivery_meta_ref(frame: &[u8]) -> WireResult<FrameDeliveryMetaRef<'_>> {
     let mut reader = Reader::new(frame);
     let variant = reader.u8()?;
     let header_variant = reader.u8()?;
@@ -478,14 +877,14 @@ pub fn decode_frame_delivery_meta(frame: &[u8]) -> WireResult<FrameDeliveryMeta>
                 "unsupported peer info variant: {peer_info_variant}"
             ));
         }
-        reader.string_vec()?; // origin multiaddrs
+        reader.wire_strings()?; // origin multiaddrs
     }
     let mode = if reader.u8()? == 1 {
         Some(read_delivery_mode(&mut reader)?)
     } else {
         None
     };
-    Ok(FrameDeliveryMeta { variant, id, mode })
+    Ok(FrameDeliveryMetaRef { variant, id, mode })
 }
 
 pub(crate) struct Writer {
@@ -675,8 +1074,20 @@ pub fn encode_signable(message: &WireMessage) -> Vec<u8> {
 /// everything before the mode option flag, two zero option flags, then
 /// everything after the signatures field.
 pub fn signable_bytes_from_frame(frame: &[u8], decoded: &DecodedFrame) -> Vec<u8> {
-    let prefix = &frame[..decoded.mode_flag_offset];
-    let suffix = &frame[decoded.signatures_end_offset..];
+    signable_bytes_between(
+        frame,
+        decoded.mode_flag_offset,
+        decoded.signatures_end_offset,
+    )
+}
+
+fn signable_bytes_between(
+    frame: &[u8],
+    mode_flag_offset: usize,
+    signatures_end_offset: usize,
+) -> Vec<u8> {
+    let prefix = &frame[..mode_flag_offset];
+    let suffix = &frame[signatures_end_offset..];
     let mut out = Vec::with_capacity(prefix.len() + 2 + suffix.len());
     out.extend_from_slice(prefix);
     out.push(0);
@@ -686,8 +1097,12 @@ pub fn signable_bytes_from_frame(frame: &[u8], decoded: &DecodedFrame) -> Vec<u8
 }
 
 pub fn signable_bytes(frame: &[u8]) -> WireResult<Vec<u8>> {
-    let decoded = decode_frame(frame)?;
-    Ok(signable_bytes_from_frame(frame, &decoded))
+    let decoded = decode_frame_ref(frame)?;
+    Ok(signable_bytes_between(
+        frame,
+        decoded.mode_flag_offset,
+        decoded.signatures_end_offset,
+    ))
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -696,9 +1111,10 @@ pub enum VerifyStatus {
     /// semantics) or at least one signature failed verification.
     Failed = 0,
     Verified = 1,
-    /// Contains a signature scheme this crate does not verify natively
-    /// (secp256k1 key or a non sha256/none prehash); callers must fall back
-    /// to the TS verification path.
+    /// Contains a signature scheme this crate does not verify natively (an
+    /// unknown prehash, or a secp256k1 signature not in the
+    /// 65-byte `joinSignature` form); callers must fall back to the TS
+    /// verification path.
     Unsupported = 2,
 }
 
@@ -733,19 +1149,83 @@ impl FrameRecord {
 
 struct PendingSignature {
     frame_index: usize,
-    use_digest: bool,
-    signature: Signature,
-    key: VerifyingKey,
+    prehash: u8,
+    check: PendingCheck,
+}
+
+enum PendingCheck {
+    Ed25519 {
+        signature: Signature,
+        key: VerifyingKey,
+    },
+    Secp256k1 {
+        signature: Secp256k1Signature,
+        recovery_id: RecoveryId,
+        key: [u8; 33],
+    },
+}
+
+/// Parses the signature `@peerbit/crypto` stores for secp256k1 keys: the
+/// UTF-8 of ethers' `joinSignature`, `0x` followed by the hex of `r`, `s`
+/// and `v`. `Ok(None)` when it is not in that 65-byte form (ethers also
+/// splits the 64-byte EIP-2098 form; those stay on the TS path), `Err` when
+/// ethers would reject it. A high `s` is normalized with the recovery id
+/// flipped, which recovers the same key as `@noble/curves` does.
+fn parse_secp256k1_signature(bytes: &[u8]) -> Result<Option<(Secp256k1Signature, RecoveryId)>, ()> {
+    let Some(hex) = bytes.strip_prefix(b"0x") else {
+        return Ok(None);
+    };
+    if hex.len() != 130 {
+        return Ok(None);
+    }
+    let mut raw = [0u8; 65];
+    for (byte, pair) in raw.iter_mut().zip(hex.chunks_exact(2)) {
+        let digit = |c: u8| (c as char).to_digit(16).ok_or(());
+        *byte = (digit(pair[0])? * 16 + digit(pair[1])?) as u8;
+    }
+    // ethers `splitSignature`: a recovery param is accepted as `v`, and any
+    // `v >= 27` maps to `1 - v % 2`.
+    let v = match raw[64] {
+        0 | 1 => raw[64] + 27,
+        v if v >= 27 => v,
+        _ => return Err(()),
+    };
+    let is_y_odd = v % 2 == 0;
+    let signature = Secp256k1Signature::from_slice(&raw[..64]).map_err(|_| ())?;
+    Ok(Some(match signature.normalize_s() {
+        Some(normalized) => (normalized, RecoveryId::new(!is_y_odd, false)),
+        None => (signature, RecoveryId::new(is_y_odd, false)),
+    }))
+}
+
+/// `verifySignatureSecp256k1Prepared`: the prepared message must be a
+/// 32-byte digest, and the key recovered from it must be the signer's.
+fn verify_secp256k1(
+    message: &[u8],
+    signature: &Secp256k1Signature,
+    recovery_id: RecoveryId,
+    key: &[u8; 33],
+) -> bool {
+    if message.len() != 32 {
+        return false;
+    }
+    match Secp256k1VerifyingKey::recover_from_prehash(message, signature, recovery_id) {
+        Ok(recovered) => recovered.to_encoded_point(true).as_bytes() == key.as_slice(),
+        Err(_) => false,
+    }
 }
 
 struct FrameSignableContext {
     signable: Vec<u8>,
-    digest: Option<[u8; 32]>,
+    /// Indexed by prehash; computed once per frame for the prehashes its
+    /// signatures use.
+    digests: [Option<[u8; 32]>; PREHASH_ETH_KECCAK_256 as usize + 1],
 }
 
 /// Decode every frame and verify signatures with the exact scheme
-/// `@peerbit/crypto` uses for the direct-stream hot path: plain Ed25519 over
-/// the (optionally sha256-prehashed) signable bytes. Every signature is
+/// `@peerbit/crypto` uses for the direct-stream hot path: plain Ed25519, or
+/// secp256k1 ECDSA by public-key recovery, over the (optionally
+/// sha256-prehashed) signable bytes. Every signature is
 /// verified serially with `verify_strict` — `verify_batch` (and the
 /// non-strict `verify`) can accept weak-key/small-order signatures that the
 /// TS verifier (libsodium) rejects, so they would diverge in the accept
@@ -759,7 +1239,7 @@ pub fn decode_and_verify_frames(frames: &[&[u8]], now_ms: u64) -> Vec<FrameRecor
     let mut pending: Vec<PendingSignature> = Vec::new();
 
     for (frame_index, frame) in frames.iter().enumerate() {
-        let decoded = match decode_frame(frame) {
+        let decoded = match decode_frame_ref(frame) {
             Ok(decoded) => decoded,
             Err(_) => {
                 records.push(FrameRecord::decode_failed());
@@ -768,14 +1248,14 @@ pub fn decode_and_verify_frames(frames: &[&[u8]], now_ms: u64) -> Vec<FrameRecor
             }
         };
         let header = decoded.message.header();
-        let signatures = header.signatures.as_deref().unwrap_or(&[]);
+        let signatures = header.signatures.unwrap_or_default();
         let mut record = FrameRecord {
             decode_ok: true,
             variant: decoded.message.variant(),
             verify: VerifyStatus::Failed,
             signature_count: signatures.len().min(u8::MAX as usize) as u8,
             priority: header.priority,
-            has_data: matches!(&decoded.message, WireMessage::Data { data: Some(_), .. }),
+            has_data: matches!(&decoded.message, WireMessageRef::Data { data: Some(_), .. }),
             data_offset: decoded.data_offset as u32,
             data_length: decoded.data_length as u32,
         };
@@ -788,8 +1268,9 @@ pub fn decode_and_verify_frames(frames: &[&[u8]], now_ms: u64) -> Vec<FrameRecor
         }
 
         let unsupported = signatures.iter().any(|signature| {
-            !matches!(signature.public_key, PublicSignKey::Ed25519(_))
-                || (signature.prehash != PREHASH_NONE && signature.prehash != PREHASH_SHA_256)
+            !is_known_prehash(signature.prehash)
+                || (matches!(signature.public_key, PublicSignKey::Secp256k1(_))
+                    && matches!(parse_secp256k1_signature(signature.signature), Ok(None)))
         });
         if unsupported {
             record.verify = VerifyStatus::Unsupported;
@@ -798,41 +1279,62 @@ pub fn decode_and_verify_frames(frames: &[&[u8]], now_ms: u64) -> Vec<FrameRecor
             continue;
         }
 
-        let signable = signable_bytes_from_frame(frame, &decoded);
+        let signable = signable_bytes_between(
+            frame,
+            decoded.mode_flag_offset,
+            decoded.signatures_end_offset,
+        );
         let mut context = FrameSignableContext {
             signable,
-            digest: None,
+            digests: [None; PREHASH_ETH_KECCAK_256 as usize + 1],
         };
         let mut malformed = false;
         let mut frame_pending: Vec<PendingSignature> = Vec::with_capacity(signatures.len());
         for signature in signatures {
-            let key_bytes = match &signature.public_key {
-                PublicSignKey::Ed25519(key) => key,
-                PublicSignKey::Secp256k1(_) => unreachable!("filtered above"),
-            };
-            let signature_bytes: &[u8; 64] = match signature.signature.as_slice().try_into() {
-                Ok(bytes) => bytes,
-                Err(_) => {
-                    malformed = true;
-                    break;
+            let check = match &signature.public_key {
+                PublicSignKey::Ed25519(key_bytes) => {
+                    let signature_bytes: &[u8; 64] = match signature.signature.try_into() {
+                        Ok(bytes) => bytes,
+                        Err(_) => {
+                            malformed = true;
+                            break;
+                        }
+                    };
+                    let key = match VerifyingKey::from_bytes(key_bytes) {
+                        Ok(key) => key,
+                        Err(_) => {
+                            malformed = true;
+                            break;
+                        }
+                    };
+                    PendingCheck::Ed25519 {
+                        signature: Signature::from_bytes(signature_bytes),
+                        key,
+                    }
                 }
-            };
-            let key = match VerifyingKey::from_bytes(key_bytes) {
-                Ok(key) => key,
-                Err(_) => {
-                    malformed = true;
-                    break;
+                PublicSignKey::Secp256k1(key) => {
+                    match parse_secp256k1_signature(signature.signature) {
+                        Ok(Some((signature, recovery_id))) => PendingCheck::Secp256k1 {
+                            signature,
+                            recovery_id,
+                            key: *key,
+                        },
+                        Ok(None) => unreachable!("filtered above"),
+                        Err(()) => {
+                            malformed = true;
+                            break;
+                        }
+                    }
                 }
             };
-            let use_digest = signature.prehash == PREHASH_SHA_256;
-            if use_digest && context.digest.is_none() {
-                context.digest = Some(Sha256::digest(&context.signable).into());
+            let prehash = signature.prehash;
+            if context.digests[prehash as usize].is_none() {
+                context.digests[prehash as usize] = prehash_digest(prehash, &context.signable);
             }
             frame_pending.push(PendingSignature {
                 frame_index,
-                use_digest,
-                signature: Signature::from_bytes(signature_bytes),
-                key,
+                prehash,
+                check,
             });
         }
         if malformed {
@@ -862,17 +1364,21 @@ pub fn decode_and_verify_frames(frames: &[&[u8]], now_ms: u64) -> Vec<FrameRecor
     // accept is memoized as `_verified = true`, suppressing the authoritative
     // TS re-check, so the accept sets must be identical. `verify_strict`
     // performs both the scalar-canonicality and small-order malleability
-    // checks libsodium does.
+    // checks libsodium does. Secp256k1 signatures are checked the way
+    // `verifySignatureSecp256k1Prepared` does, by recovering the signer.
     for entry in &pending {
-        let context = contexts[entry.frame_index]
-            .as_ref()
-            .expect("pending signature without context");
-        let message = if entry.use_digest {
-            context.digest.as_ref().expect("missing digest").as_slice()
-        } else {
-            context.signable.as_slice()
+        let message = pending_message(&contexts, entry);
+        let verified = match &entry.check {
+            PendingCheck::Ed25519 { signature, key } => {
+                key.verify_strict(message, signature).is_ok()
+            }
+            PendingCheck::Secp256k1 {
+                signature,
+                recovery_id,
+                key,
+            } => verify_secp256k1(message, signature, *recovery_id, key),
         };
-        if entry.key.verify_strict(message, &entry.signature).is_err() {
+        if !verified {
             records[entry.frame_index].verify = VerifyStatus::Failed;
         }
     }
@@ -880,6 +1386,24 @@ pub fn decode_and_verify_frames(frames: &[&[u8]], now_ms: u64) -> Vec<FrameRecor
     records
 }
 
+/// The bytes `entry` signs: the frame's signable bytes or their digest.
+fn pending_message<'a>(
+    contexts: &'a [Option<FrameSignableContext>],
+    entry: &PendingSignature,
+) -> &'a [u8] {
+    let context = contexts[entry.frame_index]
+        .as_ref()
+        .expect("pending signature without context");
+    if entry.prehash == PREHASH_NONE {
+        context.signable.as_slice()
+    } else {
+        context.digests[entry.prehash as usize]
+            .as_ref()
+            .expect("missing digest")
+            .as_slice()
+    }
+}
+
 // --- Debug JSON (parity-test surface; hand-rolled to avoid serde) ----------
 
 pub(crate) fn push_json_string(out: &mut String, value: &str) {
@@ -1090,13 +1614,10 @@ fn sign_corpus_message(message: &mut WireMessage, signers: &[(&SigningKey, u8)])
     let signable = encode_signable(message);
     let mut signatures = Vec::with_capacity(signers.len());
     for (key, prehash) in signers {
-        let signature = match *prehash {
-            PREHASH_SHA_256 => {
-                let digest: [u8; 32] = Sha256::digest(&signable).into();
-                key.sign(&digest)
-            }
-            PREHASH_NONE => key.sign(&signable),
-            other => panic!("unsupported corpus prehash {other}"),
+        let signature = match prehash_digest(*prehash, &signable) {
+            Some(digest) => key.sign(&digest),
+            None if *prehash == PREHASH_NONE => key.sign(&signable),
+            None => panic!("unsupported corpus prehash {prehash}"),
         };
         signatures.push(SignatureWithKey {
             signature: signature.to_bytes().to_vec(),
@@ -1107,6 +1628,42 @@ fn sign_corpus_message(message: &mut WireMessage, signers: &[(&SigningKey, u8)])
     message.header_mut().signatures = Some(signatures);
 }
 
+fn corpus_secp256k1_key(seed_byte: u8) -> Secp256k1SigningKey {
+    Secp256k1SigningKey::from_slice(&[seed_byte; 32]).expect("valid secp256k1 scalar")
+}
+
+/// Appends a secp256k1 signature over the sha256 digest of the signable
+/// bytes, encoded as `Secp256k1Keypair.sign` does.
+fn sign_corpus_message_secp256k1(message: &mut WireMessage, key: &Secp256k1SigningKey) {
+    let digest: [u8; 32] = Sha256::digest(encode_signable(message)).into();
+    let (signature, recovery_id) = key
+        .sign_prehash_recoverable(&digest)
+        .expect("corpus secp256k1 signature");
+    let mut joined = String::from("0x");
+    for byte in signature
+        .to_bytes()
+        .iter()
+        .chain([&(27 + recovery_id.to_byte())])
+    {
+        joined.push_str(&format!("{byte:02x}"));
+    }
+    let public_key: [u8; 33] = key
+        .verifying_key()
+        .to_encoded_point(true)
+        .as_bytes()
+        .try_into()
+        .expect("compressed secp256k1 key");
+    message
+        .header_mut()
+        .signatures
+        .get_or_insert_with(Vec::new)
+        .push(SignatureWithKey {
+            signature: joined.into_bytes(),
+            public_key: PublicSignKey::Secp256k1(public_key),
+            prehash: PREHASH_SHA_256,
+        });
+}
+
 /// Deterministic corpus of Rust-authored frames used for the reverse
 /// (Rust encode → TS decode) golden-vector direction. The TS parity suite
 /// mirrors the expected semantics per index; keep both in sync.
@@ -1238,12 +1795,43 @@ pub fn build_test_corpus() -> Vec<Vec<u8>> {
     sign_corpus_message(&mut message, &[(&key_b, PREHASH_SHA_256)]);
     frames.push(encode_frame(&message));
 
+    // 7: DataMessage, SilentDelivery, one secp256k1 sha256 signature.
+    let mut message = WireMessage::Data {
+        header: corpus_header(
+            7,
+            Some(DeliveryMode::Silent {
+                to: vec![hash_b.clone()],
+                redundancy: 1,
+            }),
+            Some(0),
+            None,
+            None,
+        ),
+        data: Some(vec![7, 7, 7]),
+    };
+    sign_corpus_message_secp256k1(&mut message, &corpus_secp256k1_key(44));
+    frames.push(encode_frame(&message));
+
+    // 8: Hello signed by an ed25519 key then a secp256k1 key.
+    let mut message = WireMessage::Hello {
+        header: corpus_header(8, Some(DeliveryMode::AnyWhere), Some(1), None, None),
+        joined: vec![hash_a.clone(), hash_b.clone()],
+    };
+    sign_corpus_message(&mut message, &[(&key_a, PREHASH_SHA_256)]);
+    sign_corpus_message_secp256k1(&mut message, &corpus_secp256k1_key(45));
+    frames.push(encode_frame(&message));
+
     frames
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use curve25519_dalek::constants::EIGHT_TORSION;
+    use curve25519_dalek::scalar::Scalar;
+    use curve25519_dalek::EdwardsPoint;
+    use k256::elliptic_curve::PrimeField;
+    use sha2::Sha512;
 
     const CORPUS_VERIFY_NOW_MS: u64 = 1_700_000_000_500;
 
@@ -1420,25 +2008,333 @@ mod tests {
         assert_eq!(records[0].verify, VerifyStatus::Failed);
     }
 
+    /// The Ed25519 group order l, little-endian.
+    const ED25519_GROUP_ORDER: [u8; 32] = [
+        0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde,
+        0x14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10,
+    ];
+
+    /// Signs `message` for the key `secret * B + key_torsion` with the
+    /// nonce point `nonce * B + nonce_torsion`; returns the key and signature.
+    fn raw_ed25519_signature(
+        secret: Scalar,
+        key_torsion: EdwardsPoint,
+        nonce: Scalar,
+        nonce_torsion: EdwardsPoint,
+        message: &[u8],
+    ) -> ([u8; 32], [u8; 64]) {
+        let key = (EdwardsPoint::mul_base(&secret) + key_torsion).compress();
+        let r = (EdwardsPoint::mul_base(&nonce) + nonce_torsion).compress();
+        let k = Scalar::from_hash(
+            Sha512::new()
+                .chain_update(r.as_bytes())
+                .chain_update(key.as_bytes())
+                .chain_update(message),
+        );
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(r.as_bytes());
+        signature[32..].copy_from_slice((nonce + k * secret).as_bytes());
+        (key.to_bytes(), signature)
+    }
+
+    /// A signed Data frame whose single signature `sign` computes from the
+    /// signable bytes.
+    fn frame_signed_raw(
+        tag: u8,
+        payload: &[u8],
+        sign: impl FnOnce(&[u8]) -> ([u8; 32], [u8; 64]),
+    ) -> Vec<u8> {
+        let mut message = WireMessage::Data {
+            header: corpus_header(tag, Some(DeliveryMode::AnyWhere), Some(0), None, None),
+            data: Some(payload.to_vec()),
+        };
+        let unsigned = encode_frame(&message);
+        let signable = signable_bytes_from_frame(&unsigned, &decode_frame(&unsigned).unwrap());
+        let (key, signature) = sign(&signable);
+        message.header_mut().signatures = Some(vec![SignatureWithKey {
+            signature: signature.to_vec(),
+            public_key: PublicSignKey::Ed25519(key),
+            prehash: PREHASH_NONE,
+        }]);
+        encode_frame(&message)
+    }
+
+    fn verify_statuses(frames: &[Vec<u8>]) -> Vec<VerifyStatus> {
+        let slices: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        decode_and_verify_frames(&slices, CORPUS_VERIFY_NOW_MS)
+            .iter()
+            .map(|record| record.verify)
+            .collect()
+    }
+
+    #[test]
+    fn crafted_ed25519_signatures_get_the_libsodium_verdicts() {
+        let secret = Scalar::from(0x5eed_u64);
+        let torsion = EIGHT_TORSION[1];
+        let identity = EdwardsPoint::default();
+        let mut honest = build_test_corpus();
+        for index in 0..16u8 {
+            let key = corpus_signing_key(index % 4 + 1);
+            let mut message = WireMessage::Data {
+                header: corpus_header(index, Some(DeliveryMode::AnyWhere), None, None, None),
+                data: Some(vec![index; index as usize + 1]),
+            };
+            sign_corpus_message(&mut message, &[(&key, PREHASH_NONE)]);
+            honest.push(encode_frame(&message));
+        }
+
+        let mut crafted = Vec::new();
+        // Signed by the holder of `secret`, but over another payload.
+        let mut tampered = frame_signed_raw(1, b"signed", |signable| {
+            raw_ed25519_signature(secret, identity, Scalar::from(7u64), identity, signable)
+        });
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        crafted.push(tampered);
+        // Small-order R.
+        crafted.push(frame_signed_raw(2, b"small R", |signable| {
+            raw_ed25519_signature(secret, identity, Scalar::ZERO, torsion, signable)
+        }));
+        // Non-canonical s: s + l still satisfies the equation mod l.
+        crafted.push(frame_signed_raw(3, b"s + l", |signable| {
+            let (key, mut signature) =
+                raw_ed25519_signature(secret, identity, Scalar::from(9u64), identity, signable);
+            let mut carry = 0u16;
+            for (byte, order_byte) in signature[32..].iter_mut().zip(&ED25519_GROUP_ORDER) {
+                let sum = *byte as u16 + *order_byte as u16 + carry;
+                *byte = sum as u8;
+                carry = sum >> 8;
+            }
+            (key, signature)
+        }));
+        // An R with an order-8 component: `verify_strict` always rejects it,
+        // but about one payload in eight makes the batch equation accept.
+        let torsion_r = (0..=u8::MAX)
+            .map(|attempt| {
+                frame_signed_raw(4, &[attempt], |signable| {
+                    raw_ed25519_signature(secret, identity, Scalar::from(11u64), torsion, signable)
+                })
+            })
+            .find(|frame| {
+                let decoded = decode_frame(frame).unwrap();
+                let signature = &decoded.message.header().signatures.as_ref().unwrap()[0];
+                let PublicSignKey::Ed25519(key) = &signature.public_key else {
+                    unreachable!()
+                };
+                let key = VerifyingKey::from_bytes(key).unwrap();
+                let signature = Signature::from_slice(&signature.signature).unwrap();
+                let signable = signable_bytes_from_frame(frame, &decoded);
+                assert!(key.verify_strict(&signable, &signature).is_err());
+                verify_batch(&[signable.as_slice()], &[signature], &[key]).is_ok()
+            })
+            .expect("some payload lets the batch equation accept a torsioned R");
+        crafted.push(torsion_r);
+        // A key with an order-8 component verifies under `verify_strict`
+        // only when 8 divides the challenge; keep one frame of each kind.
+        let mut mixed_key = [None, None];
+        for attempt in 0..=u8::MAX {
+            let frame = frame_signed_raw(5, &[attempt], |signable| {
+                raw_ed25519_signature(secret, torsion, Scalar::from(13u64), identity, signable)
+            });
+            let verified =
+                verify_statuses(std::slice::from_ref(&frame))[0] == VerifyStatus::Verified;
+            mixed_key[verified as usize].get_or_insert(frame);
+            if mixed_key.iter().all(Option::is_some) {
+                break;
+            }
+        }
+        let [Some(mixed_key_rejected), Some(mixed_key_verified)] = mixed_key else {
+            panic!("expected both outcomes for the mixed-order key");
+        };
+        crafted.push(mixed_key_rejected);
+        crafted.push(mixed_key_verified);
+
+        let everything: Vec<Vec<u8>> = honest.iter().chain(&crafted).cloned().collect();
+        let statuses = verify_statuses(&everything);
+        assert!(statuses[honest.len() - 16..honest.len()]
+            .iter()
+            .all(|status| *status == VerifyStatus::Verified));
+        assert_eq!(
+            &statuses[honest.len()..],
+            &[
+                VerifyStatus::Failed,
+                VerifyStatus::Failed,
+                VerifyStatus::Failed,
+                VerifyStatus::Failed,
+                VerifyStatus::Failed,
+                VerifyStatus::Verified,
+            ]
+        );
+        for (frame, status) in crafted.iter().zip(&statuses[honest.len()..]) {
+            assert_eq!(verify_statuses(std::slice::from_ref(frame)), vec![*status]);
+        }
+    }
+
+    #[test]
+    fn secp256k1_signatures_verify_natively() {
+        let frames = build_test_corpus();
+        let signed = decode_frame(&frames[8]).unwrap();
+        let signatures = signed.message.header().signatures.clone().unwrap();
+        assert!(matches!(
+            signatures[1].public_key,
+            PublicSignKey::Secp256k1(_)
+        ));
+
+        let with_signature = |signature: Vec<u8>| {
+            let mut message = signed.message.clone();
+            message.header_mut().signatures.as_mut().unwrap()[1].signature = signature;
+            encode_frame(&message)
+        };
+        let joined = String::from_utf8(signatures[1].signature.clone()).unwrap();
+        // Flip the recovery param: another key is recovered.
+        let v = if joined.ends_with("1b") { "1c" } else { "1b" };
+        let wrong_recovery = format!("{}{v}", &joined[..joined.len() - 2]);
+        // `v` as a bare recovery param, as ethers accepts it.
+        let bare_v = format!(
+            "{}0{}",
+            &joined[..joined.len() - 2],
+            if joined.ends_with("1b") { 0 } else { 1 }
+        );
+        // The high-s twin of the signature recovers the same key.
+        let (r, s) = joined[2..130].split_at(64);
+        let s = k256::Scalar::from_repr(
+            <[u8; 32]>::try_from(hex_bytes(s).as_slice())
+                .unwrap()
+                .into(),
+        )
+        .unwrap();
+        let high_s: [u8; 32] = (-s).to_bytes().into();
+        let mut high_s_hex = String::new();
+        for byte in high_s {
+            high_s_hex.push_str(&format!("{byte:02x}"));
+        }
+        let v = if joined.ends_with("1b") { "1c" } else { "1b" };
+        let high_s_joined = format!("0x{r}{high_s_hex}{v}");
+
+        let cases: Vec<(Vec<u8>, VerifyStatus)> = vec![
+            (joined.clone().into_bytes(), VerifyStatus::Verified),
+            (bare_v.into_bytes(), VerifyStatus::Verified),
+            (high_s_joined.into_bytes(), VerifyStatus::Verified),
+            (wrong_recovery.into_bytes(), VerifyStatus::Failed),
+            (
+                format!("{}zz", &joined[..joined.len() - 2]).into_bytes(),
+                VerifyStatus::Failed,
+            ),
+            (
+                format!("{}05", &joined[..joined.len() - 2]).into_bytes(),
+                VerifyStatus::Failed,
+            ),
+            (vec![0u8; 64], VerifyStatus::Unsupported),
+        ];
+        for (signature, expected) in cases {
+            let frame = with_signature(signature);
+            let records = decode_and_verify_frames(&[frame.as_slice()], CORPUS_VERIFY_NOW_MS);
+            assert_eq!(records[0].verify, expected);
+            assert_eq!(records[0].signature_count, 2);
+        }
+
+        // Without a prehash the signable bytes are not a 32-byte digest.
+        let mut message = signed.message.clone();
+        message.header_mut().signatures.as_mut().unwrap()[1].prehash = PREHASH_NONE;
+        let frame = encode_frame(&message);
+        let records = decode_and_verify_frames(&[frame.as_slice()], CORPUS_VERIFY_NOW_MS);
+        assert_eq!(records[0].verify, VerifyStatus::Failed);
+    }
+
+    fn hex_bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Input lengths (bytes `i % 251`) and BLAKE3 hashes from the official
+    /// BLAKE3 test vectors. The `PreHash.BLAKE3` spec in packages/utils/crypto
+    /// checks `@noble/hashes` against the same pairs.
+    const BLAKE3_VECTORS: [(usize, &str); 3] = [
+        (
+            0,
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+        ),
+        (
+            1,
+            "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213",
+        ),
+        (
+            1025,
+            "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444",
+        ),
+    ];
+
     #[test]
-    fn secp256k1_signature_reports_unsupported() {
+    fn keccak_and_blake3_prehashes_verify_natively() {
+        // ethers `hashMessage("Hello World")`
+        assert_eq!(
+            prehash_digest(PREHASH_ETH_KECCAK_256, b"Hello World")
+                .unwrap()
+                .to_vec(),
+            hex_bytes("a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2")
+        );
+        for (len, digest) in BLAKE3_VECTORS {
+            let input: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            assert_eq!(
+                prehash_digest(PREHASH_BLAKE3, &input).unwrap().to_vec(),
+                hex_bytes(digest)
+            );
+        }
         let key = corpus_signing_key(1);
+        for prehash in [PREHASH_BLAKE3, PREHASH_ETH_KECCAK_256] {
+            let mut message = WireMessage::Data {
+                header: corpus_header(
+                    10 + prehash,
+                    Some(DeliveryMode::AnyWhere),
+                    Some(0),
+                    None,
+                    None,
+                ),
+                data: Some(vec![prehash; 5]),
+            };
+            sign_corpus_message(&mut message, &[(&key, prehash), (&key, PREHASH_SHA_256)]);
+            let frame = encode_frame(&message);
+            let records = decode_and_verify_frames(&[frame.as_slice()], CORPUS_VERIFY_NOW_MS);
+            assert_eq!(records[0].verify, VerifyStatus::Verified);
+
+            // A signature over the digest of the other scheme fails.
+            let other = if prehash == PREHASH_BLAKE3 {
+                PREHASH_ETH_KECCAK_256
+            } else {
+                PREHASH_BLAKE3
+            };
+            message.header_mut().signatures.as_mut().unwrap()[0].prehash = other;
+            let frame = encode_frame(&message);
+            let records = decode_and_verify_frames(&[frame.as_slice()], CORPUS_VERIFY_NOW_MS);
+            assert_eq!(records[0].verify, VerifyStatus::Failed);
+        }
+
         let mut message = WireMessage::Data {
-            header: corpus_header(7, Some(DeliveryMode::AnyWhere), Some(0), None, None),
-            data: Some(vec![1]),
+            header: corpus_header(14, Some(DeliveryMode::AnyWhere), Some(0), None, None),
+            data: Some(vec![4]),
         };
-        sign_corpus_message(&mut message, &[(&key, PREHASH_SHA_256)]);
-        if let Some(signatures) = &mut message.header_mut().signatures {
-            signatures.push(SignatureWithKey {
-                signature: vec![0u8; 64],
-                public_key: PublicSignKey::Secp256k1([2u8; 33]),
-                prehash: PREHASH_SHA_256,
-            });
+        sign_corpus_message_secp256k1(&mut message, &corpus_secp256k1_key(46));
+        let signable = encode_signable(&message);
+        let digest = prehash_digest(PREHASH_ETH_KECCAK_256, &signable).unwrap();
+        let (signature, recovery_id) = corpus_secp256k1_key(46)
+            .sign_prehash_recoverable(&digest)
+            .unwrap();
+        let mut joined = String::from("0x");
+        for byte in signature
+            .to_bytes()
+            .iter()
+            .chain([&(27 + recovery_id.to_byte())])
+        {
+            joined.push_str(&format!("{byte:02x}"));
         }
+        let signatures = message.header_mut().signatures.as_mut().unwrap();
+        signatures[0].signature = joined.into_bytes();
+        signatures[0].prehash = PREHASH_ETH_KECCAK_256;
         let frame = encode_frame(&message);
         let records = decode_and_verify_frames(&[frame.as_slice()], CORPUS_VERIFY_NOW_MS);
-        assert_eq!(records[0].verify, VerifyStatus::Unsupported);
-        assert_eq!(records[0].signature_count, 2);
+        assert_eq!(records[0].verify, VerifyStatus::Verified);
     }
 
     #[test]
@@ -1450,7 +2346,7 @@ mod tests {
         };
         sign_corpus_message(&mut message, &[(&key, PREHASH_SHA_256)]);
         if let Some(signatures) = &mut message.header_mut().signatures {
-            signatures[0].prehash = 3; // ETH_KECCAK_256
+            signatures[0].prehash = PREHASH_ETH_KECCAK_256 + 1;
         }
         let frame = encode_frame(&message);
         let records = decode_and_verify_frames(&[frame.as_slice()], CORPUS_VERIFY_NOW_MS);
@@ -1485,6 +2381,90 @@ mod tests {
         }
     }
 
+    fn points_into(frame: &[u8], slice: &[u8]) -> bool {
+        let range = frame.as_ptr_range();
+        range.start <= slice.as_ptr() && slice.as_ptr_range().end <= range.end
+    }
+
+    #[test]
+    fn borrowed_decode_matches_owned_and_borrows_the_frame() {
+        for (index, frame) in build_test_corpus().iter().enumerate() {
+            let borrowed = decode_frame_ref(frame).unwrap();
+            assert_eq!(
+                DecodedFrame::from(borrowed),
+                decode_frame(frame).unwrap(),
+                "corpus frame {index}"
+            );
+            let header = borrowed.message.header();
+            let mut strings: Vec<&str> = Vec::new();
+            if let Some(origin) = header.origin {
+                strings.extend(origin);
+            }
+            match header.mode {
+                Some(DeliveryModeRef::Silent { to, .. }) => strings.extend(to),
+                Some(DeliveryModeRef::Acknowledge { to, hops, .. }) => {
+                    strings.extend(to);
+                    strings.extend(hops);
+                }
+                Some(DeliveryModeRef::Traced { trace }) => strings.extend(trace),
+                Some(DeliveryModeRef::AcknowledgeAnyWhere { hops, .. }) => strings.extend(hops),
+                Some(DeliveryModeRef::AnyWhere) | None => {}
+            }
+            match borrowed.message {
+                WireMessageRef::Data {
+                    data: Some(data), ..
+                } => {
+                    assert_eq!(
+                        data.as_ptr(),
+                        frame[borrowed.data_offset..].as_ptr(),
+                        "corpus frame {index}"
+                    );
+                }
+                WireMessageRef::Hello { joined, .. } => strings.extend(joined),
+                WireMessageRef::Goodbye { leaving, .. } => strings.extend(leaving),
+                _ => {}
+            }
+            for string in strings {
+                assert!(
+                    points_into(frame, string.as_bytes()),
+                    "corpus frame {index}"
+                );
+            }
+            for signature in header.signatures.unwrap_or_default() {
+                assert!(
+                    points_into(frame, signature.signature),
+                    "corpus frame {index}"
+                );
+            }
+            let meta = decode_frame_delivery_meta_ref(frame).unwrap();
+            assert_eq!(meta.variant, borrowed.message.variant());
+            assert_eq!(meta.id, header.id);
+            assert_eq!(meta.mode, header.mode);
+        }
+    }
+
+    #[test]
+    fn borrowed_decode_rejects_what_the_owned_decode_rejects() {
+        let frame = &build_test_corpus()[0];
+        for length in 0..frame.len() {
+            assert!(decode_frame_ref(&frame[..length]).is_err());
+        }
+
+        let message = WireMessage::Hello {
+            header: corpus_header(9, None, None, None, None),
+            joined: vec!["peer-x".to_string()],
+        };
+        let mut frame = encode_frame(&message);
+        assert!(decode_frame_ref(&frame).is_ok());
+        let position = frame
+            .windows(6)
+            .position(|window| window == b"peer-x")
+            .unwrap();
+        frame[position] = 0xff;
+        assert!(decode_frame(&frame).is_err());
+        assert!(decode_frame_ref(&frame).is_err());
+    }
+
     #[test]
     fn record_reports_payload_range() {
         let frames = build_test_corpus();
@@ -311,7 +311,7 @@ describe("wire parity", () => {
 		});
 
 		entries.push({
-			name: "secp256k1 signature is unsupported natively",
+			name: "secp256k1 sha256 signature verifies natively",
 			message: await new DataMessage({
 				header: new MessageHeader({
 					session,
@@ -320,11 +320,11 @@ describe("wire parity", () => {
 				data: new Uint8Array([1, 2]),
 			}).sign((bytes) => secpKeypair.sign(bytes, PreHash.SHA_256)),
 			expectVerified: true,
-			expectNativeStatus: NativeWireVerifyStatus.UNSUPPORTED,
+			expectNativeStatus: NativeWireVerifyStatus.VERIFIED,
 		});
 
 		entries.push({
-			name: "keccak prehash is unsupported natively",
+			name: "keccak prehash verifies natively",
 			message: await new DataMessage({
 				header: new MessageHeader({
 					session,
@@ -333,7 +333,7 @@ describe("wire parity", () => {
 				data: new Uint8Array([3, 4]),
 			}).sign((bytes) => keypairA.sign(bytes, PreHash.ETH_KECCAK_256)),
 			expectVerified: true,
-			expectNativeStatus: NativeWireVerifyStatus.UNSUPPORTED,
+			expectNativeStatus: NativeWireVerifyStatus.VERIFIED,
 		});
 
 		corpus = entries.map((entry) => ({
@@ -524,7 +524,7 @@ describe("wire parity", () => {
 	describe("rust to ts", () => {
 		it("ts decodes, re-encodes and verifies the rust-authored corpus", async () => {
 			const frames = wire.testCorpusFrames();
-			expect(frames.length).to.equal(7);
+			expect(frames.length).to.equal(9);
 			for (let i = 0; i < frames.length; i++) {
 				const frame = frames[i];
 				const message = decodeTs(frame);
@@ -590,6 +590,16 @@ describe("wire parity", () => {
 			expect(big.header.mode).to.be.instanceOf(AcknowledgeAnyWhere);
 			expect(big.data!.length).to.equal(4096);
 			expect(big.data![4095]).to.equal(4095 % 251);
+
+			const secp = decodeTs(frames[7]) as DataMessage;
+			const [secpSignature] = secp.header.signatures!.signatures;
+			expect(secpSignature.publicKey).to.be.instanceOf(Secp256k1PublicKey);
+			expect(secpSignature.prehash).to.equal(PreHash.SHA_256);
+
+			const mixed = decodeTs(frames[8]) as Hello;
+			expect(
+				mixed.header.signatures!.signatures.map((s) => s.publicKey.constructor),
+			).to.deep.equal([Ed25519PublicKey, Secp256k1PublicKey]);
 		});
 	});
 });
@@ -16,6 +16,76 @@ type NativeAnyStore = {
 	len(): number;
 	size(): number;
 	entries(): Array<[string, Uint8Array]>;
+	range(
+		start: string | undefined,
+		end: string | undefined,
+		limit: number,
+		reverse: boolean,
+	): [Array<[string, Uint8Array]>, string | undefined];
+	prefix(
+		prefix: string,
+		limit: number,
+		start?: string,
+	): [Array<[string, Uint8Array]>, string | undefined];
+	configure_level(
+		level: string,
+		quotaBytes: number | undefined,
+		evict: boolean,
+	): void;
+	sublevels(): string[];
+	level_get(level: string, key: string): Uint8Array | undefined;
+	/** Applies the write and returns its journal records, evictions included. */
+	level_put(
+		level: string,
+		key: string,
+		value: Uint8Array,
+		expiresAt?: number,
+	): Uint8Array;
+	level_delete(level: string, key: string): Uint8Array;
+	level_clear(level: string): Uint8Array;
+	level_len(level: string): number;
+	level_size(level: string): number;
+	level_entries(level: string): Array<[string, Uint8Array]>;
+	expires_at(level: string, key: string): number | undefined;
+	/** Deletes expired keys and returns their delete journal records. */
+	sweep(now: number): Uint8Array;
+	/** Applies all writes or throws; returns one journal record for them. */
+	apply_batch(
+		level: string,
+		keys: string[],
+		values: Array<Uint8Array | undefined>,
+		expected: Array<Uint8Array | { hash: Uint8Array } | null | undefined>,
+	): Uint8Array;
+	value_hash(level: string, key: string): Uint8Array | undefined;
+};
+
+export type RustAnyStoreBatchWrite = {
+	key: string;
+	/** Omitted to delete the key. */
+	value?: Uint8Array;
+	/**
+	 * Precondition on the key's current value: `null` when it must be absent,
+	 * otherwise its bytes or `{ hash }` with its `valueHash`.
+	 */
+	expected?: Uint8Array | { hash: Uint8Array } | null;
+};
+
+export type RustAnyStorePage = {
+	entries: Array<[string, Uint8Array]>;
+	/**
+	 * Set while more keys remain: pass it as `start` to continue a forward
+	 * scan, or as `end` to continue a reverse one.
+	 */
+	next?: string;
+};
+
+export type RustAnyStoreRangeOptions = {
+	/** Inclusive lower bound. */
+	start?: string;
+	/** Exclusive upper bound. */
+	end?: string;
+	limit?: number;
+	reverse?: boolean;
 };
 
 type JournaledNativeAnyStore = NativeAnyStore & {
@@ -94,6 +164,12 @@ const loadWasm = async (): Promise<WasmModule> => {
 
 const copyBytes = (bytes: Uint8Array): Uint8Array => new Uint8Array(bytes);
 
+// usize on the native side.
+const normalizePageLimit = (limit?: number): number =>
+	limit == null || !Number.isFinite(limit)
+		? 0xffffffff
+		: Math.max(0, Math.floor(limit));
+
 export class RustAnyStore implements AnyStore {
 	private native?: NativeAnyStore;
 	private persistence?: RustAnyStorePersistenceBackend;
@@ -415,6 +491,86 @@ export class RustAnyStore implements AnyStore {
 		return native.has_many(keys);
 	}
 
+	/**
+	 * Write `key` so that the next `sweep()` at or after `ttl` milliseconds
+	 * from now deletes it. Expired keys stay readable until swept.
+	 */
+	putExpiring(key: string, value: Uint8Array, ttl: number): Promise<void> {
+		const bytes = copyBytes(value);
+		const expiresAt = Date.now() + Math.max(0, ttl);
+		return this.enqueueMutation(async (native) => {
+			const records = native.level_put("", key, bytes, expiresAt);
+			if (this.directory) {
+				await this.recordJournal(records);
+			}
+		});
+	}
+
+	/**
+	 * Apply every write or none of them. Rejects without changing the store
+	 * when a precondition fails; recovery never observes part of a batch.
+	 */
+	batch(writes: RustAnyStoreBatchWrite[]): Promise<void> {
+		const keys = writes.map((write) => write.key);
+		const values = writes.map((write) =>
+			write.value ? copyBytes(write.value) : undefined,
+		);
+		const expected = writes.map((write) => write.expected);
+		return this.enqueueMutation(async (native) => {
+			const record = native.apply_batch("", keys, values, expected);
+			if (this.directory) {
+				await this.recordJournal(record);
+			}
+		});
+	}
+
+	/** The SHA-256 digest a batch `expected` hash is compared against. */
+	async valueHash(key: string): Promise<Uint8Array | undefined> {
+		const native = await this.ensureOpen();
+		return native.value_hash("", key) ?? undefined;
+	}
+
+	/** Delete every key whose TTL has passed, journaling the deletes. */
+	sweep(now: number = Date.now()): Promise<void> {
+		return this.enqueueMutation(async (native) => {
+			const records = native.sweep(now);
+			if (this.directory && records.byteLength > 0) {
+				await this.recordJournal(records);
+			}
+		});
+	}
+
+	/**
+	 * One page of entries with `start <= key < end`, in key order (descending
+	 * when `reverse`). Without a `limit` the page holds every matching entry.
+	 */
+	async range(
+		options: RustAnyStoreRangeOptions = {},
+	): Promise<RustAnyStorePage> {
+		const native = await this.ensureOpen();
+		const [entries, next] = native.range(
+			options.start,
+			options.end,
+			normalizePageLimit(options.limit),
+			options.reverse ?? false,
+		);
+		return { entries, next };
+	}
+
+	/** One page of entries whose key starts with `prefix`, in key order. */
+	async prefix(
+		prefix: string,
+		options: { start?: string; limit?: number } = {},
+	): Promise<RustAnyStorePage> {
+		const native = await this.ensureOpen();
+		const [entries, next] = native.prefix(
+			prefix,
+			normalizePageLimit(options.limit),
+			options.start,
+		);
+		return { entries, next };
+	}
+
 	async sublevel(
 		name: string,
 		options?: RustAnyStoreOptions,
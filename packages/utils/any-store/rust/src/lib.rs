@@ -1,9 +1,15 @@
-use indexmap::IndexMap;
-use js_sys::{Array, Uint8Array};
+use js_sys::{Array, Reflect, Uint8Array};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Bound;
 use wasm_bindgen::prelude::*;
 
 const SNAPSHOT_MAGIC: &[u8; 8] = b"PBAKVS1\0";
+const LEVELS_SNAPSHOT_MAGIC: &[u8; 8] = b"PBAKVS2\0";
 const JOURNAL_MAGIC: &[u8; 8] = b"PBAKVJ1\0";
+/// Set on the operation byte of records written to a named sublevel; the
+/// sublevel name follows it.
+const LEVEL_RECORD_FLAG: u8 = 0x80;
 
 #[repr(u8)]
 #[derive(Clone, Copy)]
@@ -11,12 +17,71 @@ enum JournalOperation {
     Put = 1,
     Delete = 2,
     Clear = 3,
+    /// Sets the deadline, in milliseconds, of an existing key.
+    Expire = 4,
+    /// Puts and deletes of one level applied together.
+    Batch = 5,
+}
+
+enum BatchWrite {
+    Put(String, Vec<u8>),
+    Delete(String),
+}
+
+/// What a batch requires of a key's value before it applies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Precondition {
+    Absent,
+    Value(Vec<u8>),
+    /// `value_hash` of the value.
+    Hash([u8; 32]),
+}
+
+impl Precondition {
+    fn holds(&self, current: Option<&Vec<u8>>) -> bool {
+        match (self, current) {
+            (Precondition::Absent, current) => current.is_none(),
+            (Precondition::Value(expected), Some(current)) => expected == current,
+            (Precondition::Hash(expected), Some(current)) => *expected == value_hash(current),
+            (_, None) => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum QuotaPolicy {
+    Reject,
+    /// Delete the least recently written keys until the write fits.
+    Evict,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Quota {
+    bytes: usize,
+    policy: QuotaPolicy,
+}
+
+/// One namespace of keys: the root store or a named sublevel, with its own
+/// size accounting, deadlines and quota.
+#[derive(Clone, Default)]
+struct Level {
+    /// Ordered by key so ranges and prefixes can be scanned in place.
+    entries: BTreeMap<String, Vec<u8>>,
+    total_size: usize,
+    expiries: HashMap<String, u64>,
+    deadlines: BTreeSet<(u64, String)>,
+    /// Keys by write sequence, oldest first.
+    written: BTreeMap<u64, String>,
+    write_sequences: HashMap<String, u64>,
+    next_sequence: u64,
+    quota: Option<Quota>,
 }
 
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct NativeAnyStore {
-    entries: IndexMap<String, Vec<u8>>,
-    total_size: usize,
+    root: Level,
+    sublevels: BTreeMap<String, Level>,
 }
 
 #[wasm_bindgen]
@@ -24,29 +89,25 @@ impl NativeAnyStore {
     #[wasm_bindgen(constructor)]
     pub fn new() -> NativeAnyStore {
         NativeAnyStore {
-            entries: IndexMap::new(),
-            total_size: 0,
+            root: Level::default(),
+            sublevels: BTreeMap::new(),
         }
     }
 
     pub fn get(&self, key: &str) -> Option<Vec<u8>> {
-        self.entries.get(key).cloned()
+        self.root.entries.get(key).cloned()
     }
 
     pub fn has_many(&self, keys: Array) -> Result<Array, JsValue> {
         let present = Array::new();
         for key in parse_keys(&keys).map_err(js_error)? {
-            present.push(&JsValue::from_bool(self.entries.contains_key(&key)));
+            present.push(&JsValue::from_bool(self.root.entries.contains_key(&key)));
         }
         Ok(present)
     }
 
     pub fn put(&mut self, key: String, value: Vec<u8>) {
-        let value_len = value.len();
-        if let Some(previous) = self.entries.insert(key, value) {
-            self.total_size = self.total_size.saturating_sub(previous.len());
-        }
-        self.total_size += value_len;
+        self.root.put(key, value);
     }
 
     pub fn put_many(&mut self, keys: Array, values: Array) -> Result<(), JsValue> {
@@ -57,14 +118,7 @@ impl NativeAnyStore {
     }
 
     pub fn delete(&mut self, key: &str) -> bool {
-        // swap_remove is O(1); iteration order is not part of the AnyStore
-        // contract (backends already disagree on it).
-        if let Some((_key, previous)) = self.entries.swap_remove_entry(key) {
-            self.total_size = self.total_size.saturating_sub(previous.len());
-            true
-        } else {
-            false
-        }
+        self.root.delete(key)
     }
 
     pub fn delete_many(&mut self, keys: Array) -> Result<usize, JsValue> {
@@ -78,33 +132,49 @@ impl NativeAnyStore {
     }
 
     pub fn clear(&mut self) {
-        self.entries.clear();
-        self.total_size = 0;
+        self.root.clear();
     }
 
     pub fn len(&self) -> usize {
-        self.entries.len()
+        self.root.entries.len()
     }
 
     pub fn size(&self) -> usize {
-        self.total_size
+        self.root.total_size
     }
 
     pub fn entries(&self) -> Array {
-        let entries = Array::new();
-        for (key, value) in &self.entries {
-            let pair = Array::new();
-            pair.push(&JsValue::from_str(key));
-            pair.push(&Uint8Array::from(value.as_slice()));
-            entries.push(&pair);
-        }
-        entries
+        entries_to_js(&self.root.entries)
+    }
+
+    /// Up to `limit` entries with `start <= key < end`, either bound open when
+    /// omitted, in key order or descending when `reverse`, as
+    /// `[entries, next]`. `next` is set while keys remain: pass it back as
+    /// `start` to continue forward, or as `end` to continue in reverse.
+    pub fn range(
+        &self,
+        start: Option<String>,
+        end: Option<String>,
+        limit: usize,
+        reverse: bool,
+    ) -> Array {
+        let (entries, next) =
+            self.root
+                .scan_range(start.as_deref(), end.as_deref(), limit, reverse);
+        page_to_js(&entries, next)
+    }
+
+    /// Up to `limit` entries whose key starts with `prefix`, in key order and
+    /// from `start` when given, as `[entries, next]` like `range`.
+    pub fn prefix(&self, prefix: &str, limit: usize, start: Option<String>) -> Array {
+        let (entries, next) = self.root.scan_prefix(prefix, limit, start.as_deref());
+        page_to_js(&entries, next)
     }
 
     pub fn get_many(&self, keys: Array) -> Result<Array, JsValue> {
         let values = Array::new();
         for key in parse_keys(&keys).map_err(js_error)? {
-            match self.entries.get(&key) {
+            match self.root.entries.get(&key) {
                 Some(value) => values.push(&Uint8Array::from(value.as_slice())),
                 None => values.push(&JsValue::UNDEFINED),
             };
@@ -112,14 +182,167 @@ impl NativeAnyStore {
         Ok(values)
     }
 
+    /// Sets the byte quota of `level` (`""` is the root), creating it if
+    /// needed. Only writes through `level_put` are held to it; without
+    /// `quota_bytes` the level is unbounded.
+    pub fn configure_level(&mut self, level: &str, quota_bytes: Option<usize>, evict: bool) {
+        self.level_mut(level).quota = quota_bytes.map(|bytes| Quota {
+            bytes,
+            policy: if evict {
+                QuotaPolicy::Evict
+            } else {
+                QuotaPolicy::Reject
+            },
+        });
+    }
+
+    /// Names of the sublevels holding keys or configured, in order.
+    pub fn sublevels(&self) -> Array {
+        self.sublevels
+            .keys()
+            .map(|name| JsValue::from_str(name))
+            .collect()
+    }
+
+    pub fn level_get(&self, level: &str, key: &str) -> Option<Vec<u8>> {
+        self.level(level)?.entries.get(key).cloned()
+    }
+
+    /// Writes `key` to `level`, expiring at `expires_at` (milliseconds, as
+    /// passed to `sweep`) when given, and returns the journal records of the
+    /// write and of anything evicted to make room for it. A write over a
+    /// rejecting quota fails without changing the store.
+    pub fn level_put(
+        &mut self,
+        level: &str,
+        key: String,
+        value: Vec<u8>,
+        expires_at: Option<f64>,
+    ) -> Result<Vec<u8>, JsValue> {
+        let expires_at = expires_at.map(millis);
+        self.level_mut(level)
+            .write(level, key, value, expires_at)
+            .map_err(js_error)
+    }
+
+    /// Applies every write of a batch to `level` or none of them, returning
+    /// a single journal record for the whole batch. `values[i]` null or
+    /// undefined deletes `keys[i]`. `expected[i]` is undefined for no
+    /// precondition, null when the key must be absent, the bytes the key's
+    /// value must have, or `{ hash }` with its `value_hash`; all are checked
+    /// against the store before the batch.
+    pub fn apply_batch(
+        &mut self,
+        level: &str,
+        keys: Array,
+        values: Array,
+        expected: Array,
+    ) -> Result<Vec<u8>, JsValue> {
+        let batch = parse_batch(&keys, &values, &expected).map_err(js_error)?;
+        self.level_mut(level)
+            .apply_batch(level, batch)
+            .map_err(js_error)
+    }
+
+    /// The SHA-256 digest `apply_batch` hash preconditions compare against.
+    pub fn value_hash(&self, level: &str, key: &str) -> Option<Vec<u8>> {
+        self.level(level)?
+            .entries
+            .get(key)
+            .map(|value| value_hash(value).to_vec())
+    }
+
+    /// Deletes `key` from `level`, returning its journal record, or nothing
+    /// when the key was absent.
+    pub fn level_delete(&mut self, level: &str, key: &str) -> Vec<u8> {
+        if self.level_mut(level).delete(key) {
+            encode_level_record(level, JournalOperation::Delete, key.as_bytes(), &[])
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn level_clear(&mut self, level: &str) -> Vec<u8> {
+        self.level_mut(level).clear();
+        encode_level_record(level, JournalOperation::Clear, &[], &[])
+    }
+
+    pub fn level_len(&self, level: &str) -> usize {
+        self.level(level).map_or(0, |level| level.entries.len())
+    }
+
+    pub fn level_size(&self, level: &str) -> usize {
+        self.level(level).map_or(0, |level| level.total_size)
+    }
+
+    pub fn level_entries(&self, level: &str) -> Array {
+        match self.level(level) {
+            Some(level) => entries_to_js(&level.entries),
+            None => Array::new(),
+        }
+    }
+
+    pub fn expires_at(&self, level: &str, key: &str) -> Option<f64> {
+        let at = self.level(level)?.expiries.get(key)?;
+        Some(*at as f64)
+    }
+
+    /// Deletes every key, in any level, whose deadline is at or before `now`
+    /// and returns their delete journal records. Expired keys stay readable
+    /// until swept.
+    pub fn sweep(&mut self, now: f64) -> Vec<u8> {
+        let now = millis(now);
+        let mut records = Vec::new();
+        for key in self.root.sweep(now) {
+            records.extend(encode_record(JournalOperation::Delete, key.as_bytes(), &[]));
+        }
+        for (name, level) in &mut self.sublevels {
+            for key in level.sweep(now) {
+                records.extend(encode_level_record(
+                    name,
+                    JournalOperation::Delete,
+                    key.as_bytes(),
+                    &[],
+                ));
+            }
+        }
+        records
+    }
+
+    /// Root-only stores keep the original snapshot layout.
     pub fn snapshot(&self) -> Vec<u8> {
-        encode_snapshot(&self.entries)
+        if self.sublevels.is_empty() && self.root.expiries.is_empty() {
+            encode_snapshot(&self.root.entries)
+        } else {
+            encode_levels_snapshot(self)
+        }
     }
 
     pub fn load_snapshot(&mut self, bytes: Vec<u8>) -> Result<(), JsValue> {
+        if bytes.starts_with(LEVELS_SNAPSHOT_MAGIC) {
+            let levels = decode_levels_snapshot(&bytes).map_err(js_error)?;
+            self.root.clear();
+            for level in self.sublevels.values_mut() {
+                level.clear();
+            }
+            for (name, entries) in levels {
+                let level = self.level_mut(&name);
+                for (key, value, expires_at) in entries {
+                    if let Some(at) = expires_at {
+                        level.put(key.clone(), value);
+                        level.set_expiry(&key, at);
+                    } else {
+                        level.put(key, value);
+                    }
+                }
+            }
+            return Ok(());
+        }
         let entries = decode_snapshot(&bytes).map_err(js_error)?;
-        self.entries = entries;
-        self.recalculate_size();
+        self.root.clear();
+        for (key, value) in entries {
+            self.root.put(key, value);
+        }
         Ok(())
     }
 
@@ -167,11 +390,312 @@ impl NativeAnyStore {
 }
 
 impl NativeAnyStore {
-    fn recalculate_size(&mut self) {
-        self.total_size = self.entries.values().map(|value| value.len()).sum();
+    fn level(&self, name: &str) -> Option<&Level> {
+        if name.is_empty() {
+            Some(&self.root)
+        } else {
+            self.sublevels.get(name)
+        }
+    }
+
+    fn level_mut(&mut self, name: &str) -> &mut Level {
+        if name.is_empty() {
+            &mut self.root
+        } else {
+            self.sublevels.entry(name.to_string()).or_default()
+        }
+    }
+}
+
+impl Level {
+    /// Writes without quota checks; a plain write drops any deadline.
+    fn put(&mut self, key: String, value: Vec<u8>) {
+        self.clear_expiry(&key);
+        self.touch(&key);
+        let value_len = value.len();
+        if let Some(previous) = self.entries.insert(key, value) {
+            self.total_size = self.total_size.saturating_sub(previous.len());
+        }
+        self.total_size += value_len;
+    }
+
+    fn delete(&mut self, key: &str) -> bool {
+        let Some(previous) = self.entries.remove(key) else {
+            return false;
+        };
+        self.total_size = self.total_size.saturating_sub(previous.len());
+        self.clear_expiry(key);
+        if let Some(sequence) = self.write_sequences.remove(key) {
+            self.written.remove(&sequence);
+        }
+        true
+    }
+
+    /// Drops every key but keeps the quota.
+    fn clear(&mut self) {
+        *self = Level {
+            quota: self.quota,
+            ..Level::default()
+        };
+    }
+
+    /// `put` held to the level's quota, returning the journal records of the
+    /// write and its evictions, addressed to `name`.
+    fn write(
+        &mut self,
+        name: &str,
+        key: String,
+        value: Vec<u8>,
+        expires_at: Option<u64>,
+    ) -> Result<Vec<u8>, String> {
+        let mut records = Vec::new();
+        if let Some(quota) = self.quota {
+            let previous = self.entries.get(&key).map_or(0, Vec::len);
+            let mut size = self.total_size - previous + value.len();
+            if size > quota.bytes {
+                if quota.policy == QuotaPolicy::Reject || value.len() > quota.bytes {
+                    return Err(format!(
+                        "sublevel quota of {} bytes exceeded by {} bytes",
+                        quota.bytes,
+                        size - quota.bytes
+                    ));
+                }
+                let mut evicted = Vec::new();
+                for victim in self.written.values() {
+                    if size <= quota.bytes {
+                        break;
+                    }
+                    if *victim != key {
+                        size -= self.entries[victim].len();
+                        evicted.push(victim.clone());
+                    }
+                }
+                for victim in evicted {
+                    self.delete(&victim);
+                    records.extend(encode_level_record(
+                        name,
+                        JournalOperation::Delete,
+                        victim.as_bytes(),
+                        &[],
+                    ));
+                }
+            }
+        }
+        records.extend(encode_level_record(
+            name,
+            JournalOperation::Put,
+            key.as_bytes(),
+            &value,
+        ));
+        if let Some(at) = expires_at {
+            records.extend(encode_level_record(
+                name,
+                JournalOperation::Expire,
+                key.as_bytes(),
+                &at.to_le_bytes(),
+            ));
+            self.put(key.clone(), value);
+            self.set_expiry(&key, at);
+        } else {
+            self.put(key, value);
+        }
+        Ok(records)
+    }
+
+    /// A batch that would take the level over its quota is rejected whatever
+    /// the policy; batches never evict.
+    fn apply_batch(
+        &mut self,
+        name: &str,
+        batch: Vec<(BatchWrite, Option<Precondition>)>,
+    ) -> Result<Vec<u8>, String> {
+        let mut sizes: HashMap<&str, usize> = HashMap::new();
+        for (write, precondition) in &batch {
+            let key = match write {
+                BatchWrite::Put(key, _) | BatchWrite::Delete(key) => key.as_str(),
+            };
+            if let Some(precondition) = precondition {
+                if !precondition.holds(self.entries.get(key)) {
+                    return Err(format!("batch precondition failed for key {key}"));
+                }
+            }
+            let size = match write {
+                BatchWrite::Put(_, value) => value.len(),
+                BatchWrite::Delete(_) => 0,
+            };
+            sizes.insert(key, size);
+        }
+        if let Some(quota) = self.quota {
+            let mut size = self.total_size;
+            for (key, after) in &sizes {
+                size = size - self.entries.get(*key).map_or(0, Vec::len) + after;
+            }
+            if size > quota.bytes {
+                return Err(format!(
+                    "sublevel quota of {} bytes exceeded by {} bytes",
+                    quota.bytes,
+                    size - quota.bytes
+                ));
+            }
+        }
+
+        let mut payload = Vec::new();
+        push_u32(&mut payload, batch.len() as u32);
+        for (write, _) in batch {
+            match write {
+                BatchWrite::Put(key, value) => {
+                    push_batch_write(&mut payload, JournalOperation::Put, &key, &value);
+                    self.put(key, value);
+                }
+                BatchWrite::Delete(key) => {
+                    push_batch_write(&mut payload, JournalOperation::Delete, &key, &[]);
+                    self.delete(&key);
+                }
+            }
+        }
+        Ok(encode_level_payload_record(
+            name,
+            JournalOperation::Batch,
+            &payload,
+        ))
+    }
+
+    /// Ignored for keys the level does not hold.
+    fn set_expiry(&mut self, key: &str, at: u64) {
+        if !self.entries.contains_key(key) {
+            return;
+        }
+        self.clear_expiry(key);
+        self.expiries.insert(key.to_string(), at);
+        self.deadlines.insert((at, key.to_string()));
+    }
+
+    fn clear_expiry(&mut self, key: &str) {
+        if let Some(at) = self.expiries.remove(key) {
+            self.deadlines.remove(&(at, key.to_string()));
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(sequence) = self.write_sequences.remove(key) {
+            self.written.remove(&sequence);
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.written.insert(sequence, key.to_string());
+        self.write_sequences.insert(key.to_string(), sequence);
+    }
+
+    /// Deletes the keys due at or before `now`, returning them.
+    fn sweep(&mut self, now: u64) -> Vec<String> {
+        let mut expired = Vec::new();
+        while let Some((at, _)) = self.deadlines.first() {
+            if *at > now {
+                break;
+            }
+            let (_, key) = self.deadlines.pop_first().expect("deadline");
+            self.delete(&key);
+            expired.push(key);
+        }
+        expired
+    }
+
+    fn scan_range(
+        &self,
+        start: Option<&str>,
+        end: Option<&str>,
+        limit: usize,
+        reverse: bool,
+    ) -> (Vec<(&str, &[u8])>, Option<String>) {
+        if let (Some(start), Some(end)) = (start, end) {
+            // BTreeMap::range panics on inverted bounds.
+            if start >= end {
+                return (Vec::new(), None);
+            }
+        }
+        let bounds = (
+            start.map_or(Bound::Unbounded, Bound::Included),
+            end.map_or(Bound::Unbounded, Bound::Excluded),
+        );
+        let range = self.entries.range::<str, _>(bounds);
+        if reverse {
+            page(range.rev(), limit, true)
+        } else {
+            page(range, limit, false)
+        }
+    }
+
+    fn scan_prefix(
+        &self,
+        prefix: &str,
+        limit: usize,
+        start: Option<&str>,
+    ) -> (Vec<(&str, &[u8])>, Option<String>) {
+        let start = start.filter(|start| *start > prefix).unwrap_or(prefix);
+        let range = self
+            .entries
+            .range::<str, _>((Bound::Included(start), Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(prefix));
+        page(range, limit, false)
     }
 }
 
+/// The first `limit` entries of `entries`, and where the next page starts:
+/// the first key not taken going forward, or the last key taken (an exclusive
+/// upper bound) in reverse.
+fn page<'a>(
+    mut entries: impl Iterator<Item = (&'a String, &'a Vec<u8>)>,
+    limit: usize,
+    reverse: bool,
+) -> (Vec<(&'a str, &'a [u8])>, Option<String>) {
+    let page: Vec<_> = entries
+        .by_ref()
+        .take(limit)
+        .map(|(key, value)| (key.as_str(), value.as_slice()))
+        .collect();
+    let next = entries.next().and_then(|(key, _)| {
+        if reverse {
+            page.last().map(|(last, _)| last.to_string())
+        } else {
+            Some(key.clone())
+        }
+    });
+    (page, next)
+}
+
+fn entries_to_js(entries: &BTreeMap<String, Vec<u8>>) -> Array {
+    let pairs = Array::new();
+    for (key, value) in entries {
+        let pair = Array::new();
+        pair.push(&JsValue::from_str(key));
+        pair.push(&Uint8Array::from(value.as_slice()));
+        pairs.push(&pair);
+    }
+    pairs
+}
+
+fn page_to_js(entries: &[(&str, &[u8])], next: Option<String>) -> Array {
+    let pairs = Array::new();
+    for (key, value) in entries {
+        let pair = Array::new();
+        pair.push(&JsValue::from_str(key));
+        pair.push(&Uint8Array::from(*value));
+        pairs.push(&pair);
+    }
+    let out = Array::new();
+    out.push(&pairs);
+    match next {
+        Some(next) => out.push(&JsValue::from_str(&next)),
+        None => out.push(&JsValue::UNDEFINED),
+    };
+    out
+}
+
+/// JS millisecond timestamps; negative and NaN ones saturate to zero.
+fn millis(value: f64) -> u64 {
+    value as u64
+}
+
 fn js_error(error: String) -> JsValue {
     JsValue::from_str(&error)
 }
@@ -207,6 +731,63 @@ fn parse_key_values(keys: &Array, values: &Array) -> Result<Vec<(String, Vec<u8>
     Ok(parsed)
 }
 
+fn parse_batch(
+    keys: &Array,
+    values: &Array,
+    expected: &Array,
+) -> Result<Vec<(BatchWrite, Option<Precondition>)>, String> {
+    if keys.length() != values.length() || keys.length() != expected.length() {
+        return Err("keys, values and expected length mismatch".to_string());
+    }
+    let mut parsed = Vec::with_capacity(keys.length() as usize);
+    for index in 0..keys.length() {
+        let key = keys
+            .get(index)
+            .as_string()
+            .ok_or_else(|| format!("key at index {index} is not a string"))?;
+        let value = values.get(index);
+        let write = if value.is_null() || value.is_undefined() {
+            BatchWrite::Delete(key)
+        } else {
+            BatchWrite::Put(key, Uint8Array::new(&value).to_vec())
+        };
+        let expected = expected.get(index);
+        let precondition = if expected.is_undefined() {
+            None
+        } else if expected.is_null() {
+            Some(Precondition::Absent)
+        } else if expected.is_instance_of::<Uint8Array>() {
+            Some(Precondition::Value(Uint8Array::new(&expected).to_vec()))
+        } else if expected.is_object() {
+            let hash = Reflect::get(&expected, &JsValue::from_str("hash"))
+                .ok()
+                .filter(|hash| hash.is_instance_of::<Uint8Array>())
+                .ok_or_else(|| format!("expected hash at index {index} is not bytes"))?;
+            Some(
+                hash_precondition(&Uint8Array::new(&hash).to_vec())
+                    .map_err(|error| format!("expected hash at index {index} {error}"))?,
+            )
+        } else {
+            return Err(format!(
+                "expected at index {index} is not bytes, a hash or null"
+            ));
+        };
+        parsed.push((write, precondition));
+    }
+    Ok(parsed)
+}
+
+fn hash_precondition(hash: &[u8]) -> Result<Precondition, String> {
+    let hash: [u8; 32] = hash
+        .try_into()
+        .map_err(|_| format!("has {} bytes, not 32", hash.len()))?;
+    Ok(Precondition::Hash(hash))
+}
+
+fn value_hash(value: &[u8]) -> [u8; 32] {
+    Sha256::digest(value).into()
+}
+
 fn fnv1a(bytes: &[u8]) -> u32 {
     let mut hash = 0x811c9dc5_u32;
     for byte in bytes {
@@ -237,7 +818,7 @@ fn read_bytes<'a>(bytes: &'a [u8], offset: &mut usize, len: usize) -> Result<&'a
     Ok(data)
 }
 
-fn encode_snapshot(entries: &IndexMap<String, Vec<u8>>) -> Vec<u8> {
+fn encode_snapshot(entries: &BTreeMap<String, Vec<u8>>) -> Vec<u8> {
     let mut payload = Vec::new();
     push_u32(&mut payload, entries.len() as u32);
     for (key, value) in entries {
@@ -255,9 +836,9 @@ fn encode_snapshot(entries: &IndexMap<String, Vec<u8>>) -> Vec<u8> {
     output
 }
 
-fn decode_snapshot(bytes: &[u8]) -> Result<IndexMap<String, Vec<u8>>, String> {
+fn decode_snapshot(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, String> {
     if bytes.is_empty() {
-        return Ok(IndexMap::new());
+        return Ok(BTreeMap::new());
     }
 
     let payload = if bytes.starts_with(SNAPSHOT_MAGIC) {
@@ -275,7 +856,7 @@ fn decode_snapshot(bytes: &[u8]) -> Result<IndexMap<String, Vec<u8>>, String> {
 
     let mut offset = 0;
     let count = read_u32(payload, &mut offset)? as usize;
-    let mut entries = IndexMap::new();
+    let mut entries = BTreeMap::new();
     for _ in 0..count {
         let key_len = read_u32(payload, &mut offset)? as usize;
         let value_len = read_u32(payload, &mut offset)? as usize;
@@ -287,6 +868,84 @@ fn decode_snapshot(bytes: &[u8]) -> Result<IndexMap<String, Vec<u8>>, String> {
     Ok(entries)
 }
 
+/// Every level with its entries and deadlines, root first under `""`.
+fn encode_levels_snapshot(store: &NativeAnyStore) -> Vec<u8> {
+    let levels = std::iter::once(("", &store.root)).chain(
+        store
+            .sublevels
+            .iter()
+            .map(|(name, level)| (name.as_str(), level)),
+    );
+    let mut payload = Vec::new();
+    push_u32(&mut payload, store.sublevels.len() as u32 + 1);
+    for (name, level) in levels {
+        push_u32(&mut payload, name.len() as u32);
+        payload.extend_from_slice(name.as_bytes());
+        push_u32(&mut payload, level.entries.len() as u32);
+        for (key, value) in &level.entries {
+            push_u32(&mut payload, key.len() as u32);
+            push_u32(&mut payload, value.len() as u32);
+            match level.expiries.get(key) {
+                Some(at) => {
+                    payload.push(1);
+                    payload.extend_from_slice(&at.to_le_bytes());
+                }
+                None => payload.push(0),
+            }
+            payload.extend_from_slice(key.as_bytes());
+            payload.extend_from_slice(value);
+        }
+    }
+
+    let mut output = Vec::with_capacity(LEVELS_SNAPSHOT_MAGIC.len() + 8 + payload.len());
+    output.extend_from_slice(LEVELS_SNAPSHOT_MAGIC);
+    push_u32(&mut output, payload.len() as u32);
+    push_u32(&mut output, fnv1a(&payload));
+    output.extend_from_slice(&payload);
+    output
+}
+
+type LevelSnapshotEntry = (String, Vec<u8>, Option<u64>);
+
+fn decode_levels_snapshot(bytes: &[u8]) -> Result<Vec<(String, Vec<LevelSnapshotEntry>)>, String> {
+    let mut offset = LEVELS_SNAPSHOT_MAGIC.len();
+    let payload_len = read_u32(bytes, &mut offset)? as usize;
+    let checksum = read_u32(bytes, &mut offset)?;
+    let payload = read_bytes(bytes, &mut offset, payload_len)?;
+    if fnv1a(payload) != checksum {
+        return Err("snapshot checksum mismatch".to_string());
+    }
+
+    let mut offset = 0;
+    let level_count = read_u32(payload, &mut offset)? as usize;
+    let mut levels = Vec::new();
+    for _ in 0..level_count {
+        let name_len = read_u32(payload, &mut offset)? as usize;
+        let name = read_bytes(payload, &mut offset, name_len)?;
+        let name = String::from_utf8(name.to_vec()).map_err(|error| error.to_string())?;
+        let count = read_u32(payload, &mut offset)? as usize;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let key_len = read_u32(payload, &mut offset)? as usize;
+            let value_len = read_u32(payload, &mut offset)? as usize;
+            let expires_at = match read_bytes(payload, &mut offset, 1)?[0] {
+                0 => None,
+                1 => {
+                    let at = read_bytes(payload, &mut offset, 8)?;
+                    Some(u64::from_le_bytes(at.try_into().expect("8 bytes")))
+                }
+                _ => return Err("invalid snapshot deadline flag".to_string()),
+            };
+            let key = read_bytes(payload, &mut offset, key_len)?;
+            let value = read_bytes(payload, &mut offset, value_len)?;
+            let key = String::from_utf8(key.to_vec()).map_err(|error| error.to_string())?;
+            entries.push((key, value.to_vec(), expires_at));
+        }
+        levels.push((name, entries));
+    }
+    Ok(levels)
+}
+
 fn encode_record(operation: JournalOperation, key: &[u8], value: &[u8]) -> Vec<u8> {
     let mut payload = Vec::with_capacity(1 + 8 + key.len() + value.len());
     payload.push(operation as u8);
@@ -294,12 +953,58 @@ fn encode_record(operation: JournalOperation, key: &[u8], value: &[u8]) -> Vec<u
     push_u32(&mut payload, value.len() as u32);
     payload.extend_from_slice(key);
     payload.extend_from_slice(value);
+    frame_record(&payload)
+}
+
+/// Records for the root level keep the original layout.
+fn encode_level_record(
+    level: &str,
+    operation: JournalOperation,
+    key: &[u8],
+    value: &[u8],
+) -> Vec<u8> {
+    if level.is_empty() {
+        return encode_record(operation, key, value);
+    }
+    let mut payload = Vec::with_capacity(1 + 12 + level.len() + key.len() + value.len());
+    payload.push(operation as u8 | LEVEL_RECORD_FLAG);
+    push_u32(&mut payload, level.len() as u32);
+    payload.extend_from_slice(level.as_bytes());
+    push_u32(&mut payload, key.len() as u32);
+    push_u32(&mut payload, value.len() as u32);
+    payload.extend_from_slice(key);
+    payload.extend_from_slice(value);
+    frame_record(&payload)
+}
+
+/// A record whose payload after the operation (and level) is `body`.
+fn encode_level_payload_record(level: &str, operation: JournalOperation, body: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(1 + 4 + level.len() + body.len());
+    if level.is_empty() {
+        payload.push(operation as u8);
+    } else {
+        payload.push(operation as u8 | LEVEL_RECORD_FLAG);
+        push_u32(&mut payload, level.len() as u32);
+        payload.extend_from_slice(level.as_bytes());
+    }
+    payload.extend_from_slice(body);
+    frame_record(&payload)
+}
 
+fn push_batch_write(payload: &mut Vec<u8>, operation: JournalOperation, key: &str, value: &[u8]) {
+    payload.push(operation as u8);
+    push_u32(payload, key.len() as u32);
+    push_u32(payload, value.len() as u32);
+    payload.extend_from_slice(key.as_bytes());
+    payload.extend_from_slice(value);
+}
+
+fn frame_record(payload: &[u8]) -> Vec<u8> {
     let mut output = Vec::with_capacity(JOURNAL_MAGIC.len() + 8 + payload.len());
     output.extend_from_slice(JOURNAL_MAGIC);
     push_u32(&mut output, payload.len() as u32);
-    push_u32(&mut output, fnv1a(&payload));
-    output.extend_from_slice(&payload);
+    push_u32(&mut output, fnv1a(payload));
+    output.extend_from_slice(payload);
     output
 }
 
@@ -309,10 +1014,7 @@ fn encode_record(operation: JournalOperation, key: &[u8], value: &[u8]) -> Vec<u
 /// a partially applied corrupt generation. Returns the verified prefix length
 /// so callers can durably truncate an incomplete EOF tail.
 fn apply_journal(store: &mut NativeAnyStore, bytes: &[u8]) -> Result<usize, String> {
-    let mut replay = NativeAnyStore {
-        entries: store.entries.clone(),
-        total_size: store.total_size,
-    };
+    let mut replay = store.clone();
     let mut applied = 0;
     while applied < bytes.len() {
         let mut offset = applied;
@@ -342,30 +1044,66 @@ fn apply_journal(store: &mut NativeAnyStore, bytes: &[u8]) -> Result<usize, Stri
         apply_record_payload(&mut replay, payload)?;
         applied = offset;
     }
-    store.entries = replay.entries;
-    store.total_size = replay.total_size;
+    *store = replay;
     Ok(applied)
 }
 
 fn apply_record_payload(store: &mut NativeAnyStore, payload: &[u8]) -> Result<(), String> {
     let mut offset = 0;
-    let operation = *read_bytes(payload, &mut offset, 1)?
+    let mut operation = *read_bytes(payload, &mut offset, 1)?
         .first()
         .ok_or_else(|| "missing journal operation".to_string())?;
-    let key_len = read_u32(payload, &mut offset)? as usize;
-    let value_len = read_u32(payload, &mut offset)? as usize;
-    let key = read_bytes(payload, &mut offset, key_len)?;
-    let value = read_bytes(payload, &mut offset, value_len)?;
+    let mut level = "";
+    if operation & LEVEL_RECORD_FLAG != 0 {
+        operation &= !LEVEL_RECORD_FLAG;
+        let level_len = read_u32(payload, &mut offset)? as usize;
+        level = std::str::from_utf8(read_bytes(payload, &mut offset, level_len)?)
+            .map_err(|error| error.to_string())?;
+    }
+    let level = store.level_mut(level);
+    if operation == JournalOperation::Batch as u8 {
+        let count = read_u32(payload, &mut offset)?;
+        for _ in 0..count {
+            let operation = read_bytes(payload, &mut offset, 1)?[0];
+            if operation != JournalOperation::Put as u8
+                && operation != JournalOperation::Delete as u8
+            {
+                return Err("unknown journal batch operation".to_string());
+            }
+            apply_level_operation(level, operation, payload, &mut offset)?;
+        }
+        return Ok(());
+    }
+    apply_level_operation(level, operation, payload, &mut offset)
+}
+
+fn apply_level_operation(
+    level: &mut Level,
+    operation: u8,
+    payload: &[u8],
+    offset: &mut usize,
+) -> Result<(), String> {
+    let key_len = read_u32(payload, offset)? as usize;
+    let value_len = read_u32(payload, offset)? as usize;
+    let key = read_bytes(payload, offset, key_len)?;
+    let value = read_bytes(payload, offset, value_len)?;
     match operation {
         1 => {
             let key = String::from_utf8(key.to_vec()).map_err(|error| error.to_string())?;
-            store.put(key, value.to_vec());
+            level.put(key, value.to_vec());
         }
         2 => {
             let key = String::from_utf8(key.to_vec()).map_err(|error| error.to_string())?;
-            store.delete(&key);
+            level.delete(&key);
+        }
+        3 => level.clear(),
+        4 => {
+            let key = std::str::from_utf8(key).map_err(|error| error.to_string())?;
+            let at: [u8; 8] = value
+                .try_into()
+                .map_err(|_| "invalid journal deadline".to_string())?;
+            level.set_expiry(key, u64::from_le_bytes(at));
         }
-        3 => store.clear(),
         _ => return Err("unknown journal operation".to_string()),
     }
     Ok(())
@@ -373,7 +1111,9 @@ fn apply_record_payload(store: &mut NativeAnyStore, payload: &[u8]) -> Result<()
 
 #[cfg(test)]
 mod tests {
-    use super::{apply_journal, decode_snapshot, NativeAnyStore};
+    use super::{
+        apply_journal, decode_snapshot, hash_precondition, BatchWrite, NativeAnyStore, Precondition,
+    };
 
     #[test]
     fn snapshot_roundtrips() {
@@ -386,6 +1126,180 @@ mod tests {
         assert_eq!(entries.get("b").unwrap(), &vec![4]);
     }
 
+    #[test]
+    fn scans_ranges_and_prefixes_in_pages() {
+        let mut store = NativeAnyStore::new();
+        for key in ["b/2", "a/1", "b/1", "c", "b/3", "a/2"] {
+            store.put(key.to_string(), key.as_bytes().to_vec());
+        }
+        store.delete("a/2");
+        fn keys<'a>(
+            (entries, next): (Vec<(&'a str, &'a [u8])>, Option<String>),
+        ) -> (Vec<&'a str>, Option<String>) {
+            (entries.into_iter().map(|(key, _)| key).collect(), next)
+        }
+
+        assert_eq!(
+            keys(store.root.scan_range(Some("b"), None, 2, false)),
+            (vec!["b/1", "b/2"], Some("b/3".to_string()))
+        );
+        assert_eq!(
+            keys(store.root.scan_range(Some("b/3"), None, 2, false)),
+            (vec!["b/3", "c"], None)
+        );
+        assert_eq!(
+            keys(store.root.scan_range(None, Some("c"), 2, true)),
+            (vec!["b/3", "b/2"], Some("b/2".to_string()))
+        );
+        assert_eq!(
+            keys(store.root.scan_range(None, Some("b/2"), 2, true)),
+            (vec!["b/1", "a/1"], None)
+        );
+        assert_eq!(
+            keys(store.root.scan_range(Some("c"), Some("a"), 2, false))
+                .0
+                .len(),
+            0
+        );
+
+        assert_eq!(
+            keys(store.root.scan_prefix("b/", 2, None)),
+            (vec!["b/1", "b/2"], Some("b/3".to_string()))
+        );
+        assert_eq!(
+            keys(store.root.scan_prefix("b/", 2, Some("b/3"))),
+            (vec!["b/3"], None)
+        );
+        assert_eq!(keys(store.root.scan_prefix("d", 2, None)).0.len(), 0);
+        assert_eq!(
+            store.root.scan_prefix("a", 1, None).0,
+            vec![("a/1", "a/1".as_bytes())]
+        );
+    }
+
+    #[test]
+    fn sublevels_expire_and_hold_to_their_quotas() {
+        let mut store = NativeAnyStore::new();
+        store.put("a".to_string(), vec![1]);
+        store.configure_level("cache", Some(6), true);
+        store.configure_level("strict", Some(4), false);
+
+        let mut journal = Vec::new();
+        for (key, expires_at) in [("x", Some(10.0)), ("y", None), ("z", Some(30.0))] {
+            journal.extend(
+                store
+                    .level_put("cache", key.to_string(), vec![0; 2], expires_at)
+                    .unwrap(),
+            );
+        }
+        assert_eq!((store.level_size("cache"), store.size()), (6, 1));
+        assert_eq!(store.level_get("", "a"), Some(vec![1]));
+        assert_eq!(store.level_get("cache", "a"), None);
+
+        // Rewriting "x" makes "y" the oldest write.
+        journal.extend(
+            store
+                .level_put("cache", "x".to_string(), vec![0; 2], Some(10.0))
+                .unwrap(),
+        );
+        journal.extend(
+            store
+                .level_put("cache", "w".to_string(), vec![0; 2], None)
+                .unwrap(),
+        );
+        assert_eq!(store.level_get("cache", "y"), None);
+        assert_eq!(store.level_size("cache"), 6);
+
+        let strict = store.level_mut("strict");
+        strict
+            .write("strict", "p".to_string(), vec![0; 3], None)
+            .unwrap();
+        assert_eq!(
+            strict.write("strict", "q".to_string(), vec![0; 2], None),
+            Err("sublevel quota of 4 bytes exceeded by 1 bytes".to_string())
+        );
+        assert_eq!(store.level_len("strict"), 1);
+
+        journal.extend(store.sweep(20.0));
+        assert_eq!(store.level_get("cache", "x"), None);
+        assert_eq!(store.expires_at("cache", "z"), Some(30.0));
+        assert_eq!(store.level_size("cache"), 4);
+
+        let mut replayed = NativeAnyStore::new();
+        replayed.apply_journal(journal).unwrap();
+        let mut restored = NativeAnyStore::new();
+        restored.load_snapshot(store.snapshot()).unwrap();
+        for store in [replayed, restored] {
+            assert_eq!(store.level_len("cache"), 2);
+            assert_eq!(store.level_size("cache"), 4);
+            assert_eq!(store.expires_at("cache", "z"), Some(30.0));
+            assert_eq!(store.level_get("cache", "w"), Some(vec![0; 2]));
+        }
+    }
+
+    #[test]
+    fn batches_apply_all_or_nothing_as_one_journal_record() {
+        let mut store = NativeAnyStore::new();
+        store.put("a".to_string(), vec![1]);
+        store.put("b".to_string(), vec![2]);
+        let a_hash = hash_precondition(&store.value_hash("", "a").unwrap()).unwrap();
+        assert_eq!(
+            hash_precondition(&[0; 4]),
+            Err("has 4 bytes, not 32".to_string())
+        );
+
+        let failing = vec![
+            (
+                BatchWrite::Put("a".to_string(), vec![10]),
+                Some(a_hash.clone()),
+            ),
+            (
+                BatchWrite::Delete("b".to_string()),
+                Some(Precondition::Value(vec![3])),
+            ),
+        ];
+        assert_eq!(
+            store.root.apply_batch("", failing),
+            Err("batch precondition failed for key b".to_string())
+        );
+        assert_eq!(store.get("a"), Some(vec![1]));
+
+        let batch = vec![
+            (
+                BatchWrite::Put("a".to_string(), vec![10, 11]),
+                Some(a_hash.clone()),
+            ),
+            (
+                BatchWrite::Delete("b".to_string()),
+                Some(Precondition::Value(vec![2])),
+            ),
+            (
+                BatchWrite::Put("c".to_string(), vec![3]),
+                Some(Precondition::Absent),
+            ),
+        ];
+        let record = store.root.apply_batch("", batch).unwrap();
+        assert_eq!((store.get("a"), store.get("b")), (Some(vec![10, 11]), None));
+        assert_eq!(store.size(), 3);
+
+        let mut journal = NativeAnyStore::new().encode_put_record("b".to_string(), vec![2]);
+        let clean_len = journal.len();
+        journal.extend_from_slice(&record[..record.len() - 1]);
+        let mut torn = NativeAnyStore::new();
+        assert_eq!(apply_journal(&mut torn, &journal).unwrap(), clean_len);
+        assert_eq!((torn.get("b"), torn.get("c")), (Some(vec![2]), None));
+
+        journal.truncate(clean_len);
+        journal.extend_from_slice(&record);
+        let mut replayed = NativeAnyStore::new();
+        assert_eq!(
+            apply_journal(&mut replayed, &journal).unwrap(),
+            journal.len()
+        );
+        assert_eq!(replayed.root.entries.len(), 2);
+        assert_eq!(replayed.get("c"), Some(vec![3]));
+    }
+
     #[test]
     fn journal_replays() {
         let encoder = NativeAnyStore::new();
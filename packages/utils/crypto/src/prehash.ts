@@ -1,4 +1,5 @@
 import { toUtf8Bytes } from "@ethersproject/strings";
+import { blake3 } from "@noble/hashes/blake3";
 import sha3 from "js-sha3";
 import { concat } from "uint8arrays";
 import { sha256 } from "./hash.js";
@@ -20,7 +21,7 @@ const ethKeccak256Hash = (message: Uint8Array) =>
 export enum PreHash {
 	NONE = 0,
 	SHA_256 = 1,
-	// BLAKE3 = 2,
+	BLAKE3 = 2,
 	ETH_KECCAK_256 = 3,
 }
 
@@ -34,6 +35,9 @@ export const prehashFn = (
 	if (prehash === PreHash.SHA_256) {
 		return sha256(data);
 	}
+	if (prehash === PreHash.BLAKE3) {
+		return blake3(data);
+	}
 	if (prehash === PreHash.ETH_KECCAK_256) {
 		return ethKeccak256Hash(data);
 	}
@@ -28,7 +28,7 @@ export class SignatureWithKey {
 	publicKey: PublicSignKey;
 
 	@field({ type: "u8" })
-	prehash: PreHash = PreHash.NONE; // 0 no prehash, 1 sha256, 2 blake3?
+	prehash: PreHash = PreHash.NONE; // 0 no prehash, 1 sha256, 2 blake3, 3 eth keccak256
 
 	constructor(props: {
 		signature: Uint8Array;
@@ -24,7 +24,8 @@ import {
 	verifySignatureSecp256k1,
 } from "../src/index.js";
 import * as cryptoRoot from "../src/index.js";
-import { PreHash } from "../src/prehash.js";
+import { PreHash, prehashFn } from "../src/prehash.js";
+import { toHexString } from "../src/utils.js";
 
 describe("Ed25519", () => {
 	it("ser/der", async () => {
@@ -93,6 +94,15 @@ describe("Ed25519", () => {
 			expect(isNotVerified).to.be.false;
 		});
 
+		it("verify blake3 hashed", async () => {
+			const keypair = await Ed25519Keypair.create();
+			const data = new Uint8Array([1, 2, 3]);
+			const signature = await keypair.sign(data, PreHash.BLAKE3);
+			expect(signature.prehash).to.equal(2);
+			expect(await verify(signature, data)).to.be.true;
+			expect(await verify(signature, data.reverse())).to.be.false;
+		});
+
 		it("exports verifyPrepared from the root entrypoint", async () => {
 			const keypair = await Ed25519Keypair.create();
 			const data = new Uint8Array([1, 2, 3]);
@@ -345,3 +355,27 @@ describe("Sepck2561k1", () => {
 		expect(derser.equals(pk)).to.be.true;
 	});
 });
+
+describe("PreHash", () => {
+	before(async () => {
+		await sodium.ready;
+	});
+
+	// Official BLAKE3 test vectors (input bytes `i % 251`). The native
+	// verifiers in @peerbit/network-rust (`wire::prehash_digest`) and
+	// @peerbit/log-rust pin the same pairs.
+	const blake3Vectors: [number, string][] = [
+		[0, "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"],
+		[1, "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213"],
+		[1025, "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444"],
+	];
+
+	it("blake3", async () => {
+		for (const [length, digest] of blake3Vectors) {
+			const input = Uint8Array.from({ length }, (_, i) => i % 251);
+			expect(toHexString(await prehashFn(input, PreHash.BLAKE3))).to.equal(
+				digest,
+			);
+		}
+	});
+});
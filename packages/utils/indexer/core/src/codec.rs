@@ -1,18 +1,50 @@
 use crate::planner::{
-    Compare, FieldPath, FieldValue, Query, SortDirection, SortField, StringMatchMethod,
+    Aggregation, AggregationResult, Compare, FieldPath, FieldValue, HistogramBucket, Query,
+    SearchCursor, SortDirection, SortField, StringMatchMethod, SumResult, TextOperator,
+    ValueBucket,
 };
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 
-const BRIDGE_VERSION: u8 = 1;
+const BRIDGE_VERSION: u8 = 4;
+// Payloads from older bridges are still accepted as long as they only use
+// variants that existed in their version (see `QueryDto::min_version`).
+const MIN_BRIDGE_VERSION: u8 = 1;
+const TEXT_QUERY_BRIDGE_VERSION: u8 = 2;
+const SET_QUERY_BRIDGE_VERSION: u8 = 3;
+const STRING_PATTERN_BRIDGE_VERSION: u8 = 4;
+const FLOAT_VALUE_BRIDGE_VERSION: u8 = 4;
+const WIDE_UNSIGNED_BRIDGE_VERSION: u8 = 4;
 
 // Enum declaration order is part of the TS/Rust bridge ABI.
-#[derive(Clone, BorshDeserialize)]
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
 enum FieldValueDto {
     Bool(bool),
     I64(i64),
     U64(u64),
     String(String),
     Bytes(Vec<u8>),
+    F64(F64Bits),
+    // Big-endian, above u64::MAX (smaller values travel as U64).
+    BigUint(Vec<u8>),
+}
+
+/// An `f64` written as its little-endian bit pattern: the bytes borsh writes
+/// for any other float, but NaN (which borsh refuses) crosses the bridge too.
+#[derive(Clone, Copy)]
+struct F64Bits(f64);
+
+impl BorshSerialize for F64Bits {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.0.to_bits().to_le_bytes())
+    }
+}
+
+impl BorshDeserialize for F64Bits {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut bytes = [0u8; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self(f64::from_bits(u64::from_le_bytes(bytes))))
+    }
 }
 
 #[derive(BorshDeserialize)]
@@ -52,6 +84,21 @@ enum QueryDto {
     IsNull {
         field: u32,
     },
+    Text {
+        field: u32,
+        value: String,
+        operator: TextOperatorDto,
+    },
+    In {
+        field: u32,
+        values: Vec<FieldValueDto>,
+    },
+    Between {
+        field: u32,
+        low: FieldValueDto,
+        high: FieldValueDto,
+        inclusive: bool,
+    },
 }
 
 // Enum declaration order is part of the TS/Rust bridge ABI.
@@ -62,6 +109,7 @@ enum CompareDto {
     GreaterOrEqual,
     Less,
     LessOrEqual,
+    NotEqual,
 }
 
 #[derive(BorshDeserialize)]
@@ -89,13 +137,154 @@ enum StringMatchMethodDto {
     Exact,
     Prefix,
     Contains,
+    Suffix,
+    Glob,
+}
+
+// Enum declaration order is part of the TS/Rust bridge ABI.
+#[derive(Clone, Copy, BorshDeserialize)]
+enum TextOperatorDto {
+    Any,
+    All,
+}
+
+#[derive(BorshDeserialize)]
+struct AggregationPayloadDto {
+    version: u8,
+    query: QueryDto,
+    aggregation: AggregationDto,
+}
+
+// Enum declaration order is part of the TS/Rust bridge ABI.
+#[derive(BorshDeserialize)]
+enum AggregationDto {
+    Count,
+    Sum { field: u32 },
+    Min { field: u32 },
+    Max { field: u32 },
+    Average { field: u32 },
+    DistinctCount { field: u32 },
+    GroupBy { field: u32 },
+    Histogram { field: u32, interval: u64 },
+}
+
+#[derive(BorshSerialize)]
+struct AggregationResultPayloadDto {
+    version: u8,
+    result: AggregationResultDto,
+}
+
+// Enum declaration order is part of the TS/Rust bridge ABI.
+#[derive(BorshSerialize)]
+enum AggregationResultDto {
+    Count(u64),
+    Sum(SumDto),
+    Value(Option<FieldValueDto>),
+    Average(Option<F64Bits>),
+    Groups(Vec<ValueBucketDto>),
+    Histogram(Vec<HistogramBucketDto>),
+}
+
+// Enum declaration order is part of the TS/Rust bridge ABI.
+#[derive(BorshSerialize)]
+enum SumDto {
+    None,
+    I64(i128),
+    U64(u128),
+    F64(F64Bits),
+    // Big-endian.
+    BigUint(Vec<u8>),
+}
+
+#[derive(BorshSerialize)]
+struct ValueBucketDto {
+    value: FieldValueDto,
+    count: u64,
+}
+
+#[derive(BorshSerialize)]
+struct HistogramBucketDto {
+    start: i128,
+    count: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct SearchCursorPayloadDto {
+    version: u8,
+    values: Vec<Option<FieldValueDto>>,
+    id: String,
 }
 
 pub fn decode_query(query_bytes: &[u8]) -> Result<Query, String> {
     let payload =
         QueryPayloadDto::try_from_slice(query_bytes).map_err(|error| error.to_string())?;
+    decode_versioned_query(payload.version, payload.query)
+}
+
+pub fn decode_aggregation(request_bytes: &[u8]) -> Result<(Query, Aggregation), String> {
+    let payload =
+        AggregationPayloadDto::try_from_slice(request_bytes).map_err(|error| error.to_string())?;
+    let query = decode_versioned_query(payload.version, payload.query)?;
+    Ok((query, payload.aggregation.into()))
+}
+
+pub fn encode_aggregation_result(result: AggregationResult) -> Vec<u8> {
+    borsh::to_vec(&AggregationResultPayloadDto {
+        version: BRIDGE_VERSION,
+        result: result.into(),
+    })
+    .expect("aggregation results serialize into memory")
+}
+
+pub fn encode_search_cursor(cursor: &SearchCursor) -> Vec<u8> {
+    borsh::to_vec(&SearchCursorPayloadDto {
+        version: BRIDGE_VERSION,
+        values: cursor
+            .values
+            .iter()
+            .map(|value| value.clone().map(Into::into))
+            .collect(),
+        id: cursor.id.clone(),
+    })
+    .expect("search cursors serialize into memory")
+}
+
+pub fn decode_search_cursor(cursor_bytes: &[u8]) -> Result<SearchCursor, String> {
+    let payload =
+        SearchCursorPayloadDto::try_from_slice(cursor_bytes).map_err(|error| error.to_string())?;
     ensure_bridge_version(payload.version)?;
-    payload.query.try_into()
+    let required = payload
+        .values
+        .iter()
+        .flatten()
+        .map(FieldValueDto::min_version)
+        .max()
+        .unwrap_or(MIN_BRIDGE_VERSION);
+    if payload.version < required {
+        return Err(format!(
+            "Search cursor requires bridge payload version {required}, got {}",
+            payload.version
+        ));
+    }
+    Ok(SearchCursor {
+        values: payload
+            .values
+            .into_iter()
+            .map(|value| value.map(Into::into))
+            .collect(),
+        id: payload.id,
+    })
+}
+
+fn decode_versioned_query(version: u8, query: QueryDto) -> Result<Query, String> {
+    ensure_bridge_version(version)?;
+    let required = query.min_version();
+    if version < required {
+        return Err(format!(
+            "Query requires bridge payload version {required}, got {version}"
+        ));
+    }
+    query.try_into()
 }
 
 pub fn decode_sort(sort_bytes: &[u8]) -> Result<Vec<SortField>, String> {
@@ -111,6 +300,49 @@ pub fn decode_sort(sort_bytes: &[u8]) -> Result<Vec<SortField>, String> {
         .collect())
 }
 
+impl FieldValueDto {
+    fn min_version(&self) -> u8 {
+        match self {
+            FieldValueDto::F64(_) => FLOAT_VALUE_BRIDGE_VERSION,
+            FieldValueDto::BigUint(_) => WIDE_UNSIGNED_BRIDGE_VERSION,
+            _ => MIN_BRIDGE_VERSION,
+        }
+    }
+}
+
+impl QueryDto {
+    fn min_version(&self) -> u8 {
+        match self {
+            QueryDto::Text { .. } => TEXT_QUERY_BRIDGE_VERSION,
+            QueryDto::Exact { value, .. } => value.min_version(),
+            QueryDto::Range {
+                compare: CompareDto::NotEqual,
+                value,
+                ..
+            } => SET_QUERY_BRIDGE_VERSION.max(value.min_version()),
+            QueryDto::Range { value, .. } => value.min_version(),
+            QueryDto::In { values, .. } => values
+                .iter()
+                .map(FieldValueDto::min_version)
+                .fold(SET_QUERY_BRIDGE_VERSION, u8::max),
+            QueryDto::Between { low, high, .. } => SET_QUERY_BRIDGE_VERSION
+                .max(low.min_version())
+                .max(high.min_version()),
+            QueryDto::StringMatch {
+                method: StringMatchMethodDto::Suffix | StringMatchMethodDto::Glob,
+                ..
+            } => STRING_PATTERN_BRIDGE_VERSION,
+            QueryDto::And { queries } | QueryDto::Or { queries } => queries
+                .iter()
+                .map(QueryDto::min_version)
+                .max()
+                .unwrap_or(MIN_BRIDGE_VERSION),
+            QueryDto::Not { query } => query.min_version(),
+            _ => MIN_BRIDGE_VERSION,
+        }
+    }
+}
+
 impl TryFrom<QueryDto> for Query {
     type Error = String;
 
@@ -147,6 +379,30 @@ impl TryFrom<QueryDto> for Query {
             QueryDto::IsNull { field } => Query::IsNull {
                 field: FieldPath::Id(field),
             },
+            QueryDto::Text {
+                field,
+                value,
+                operator,
+            } => Query::Text {
+                field: FieldPath::Id(field),
+                value,
+                operator: operator.into(),
+            },
+            QueryDto::In { field, values } => Query::In {
+                field: FieldPath::Id(field),
+                values: values.into_iter().map(FieldValue::from).collect(),
+            },
+            QueryDto::Between {
+                field,
+                low,
+                high,
+                inclusive,
+            } => Query::Between {
+                field: FieldPath::Id(field),
+                low: low.into(),
+                high: high.into(),
+                inclusive,
+            },
         })
     }
 }
@@ -159,6 +415,74 @@ impl From<FieldValueDto> for FieldValue {
             FieldValueDto::U64(value) => FieldValue::U64(value),
             FieldValueDto::String(value) => FieldValue::from(value),
             FieldValueDto::Bytes(value) => FieldValue::from(value),
+            FieldValueDto::F64(value) => FieldValue::from(value.0),
+            FieldValueDto::BigUint(value) => FieldValue::from_unsigned_be_bytes(&value),
+        }
+    }
+}
+
+impl From<FieldValue> for FieldValueDto {
+    fn from(value: FieldValue) -> Self {
+        match value {
+            FieldValue::Bool(value) => FieldValueDto::Bool(value),
+            FieldValue::I64(value) => FieldValueDto::I64(value),
+            FieldValue::U64(value) => FieldValueDto::U64(value),
+            FieldValue::String(value) => FieldValueDto::String(value.to_string()),
+            FieldValue::Bytes(value) => FieldValueDto::Bytes(value.to_vec()),
+            FieldValue::F64(value) => FieldValueDto::F64(F64Bits(value.get())),
+            FieldValue::BigUint(value) => FieldValueDto::BigUint(value.to_be_bytes().to_vec()),
+        }
+    }
+}
+
+impl From<AggregationDto> for Aggregation {
+    fn from(value: AggregationDto) -> Self {
+        match value {
+            AggregationDto::Count => Aggregation::Count,
+            AggregationDto::Sum { field } => Aggregation::Sum(FieldPath::Id(field)),
+            AggregationDto::Min { field } => Aggregation::Min(FieldPath::Id(field)),
+            AggregationDto::Max { field } => Aggregation::Max(FieldPath::Id(field)),
+            AggregationDto::Average { field } => Aggregation::Average(FieldPath::Id(field)),
+            AggregationDto::DistinctCount { field } => {
+                Aggregation::DistinctCount(FieldPath::Id(field))
+            }
+            AggregationDto::GroupBy { field } => Aggregation::GroupBy(FieldPath::Id(field)),
+            AggregationDto::Histogram { field, interval } => Aggregation::Histogram {
+                field: FieldPath::Id(field),
+                interval,
+            },
+        }
+    }
+}
+
+impl From<AggregationResult> for AggregationResultDto {
+    fn from(value: AggregationResult) -> Self {
+        match value {
+            AggregationResult::Count(count) => AggregationResultDto::Count(count),
+            AggregationResult::Sum(sum) => AggregationResultDto::Sum(match sum {
+                SumResult::None => SumDto::None,
+                SumResult::I64(value) => SumDto::I64(value),
+                SumResult::U64(value) => SumDto::U64(value),
+                SumResult::F64(value) => SumDto::F64(F64Bits(value)),
+                SumResult::BigUint(value) => SumDto::BigUint(value.to_be_bytes().to_vec()),
+            }),
+            AggregationResult::Value(value) => AggregationResultDto::Value(value.map(Into::into)),
+            AggregationResult::Average(value) => AggregationResultDto::Average(value.map(F64Bits)),
+            AggregationResult::Groups(buckets) => AggregationResultDto::Groups(
+                buckets
+                    .into_iter()
+                    .map(|ValueBucket { value, count }| ValueBucketDto {
+                        value: value.into(),
+                        count,
+                    })
+                    .collect(),
+            ),
+            AggregationResult::Histogram(buckets) => AggregationResultDto::Histogram(
+                buckets
+                    .into_iter()
+                    .map(|HistogramBucket { start, count }| HistogramBucketDto { start, count })
+                    .collect(),
+            ),
         }
     }
 }
@@ -171,6 +495,7 @@ impl From<CompareDto> for Compare {
             CompareDto::GreaterOrEqual => Compare::GreaterOrEqual,
             CompareDto::Less => Compare::Less,
             CompareDto::LessOrEqual => Compare::LessOrEqual,
+            CompareDto::NotEqual => Compare::NotEqual,
         }
     }
 }
@@ -190,6 +515,17 @@ impl From<StringMatchMethodDto> for StringMatchMethod {
             StringMatchMethodDto::Exact => StringMatchMethod::Exact,
             StringMatchMethodDto::Prefix => StringMatchMethod::Prefix,
             StringMatchMethodDto::Contains => StringMatchMethod::Contains,
+            StringMatchMethodDto::Suffix => StringMatchMethod::Suffix,
+            StringMatchMethodDto::Glob => StringMatchMethod::Glob,
+        }
+    }
+}
+
+impl From<TextOperatorDto> for TextOperator {
+    fn from(value: TextOperatorDto) -> Self {
+        match value {
+            TextOperatorDto::Any => TextOperator::Any,
+            TextOperatorDto::All => TextOperator::All,
         }
     }
 }
@@ -199,9 +535,340 @@ fn decode_queries(queries: Vec<QueryDto>) -> Result<Vec<Query>, String> {
 }
 
 fn ensure_bridge_version(version: u8) -> Result<(), String> {
-    if version == BRIDGE_VERSION {
+    if (MIN_BRIDGE_VERSION..=BRIDGE_VERSION).contains(&version) {
         Ok(())
     } else {
         Err(format!("Unsupported bridge payload version {version}"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_aggregation, decode_query, decode_search_cursor, decode_sort,
+        encode_aggregation_result, encode_search_cursor,
+    };
+    use crate::planner::{
+        Aggregation, AggregationResult, Compare, DocumentFields, FieldPath, FieldValue,
+        NativeQueryIndex, Query, SortDirection, SortField, StringMatchMethod, TextOperator,
+        ValueBucket,
+    };
+
+    fn text_query_payload(version: u8) -> Vec<u8> {
+        let mut bytes = vec![version, 8];
+        bytes.extend_from_slice(&3_u32.to_le_bytes());
+        bytes.extend_from_slice(&4_u32.to_le_bytes());
+        bytes.extend_from_slice(b"rust");
+        bytes.push(1);
+        bytes
+    }
+
+    #[test]
+    fn decodes_text_queries_and_still_accepts_version_one_payloads() {
+        let Query::Text {
+            field,
+            value,
+            operator,
+        } = decode_query(&text_query_payload(2)).unwrap()
+        else {
+            panic!("expected a text query");
+        };
+        assert_eq!(field, FieldPath::Id(3));
+        assert_eq!(value, "rust");
+        assert_eq!(operator, TextOperator::All);
+
+        assert!(decode_query(&text_query_payload(1)).is_err());
+        assert!(matches!(decode_query(&[1, 0]), Ok(Query::All)));
+        assert!(decode_sort(&[1, 0, 0, 0, 0]).unwrap().is_empty());
+        assert!(decode_query(&[5, 0]).is_err());
+    }
+
+    #[test]
+    fn decodes_set_queries_only_from_version_three_payloads() {
+        // In { field: 2, values: [U64(7), String("a")] }
+        let mut in_query = vec![3, 9, 2, 0, 0, 0, 2, 0, 0, 0, 2];
+        in_query.extend_from_slice(&7_u64.to_le_bytes());
+        in_query.extend_from_slice(&[3, 1, 0, 0, 0, b'a']);
+        assert_eq!(
+            decode_query(&in_query).unwrap(),
+            Query::In {
+                field: FieldPath::Id(2),
+                values: vec![FieldValue::U64(7), FieldValue::from("a")],
+            }
+        );
+
+        // Between { field: 1, low: I64(-1), high: I64(4), inclusive: false }
+        let mut between = vec![3, 10, 1, 0, 0, 0, 1];
+        between.extend_from_slice(&(-1_i64).to_le_bytes());
+        between.push(1);
+        between.extend_from_slice(&4_i64.to_le_bytes());
+        between.push(0);
+        assert_eq!(
+            decode_query(&between).unwrap(),
+            Query::Between {
+                field: FieldPath::Id(1),
+                low: FieldValue::I64(-1),
+                high: FieldValue::I64(4),
+                inclusive: false,
+            }
+        );
+
+        // Range { field: 0, compare: NotEqual, value: Bool(true) }
+        let not_equal = [3, 2, 0, 0, 0, 0, 5, 0, 1];
+        assert_eq!(
+            decode_query(&not_equal).unwrap(),
+            Query::Range {
+                field: FieldPath::Id(0),
+                compare: Compare::NotEqual,
+                value: FieldValue::Bool(true),
+            }
+        );
+
+        for mut payload in [in_query, between, not_equal.to_vec()] {
+            payload[0] = 2;
+            assert!(decode_query(&payload).is_err());
+        }
+        assert!(decode_query(&text_query_payload(3)).is_ok());
+        assert!(matches!(decode_query(&[2, 0]), Ok(Query::All)));
+    }
+
+    #[test]
+    fn decodes_the_set_queries_the_typescript_compiler_folds() {
+        // `writeNativeQuerySpec` for
+        // and([in(1, [u64 1, u64 3]), between(2, u64 2, u64 3, true), range(3, neq, u64 2)])
+        let mut payload = vec![3, 3, 3, 0, 0, 0];
+        payload.extend_from_slice(&[9, 1, 0, 0, 0, 2, 0, 0, 0]);
+        for value in [1_u64, 3] {
+            payload.push(2);
+            payload.extend_from_slice(&value.to_le_bytes());
+        }
+        payload.extend_from_slice(&[10, 2, 0, 0, 0]);
+        for value in [2_u64, 3] {
+            payload.push(2);
+            payload.extend_from_slice(&value.to_le_bytes());
+        }
+        payload.push(1);
+        payload.extend_from_slice(&[2, 3, 0, 0, 0, 5, 2]);
+        payload.extend_from_slice(&2_u64.to_le_bytes());
+
+        let query = decode_query(&payload).unwrap();
+        assert_eq!(
+            query,
+            Query::And(vec![
+                Query::In {
+                    field: FieldPath::Id(1),
+                    values: vec![FieldValue::U64(1), FieldValue::U64(3)],
+                },
+                Query::Between {
+                    field: FieldPath::Id(2),
+                    low: FieldValue::U64(2),
+                    high: FieldValue::U64(3),
+                    inclusive: true,
+                },
+                Query::Range {
+                    field: FieldPath::Id(3),
+                    compare: Compare::NotEqual,
+                    value: FieldValue::U64(2),
+                },
+            ])
+        );
+
+        let mut index = NativeQueryIndex::new();
+        for (id, value) in [("a", 1_u64), ("b", 2), ("c", 3)] {
+            index.put(
+                id,
+                DocumentFields::new()
+                    .with_scalar(FieldPath::Id(1), value)
+                    .with_scalar(FieldPath::Id(2), value)
+                    .with_scalar(FieldPath::Id(3), value),
+            );
+        }
+        assert_eq!(index.search(&query, &[], None), vec!["c"]);
+    }
+
+    #[test]
+    fn decodes_suffix_and_glob_string_matches_from_version_four() {
+        // StringMatch { field: 1, value: "*.rs", method: Glob, case_insensitive: true }
+        let mut glob = vec![4, 6, 1, 0, 0, 0, 4, 0, 0, 0];
+        glob.extend_from_slice(b"*.rs");
+        glob.extend_from_slice(&[4, 1]);
+        assert_eq!(
+            decode_query(&glob).unwrap(),
+            Query::StringMatch {
+                field: FieldPath::Id(1),
+                value: "*.rs".into(),
+                method: StringMatchMethod::Glob,
+                case_insensitive: true,
+            }
+        );
+        glob[0] = 3;
+        assert!(decode_query(&glob).is_err());
+        let method = glob.len() - 2;
+        glob[method] = 2;
+        assert!(decode_query(&glob).is_ok());
+    }
+
+    #[test]
+    fn decodes_float_values_only_from_version_four() {
+        // Exact { field: 1, value: F64(0.5) }
+        let mut exact = vec![4, 1, 1, 0, 0, 0, 5];
+        exact.extend_from_slice(&0.5_f64.to_le_bytes());
+        assert_eq!(
+            decode_query(&exact).unwrap(),
+            Query::Exact {
+                field: FieldPath::Id(1),
+                value: FieldValue::from(0.5),
+            }
+        );
+        // In { field: 1, values: [U64(1), F64(0.5)] }
+        let mut in_query = vec![4, 9, 1, 0, 0, 0, 2, 0, 0, 0, 2];
+        in_query.extend_from_slice(&1_u64.to_le_bytes());
+        in_query.push(5);
+        in_query.extend_from_slice(&0.5_f64.to_le_bytes());
+        assert!(decode_query(&in_query).is_ok());
+        // Search cursor { values: [Some(F64(0.5))], id: "a" }
+        let mut cursor = vec![4, 1, 0, 0, 0, 1, 5];
+        cursor.extend_from_slice(&0.5_f64.to_le_bytes());
+        cursor.extend_from_slice(&[1, 0, 0, 0, b'a']);
+        assert!(decode_search_cursor(&cursor).is_ok());
+
+        for mut payload in [exact, in_query] {
+            payload[0] = 3;
+            let error = decode_query(&payload).unwrap_err();
+            assert_eq!(error, "Query requires bridge payload version 4, got 3");
+        }
+        cursor[0] = 3;
+        assert_eq!(
+            decode_search_cursor(&cursor).unwrap_err(),
+            "Search cursor requires bridge payload version 4, got 3"
+        );
+    }
+
+    #[test]
+    fn decodes_wide_unsigned_values_only_from_version_four() {
+        // Between { field: 2, low: U64(1), high: BigUint(2^64), inclusive: true }
+        let mut between = vec![4, 10, 2, 0, 0, 0, 2];
+        between.extend_from_slice(&1_u64.to_le_bytes());
+        between.extend_from_slice(&[6, 9, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(
+            decode_query(&between).unwrap(),
+            Query::Between {
+                field: FieldPath::Id(2),
+                low: FieldValue::U64(1),
+                high: FieldValue::from_unsigned_be_bytes(&[1, 0, 0, 0, 0, 0, 0, 0, 0]),
+                inclusive: true,
+            }
+        );
+        between[0] = 3;
+        assert_eq!(
+            decode_query(&between).unwrap_err(),
+            "Query requires bridge payload version 4, got 3"
+        );
+    }
+
+    #[test]
+    fn round_trips_aggregation_requests_and_results() {
+        let (query, aggregation) =
+            decode_aggregation(&[2, 0, 7, 5, 0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert!(matches!(query, Query::All));
+        assert_eq!(
+            aggregation,
+            Aggregation::Histogram {
+                field: FieldPath::Id(5),
+                interval: 10,
+            }
+        );
+
+        let encoded = encode_aggregation_result(AggregationResult::Groups(vec![ValueBucket {
+            value: FieldValue::from("a"),
+            count: 3,
+        }]));
+        assert_eq!(
+            encoded,
+            vec![4, 4, 1, 0, 0, 0, 3, 1, 0, 0, 0, b'a', 3, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            encode_aggregation_result(AggregationResult::Value(None)),
+            vec![4, 2, 0]
+        );
+        let mut float = vec![4, 2, 1, 5];
+        float.extend_from_slice(&1.5_f64.to_le_bytes());
+        assert_eq!(
+            encode_aggregation_result(AggregationResult::Value(Some(FieldValue::from(1.5)))),
+            float
+        );
+    }
+
+    #[test]
+    fn search_cursors_round_trip() {
+        let mut index = NativeQueryIndex::new();
+        for id in ["a", "b", "c"] {
+            index.put(id, DocumentFields::new().with_scalar("name", id));
+        }
+        let sort = [SortField {
+            field: "name".into(),
+            direction: SortDirection::Desc,
+        }];
+        let page = index
+            .search_page_after(&Query::All, &sort, None, 1)
+            .unwrap();
+        let cursor = page.cursor.unwrap();
+        let decoded = decode_search_cursor(&encode_search_cursor(&cursor)).unwrap();
+        assert_eq!(decoded, cursor);
+        let next = index
+            .search_page_after(&Query::All, &sort, Some(&decoded), 5)
+            .unwrap();
+        assert_eq!(next.ids, vec!["b", "a"]);
+        assert!(decode_search_cursor(&[9]).is_err());
+    }
+
+    #[test]
+    fn nan_fields_aggregate_and_page_across_the_bridge() {
+        let mut index = NativeQueryIndex::new();
+        for (id, value) in [("a", 1.5), ("b", f64::NAN), ("c", -2.0)] {
+            index.put(id, DocumentFields::new().with_scalar("score", value));
+        }
+        let nan = FieldValue::from(f64::NAN);
+        let mut encoded_nan = vec![4, 2, 1, 5];
+        encoded_nan.extend_from_slice(&f64::NAN.to_bits().to_le_bytes());
+
+        // `total_cmp` orders positive NaN above every number.
+        let max = index
+            .aggregate(&Query::All, &Aggregation::Max("score".into()))
+            .unwrap();
+        assert_eq!(max, AggregationResult::Value(Some(nan.clone())));
+        assert_eq!(encode_aggregation_result(max), encoded_nan);
+
+        let groups = index
+            .aggregate(&Query::All, &Aggregation::GroupBy("score".into()))
+            .unwrap();
+        let AggregationResult::Groups(buckets) = &groups else {
+            panic!("expected groups");
+        };
+        assert!(buckets.iter().any(|bucket| bucket.value == nan));
+        assert_eq!(encode_aggregation_result(groups)[..2], [4, 4]);
+        for aggregation in [
+            Aggregation::Min("score".into()),
+            Aggregation::Sum("score".into()),
+            Aggregation::Average("score".into()),
+        ] {
+            encode_aggregation_result(index.aggregate(&Query::All, &aggregation).unwrap());
+        }
+
+        let sort = [SortField {
+            field: "score".into(),
+            direction: SortDirection::Desc,
+        }];
+        let page = index
+            .search_page_after(&Query::All, &sort, None, 1)
+            .unwrap();
+        assert_eq!(page.ids, vec!["b"]);
+        let cursor = page.cursor.unwrap();
+        let decoded = decode_search_cursor(&encode_search_cursor(&cursor)).unwrap();
+        assert_eq!(decoded, cursor);
+        let next = index
+            .search_page_after(&Query::All, &sort, Some(&decoded), 5)
+            .unwrap();
+        assert_eq!(next.ids, vec!["a", "c"]);
+    }
+}
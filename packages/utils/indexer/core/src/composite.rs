@@ -0,0 +1,230 @@
+use crate::planner::{
+    ordered_range_candidates, union_bitmaps, BigUint, Compare, DocId, DocumentFields, FieldPath,
+    FieldValue, TotalF64,
+};
+use im::OrdMap;
+use roaring::RoaringBitmap;
+use std::sync::Arc;
+
+/// Index over a prefix of equality-matched fields followed by one ordered
+/// suffix field, e.g. `(owner, timestamp)`. Documents are grouped by their
+/// prefix values (under every combination when a prefix field holds several),
+/// and each group orders its documents by their first suffix value the way
+/// the index-wide sort maps do. A query pinning every prefix field with
+/// `Exact` then sorts or range-filters on the suffix within one group instead
+/// of walking the suffix's whole sort index.
+#[derive(Clone, Debug)]
+pub struct CompositeIndex {
+    prefix: Vec<FieldPath>,
+    suffix: FieldPath,
+    groups: im::HashMap<Vec<FieldValue>, CompositeGroup>,
+}
+
+/// The documents of one combination of prefix values.
+#[derive(Clone, Debug, Default)]
+pub struct CompositeGroup {
+    pub(crate) sort_bool: OrdMap<bool, Arc<RoaringBitmap>>,
+    pub(crate) sort_i64: OrdMap<i64, Arc<RoaringBitmap>>,
+    pub(crate) sort_u64: OrdMap<u64, Arc<RoaringBitmap>>,
+    pub(crate) sort_big_uint: OrdMap<BigUint, Arc<RoaringBitmap>>,
+    pub(crate) sort_f64: OrdMap<TotalF64, Arc<RoaringBitmap>>,
+    pub(crate) sort_string: OrdMap<Arc<str>, Arc<RoaringBitmap>>,
+    pub(crate) sort_bytes: OrdMap<Arc<[u8]>, Arc<RoaringBitmap>>,
+    /// Docs without a suffix value.
+    pub(crate) missing: RoaringBitmap,
+    /// Docs with several suffix values: ordered by the first, but a range may
+    /// match any of them.
+    multi_valued: RoaringBitmap,
+    docs: RoaringBitmap,
+}
+
+impl CompositeIndex {
+    pub fn new(prefix: Vec<FieldPath>, suffix: FieldPath) -> Self {
+        Self {
+            prefix,
+            suffix,
+            groups: im::HashMap::new(),
+        }
+    }
+
+    pub fn prefix(&self) -> &[FieldPath] {
+        &self.prefix
+    }
+
+    pub fn suffix(&self) -> &FieldPath {
+        &self.suffix
+    }
+
+    pub fn group(&self, prefix_values: &[FieldValue]) -> Option<&CompositeGroup> {
+        self.groups.get(prefix_values)
+    }
+
+    /// Whether replacing `before` with `after` changes this index's entries.
+    pub fn affected_by(&self, before: &DocumentFields, after: &DocumentFields) -> bool {
+        self.prefix
+            .iter()
+            .chain([&self.suffix])
+            .any(|path| before.scalar_values(path) != after.scalar_values(path))
+    }
+
+    pub fn insert(&mut self, doc_id: DocId, fields: &DocumentFields) {
+        let suffix = fields.scalar_values(&self.suffix).unwrap_or_default();
+        for key in self.prefix_keys(fields) {
+            let group = self.groups.entry(key).or_default();
+            group.docs.insert(doc_id);
+            if suffix.len() > 1 {
+                group.multi_valued.insert(doc_id);
+            }
+            match suffix.first() {
+                Some(value) => group.insert_sort_value(value, doc_id),
+                None => {
+                    group.missing.insert(doc_id);
+                }
+            }
+        }
+    }
+
+    /// `fields` must be what `doc_id` was inserted with.
+    pub fn remove(&mut self, doc_id: DocId, fields: &DocumentFields) {
+        let suffix = fields.scalar_values(&self.suffix).unwrap_or_default();
+        for key in self.prefix_keys(fields) {
+            let Some(group) = self.groups.get_mut(&key) else {
+                continue;
+            };
+            group.docs.remove(doc_id);
+            group.multi_valued.remove(doc_id);
+            match suffix.first() {
+                Some(value) => group.remove_sort_value(value, doc_id),
+                None => {
+                    group.missing.remove(doc_id);
+                }
+            }
+            if group.docs.is_empty() {
+                self.groups.remove(&key);
+            }
+        }
+    }
+
+    /// Every combination of the document's prefix values; none when a prefix
+    /// field has no value.
+    fn prefix_keys(&self, fields: &DocumentFields) -> Vec<Vec<FieldValue>> {
+        let mut keys = vec![Vec::with_capacity(self.prefix.len())];
+        for path in &self.prefix {
+            let values = fields.scalar_values(path).unwrap_or_default();
+            keys = keys
+                .into_iter()
+                .flat_map(|key| {
+                    values.iter().map(move |value| {
+                        let mut key = key.clone();
+                        key.push(value.clone());
+                        key
+                    })
+                })
+                .collect();
+        }
+        keys
+    }
+}
+
+impl CompositeGroup {
+    pub fn len(&self) -> u64 {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    pub fn docs(&self) -> &RoaringBitmap {
+        &self.docs
+    }
+
+    /// Superset of the group's docs with a suffix value matching
+    /// `compare value`, with the planner's cross-kind rules.
+    pub fn range_candidates(&self, compare: Compare, value: &FieldValue) -> RoaringBitmap {
+        let mut candidates = match value {
+            FieldValue::I64(value) => {
+                ordered_range_candidates(Some(&self.sort_i64), compare, value)
+            }
+            FieldValue::U64(value) => {
+                let mut candidates = ordered_range_candidates(Some(&self.sort_u64), compare, value);
+                if matches!(
+                    compare,
+                    Compare::Greater | Compare::GreaterOrEqual | Compare::NotEqual
+                ) {
+                    candidates |= union_bitmaps(self.sort_big_uint.values());
+                }
+                candidates
+            }
+            FieldValue::BigUint(value) => {
+                let mut candidates =
+                    ordered_range_candidates(Some(&self.sort_big_uint), compare, value);
+                if matches!(
+                    compare,
+                    Compare::Less | Compare::LessOrEqual | Compare::NotEqual
+                ) {
+                    candidates |= union_bitmaps(self.sort_u64.values());
+                }
+                candidates
+            }
+            FieldValue::F64(value) => {
+                ordered_range_candidates(Some(&self.sort_f64), compare, value)
+            }
+            _ => return RoaringBitmap::new(),
+        };
+        candidates |= &self.multi_valued;
+        candidates
+    }
+
+    fn insert_sort_value(&mut self, value: &FieldValue, doc_id: DocId) {
+        match value {
+            FieldValue::Bool(value) => insert_ordered(&mut self.sort_bool, *value, doc_id),
+            FieldValue::I64(value) => insert_ordered(&mut self.sort_i64, *value, doc_id),
+            FieldValue::U64(value) => insert_ordered(&mut self.sort_u64, *value, doc_id),
+            FieldValue::BigUint(value) => {
+                insert_ordered(&mut self.sort_big_uint, value.clone(), doc_id)
+            }
+            FieldValue::F64(value) => insert_ordered(&mut self.sort_f64, *value, doc_id),
+            FieldValue::String(value) => {
+                insert_ordered(&mut self.sort_string, Arc::clone(value), doc_id)
+            }
+            FieldValue::Bytes(value) => {
+                insert_ordered(&mut self.sort_bytes, Arc::clone(value), doc_id)
+            }
+        }
+    }
+
+    fn remove_sort_value(&mut self, value: &FieldValue, doc_id: DocId) {
+        match value {
+            FieldValue::Bool(value) => remove_ordered(&mut self.sort_bool, value, doc_id),
+            FieldValue::I64(value) => remove_ordered(&mut self.sort_i64, value, doc_id),
+            FieldValue::U64(value) => remove_ordered(&mut self.sort_u64, value, doc_id),
+            FieldValue::BigUint(value) => remove_ordered(&mut self.sort_big_uint, value, doc_id),
+            FieldValue::F64(value) => remove_ordered(&mut self.sort_f64, value, doc_id),
+            FieldValue::String(value) => remove_ordered(&mut self.sort_string, value, doc_id),
+            FieldValue::Bytes(value) => remove_ordered(&mut self.sort_bytes, value, doc_id),
+        }
+    }
+}
+
+fn insert_ordered<K: Ord + Clone>(
+    index: &mut OrdMap<K, Arc<RoaringBitmap>>,
+    key: K,
+    doc_id: DocId,
+) {
+    Arc::make_mut(index.entry(key).or_default()).insert(doc_id);
+}
+
+fn remove_ordered<K: Ord + Clone>(
+    index: &mut OrdMap<K, Arc<RoaringBitmap>>,
+    key: &K,
+    doc_id: DocId,
+) {
+    let Some(docs) = index.get_mut(key) else {
+        return;
+    };
+    Arc::make_mut(docs).remove(doc_id);
+    if docs.is_empty() {
+        index.remove(key);
+    }
+}
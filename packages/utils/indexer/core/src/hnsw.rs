@@ -0,0 +1,579 @@
+use crate::planner::{vector_distance, DocId, VectorMetric};
+use im::HashMap;
+use roaring::RoaringBitmap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+const LEVEL_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Construction parameters for a per-field HNSW graph.
+///
+/// The graph is built for a single metric; vector searches using a different
+/// metric fall back to the exact scan.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VectorIndexConfig {
+    pub metric: VectorMetric,
+    /// Target out-degree on upper layers (`M`). Layer 0 keeps `2 * M` links.
+    pub max_connections: usize,
+    pub ef_construction: usize,
+}
+
+impl VectorIndexConfig {
+    pub fn new(metric: VectorMetric) -> Self {
+        Self {
+            metric,
+            max_connections: 16,
+            ef_construction: 200,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct HnswNode {
+    // `neighbors[layer]` for every layer the node participates in, so the
+    // node's level is `neighbors.len() - 1`.
+    neighbors: Vec<Vec<DocId>>,
+}
+
+impl HnswNode {
+    fn level(&self) -> usize {
+        self.neighbors.len() - 1
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Candidate {
+    distance: f32,
+    doc_id: DocId,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then_with(|| self.doc_id.cmp(&other.doc_id))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Incrementally maintained hierarchical navigable small world graph over the
+/// vectors of one field.
+///
+/// The graph only stores adjacency; vectors stay in the owning
+/// `NativeQueryIndex` and are passed in on every call so they are not
+/// duplicated. Deleted nodes are unlinked from their own neighbors and those
+/// neighbors are reconnected locally; any remaining one-way links to a removed
+/// (or recycled) doc id are tolerated and skipped during traversal.
+#[derive(Clone, Debug)]
+pub struct HnswIndex {
+    config: VectorIndexConfig,
+    dimension: Option<usize>,
+    nodes: HashMap<DocId, HnswNode>,
+    entry_point: Option<DocId>,
+    level_state: u64,
+}
+
+impl HnswIndex {
+    pub fn new(config: VectorIndexConfig) -> Self {
+        Self {
+            config,
+            dimension: None,
+            nodes: HashMap::new(),
+            entry_point: None,
+            level_state: LEVEL_SEED,
+        }
+    }
+
+    pub fn config(&self) -> VectorIndexConfig {
+        self.config
+    }
+
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn contains(&self, doc_id: DocId) -> bool {
+        self.nodes.contains_key(&doc_id)
+    }
+
+    pub fn insert(&mut self, doc_id: DocId, vectors: &HashMap<DocId, Vec<f32>>) {
+        if self.nodes.contains_key(&doc_id) {
+            self.remove(doc_id, vectors);
+        }
+        let Some(query) = vectors.get(&doc_id) else {
+            return;
+        };
+        // The graph is only meaningful within one dimensionality; vectors of
+        // another length are left to the exact scan, which skips them for
+        // queries of this index's dimension anyway.
+        match self.dimension {
+            Some(dimension) if dimension != query.len() => return,
+            Some(_) => {}
+            None => self.dimension = Some(query.len()),
+        }
+
+        let level = self.random_level();
+        let Some(entry_point) = self.entry_point else {
+            self.nodes.insert(
+                doc_id,
+                HnswNode {
+                    neighbors: vec![Vec::new(); level + 1],
+                },
+            );
+            self.entry_point = Some(doc_id);
+            return;
+        };
+
+        let top_level = self.nodes[&entry_point].level();
+        let mut entry = Candidate {
+            distance: self.distance(query, entry_point, vectors),
+            doc_id: entry_point,
+        };
+        for layer in (level + 1..=top_level).rev() {
+            entry = self.greedy_closest(query, entry, layer, vectors);
+        }
+
+        let mut neighbors = vec![Vec::new(); level + 1];
+        let mut entries = vec![entry];
+        for layer in (0..=level.min(top_level)).rev() {
+            let found = self.search_layer(
+                query,
+                &entries,
+                self.config.ef_construction.max(1),
+                layer,
+                None,
+                vectors,
+            );
+            neighbors[layer] = self.select_neighbors(&found, self.max_links(layer), vectors);
+            entries = found;
+        }
+
+        self.nodes.insert(
+            doc_id,
+            HnswNode {
+                neighbors: neighbors.clone(),
+            },
+        );
+        for (layer, links) in neighbors.into_iter().enumerate() {
+            for neighbor in links {
+                self.link(neighbor, doc_id, layer, vectors);
+            }
+        }
+        if level > top_level {
+            self.entry_point = Some(doc_id);
+        }
+    }
+
+    pub fn remove(&mut self, doc_id: DocId, vectors: &HashMap<DocId, Vec<f32>>) {
+        let Some(node) = self.nodes.remove(&doc_id) else {
+            return;
+        };
+
+        for (layer, links) in node.neighbors.iter().enumerate() {
+            for &neighbor in links {
+                let Some(vector) = vectors.get(&neighbor) else {
+                    continue;
+                };
+                let Some(existing) = self
+                    .nodes
+                    .get(&neighbor)
+                    .and_then(|node| node.neighbors.get(layer))
+                else {
+                    continue;
+                };
+                let mut pool: HashSet<DocId> = existing.iter().copied().collect();
+                pool.extend(links.iter().copied());
+                pool.remove(&neighbor);
+                pool.remove(&doc_id);
+                let mut candidates: Vec<Candidate> = pool
+                    .into_iter()
+                    .filter(|candidate| self.is_linkable(*candidate, layer))
+                    .map(|candidate| Candidate {
+                        distance: self.distance(vector, candidate, vectors),
+                        doc_id: candidate,
+                    })
+                    .collect();
+                candidates.sort();
+                let repaired = self.select_neighbors(&candidates, self.max_links(layer), vectors);
+                if let Some(node) = self.nodes.get_mut(&neighbor) {
+                    node.neighbors[layer] = repaired;
+                }
+            }
+        }
+
+        if self.entry_point == Some(doc_id) {
+            self.entry_point = self
+                .nodes
+                .iter()
+                .max_by(|(left_id, left), (right_id, right)| {
+                    left.level()
+                        .cmp(&right.level())
+                        .then_with(|| right_id.cmp(left_id))
+                })
+                .map(|(doc_id, _)| *doc_id);
+        }
+        if self.nodes.is_empty() {
+            self.dimension = None;
+        }
+    }
+
+    /// Return up to `limit` nearest doc ids (closest first) restricted to
+    /// `allowed` when given. `ef` is the size of the dynamic candidate list on
+    /// the base layer; raising it trades latency for recall.
+    pub fn search(
+        &self,
+        query: &[f32],
+        limit: usize,
+        ef: usize,
+        allowed: Option<&RoaringBitmap>,
+        vectors: &HashMap<DocId, Vec<f32>>,
+    ) -> Vec<(DocId, f32)> {
+        let Some(entry_point) = self.entry_point else {
+            return Vec::new();
+        };
+        if limit == 0 || self.dimension != Some(query.len()) {
+            return Vec::new();
+        }
+        let mut entry = Candidate {
+            distance: self.distance(query, entry_point, vectors),
+            doc_id: entry_point,
+        };
+        for layer in (1..=self.nodes[&entry_point].level()).rev() {
+            entry = self.greedy_closest(query, entry, layer, vectors);
+        }
+        let mut found = self.search_layer(query, &[entry], ef.max(limit), 0, allowed, vectors);
+        found.truncate(limit);
+        found
+            .into_iter()
+            .map(|candidate| (candidate.doc_id, candidate.distance))
+            .collect()
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        let max_connections = self.config.max_connections.max(2);
+        if layer == 0 {
+            max_connections * 2
+        } else {
+            max_connections
+        }
+    }
+
+    fn is_linkable(&self, doc_id: DocId, layer: usize) -> bool {
+        self.nodes
+            .get(&doc_id)
+            .is_some_and(|node| node.level() >= layer)
+    }
+
+    fn distance(&self, query: &[f32], doc_id: DocId, vectors: &HashMap<DocId, Vec<f32>>) -> f32 {
+        vectors
+            .get(&doc_id)
+            .map(|vector| vector_distance(query, vector, self.config.metric))
+            .unwrap_or(f32::INFINITY)
+    }
+
+    fn greedy_closest(
+        &self,
+        query: &[f32],
+        mut current: Candidate,
+        layer: usize,
+        vectors: &HashMap<DocId, Vec<f32>>,
+    ) -> Candidate {
+        loop {
+            let mut improved = false;
+            let links = self
+                .nodes
+                .get(&current.doc_id)
+                .and_then(|node| node.neighbors.get(layer));
+            for &neighbor in links.into_iter().flatten() {
+                if !self.is_linkable(neighbor, layer) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    distance: self.distance(query, neighbor, vectors),
+                    doc_id: neighbor,
+                };
+                if candidate < current {
+                    current = candidate;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Best-first search on one layer. Every reachable node is a traversal
+    /// candidate, but only `allowed` nodes enter the result set, so filtered
+    /// searches keep walking through disallowed regions of the graph.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entries: &[Candidate],
+        ef: usize,
+        layer: usize,
+        allowed: Option<&RoaringBitmap>,
+        vectors: &HashMap<DocId, Vec<f32>>,
+    ) -> Vec<Candidate> {
+        let is_allowed = |doc_id: DocId| allowed.is_none_or(|allowed| allowed.contains(doc_id));
+        let mut visited = RoaringBitmap::new();
+        let mut candidates = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+        for entry in entries {
+            if !visited.insert(entry.doc_id) {
+                continue;
+            }
+            candidates.push(Reverse(*entry));
+            if is_allowed(entry.doc_id) {
+                results.push(*entry);
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|worst| current > *worst) {
+                break;
+            }
+            let links = self
+                .nodes
+                .get(&current.doc_id)
+                .and_then(|node| node.neighbors.get(layer));
+            for &neighbor in links.into_iter().flatten() {
+                if !self.is_linkable(neighbor, layer) || !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    distance: self.distance(query, neighbor, vectors),
+                    doc_id: neighbor,
+                };
+                if results.len() < ef || results.peek().is_some_and(|worst| candidate < *worst) {
+                    candidates.push(Reverse(candidate));
+                    if is_allowed(neighbor) {
+                        results.push(candidate);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Neighbor selection heuristic from the HNSW paper: keep a candidate only
+    /// if it is closer to the base node than to every neighbor already kept,
+    /// then top up with the closest discarded candidates.
+    fn select_neighbors(
+        &self,
+        candidates: &[Candidate],
+        max_links: usize,
+        vectors: &HashMap<DocId, Vec<f32>>,
+    ) -> Vec<DocId> {
+        let mut selected: Vec<Candidate> = Vec::with_capacity(max_links);
+        let mut discarded = Vec::new();
+        for candidate in candidates {
+            if selected.len() >= max_links {
+                break;
+            }
+            let Some(vector) = vectors.get(&candidate.doc_id) else {
+                continue;
+            };
+            let diverse = selected
+                .iter()
+                .all(|kept| self.distance(vector, kept.doc_id, vectors) > candidate.distance);
+            if diverse {
+                selected.push(*candidate);
+            } else {
+                discarded.push(*candidate);
+            }
+        }
+        for candidate in discarded {
+            if selected.len() >= max_links {
+                break;
+            }
+            selected.push(candidate);
+        }
+        selected
+            .into_iter()
+            .map(|candidate| candidate.doc_id)
+            .collect()
+    }
+
+    fn link(&mut self, from: DocId, to: DocId, layer: usize, vectors: &HashMap<DocId, Vec<f32>>) {
+        let max_links = self.max_links(layer);
+        let Some(links) = self
+            .nodes
+            .get_mut(&from)
+            .and_then(|node| node.neighbors.get_mut(layer))
+        else {
+            return;
+        };
+        if links.contains(&to) {
+            return;
+        }
+        links.push(to);
+        if links.len() <= max_links {
+            return;
+        }
+        let links = links.clone();
+        let Some(vector) = vectors.get(&from) else {
+            return;
+        };
+        let mut candidates: Vec<Candidate> = links
+            .into_iter()
+            .filter(|candidate| self.is_linkable(*candidate, layer))
+            .map(|candidate| Candidate {
+                distance: self.distance(vector, candidate, vectors),
+                doc_id: candidate,
+            })
+            .collect();
+        candidates.sort();
+        let pruned = self.select_neighbors(&candidates, max_links, vectors);
+        if let Some(node) = self.nodes.get_mut(&from) {
+            node.neighbors[layer] = pruned;
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        // splitmix64 keeps level assignment deterministic for a given insert
+        // order without pulling in an rng crate.
+        self.level_state = self.level_state.wrapping_add(LEVEL_SEED);
+        let mut value = self.level_state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^= value >> 31;
+        let uniform = ((value >> 11) as f64 + 1.0) / ((1_u64 << 53) as f64 + 1.0);
+        let level_multiplier = 1.0 / (self.config.max_connections.max(2) as f64).ln();
+        (-uniform.ln() * level_multiplier).floor() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HnswIndex, VectorIndexConfig};
+    use crate::planner::{vector_distance, DocId, VectorMetric};
+    use im::HashMap;
+    use roaring::RoaringBitmap;
+
+    fn pseudo_random_vectors(count: u32, dimension: usize) -> HashMap<DocId, Vec<f32>> {
+        let mut state = 7_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 10_000) as f32 / 10_000.0 - 0.5
+        };
+        (0..count)
+            .map(|doc_id| (doc_id, (0..dimension).map(|_| next()).collect()))
+            .collect()
+    }
+
+    fn exact_top(
+        vectors: &im::HashMap<DocId, Vec<f32>>,
+        query: &[f32],
+        limit: usize,
+        allowed: Option<&RoaringBitmap>,
+    ) -> Vec<DocId> {
+        let mut scored: Vec<_> = vectors
+            .iter()
+            .filter(|(doc_id, _)| allowed.is_none_or(|allowed| allowed.contains(**doc_id)))
+            .map(|(doc_id, vector)| (vector_distance(query, vector, VectorMetric::L2), *doc_id))
+            .collect();
+        scored.sort_by(|left, right| left.0.total_cmp(&right.0).then(left.1.cmp(&right.1)));
+        scored.into_iter().take(limit).map(|(_, id)| id).collect()
+    }
+
+    fn test_index() -> HnswIndex {
+        HnswIndex::new(VectorIndexConfig {
+            max_connections: 8,
+            ef_construction: 32,
+            ..VectorIndexConfig::new(VectorMetric::L2)
+        })
+    }
+
+    fn recall(expected: &[DocId], actual: &[(DocId, f32)]) -> f32 {
+        let hits = actual
+            .iter()
+            .filter(|(doc_id, _)| expected.contains(doc_id))
+            .count();
+        hits as f32 / expected.len() as f32
+    }
+
+    #[test]
+    fn approximate_search_has_high_recall() {
+        let vectors = pseudo_random_vectors(500, 8);
+        let mut index = test_index();
+        for doc_id in 0..500 {
+            index.insert(doc_id, &vectors);
+        }
+        let queries = pseudo_random_vectors(10, 8);
+        let mut total = 0.0;
+        for query in queries.values() {
+            let expected = exact_top(&vectors, query, 10, None);
+            total += recall(&expected, &index.search(query, 10, 64, None, &vectors));
+        }
+        assert!(total / 10.0 >= 0.9, "recall {}", total / 10.0);
+    }
+
+    #[test]
+    fn filtered_search_only_returns_allowed_docs() {
+        let vectors = pseudo_random_vectors(500, 4);
+        let mut index = test_index();
+        for doc_id in 0..500 {
+            index.insert(doc_id, &vectors);
+        }
+        let allowed: RoaringBitmap = (0..500).filter(|doc_id| doc_id % 5 == 0).collect();
+        let query = vec![0.1, -0.2, 0.3, 0.0];
+        let results = index.search(&query, 5, 64, Some(&allowed), &vectors);
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|(doc_id, _)| allowed.contains(*doc_id)));
+        assert!(recall(&exact_top(&vectors, &query, 5, Some(&allowed)), &results) >= 0.8);
+    }
+
+    #[test]
+    fn removed_nodes_are_never_returned() {
+        let vectors = pseudo_random_vectors(300, 4);
+        let mut index = test_index();
+        for doc_id in 0..300 {
+            index.insert(doc_id, &vectors);
+        }
+        for doc_id in (0..300).filter(|doc_id| doc_id % 2 == 0) {
+            index.remove(doc_id, &vectors);
+        }
+        assert_eq!(index.len(), 150);
+        let query = vec![0.0, 0.0, 0.0, 0.0];
+        let results = index.search(&query, 20, 64, None, &vectors);
+        assert_eq!(results.len(), 20);
+        assert!(results.iter().all(|(doc_id, _)| doc_id % 2 == 1));
+
+        for doc_id in 0..300 {
+            index.remove(doc_id, &vectors);
+        }
+        assert!(index.is_empty());
+        assert_eq!(index.dimension(), None);
+        assert!(index.search(&query, 5, 16, None, &vectors).is_empty());
+    }
+}
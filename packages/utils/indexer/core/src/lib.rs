@@ -1,6 +1,11 @@
 pub mod codec;
+pub mod composite;
+pub mod hnsw;
+pub mod migration;
+pub mod ngram;
 pub mod persistence;
 pub mod planner;
 pub mod schema;
 pub mod storage;
+pub mod text;
 pub mod wire;
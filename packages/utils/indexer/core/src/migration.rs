@@ -0,0 +1,332 @@
+use crate::planner::{FieldPath, NativeQueryIndex};
+use crate::schema::{
+    extract_encoded_document_fields_from_parts_with_byte_limits, NativeSchemaIr, SchemaError,
+};
+use crate::storage::ByteStorage;
+
+/// Field ids whose declaration differs between two schema IRs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    pub added: Vec<u32>,
+    pub removed: Vec<u32>,
+    /// Declared in both, with another type, container or variant table.
+    pub retyped: Vec<u32>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.retyped.is_empty()
+    }
+
+    /// Whether `field` holds values extracted under a different declaration.
+    pub fn touches(&self, field: &FieldPath) -> bool {
+        let FieldPath::Id(id) = field else {
+            return false;
+        };
+        self.added.binary_search(id).is_ok()
+            || self.removed.binary_search(id).is_ok()
+            || self.retyped.binary_search(id).is_ok()
+    }
+}
+
+/// An index left stale by a schema change until the stored documents are
+/// reindexed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvalidatedIndex {
+    /// The exact, range and sort indexes every field has.
+    Field(FieldPath),
+    Text(FieldPath),
+    Trigram(FieldPath),
+    Composite {
+        prefix: Vec<FieldPath>,
+        suffix: FieldPath,
+    },
+}
+
+pub fn diff_schema_ir(before: &NativeSchemaIr, after: &NativeSchemaIr) -> SchemaDiff {
+    let before = before.field_shapes();
+    let after = after.field_shapes();
+    let mut diff = SchemaDiff::default();
+    for (field, shapes) in &after {
+        match before.get(field) {
+            None => diff.added.push(*field),
+            Some(previous) if previous != shapes => diff.retyped.push(*field),
+            Some(_) => {}
+        }
+    }
+    diff.removed = before
+        .keys()
+        .filter(|field| !after.contains_key(field))
+        .copied()
+        .collect();
+    diff
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// Keys stored when the migration started.
+    pub total: usize,
+    pub processed: usize,
+    pub reindexed: usize,
+    /// Deleted from storage before the migration reached them.
+    pub missing: usize,
+    /// Could not be extracted under the new schema; their index entries are
+    /// left as they were.
+    pub failed: usize,
+}
+
+/// Incremental reindex of stored encoded documents under a new schema IR.
+/// The keys are captured when the migration starts and each `step` re-extracts
+/// the next batch of them, so a migration can be spread over many calls and
+/// resumed after any of them. Documents written through the new schema while
+/// it runs are already current; reindexing them again is harmless.
+pub struct SchemaMigration {
+    schema: NativeSchemaIr,
+    diff: SchemaDiff,
+    keys: Vec<String>,
+    byte_element_index_limit: usize,
+    byte_exact_index_limit: usize,
+    progress: MigrationProgress,
+    failures: Vec<(String, SchemaError)>,
+}
+
+impl SchemaMigration {
+    pub fn new(
+        before: &NativeSchemaIr,
+        after: NativeSchemaIr,
+        storage: &impl ByteStorage,
+        byte_element_index_limit: usize,
+        byte_exact_index_limit: usize,
+    ) -> Self {
+        let keys: Vec<String> = storage
+            .entries()
+            .into_iter()
+            .map(|(key, _)| key.to_string())
+            .collect();
+        Self {
+            diff: diff_schema_ir(before, &after),
+            schema: after,
+            progress: MigrationProgress {
+                total: keys.len(),
+                ..MigrationProgress::default()
+            },
+            keys,
+            byte_element_index_limit,
+            byte_exact_index_limit,
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn diff(&self) -> &SchemaDiff {
+        &self.diff
+    }
+
+    pub fn schema(&self) -> &NativeSchemaIr {
+        &self.schema
+    }
+
+    pub fn progress(&self) -> MigrationProgress {
+        self.progress
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.progress.processed == self.keys.len()
+    }
+
+    /// Keys that could not be extracted, with why.
+    pub fn failures(&self) -> &[(String, SchemaError)] {
+        &self.failures
+    }
+
+    /// Reindex up to `budget` more documents.
+    pub fn step(
+        &mut self,
+        storage: &impl ByteStorage,
+        index: &mut NativeQueryIndex,
+        budget: usize,
+    ) -> MigrationProgress {
+        let end = self.keys.len().min(self.progress.processed + budget);
+        for key in &self.keys[self.progress.processed..end] {
+            self.progress.processed += 1;
+            let Some(bytes) = storage.get(key) else {
+                self.progress.missing += 1;
+                continue;
+            };
+            match extract_encoded_document_fields_from_parts_with_byte_limits(
+                &self.schema,
+                bytes,
+                &[],
+                self.byte_element_index_limit,
+                self.byte_exact_index_limit,
+            ) {
+                Ok(fields) => {
+                    index.put(key.clone(), fields);
+                    self.progress.reindexed += 1;
+                }
+                Err(error) => {
+                    self.progress.failed += 1;
+                    self.failures.push((key.clone(), error));
+                }
+            }
+        }
+        self.progress
+    }
+
+    /// Finish the migration, returning the schema to extract with from now on.
+    pub fn into_schema(self) -> NativeSchemaIr {
+        self.schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_schema_ir, InvalidatedIndex, MigrationProgress, SchemaMigration};
+    use crate::planner::{Compare, FieldPath, FieldValue, NativeQueryIndex, Query};
+    use crate::schema::{decode_native_schema_ir, extract_encoded_document_fields, SchemaError};
+    use crate::storage::{ByteStorage, MemoryByteStorage};
+
+    fn write_u32(out: &mut Vec<u8>, value: u32) {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_string(out: &mut Vec<u8>, value: &str) {
+        write_u32(out, value.len() as u32);
+        out.extend_from_slice(value.as_bytes());
+    }
+
+    fn write_field(out: &mut Vec<u8>, key: &str, field: u32, tag: u8) {
+        write_string(out, key);
+        write_u32(out, field);
+        write_u32(out, field + 100);
+        out.push(tag);
+    }
+
+    /// `{ id: string, score: u32, legacy: bool }`
+    fn schema_v1() -> Vec<u8> {
+        let mut out = vec![1, 14];
+        write_u32(&mut out, 0);
+        write_u32(&mut out, 3);
+        write_field(&mut out, "id", 1, 12);
+        write_field(&mut out, "score", 2, 3);
+        write_field(&mut out, "legacy", 3, 0);
+        out
+    }
+
+    /// `{ id: string, score: u64, tag: string }`
+    fn schema_v2() -> Vec<u8> {
+        let mut out = vec![1, 14];
+        write_u32(&mut out, 0);
+        write_u32(&mut out, 3);
+        write_field(&mut out, "id", 1, 12);
+        write_field(&mut out, "score", 2, 4);
+        write_field(&mut out, "tag", 4, 12);
+        out
+    }
+
+    fn document_v1(id: &str, score: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_string(&mut out, id);
+        write_u32(&mut out, score);
+        out.push(1);
+        out
+    }
+
+    fn document_v2(id: &str, score: u64, tag: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_string(&mut out, id);
+        out.extend_from_slice(&score.to_le_bytes());
+        write_string(&mut out, tag);
+        out
+    }
+
+    #[test]
+    fn reindexes_stored_documents_under_a_new_schema_in_steps() {
+        let v1 = decode_native_schema_ir(&schema_v1()).unwrap();
+        let v2 = decode_native_schema_ir(&schema_v2()).unwrap();
+        let mut storage = MemoryByteStorage::new();
+        let mut index = NativeQueryIndex::new();
+        index.configure_trigram_index(FieldPath::Id(4));
+        index.configure_composite_index(vec![FieldPath::Id(1)], FieldPath::Id(2));
+        for doc in 0..5 {
+            let id = format!("doc-{doc}");
+            let bytes = document_v1(&id, doc);
+            index.put(
+                id.clone(),
+                extract_encoded_document_fields(&v1, &bytes, 0).unwrap(),
+            );
+            // The stored values were rewritten in the new layout.
+            storage.put(id.clone(), document_v2(&id, u64::from(doc) << 40, "new"));
+        }
+        storage.put("doc-5".to_string(), vec![0xff]);
+
+        let mut migration = SchemaMigration::new(&v1, v2, &storage, 0, usize::MAX);
+        assert_eq!(migration.diff().added, vec![4]);
+        assert_eq!(migration.diff().removed, vec![3]);
+        assert_eq!(migration.diff().retyped, vec![2]);
+        assert_eq!(
+            index.indexes_invalidated_by(migration.diff()),
+            vec![
+                InvalidatedIndex::Field(FieldPath::Id(2)),
+                InvalidatedIndex::Field(FieldPath::Id(3)),
+                InvalidatedIndex::Field(FieldPath::Id(4)),
+                InvalidatedIndex::Trigram(FieldPath::Id(4)),
+                InvalidatedIndex::Composite {
+                    prefix: vec![FieldPath::Id(1)],
+                    suffix: FieldPath::Id(2),
+                },
+            ]
+        );
+
+        let large = Query::Range {
+            field: FieldPath::Id(2),
+            compare: Compare::GreaterOrEqual,
+            value: FieldValue::U64(1 << 40),
+        };
+        assert_eq!(
+            migration.step(&storage, &mut index, 2),
+            MigrationProgress {
+                total: 6,
+                processed: 2,
+                reindexed: 2,
+                missing: 0,
+                failed: 0,
+            }
+        );
+        assert_eq!(index.count(&large), 1);
+
+        storage.delete("doc-3");
+        while !migration.is_complete() {
+            migration.step(&storage, &mut index, 2);
+        }
+        assert_eq!(
+            migration.progress(),
+            MigrationProgress {
+                total: 6,
+                processed: 6,
+                reindexed: 4,
+                missing: 1,
+                failed: 1,
+            }
+        );
+        assert_eq!(
+            migration.failures(),
+            &[("doc-5".to_string(), SchemaError::UnexpectedEof)]
+        );
+        assert_eq!(index.count(&large), 3);
+        assert_eq!(
+            index.count(&Query::Exact {
+                field: FieldPath::Id(4),
+                value: FieldValue::from("new"),
+            }),
+            4
+        );
+        assert!(index
+            .document_fields_by_id("doc-0")
+            .unwrap()
+            .scalar_values(&FieldPath::Id(3))
+            .is_none());
+
+        let v2 = migration.into_schema();
+        assert!(diff_schema_ir(&v2, &v2).is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::planner::{DocId, DocSet, FieldValue, StringMatchMethod};
+use im::HashMap;
+use roaring::RoaringBitmap;
+use std::sync::Arc;
+
+// Sentinels framing every indexed string so prefix, suffix and exact patterns
+// get trigrams anchored to the ends of the value.
+const START: char = '\u{2}';
+const END: char = '\u{3}';
+
+type Trigram = [char; 3];
+
+/// Unicode full case folding, for caseless comparison. Lowercasing is the
+/// same fold for almost every character; the exceptions below are the ones
+/// where `CaseFolding.txt` maps to something else (`ß` to `ss`, final sigma to
+/// `σ`, ligatures to their letters, ...). Unlike `str::to_lowercase` the fold
+/// is context free, so `fold_case(a + b) == fold_case(a) + fold_case(b)`.
+pub fn fold_case(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for character in text.chars() {
+        match full_fold(character) {
+            Some(replacement) => folded.push_str(replacement),
+            None => folded.extend(character.to_lowercase()),
+        }
+    }
+    folded
+}
+
+fn full_fold(character: char) -> Option<&'static str> {
+    Some(match character {
+        '\u{00DF}' | '\u{1E9E}' => "ss",
+        '\u{0149}' => "\u{02BC}n",
+        '\u{017F}' => "s",
+        '\u{01F0}' => "j\u{030C}",
+        '\u{0345}' | '\u{1FBE}' => "\u{03B9}",
+        '\u{03C2}' => "\u{03C3}",
+        '\u{03D0}' => "\u{03B2}",
+        '\u{03D1}' => "\u{03B8}",
+        '\u{03D5}' => "\u{03C6}",
+        '\u{03D6}' => "\u{03C0}",
+        '\u{03F0}' => "\u{03BA}",
+        '\u{03F1}' => "\u{03C1}",
+        '\u{03F5}' => "\u{03B5}",
+        '\u{0587}' => "\u{0565}\u{0582}",
+        '\u{1E96}' => "h\u{0331}",
+        '\u{1E97}' => "t\u{0308}",
+        '\u{1E98}' => "w\u{030A}",
+        '\u{1E99}' => "y\u{030A}",
+        '\u{1E9A}' => "a\u{02BE}",
+        '\u{1E9B}' => "\u{1E61}",
+        '\u{FB00}' => "ff",
+        '\u{FB01}' => "fi",
+        '\u{FB02}' => "fl",
+        '\u{FB03}' => "ffi",
+        '\u{FB04}' => "ffl",
+        '\u{FB05}' | '\u{FB06}' => "st",
+        _ => return None,
+    })
+}
+
+/// Trigram index over the case-folded string values of one field. It only
+/// prunes: a document is a candidate for a pattern when it holds every
+/// trigram of the pattern's literal parts, and the planner then verifies each
+/// candidate, so case-sensitive and caseless matching share one index.
+#[derive(Clone, Debug, Default)]
+pub struct TrigramIndex {
+    postings: HashMap<Trigram, Arc<RoaringBitmap>>,
+    /// Docs with at least one string value: the candidates of a pattern too
+    /// short to contain a trigram.
+    docs: DocSet,
+}
+
+impl TrigramIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.docs.len() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    pub fn insert(&mut self, doc_id: DocId, values: &[FieldValue]) {
+        for value in string_values(values) {
+            self.docs.insert(doc_id);
+            for trigram in trigrams(&framed(value, true, true)) {
+                Arc::make_mut(self.postings.entry(trigram).or_default()).insert(doc_id);
+            }
+        }
+    }
+
+    /// `values` must be everything `doc_id` was inserted with.
+    pub fn remove(&mut self, doc_id: DocId, values: &[FieldValue]) {
+        if !self.docs.remove(doc_id) {
+            return;
+        }
+        for value in string_values(values) {
+            for trigram in trigrams(&framed(value, true, true)) {
+                let Some(docs) = self.postings.get_mut(&trigram) else {
+                    continue;
+                };
+                Arc::make_mut(docs).remove(doc_id);
+                if docs.is_empty() {
+                    self.postings.remove(&trigram);
+                }
+            }
+        }
+    }
+
+    /// Superset of the docs with a string value matching `pattern`.
+    pub fn candidates(&self, pattern: &str, method: StringMatchMethod) -> RoaringBitmap {
+        let required = pattern_trigrams(pattern, method);
+        if required.is_empty() {
+            return self.docs.bitmap().clone();
+        }
+        let mut postings = required.iter().map(|trigram| self.postings.get(trigram));
+        let Some(Some(first)) = postings.next() else {
+            return RoaringBitmap::new();
+        };
+        let mut candidates = (**first).clone();
+        for docs in postings {
+            let Some(docs) = docs else {
+                return RoaringBitmap::new();
+            };
+            candidates &= &**docs;
+        }
+        candidates
+    }
+
+    pub fn estimated_candidate_len(&self, pattern: &str, method: StringMatchMethod) -> u64 {
+        pattern_trigrams(pattern, method)
+            .iter()
+            .map(|trigram| self.postings.get(trigram).map_or(0, |docs| docs.len()))
+            .min()
+            .unwrap_or_else(|| self.docs.len())
+    }
+}
+
+fn string_values(values: &[FieldValue]) -> impl Iterator<Item = &str> {
+    values.iter().filter_map(|value| match value {
+        FieldValue::String(value) => Some(value.as_ref()),
+        _ => None,
+    })
+}
+
+fn framed(text: &str, anchored_start: bool, anchored_end: bool) -> Vec<char> {
+    let mut chars = Vec::with_capacity(text.len() + 2);
+    if anchored_start {
+        chars.push(START);
+    }
+    chars.extend(fold_case(text).chars());
+    if anchored_end {
+        chars.push(END);
+    }
+    chars
+}
+
+fn trigrams(chars: &[char]) -> impl Iterator<Item = Trigram> + '_ {
+    chars
+        .windows(3)
+        .map(|window| [window[0], window[1], window[2]])
+}
+
+/// Trigrams every matching value must contain. Glob patterns contribute the
+/// literal runs between their `*`/`?` wildcards; only the first and last run
+/// are anchored to the ends of the value.
+fn pattern_trigrams(pattern: &str, method: StringMatchMethod) -> Vec<Trigram> {
+    let literals: Vec<(&str, bool, bool)> = match method {
+        StringMatchMethod::Exact => vec![(pattern, true, true)],
+        StringMatchMethod::Prefix => vec![(pattern, true, false)],
+        StringMatchMethod::Suffix => vec![(pattern, false, true)],
+        StringMatchMethod::Contains => vec![(pattern, false, false)],
+        StringMatchMethod::Glob => {
+            let parts: Vec<_> = pattern.split(['*', '?']).collect();
+            let last = parts.len() - 1;
+            parts
+                .into_iter()
+                .enumerate()
+                .map(|(index, part)| (part, index == 0, index == last))
+                .collect()
+        }
+    };
+    let mut required = Vec::new();
+    for (literal, anchored_start, anchored_end) in literals {
+        for trigram in trigrams(&framed(literal, anchored_start, anchored_end)) {
+            if !required.contains(&trigram) {
+                required.push(trigram);
+            }
+        }
+    }
+    required
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold_case, TrigramIndex};
+    use crate::planner::{FieldValue, StringMatchMethod};
+    use std::sync::Arc;
+
+    #[test]
+    fn folds_case_beyond_lowercasing() {
+        assert_eq!(fold_case("Straße"), "strasse");
+        assert_eq!(fold_case("ΟΔΟΣ"), fold_case("οδος"));
+        assert_eq!(fold_case("ﬁle"), "file");
+        assert_eq!(fold_case("ǅ"), "ǆ");
+    }
+
+    #[test]
+    fn prunes_candidates_by_anchored_trigrams() {
+        let mut index = TrigramIndex::new();
+        index.insert(0, &[FieldValue::from("photo.JPEG")]);
+        index.insert(1, &[FieldValue::from("jpeg-notes.txt")]);
+        index.insert(2, &[FieldValue::from("ab")]);
+        index.insert(3, &[FieldValue::U64(1)]);
+
+        let ids = |index: &TrigramIndex, pattern, method| {
+            index.candidates(pattern, method).iter().collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&index, "jpeg", StringMatchMethod::Contains), vec![0, 1]);
+        assert_eq!(ids(&index, ".jpeg", StringMatchMethod::Suffix), vec![0]);
+        assert_eq!(ids(&index, "JPEG", StringMatchMethod::Prefix), vec![1]);
+        assert_eq!(ids(&index, "*.jp?g", StringMatchMethod::Glob), vec![0]);
+        assert_eq!(ids(&index, "a", StringMatchMethod::Contains), vec![0, 1, 2]);
+        assert_eq!(ids(&index, "ab", StringMatchMethod::Exact), vec![2]);
+        assert!(ids(&index, "png", StringMatchMethod::Contains).is_empty());
+        assert_eq!(
+            index.estimated_candidate_len("jpeg", StringMatchMethod::Contains),
+            2
+        );
+
+        index.remove(1, &[FieldValue::from("jpeg-notes.txt")]);
+        assert_eq!(ids(&index, "jpeg", StringMatchMethod::Contains), vec![0]);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn writes_after_a_clone_copy_only_touched_postings() {
+        let mut index = TrigramIndex::new();
+        index.insert(0, &[FieldValue::from("photo")]);
+        index.insert(1, &[FieldValue::from("notes")]);
+        let snapshot = index.clone();
+
+        index.insert(2, &[FieldValue::from("photo")]);
+        index.remove(1, &[FieldValue::from("notes")]);
+        let posting = |index: &TrigramIndex, trigram: &str| {
+            let trigram: Vec<char> = trigram.chars().collect();
+            index.postings[&[trigram[0], trigram[1], trigram[2]]].clone()
+        };
+        assert!(!Arc::ptr_eq(
+            &posting(&index, "pho"),
+            &posting(&snapshot, "pho")
+        ));
+        assert!(!index.postings.contains_key(&['n', 'o', 't']));
+        assert_eq!(
+            snapshot
+                .candidates("not", StringMatchMethod::Contains)
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(
+            snapshot
+                .candidates("pho", StringMatchMethod::Contains)
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![0]
+        );
+
+        // A posting the write does not touch stays shared.
+        let snapshot = index.clone();
+        index.insert(3, &[FieldValue::from("xyz")]);
+        assert!(Arc::ptr_eq(
+            &posting(&index, "pho"),
+            &posting(&snapshot, "pho")
+        ));
+    }
+}
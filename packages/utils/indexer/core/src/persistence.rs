@@ -4,6 +4,7 @@ use std::fmt;
 pub const VALUE_SNAPSHOT_MAGIC: &[u8; 8] = b"PBRIDXS1";
 pub const KEY_VALUE_SNAPSHOT_MAGIC: &[u8; 8] = b"PBRIDXK1";
 pub const JOURNAL_MAGIC: &[u8; 8] = b"PBRIDXW1";
+pub const INDEX_SNAPSHOT_MAGIC: &[u8; 8] = b"PBRIDXQ1";
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum JournalOperation {
@@ -125,6 +126,10 @@ pub enum DecodeError {
     InvalidOperation(u8),
     InvalidUtf8,
     TrailingSnapshotBytes,
+    MissingMagic,
+    UnsupportedVersion(u8),
+    InvalidTag(&'static str, u8),
+    InvalidBitmap,
 }
 
 impl fmt::Display for DecodeError {
@@ -135,6 +140,12 @@ impl fmt::Display for DecodeError {
             Self::InvalidOperation(operation) => write!(formatter, "invalid operation {operation}"),
             Self::InvalidUtf8 => write!(formatter, "invalid utf-8"),
             Self::TrailingSnapshotBytes => write!(formatter, "trailing snapshot bytes"),
+            Self::MissingMagic => write!(formatter, "missing snapshot magic"),
+            Self::UnsupportedVersion(version) => {
+                write!(formatter, "unsupported snapshot version {version}")
+            }
+            Self::InvalidTag(label, tag) => write!(formatter, "invalid {label} tag {tag}"),
+            Self::InvalidBitmap => write!(formatter, "invalid bitmap"),
         }
     }
 }
@@ -196,8 +207,7 @@ pub fn decode_key_value_snapshot(bytes: &[u8]) -> Result<IndexMap<String, Vec<u8
     // Cap preallocation at what the payload could hold (>= 8 bytes per entry)
     // so a corrupt count fails with Truncated below instead of aborting on
     // allocation.
-    let mut entries =
-        IndexMap::with_capacity(count.min(payload.len().saturating_sub(offset) / 8));
+    let mut entries = IndexMap::with_capacity(count.min(payload.len().saturating_sub(offset) / 8));
     for _ in 0..count {
         let key = read_string(payload, &mut offset, "key-value snapshot key")?;
         let value = read_bytes(payload, &mut offset, "key-value snapshot value")?.to_vec();
@@ -277,11 +287,18 @@ pub fn encode_journal_records(records: impl IntoIterator<Item = JournalRecord>)
 }
 
 pub fn decode_journal(bytes: &[u8]) -> Result<Vec<JournalRecord>, DecodeError> {
+    decode_journal_prefix(bytes).map(|(records, _)| records)
+}
+
+/// Like `decode_journal`, also returning the length of the intact prefix the
+/// records were read from, so a torn tail can be truncated before appending.
+pub fn decode_journal_prefix(bytes: &[u8]) -> Result<(Vec<JournalRecord>, usize), DecodeError> {
     let mut offset = if has_magic(bytes, JOURNAL_MAGIC) {
         JOURNAL_MAGIC.len()
     } else {
         0
     };
+    let mut intact = offset;
     let mut records = Vec::new();
     while offset < bytes.len() {
         if offset + 8 > bytes.len() {
@@ -301,11 +318,12 @@ pub fn decode_journal(bytes: &[u8]) -> Result<Vec<JournalRecord>, DecodeError> {
             break;
         }
         records.push(decode_journal_payload(payload)?);
+        intact = offset;
     }
-    Ok(records)
+    Ok((records, intact))
 }
 
-fn encode_envelope(magic: &[u8; 8], payload: &[u8]) -> Vec<u8> {
+pub(crate) fn encode_envelope(magic: &[u8; 8], payload: &[u8]) -> Vec<u8> {
     let mut out = Vec::with_capacity(magic.len() + 8 + payload.len());
     out.extend_from_slice(magic);
     write_u32(&mut out, payload.len() as u32);
@@ -314,7 +332,10 @@ fn encode_envelope(magic: &[u8; 8], payload: &[u8]) -> Vec<u8> {
     out
 }
 
-fn decode_envelope<'a>(bytes: &'a [u8], magic: &[u8; 8]) -> Result<&'a [u8], DecodeError> {
+pub(crate) fn decode_envelope<'a>(
+    bytes: &'a [u8],
+    magic: &[u8; 8],
+) -> Result<&'a [u8], DecodeError> {
     if !has_magic(bytes, magic) {
         return Ok(bytes);
     }
@@ -334,11 +355,15 @@ fn decode_envelope<'a>(bytes: &'a [u8], magic: &[u8; 8]) -> Result<&'a [u8], Dec
     Ok(payload)
 }
 
-fn write_u32(out: &mut Vec<u8>, value: u32) {
+pub(crate) fn write_u32(out: &mut Vec<u8>, value: u32) {
     out.extend_from_slice(&value.to_le_bytes());
 }
 
-fn read_u32(bytes: &[u8], offset: &mut usize, label: &'static str) -> Result<u32, DecodeError> {
+pub(crate) fn read_u32(
+    bytes: &[u8],
+    offset: &mut usize,
+    label: &'static str,
+) -> Result<u32, DecodeError> {
     let end = offset.checked_add(4).ok_or(DecodeError::Truncated(label))?;
     if end > bytes.len() {
         return Err(DecodeError::Truncated(label));
@@ -352,12 +377,12 @@ fn read_u32(bytes: &[u8], offset: &mut usize, label: &'static str) -> Result<u32
     Ok(value)
 }
 
-fn write_bytes(out: &mut Vec<u8>, value: &[u8]) {
+pub(crate) fn write_bytes(out: &mut Vec<u8>, value: &[u8]) {
     write_u32(out, value.len() as u32);
     out.extend_from_slice(value);
 }
 
-fn read_bytes<'a>(
+pub(crate) fn read_bytes<'a>(
     bytes: &'a [u8],
     offset: &mut usize,
     label: &'static str,
@@ -374,11 +399,11 @@ fn read_bytes<'a>(
     Ok(value)
 }
 
-fn write_string(out: &mut Vec<u8>, value: &str) {
+pub(crate) fn write_string(out: &mut Vec<u8>, value: &str) {
     write_bytes(out, value.as_bytes());
 }
 
-fn read_string(
+pub(crate) fn read_string(
     bytes: &[u8],
     offset: &mut usize,
     label: &'static str,
@@ -387,7 +412,7 @@ fn read_string(
         .map_err(|_| DecodeError::InvalidUtf8)
 }
 
-fn has_magic(bytes: &[u8], magic: &[u8]) -> bool {
+pub(crate) fn has_magic(bytes: &[u8], magic: &[u8]) -> bool {
     bytes.len() >= magic.len() && &bytes[..magic.len()] == magic
 }
 
@@ -403,8 +428,9 @@ fn fnv1a(bytes: &[u8]) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::{
-        decode_journal, decode_key_value_snapshot, decode_value_snapshot, encode_journal,
-        encode_key_value_snapshot, encode_value_snapshot, DurableIndexState, JournalRecord,
+        decode_journal, decode_journal_prefix, decode_key_value_snapshot, decode_value_snapshot,
+        encode_journal, encode_key_value_snapshot, encode_value_snapshot, DurableIndexState,
+        JournalRecord,
     };
 
     #[test]
@@ -413,8 +439,10 @@ mod tests {
             JournalRecord::put("a", vec![1, 2]),
             JournalRecord::delete("b"),
         ]);
+        let intact = bytes.len();
         bytes.extend_from_slice(&[1, 2, 3]);
 
+        assert_eq!(decode_journal_prefix(&bytes).unwrap().1, intact);
         assert_eq!(
             decode_journal(&bytes).unwrap(),
             vec![
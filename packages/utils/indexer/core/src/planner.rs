@@ -1,10 +1,25 @@
+use crate::composite::{CompositeGroup, CompositeIndex};
+use crate::hnsw::{HnswIndex, VectorIndexConfig};
+use crate::migration::{InvalidatedIndex, SchemaDiff};
+use crate::ngram::{fold_case, TrigramIndex};
+use crate::persistence::{
+    decode_envelope, encode_envelope, has_magic, read_bytes, read_string, read_u32, write_bytes,
+    write_string, write_u32, DecodeError, INDEX_SNAPSHOT_MAGIC,
+};
+use crate::text::{TextAnalyzer, TextIndex};
+use im::OrdMap;
 use roaring::RoaringBitmap;
+use std::borrow::{Borrow, Cow};
+use std::cell::Cell;
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashMap};
-use std::ops::Bound::{Excluded, Unbounded};
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::ops::Bound::{Excluded, Included, Unbounded};
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
 
 pub type DocId = u32;
+const INDEX_SNAPSHOT_VERSION: u8 = 1;
 const MAX_EXACT_INDEXED_BYTE_FIELD_LENGTH: usize = 128;
 
 /// A document's primary-key id parsed into a typed, canonically-comparable form.
@@ -155,16 +170,192 @@ impl From<&str> for FieldPath {
     }
 }
 
+/// An `f64` with a total order, so float fields can key the exact, range and
+/// sort indexes like the integer kinds. `-0.0` is stored as `0.0` and every NaN
+/// as one canonical NaN, which sorts after `+inf` but never satisfies a range
+/// comparison.
+#[derive(Clone, Copy, Debug)]
+pub struct TotalF64(f64);
+
+impl TotalF64 {
+    pub fn new(value: f64) -> Self {
+        if value.is_nan() {
+            Self(f64::NAN)
+        } else if value == 0.0 {
+            Self(0.0)
+        } else {
+            Self(value)
+        }
+    }
+
+    pub fn get(self) -> f64 {
+        self.0
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.0.is_nan()
+    }
+}
+
+impl PartialEq for TotalF64 {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for TotalF64 {}
+
+impl std::hash::Hash for TotalF64 {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl Ord for TotalF64 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for TotalF64 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<f64> for TotalF64 {
+    fn from(value: f64) -> Self {
+        Self::new(value)
+    }
+}
+
+/// An unsigned integer above `u64::MAX` (from u128/u256/u512 fields), kept as
+/// big-endian bytes without leading zeros so that ordering is numeric. Smaller
+/// values are always `FieldValue::U64`, which makes every `BigUint` greater
+/// than every `U64`; use `FieldValue::from_unsigned_le_bytes` and friends to
+/// get the canonical kind.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BigUint(Arc<[u8]>);
+
+impl BigUint {
+    pub fn to_be_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn to_u128(&self) -> Option<u128> {
+        if self.0.len() > 16 {
+            return None;
+        }
+        Some(
+            self.0
+                .iter()
+                .fold(0_u128, |value, byte| (value << 8) | *byte as u128),
+        )
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.0
+            .iter()
+            .fold(0.0, |value, byte| value * 256.0 + *byte as f64)
+    }
+
+    fn add_be_bytes(&self, other: &[u8]) -> BigUint {
+        let (long, short) = if self.0.len() >= other.len() {
+            (self.0.as_ref(), other)
+        } else {
+            (other, self.0.as_ref())
+        };
+        let mut sum = vec![0_u8; long.len() + 1];
+        let mut carry = 0_u16;
+        for index in 0..long.len() {
+            let short_byte = short
+                .len()
+                .checked_sub(index + 1)
+                .map_or(0, |position| short[position]);
+            let total = long[long.len() - 1 - index] as u16 + short_byte as u16 + carry;
+            let position = sum.len() - 1 - index;
+            sum[position] = total as u8;
+            carry = total >> 8;
+        }
+        sum[0] = carry as u8;
+        BigUint(trim_leading_zeros(&sum).into())
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Decimal digits, matching how u64 values cross to JS as strings.
+impl fmt::Display for BigUint {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.0.to_vec();
+        let mut digits = Vec::new();
+        while !value.is_empty() {
+            let mut remainder = 0_u16;
+            for byte in &mut value {
+                let current = (remainder << 8) | *byte as u16;
+                *byte = (current / 10) as u8;
+                remainder = current % 10;
+            }
+            digits.push(b'0' + remainder as u8);
+            let zeros = value.iter().take_while(|byte| **byte == 0).count();
+            value.drain(..zeros);
+        }
+        if digits.is_empty() {
+            digits.push(b'0');
+        }
+        digits.reverse();
+        formatter.write_str(std::str::from_utf8(&digits).map_err(|_| fmt::Error)?)
+    }
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    &bytes[zeros..]
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum FieldValue {
     Bool(bool),
     I64(i64),
     U64(u64),
+    BigUint(BigUint),
+    F64(TotalF64),
     String(Arc<str>),
     Bytes(Arc<[u8]>),
 }
 
 impl FieldValue {
+    /// Unsigned integer of any width from big-endian bytes: `U64` when it
+    /// fits, `BigUint` otherwise.
+    pub fn from_unsigned_be_bytes(bytes: &[u8]) -> Self {
+        let bytes = trim_leading_zeros(bytes);
+        if bytes.len() <= 8 {
+            let mut value = [0_u8; 8];
+            value[8 - bytes.len()..].copy_from_slice(bytes);
+            return Self::U64(u64::from_be_bytes(value));
+        }
+        Self::BigUint(BigUint(bytes.into()))
+    }
+
+    pub fn from_unsigned_le_bytes(bytes: &[u8]) -> Self {
+        let mut bytes = bytes.to_vec();
+        bytes.reverse();
+        Self::from_unsigned_be_bytes(&bytes)
+    }
+
     fn as_i64(&self) -> Option<i64> {
         match self {
             Self::I64(value) => Some(*value),
@@ -178,6 +369,21 @@ impl FieldValue {
             _ => None,
         }
     }
+
+    fn as_big_uint(&self) -> Option<&BigUint> {
+        match self {
+            Self::BigUint(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Float value usable as a range key; NaN has no place in a range.
+    fn as_ordered_f64(&self) -> Option<TotalF64> {
+        match self {
+            Self::F64(value) if !value.is_nan() => Some(*value),
+            _ => None,
+        }
+    }
 }
 
 impl From<bool> for FieldValue {
@@ -198,6 +404,18 @@ impl From<u64> for FieldValue {
     }
 }
 
+impl From<u128> for FieldValue {
+    fn from(value: u128) -> Self {
+        Self::from_unsigned_be_bytes(&value.to_be_bytes())
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        Self::F64(TotalF64::new(value))
+    }
+}
+
 impl From<String> for FieldValue {
     fn from(value: String) -> Self {
         Self::String(value.into())
@@ -312,9 +530,11 @@ pub enum Compare {
     LessOrEqual,
     Greater,
     GreaterOrEqual,
+    /// Matches documents with some value of the same kind that differs.
+    NotEqual,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Query {
     All,
     Exact {
@@ -335,6 +555,28 @@ pub enum Query {
     IsNull {
         field: FieldPath,
     },
+    /// Matches when any of the field's values equals one of `values`; read
+    /// straight from the exact index instead of an `Or` of `Exact`s.
+    In {
+        field: FieldPath,
+        values: Vec<FieldValue>,
+    },
+    /// `low <= value <= high` (or strict on both ends when not `inclusive`)
+    /// for a single value of the field.
+    Between {
+        field: FieldPath,
+        low: FieldValue,
+        high: FieldValue,
+        inclusive: bool,
+    },
+    /// Full-text match on a string field. `value` goes through the field's text
+    /// analyzer (the default analyzer when no text index is configured); the
+    /// document matches when its values for `field` contain any/all terms.
+    Text {
+        field: FieldPath,
+        value: String,
+        operator: TextOperator,
+    },
     And(Vec<Query>),
     Or(Vec<Query>),
     Not(Box<Query>),
@@ -345,6 +587,16 @@ pub enum StringMatchMethod {
     Exact,
     Prefix,
     Contains,
+    Suffix,
+    /// `*` matches any run of characters and `?` exactly one; there is no
+    /// escape for matching them literally.
+    Glob,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextOperator {
+    Any,
+    All,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -371,6 +623,28 @@ pub struct VectorSort {
     pub field: FieldPath,
     pub query: Vec<f32>,
     pub metric: VectorMetric,
+    pub mode: VectorSearchMode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VectorSearchMode {
+    /// Score every filtered candidate. Always correct; linear in the number of
+    /// candidates.
+    Exact,
+    /// Use the field's HNSW graph when one is configured for the requested
+    /// metric. `ef` is the base-layer candidate list size (higher = better
+    /// recall, slower). Falls back to `Exact` when no graph matches or when
+    /// the filtered candidate set is no larger than `ef`.
+    Approximate { ef: usize },
+}
+
+/// Rank matches of `query` on `field` by BM25 relevance, highest first.
+/// Requires a text index on `field`.
+#[derive(Clone, Debug)]
+pub struct TextSort {
+    pub field: FieldPath,
+    pub query: String,
+    pub operator: TextOperator,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -379,11 +653,432 @@ pub struct ScoredDocument {
     pub score: f32,
 }
 
+/// Resume point for `NativeQueryIndex::search_page_after`: the sort key of the
+/// last document returned (its first value per sort field, then its id).
+/// Treat as opaque; `codec::encode_search_cursor` gives a portable encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchCursor {
+    pub(crate) values: Vec<Option<FieldValue>>,
+    pub(crate) id: String,
+}
+
+/// Bounds on the work one query may do before it gives up with
+/// `BudgetExceeded`, so a badly shaped query cannot block its caller.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QueryBudget {
+    /// Candidate documents verified against the query.
+    pub max_candidates: Option<u64>,
+    /// Documents taken into the result: returned, sorted, summed or scored.
+    pub max_materialized: Option<u64>,
+    pub deadline: Option<Deadline>,
+}
+
+/// A point in time on `clock`, in milliseconds (`Date.now()` under wasm,
+/// where the standard clocks are unavailable). Checked every few hundred
+/// candidates.
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline {
+    pub at: f64,
+    pub clock: fn() -> f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetLimit {
+    Candidates,
+    Materialized,
+    Deadline,
+}
+
+/// The query was abandoned; the counts are the work done until then.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub limit: BudgetLimit,
+    pub candidates_examined: u64,
+    pub documents_materialized: u64,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = match self.limit {
+            BudgetLimit::Candidates => "candidate",
+            BudgetLimit::Materialized => "materialized document",
+            BudgetLimit::Deadline => "deadline",
+        };
+        write!(
+            formatter,
+            "native query {limit} budget exceeded after examining {} candidates and materializing {} documents",
+            self.candidates_examined, self.documents_materialized
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Error of a budgeted query that can also fail on its own, like a sum
+/// overflowing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryError {
+    BudgetExceeded(BudgetExceeded),
+    Failed(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BudgetExceeded(exceeded) => exceeded.fmt(formatter),
+            Self::Failed(message) => formatter.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl From<BudgetExceeded> for QueryError {
+    fn from(exceeded: BudgetExceeded) -> Self {
+        Self::BudgetExceeded(exceeded)
+    }
+}
+
+impl From<String> for QueryError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+// Deadline checks cost a clock read, so they only happen once per this many
+// candidates.
+const DEADLINE_CHECK_INTERVAL: u64 = 256;
+
+/// Running count of the work against a `QueryBudget`. Cells, so it can be
+/// threaded through the `&self` query paths; once exceeded every further
+/// check fails and callers unwind with what they have.
+struct BudgetMeter {
+    budget: QueryBudget,
+    examined: Cell<u64>,
+    materialized: Cell<u64>,
+    exceeded: Cell<Option<BudgetLimit>>,
+}
+
+impl BudgetMeter {
+    fn new(budget: &QueryBudget) -> Self {
+        Self {
+            budget: *budget,
+            examined: Cell::new(0),
+            materialized: Cell::new(0),
+            exceeded: Cell::new(None),
+        }
+    }
+
+    fn unlimited() -> Self {
+        Self::new(&QueryBudget::default())
+    }
+
+    /// Account for verifying one more candidate; false once over budget.
+    fn examine(&self) -> bool {
+        if self.exceeded.get().is_some() {
+            return false;
+        }
+        let examined = self.examined.get() + 1;
+        self.examined.set(examined);
+        if self.budget.max_candidates.is_some_and(|max| examined > max) {
+            return self.exceed(BudgetLimit::Candidates);
+        }
+        if let Some(deadline) = self.budget.deadline {
+            if (examined - 1).is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                && (deadline.clock)() >= deadline.at
+            {
+                return self.exceed(BudgetLimit::Deadline);
+            }
+        }
+        true
+    }
+
+    /// Account for taking `count` more documents into the result.
+    fn materialize(&self, count: u64) -> bool {
+        if self.exceeded.get().is_some() {
+            return false;
+        }
+        let materialized = self.materialized.get() + count;
+        self.materialized.set(materialized);
+        if self
+            .budget
+            .max_materialized
+            .is_some_and(|max| materialized > max)
+        {
+            return self.exceed(BudgetLimit::Materialized);
+        }
+        true
+    }
+
+    fn exceed(&self, limit: BudgetLimit) -> bool {
+        self.exceeded.set(Some(limit));
+        false
+    }
+
+    fn finish<T>(&self, value: T) -> Result<T, BudgetExceeded> {
+        match self.exceeded.get() {
+            Some(limit) => Err(BudgetExceeded {
+                limit,
+                candidates_examined: self.examined.get(),
+                documents_materialized: self.materialized.get(),
+            }),
+            None => Ok(value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchPage {
+    pub ids: Vec<String>,
+    /// `None` once the result set is exhausted.
+    pub cursor: Option<SearchCursor>,
+    /// Index generation the page was read at; a later generation means writes
+    /// happened between pages.
+    pub generation: u64,
+}
+
+pub type SubscriptionId = u32;
+
+/// Change to a subscription's view made by one write (`put`, `delete`,
+/// `apply_batch`, `delete_matching`, ...). Applying the diffs in order to the
+/// view returned by `subscribe` keeps it equal to `search(query, sort, limit)`:
+/// drop `removed` and `moved`, then insert `added` and `moved` at their
+/// indexes, lowest index first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubscriptionDiff {
+    pub subscription: SubscriptionId,
+    /// Index generation after the write.
+    pub generation: u64,
+    /// Ids that entered the view, with their index in the new view.
+    pub added: Vec<(String, usize)>,
+    pub removed: Vec<String>,
+    /// Ids that stayed in the view but changed place relative to the rest of
+    /// it, with their index in the new view.
+    pub moved: Vec<(String, usize)>,
+}
+
+impl SubscriptionDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// What outlives a wholesale replacement of the documents (`clear`,
+/// `load_snapshot`): readers' subscriptions and pins, and the generation they
+/// are keyed by.
+struct ReaderState {
+    generation: u64,
+    subscriptions: BTreeMap<SubscriptionId, Subscription>,
+    next_subscription_id: SubscriptionId,
+    subscription_diffs: Vec<SubscriptionDiff>,
+    pinned_snapshots: BTreeMap<u64, PinnedSnapshot>,
+}
+
+struct PinnedSnapshot {
+    snapshot: IndexSnapshot,
+    pins: usize,
+}
+
+/// Read-only view of a `NativeQueryIndex` as of one generation, so pages,
+/// counts and sums read from one snapshot agree with each other whatever is
+/// written meanwhile. Every read method of `NativeQueryIndex` is available
+/// through `Deref`.
+///
+/// Taking one shares the index's persistent maps. A later write copies the
+/// map nodes on the path to what it changes, the bitmap of each posting it
+/// touches (one field value's docs, one term's or trigram's docs) and one
+/// 2^16-id chunk of each `DocSet` it touches. Composite indexes are copied
+/// per index but share their groups.
+#[derive(Clone)]
+pub struct IndexSnapshot(Arc<NativeQueryIndex>);
+
+impl Deref for IndexSnapshot {
+    type Target = NativeQueryIndex;
+
+    fn deref(&self) -> &NativeQueryIndex {
+        &self.0
+    }
+}
+
+struct Subscription {
+    query: Query,
+    sort: Vec<SortField>,
+    limit: Option<usize>,
+    members: RoaringBitmap,
+    /// Every member, in `compare_docs` order, so a window past a removed
+    /// member is refilled without re-running the query.
+    ordered: Vec<DocId>,
+    view: Vec<String>,
+}
+
+/// Window and running state of one `search_index_sorted_page` scan, shared by
+/// the helpers that walk each sort index in turn.
+struct SortedPage<'a> {
+    query: &'a Query,
+    offset: usize,
+    limit: usize,
+    meter: &'a BudgetMeter,
+    skipped: usize,
+    seen: RoaringBitmap,
+    result: Vec<String>,
+}
+
+/// Ordered docs for one sort field, by value kind in `field_value_rank` order.
+struct SortSource<'a> {
+    composite: Option<&'a CompositeIndex>,
+    bool: Option<&'a OrdMap<bool, Arc<RoaringBitmap>>>,
+    i64: Option<&'a OrdMap<i64, Arc<RoaringBitmap>>>,
+    u64: Option<&'a OrdMap<u64, Arc<RoaringBitmap>>>,
+    big_uint: Option<&'a OrdMap<BigUint, Arc<RoaringBitmap>>>,
+    f64: Option<&'a OrdMap<TotalF64, Arc<RoaringBitmap>>>,
+    string: Option<&'a OrdMap<Arc<str>, Arc<RoaringBitmap>>>,
+    bytes: Option<&'a OrdMap<Arc<[u8]>, Arc<RoaringBitmap>>>,
+    /// Docs without a value; found by scanning every doc when `None`.
+    missing: Option<Cow<'a, RoaringBitmap>>,
+}
+
+/// Result of `NativeQueryIndex::explain`: how a search would be served and
+/// what it costs on the current contents of the index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryPlan {
+    pub root: PlanNode,
+    pub sort: SortStrategy,
+    /// Every live document is evaluated against the query: the root's access
+    /// path takes all documents as candidates instead of narrowing them
+    /// through an index.
+    pub full_scan: bool,
+    pub documents_evaluated: u64,
+    pub matched: u64,
+    pub returned: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlanNode {
+    /// `all`, `exact`, `in`, `range`, `between`, `string_match`, `is_null`,
+    /// `text`, `and`, `or` or `not`.
+    pub predicate: &'static str,
+    pub field: Option<FieldPath>,
+    pub access: PlanAccess,
+    pub estimated_candidates: u64,
+    pub actual_candidates: u64,
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    /// Whether this node's candidates are every document rather than what an
+    /// index yields. A union scans when any child does; a complement takes
+    /// what its child's index leaves out, so it never does.
+    fn scans_all_documents(&self) -> bool {
+        match self.access {
+            PlanAccess::AllDocuments | PlanAccess::FullScan => true,
+            PlanAccess::Seeded { child } => self.children[child].scans_all_documents(),
+            PlanAccess::Union => self.children.iter().any(PlanNode::scans_all_documents),
+            PlanAccess::ExactIndex
+            | PlanAccess::LargeByteValues
+            | PlanAccess::RangeIndex
+            | PlanAccess::TextIndex
+            | PlanAccess::TrigramIndex
+            | PlanAccess::CompositeIndex
+            | PlanAccess::Complement => false,
+        }
+    }
+}
+
+/// Where a predicate's candidate set comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanAccess {
+    AllDocuments,
+    ExactIndex,
+    /// Byte values too large for the exact index; every doc holding one is a
+    /// candidate.
+    LargeByteValues,
+    RangeIndex,
+    TextIndex,
+    /// Trigram index of a string field, verified per candidate.
+    TrigramIndex,
+    /// No index applies; every document is a candidate.
+    FullScan,
+    /// One group of a composite index, narrowed by ranges on its suffix (an
+    /// `And` pinning the index's prefix).
+    CompositeIndex,
+    /// Candidates of the child with the smallest estimate (an `And`).
+    Seeded {
+        child: usize,
+    },
+    Union,
+    Complement,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SortStrategy {
+    /// No sort fields: matches in internal document order.
+    DocumentOrder,
+    /// Walks the field's ordered sort indexes, stopping once the page is full.
+    SortIndex { field: FieldPath },
+    /// Walks one group of a composite index, pinned by the query's exact
+    /// matches on `prefix`.
+    CompositeIndex {
+        prefix: Vec<FieldPath>,
+        field: FieldPath,
+    },
+    /// Materializes and sorts every match.
+    InMemory,
+}
+
+/// Integer sums are exact: unsigned sums widen to `BigUint` past `u128`, and
+/// once a float is added the sum becomes a float.
+#[derive(Clone, Debug, PartialEq)]
 pub enum SumResult {
     None,
     I64(i128),
     U64(u128),
+    BigUint(BigUint),
+    F64(f64),
+}
+
+/// Aggregations evaluated over the documents matching a `Query`.
+///
+/// Every aggregation reads all values of a multi-valued field, not just the
+/// one a document sorts by: `Sum`, `Min`, `Max` and `Average` fold over each
+/// value, while `DistinctCount`, `GroupBy` and `Histogram` count a document
+/// once per distinct value/bucket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Aggregation {
+    Count,
+    Sum(FieldPath),
+    Min(FieldPath),
+    Max(FieldPath),
+    Average(FieldPath),
+    DistinctCount(FieldPath),
+    GroupBy(FieldPath),
+    /// Numeric buckets `[start, start + interval)` with `start` a multiple of
+    /// `interval`.
+    Histogram {
+        field: FieldPath,
+        interval: u64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregationResult {
+    Count(u64),
+    Sum(SumResult),
+    Value(Option<FieldValue>),
+    Average(Option<f64>),
+    Groups(Vec<ValueBucket>),
+    Histogram(Vec<HistogramBucket>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueBucket {
+    pub value: FieldValue,
+    pub count: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistogramBucket {
+    pub start: i128,
+    pub count: u64,
 }
 
 #[derive(Default)]
@@ -412,58 +1107,633 @@ impl IndexBatch {
     }
 }
 
-#[derive(Default)]
-pub struct NativeQueryIndex {
-    generation: u64,
-    next_doc_id: DocId,
-    free_doc_ids: Vec<DocId>,
-    all_docs: RoaringBitmap,
-    external_to_internal: HashMap<String, DocId>,
-    internal_to_external: HashMap<DocId, String>,
-    // Typed, canonically-comparable form of each document's store-key id, parsed
-    // once at insert. Kept parallel to `internal_to_external` (never reformats the
-    // stored string) purely so tie-breaks order by the id's natural typed order.
-    internal_to_sort_key: HashMap<DocId, IdSortKey>,
-    documents: HashMap<DocId, DocumentFields>,
-    exact: HashMap<FieldPath, HashMap<FieldValue, RoaringBitmap>>,
-    range_i64: HashMap<FieldPath, BTreeMap<i64, RoaringBitmap>>,
-    range_u64: HashMap<FieldPath, BTreeMap<u64, RoaringBitmap>>,
-    sort_bool: HashMap<FieldPath, BTreeMap<bool, RoaringBitmap>>,
-    sort_i64: HashMap<FieldPath, BTreeMap<i64, RoaringBitmap>>,
-    sort_u64: HashMap<FieldPath, BTreeMap<u64, RoaringBitmap>>,
-    sort_string: HashMap<FieldPath, BTreeMap<Arc<str>, RoaringBitmap>>,
-    sort_bytes: HashMap<FieldPath, BTreeMap<Arc<[u8]>, RoaringBitmap>>,
-    large_exact_bytes: HashMap<FieldPath, RoaringBitmap>,
-    vectors: HashMap<FieldPath, HashMap<DocId, Vec<f32>>>,
+/// Per-field map from an ordered key to the docs holding it.
+type OrderedIndex<K> = im::HashMap<FieldPath, OrdMap<K, Arc<RoaringBitmap>>>;
+
+/// A doc set that grows with the whole index (every live doc, every doc of a
+/// trigram index), kept as one bitmap per 2^16 ids in a persistent map so a
+/// write after a snapshot copies one chunk rather than the whole set.
+///
+/// Reads that need a single `RoaringBitmap` get one built from the chunks on
+/// first use and shared with snapshots taken afterwards. While nothing else
+/// holds that bitmap, writes update it in place; otherwise they drop it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DocSet {
+    chunks: OrdMap<u16, Arc<RoaringBitmap>>,
+    len: u64,
+    bitmap: OnceLock<Arc<RoaringBitmap>>,
 }
 
-impl NativeQueryIndex {
-    pub fn new() -> Self {
-        Self::default()
+impl DocSet {
+    pub(crate) fn len(&self) -> u64 {
+        self.len
     }
 
-    pub fn generation(&self) -> u64 {
-        self.generation
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
-    pub fn len(&self) -> usize {
-        self.documents.len()
+    pub(crate) fn contains(&self, doc_id: DocId) -> bool {
+        self.chunks
+            .get(&((doc_id >> 16) as u16))
+            .is_some_and(|chunk| chunk.contains(doc_id))
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.documents.is_empty()
+    pub(crate) fn iter(&self) -> impl Iterator<Item = DocId> + '_ {
+        self.chunks.values().flat_map(|chunk| chunk.iter())
     }
 
-    pub fn clear(&mut self) {
-        *self = Self::default();
+    pub(crate) fn bitmap(&self) -> &RoaringBitmap {
+        self.bitmap.get_or_init(|| {
+            Arc::new(
+                self.chunks
+                    .values()
+                    .fold(RoaringBitmap::new(), |bitmap, chunk| bitmap | &**chunk),
+            )
+        })
     }
 
-    pub fn reserve_documents(&mut self, additional: usize) {
-        self.external_to_internal.reserve(additional);
-        self.internal_to_external.reserve(additional);
-        self.internal_to_sort_key.reserve(additional);
-        self.documents.reserve(additional);
-    }
+    /// Returns false when `doc_id` was already in the set.
+    pub(crate) fn insert(&mut self, doc_id: DocId) -> bool {
+        let chunk = self.chunks.entry((doc_id >> 16) as u16).or_default();
+        if !Arc::make_mut(chunk).insert(doc_id) {
+            return false;
+        }
+        self.len += 1;
+        if let Some(bitmap) = self.unshared_bitmap() {
+            bitmap.insert(doc_id);
+        }
+        true
+    }
+
+    /// Returns false when `doc_id` was not in the set.
+    pub(crate) fn remove(&mut self, doc_id: DocId) -> bool {
+        let high = (doc_id >> 16) as u16;
+        let Some(chunk) = self.chunks.get_mut(&high) else {
+            return false;
+        };
+        if !chunk.contains(doc_id) {
+            return false;
+        }
+        Arc::make_mut(chunk).remove(doc_id);
+        if chunk.is_empty() {
+            self.chunks.remove(&high);
+        }
+        self.len -= 1;
+        if let Some(bitmap) = self.unshared_bitmap() {
+            bitmap.remove(doc_id);
+        }
+        true
+    }
+
+    fn unshared_bitmap(&mut self) -> Option<&mut RoaringBitmap> {
+        if self
+            .bitmap
+            .get_mut()
+            .is_some_and(|bitmap| Arc::get_mut(bitmap).is_none())
+        {
+            self.bitmap = OnceLock::new();
+        }
+        self.bitmap.get_mut().and_then(Arc::get_mut)
+    }
+}
+
+#[derive(Default)]
+pub struct NativeQueryIndex {
+    generation: u64,
+    next_doc_id: DocId,
+    free_doc_ids: im::Vector<DocId>,
+    all_docs: DocSet,
+    external_to_internal: im::HashMap<String, DocId>,
+    internal_to_external: im::HashMap<DocId, String>,
+    // Typed, canonically-comparable form of each document's store-key id, parsed
+    // once at insert. Kept parallel to `internal_to_external` (never reformats the
+    // stored string) purely so tie-breaks order by the id's natural typed order.
+    internal_to_sort_key: im::HashMap<DocId, IdSortKey>,
+    documents: im::HashMap<DocId, DocumentFields>,
+    exact: im::HashMap<FieldPath, im::HashMap<FieldValue, Arc<RoaringBitmap>>>,
+    range_i64: OrderedIndex<i64>,
+    range_u64: OrderedIndex<u64>,
+    range_big_uint: OrderedIndex<BigUint>,
+    range_f64: OrderedIndex<TotalF64>,
+    sort_bool: OrderedIndex<bool>,
+    sort_i64: OrderedIndex<i64>,
+    sort_u64: OrderedIndex<u64>,
+    sort_big_uint: OrderedIndex<BigUint>,
+    sort_f64: OrderedIndex<TotalF64>,
+    sort_string: OrderedIndex<Arc<str>>,
+    sort_bytes: OrderedIndex<Arc<[u8]>>,
+    large_exact_bytes: im::HashMap<FieldPath, Arc<RoaringBitmap>>,
+    vectors: im::HashMap<FieldPath, im::HashMap<DocId, Vec<f32>>>,
+    vector_indexes: im::HashMap<FieldPath, HnswIndex>,
+    text_indexes: im::HashMap<FieldPath, TextIndex>,
+    trigram_indexes: im::HashMap<FieldPath, TrigramIndex>,
+    composite_indexes: Arc<Vec<CompositeIndex>>,
+    subscriptions: BTreeMap<SubscriptionId, Subscription>,
+    next_subscription_id: SubscriptionId,
+    /// External ids written since subscription diffs were last published;
+    /// only collected while there are subscriptions.
+    subscription_touched: HashSet<String>,
+    subscription_diffs: Vec<SubscriptionDiff>,
+    pinned_snapshots: BTreeMap<u64, PinnedSnapshot>,
+}
+
+impl NativeQueryIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        // Vector index configuration is declared by the caller, not derived
+        // from documents, so it survives a clear with empty graphs.
+        let vector_indexes = std::mem::take(&mut self.vector_indexes);
+        let text_indexes = std::mem::take(&mut self.text_indexes);
+        let trigram_indexes = std::mem::take(&mut self.trigram_indexes);
+        let composite_indexes = std::mem::take(&mut self.composite_indexes);
+        let readers = self.take_reader_state();
+        *self = Self::default();
+        self.vector_indexes = vector_indexes
+            .iter()
+            .map(|(path, index)| (path.clone(), HnswIndex::new(index.config())))
+            .collect();
+        self.text_indexes = text_indexes
+            .iter()
+            .map(|(path, index)| (path.clone(), TextIndex::new(index.analyzer().clone())))
+            .collect();
+        self.trigram_indexes = trigram_indexes
+            .keys()
+            .map(|path| (path.clone(), TrigramIndex::new()))
+            .collect();
+        self.composite_indexes = Arc::new(
+            composite_indexes
+                .iter()
+                .map(|index| CompositeIndex::new(index.prefix().to_vec(), index.suffix().clone()))
+                .collect(),
+        );
+        self.restore_reader_state(readers);
+    }
+
+    /// Maintain an HNSW graph for `field`, built from the vectors already
+    /// stored and updated on every subsequent put/delete.
+    pub fn configure_vector_index(
+        &mut self,
+        field: impl Into<FieldPath>,
+        config: VectorIndexConfig,
+    ) {
+        let field = field.into();
+        let mut index = HnswIndex::new(config);
+        if let Some(vectors) = self.vectors.get(&field) {
+            let mut doc_ids: Vec<_> = vectors.keys().copied().collect();
+            doc_ids.sort_unstable();
+            for doc_id in doc_ids {
+                index.insert(doc_id, vectors);
+            }
+        }
+        self.vector_indexes.insert(field, index);
+    }
+
+    pub fn remove_vector_index(&mut self, field: &FieldPath) -> bool {
+        self.vector_indexes.remove(field).is_some()
+    }
+
+    pub fn vector_index_config(&self, field: &FieldPath) -> Option<VectorIndexConfig> {
+        self.vector_indexes.get(field).map(HnswIndex::config)
+    }
+
+    /// Maintain an inverted index with BM25 statistics over the string values
+    /// of `field`, built from the documents already stored. `analyzer` is also
+    /// used when evaluating `Query::Text` on this field.
+    pub fn configure_text_index(&mut self, field: impl Into<FieldPath>, analyzer: TextAnalyzer) {
+        let field = field.into();
+        let mut index = TextIndex::new(analyzer);
+        for (doc_id, document) in self.documents.iter() {
+            if let Some(values) = document.scalar_values(&field) {
+                index.insert(*doc_id, values);
+            }
+        }
+        self.text_indexes.insert(field, index);
+    }
+
+    pub fn remove_text_index(&mut self, field: &FieldPath) -> bool {
+        self.text_indexes.remove(field).is_some()
+    }
+
+    pub fn text_analyzer(&self, field: &FieldPath) -> Option<&TextAnalyzer> {
+        self.text_indexes.get(field).map(TextIndex::analyzer)
+    }
+
+    /// Maintain a trigram index over the string values of `field`, built from
+    /// the documents already stored. It narrows the candidates of every
+    /// `Query::StringMatch` on the field except case-sensitive `Exact`, which
+    /// the exact index already answers.
+    pub fn configure_trigram_index(&mut self, field: impl Into<FieldPath>) {
+        let field = field.into();
+        let mut index = TrigramIndex::new();
+        for (doc_id, document) in self.documents.iter() {
+            if let Some(values) = document.scalar_values(&field) {
+                index.insert(*doc_id, values);
+            }
+        }
+        self.trigram_indexes.insert(field, index);
+    }
+
+    pub fn remove_trigram_index(&mut self, field: &FieldPath) -> bool {
+        self.trigram_indexes.remove(field).is_some()
+    }
+
+    /// Maintain a composite index over the `prefix` fields, matched with
+    /// `Exact`, then `suffix`, built from the documents already stored. Queries
+    /// pinning every prefix field read one group of it to sort or range-filter
+    /// on `suffix`, e.g. `(owner, timestamp)` serves "owner = x, newest first".
+    pub fn configure_composite_index(
+        &mut self,
+        prefix: Vec<FieldPath>,
+        suffix: impl Into<FieldPath>,
+    ) {
+        let suffix = suffix.into();
+        self.remove_composite_index(&prefix, &suffix);
+        let mut index = CompositeIndex::new(prefix, suffix);
+        for (doc_id, document) in self.documents.iter() {
+            index.insert(*doc_id, document);
+        }
+        Arc::make_mut(&mut self.composite_indexes).push(index);
+    }
+
+    pub fn remove_composite_index(&mut self, prefix: &[FieldPath], suffix: &FieldPath) -> bool {
+        let Some(position) = self
+            .composite_indexes
+            .iter()
+            .position(|index| index.prefix() == prefix && index.suffix() == suffix)
+        else {
+            return false;
+        };
+        Arc::make_mut(&mut self.composite_indexes).remove(position);
+        true
+    }
+
+    /// Indexes holding values of the fields `diff` adds, removes or retypes.
+    /// They stay stale until the stored documents are reindexed under the new
+    /// schema, see `SchemaMigration`.
+    pub fn indexes_invalidated_by(&self, diff: &SchemaDiff) -> Vec<InvalidatedIndex> {
+        let mut fields: Vec<u32> = diff
+            .added
+            .iter()
+            .chain(&diff.removed)
+            .chain(&diff.retyped)
+            .copied()
+            .collect();
+        fields.sort_unstable();
+        let mut invalidated: Vec<_> = fields
+            .into_iter()
+            .map(|id| InvalidatedIndex::Field(FieldPath::Id(id)))
+            .collect();
+        invalidated.extend(
+            touched_field_ids(self.text_indexes.keys(), diff)
+                .map(|id| InvalidatedIndex::Text(FieldPath::Id(id))),
+        );
+        invalidated.extend(
+            touched_field_ids(self.trigram_indexes.keys(), diff)
+                .map(|id| InvalidatedIndex::Trigram(FieldPath::Id(id))),
+        );
+        invalidated.extend(
+            self.composite_indexes
+                .iter()
+                .filter(|index| {
+                    index
+                        .prefix()
+                        .iter()
+                        .chain([index.suffix()])
+                        .any(|field| diff.touches(field))
+                })
+                .map(|index| InvalidatedIndex::Composite {
+                    prefix: index.prefix().to_vec(),
+                    suffix: index.suffix().clone(),
+                }),
+        );
+        invalidated
+    }
+
+    /// Binary snapshot of the built index: doc-id allocation, ids, extracted
+    /// fields and every bitmap and ordered index, tagged with `fingerprint`
+    /// (normally the fingerprint of the schema the fields were extracted
+    /// under). Vector, text, trigram and composite index configuration is not
+    /// included.
+    pub fn encode_snapshot(&self, fingerprint: u64) -> Vec<u8> {
+        let mut out = vec![INDEX_SNAPSHOT_VERSION];
+        out.extend_from_slice(&fingerprint.to_le_bytes());
+        out.extend_from_slice(&self.generation.to_le_bytes());
+        write_u32(&mut out, self.next_doc_id);
+        write_u32(&mut out, self.free_doc_ids.len() as u32);
+        for doc_id in self.free_doc_ids.iter() {
+            write_u32(&mut out, *doc_id);
+        }
+        let mut doc_ids: Vec<_> = self.documents.keys().copied().collect();
+        doc_ids.sort_unstable();
+        write_u32(&mut out, doc_ids.len() as u32);
+        for doc_id in doc_ids {
+            write_u32(&mut out, doc_id);
+            write_string(&mut out, &self.internal_to_external[&doc_id]);
+            write_snapshot_document(&mut out, &self.documents[&doc_id]);
+        }
+        write_u32(&mut out, self.exact.len() as u32);
+        for (path, values) in self.exact.iter() {
+            write_snapshot_path(&mut out, path);
+            write_u32(&mut out, values.len() as u32);
+            for (value, docs) in values {
+                write_snapshot_value(&mut out, value);
+                write_snapshot_bitmap(&mut out, docs);
+            }
+        }
+        let write_i64 = |out: &mut Vec<u8>, key: &i64| out.extend_from_slice(&key.to_le_bytes());
+        let write_u64 = |out: &mut Vec<u8>, key: &u64| out.extend_from_slice(&key.to_le_bytes());
+        let write_big_uint = |out: &mut Vec<u8>, key: &BigUint| write_bytes(out, key.to_be_bytes());
+        let write_f64 =
+            |out: &mut Vec<u8>, key: &TotalF64| out.extend_from_slice(&key.get().to_le_bytes());
+        write_ordered_snapshot(&mut out, &self.range_i64, write_i64);
+        write_ordered_snapshot(&mut out, &self.range_u64, write_u64);
+        write_ordered_snapshot(&mut out, &self.range_big_uint, write_big_uint);
+        write_ordered_snapshot(&mut out, &self.range_f64, write_f64);
+        write_ordered_snapshot(&mut out, &self.sort_bool, |out, key| {
+            out.push(u8::from(*key))
+        });
+        write_ordered_snapshot(&mut out, &self.sort_i64, write_i64);
+        write_ordered_snapshot(&mut out, &self.sort_u64, write_u64);
+        write_ordered_snapshot(&mut out, &self.sort_big_uint, write_big_uint);
+        write_ordered_snapshot(&mut out, &self.sort_f64, write_f64);
+        write_ordered_snapshot(&mut out, &self.sort_string, |out, key| {
+            write_string(out, key)
+        });
+        write_ordered_snapshot(&mut out, &self.sort_bytes, |out, key| write_bytes(out, key));
+        write_u32(&mut out, self.large_exact_bytes.len() as u32);
+        for (path, docs) in self.large_exact_bytes.iter() {
+            write_snapshot_path(&mut out, path);
+            write_snapshot_bitmap(&mut out, docs);
+        }
+        encode_envelope(INDEX_SNAPSHOT_MAGIC, &out)
+    }
+
+    /// Replace the contents with a snapshot from `encode_snapshot`, without
+    /// re-indexing a single document. Returns `Ok(false)`, leaving the index
+    /// untouched, when the snapshot was written under another `fingerprint`;
+    /// the caller then rebuilds from its documents. Configured vector, text
+    /// and trigram indexes are kept and rebuilt over the loaded documents.
+    pub fn load_snapshot(&mut self, bytes: &[u8], fingerprint: u64) -> Result<bool, DecodeError> {
+        if !has_magic(bytes, INDEX_SNAPSHOT_MAGIC) {
+            return Err(DecodeError::MissingMagic);
+        }
+        let mut reader = SnapshotReader {
+            bytes: decode_envelope(bytes, INDEX_SNAPSHOT_MAGIC)?,
+            offset: 0,
+        };
+        let [version] = reader.fixed("index snapshot version")?;
+        if version != INDEX_SNAPSHOT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        if u64::from_le_bytes(reader.fixed("index snapshot fingerprint")?) != fingerprint {
+            return Ok(false);
+        }
+
+        let mut loaded = Self {
+            generation: u64::from_le_bytes(reader.fixed("index snapshot generation")?),
+            next_doc_id: reader.u32("index snapshot next doc id")?,
+            ..Self::default()
+        };
+        for _ in 0..reader.u32("index snapshot free doc ids")? {
+            loaded
+                .free_doc_ids
+                .push_back(reader.u32("index snapshot free doc id")?);
+        }
+        for _ in 0..reader.u32("index snapshot documents")? {
+            let doc_id = reader.u32("index snapshot doc id")?;
+            let external_id = reader.string("index snapshot document id")?;
+            let fields = reader.document()?;
+            for (path, vector) in &fields.vectors {
+                loaded
+                    .vectors
+                    .entry(path.clone())
+                    .or_default()
+                    .insert(doc_id, vector.clone());
+            }
+            loaded
+                .internal_to_sort_key
+                .insert(doc_id, IdSortKey::from_store_key(&external_id));
+            loaded
+                .external_to_internal
+                .insert(external_id.clone(), doc_id);
+            loaded.internal_to_external.insert(doc_id, external_id);
+            loaded.documents.insert(doc_id, fields);
+            loaded.all_docs.insert(doc_id);
+        }
+        for _ in 0..reader.u32("index snapshot exact fields")? {
+            let path = reader.path()?;
+            let values = loaded.exact.entry(path).or_default();
+            for _ in 0..reader.u32("index snapshot exact values")? {
+                values.insert(reader.value()?, Arc::new(reader.bitmap()?));
+            }
+        }
+        let read_i64 = |reader: &mut SnapshotReader| {
+            Ok(i64::from_le_bytes(reader.fixed("index snapshot i64")?))
+        };
+        let read_u64 = |reader: &mut SnapshotReader| {
+            Ok(u64::from_le_bytes(reader.fixed("index snapshot u64")?))
+        };
+        let read_big_uint = |reader: &mut SnapshotReader| {
+            let bytes = reader.bytes("index snapshot big uint")?;
+            Ok(BigUint(trim_leading_zeros(bytes).into()))
+        };
+        let read_f64 = |reader: &mut SnapshotReader| {
+            Ok(TotalF64::new(f64::from_le_bytes(
+                reader.fixed("index snapshot f64")?,
+            )))
+        };
+        loaded.range_i64 = reader.ordered(read_i64)?;
+        loaded.range_u64 = reader.ordered(read_u64)?;
+        loaded.range_big_uint = reader.ordered(read_big_uint)?;
+        loaded.range_f64 = reader.ordered(read_f64)?;
+        loaded.sort_bool =
+            reader.ordered(|reader| match reader.fixed("index snapshot bool")? {
+                [0] => Ok(false),
+                [1] => Ok(true),
+                [tag] => Err(DecodeError::InvalidTag("index snapshot bool", tag)),
+            })?;
+        loaded.sort_i64 = reader.ordered(read_i64)?;
+        loaded.sort_u64 = reader.ordered(read_u64)?;
+        loaded.sort_big_uint = reader.ordered(read_big_uint)?;
+        loaded.sort_f64 = reader.ordered(read_f64)?;
+        loaded.sort_string =
+            reader.ordered(|reader| Ok(reader.string("index snapshot string")?.into()))?;
+        loaded.sort_bytes =
+            reader.ordered(|reader| Ok(reader.bytes("index snapshot bytes")?.into()))?;
+        for _ in 0..reader.u32("index snapshot large byte fields")? {
+            let path = reader.path()?;
+            loaded
+                .large_exact_bytes
+                .insert(path, Arc::new(reader.bitmap()?));
+        }
+        if reader.offset != reader.bytes.len() {
+            return Err(DecodeError::TrailingSnapshotBytes);
+        }
+
+        let vector_indexes: Vec<_> = self
+            .vector_indexes
+            .iter()
+            .map(|(path, index)| (path.clone(), index.config()))
+            .collect();
+        let text_indexes: Vec<_> = self
+            .text_indexes
+            .iter()
+            .map(|(path, index)| (path.clone(), index.analyzer().clone()))
+            .collect();
+        let trigram_indexes: Vec<_> = self.trigram_indexes.keys().cloned().collect();
+        let composite_indexes: Vec<_> = self
+            .composite_indexes
+            .iter()
+            .map(|index| (index.prefix().to_vec(), index.suffix().clone()))
+            .collect();
+        let readers = self.take_reader_state();
+        *self = loaded;
+        for (path, config) in vector_indexes {
+            self.configure_vector_index(path, config);
+        }
+        for (path, analyzer) in text_indexes {
+            self.configure_text_index(path, analyzer);
+        }
+        for path in trigram_indexes {
+            self.configure_trigram_index(path);
+        }
+        for (prefix, suffix) in composite_indexes {
+            self.configure_composite_index(prefix, suffix);
+        }
+        self.restore_reader_state(readers);
+        Ok(true)
+    }
+
+    /// Keep `search(&query, &sort, limit)` up to date as documents are
+    /// written. Returns the subscription id and the current view; every later
+    /// write that changes the view queues a `SubscriptionDiff` for
+    /// `take_subscription_diffs`. Only the written documents are re-evaluated.
+    pub fn subscribe(
+        &mut self,
+        query: Query,
+        sort: Vec<SortField>,
+        limit: Option<usize>,
+    ) -> (SubscriptionId, Vec<String>) {
+        let id = self.next_subscription_id;
+        self.next_subscription_id = self.next_subscription_id.wrapping_add(1);
+        let mut subscription = Subscription {
+            query,
+            sort,
+            limit,
+            members: RoaringBitmap::new(),
+            ordered: Vec::new(),
+            view: Vec::new(),
+        };
+        self.populate_subscription(&mut subscription);
+        subscription.view = self.subscription_view(&subscription);
+        let view = subscription.view.clone();
+        self.subscriptions.insert(id, subscription);
+        (id, view)
+    }
+
+    /// Drops the subscription and its undelivered diffs.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.subscription_diffs
+            .retain(|diff| diff.subscription != id);
+        let removed = self.subscriptions.remove(&id).is_some();
+        if self.subscriptions.is_empty() {
+            self.subscription_touched.clear();
+        }
+        removed
+    }
+
+    /// Diffs queued since the last call, in write order.
+    pub fn take_subscription_diffs(&mut self) -> Vec<SubscriptionDiff> {
+        std::mem::take(&mut self.subscription_diffs)
+    }
+
+    /// Snapshot of the current generation, released when dropped.
+    pub fn snapshot(&self) -> IndexSnapshot {
+        IndexSnapshot(Arc::new(Self {
+            generation: self.generation,
+            next_doc_id: self.next_doc_id,
+            free_doc_ids: self.free_doc_ids.clone(),
+            all_docs: self.all_docs.clone(),
+            external_to_internal: self.external_to_internal.clone(),
+            internal_to_external: self.internal_to_external.clone(),
+            internal_to_sort_key: self.internal_to_sort_key.clone(),
+            documents: self.documents.clone(),
+            exact: self.exact.clone(),
+            range_i64: self.range_i64.clone(),
+            range_u64: self.range_u64.clone(),
+            range_big_uint: self.range_big_uint.clone(),
+            range_f64: self.range_f64.clone(),
+            sort_bool: self.sort_bool.clone(),
+            sort_i64: self.sort_i64.clone(),
+            sort_u64: self.sort_u64.clone(),
+            sort_big_uint: self.sort_big_uint.clone(),
+            sort_f64: self.sort_f64.clone(),
+            sort_string: self.sort_string.clone(),
+            sort_bytes: self.sort_bytes.clone(),
+            large_exact_bytes: self.large_exact_bytes.clone(),
+            vectors: self.vectors.clone(),
+            vector_indexes: self.vector_indexes.clone(),
+            text_indexes: self.text_indexes.clone(),
+            trigram_indexes: self.trigram_indexes.clone(),
+            composite_indexes: Arc::clone(&self.composite_indexes),
+            ..Self::default()
+        }))
+    }
+
+    /// Pins a snapshot of the current generation, for callers that cannot hold
+    /// an `IndexSnapshot` (e.g. across the wasm boundary), and returns the
+    /// generation to read it by. Pinning a generation again counts as another
+    /// pin; each needs a `release_snapshot`.
+    pub fn pin_snapshot(&mut self) -> u64 {
+        let generation = self.generation;
+        match self.pinned_snapshots.get_mut(&generation) {
+            Some(pinned) => pinned.pins += 1,
+            None => {
+                let snapshot = self.snapshot();
+                self.pinned_snapshots
+                    .insert(generation, PinnedSnapshot { snapshot, pins: 1 });
+            }
+        }
+        generation
+    }
+
+    pub fn pinned_snapshot(&self, generation: u64) -> Option<&IndexSnapshot> {
+        self.pinned_snapshots
+            .get(&generation)
+            .map(|pinned| &pinned.snapshot)
+    }
+
+    /// Drops one pin of `generation`; returns false when it was not pinned.
+    pub fn release_snapshot(&mut self, generation: u64) -> bool {
+        let Some(pinned) = self.pinned_snapshots.get_mut(&generation) else {
+            return false;
+        };
+        pinned.pins -= 1;
+        if pinned.pins == 0 {
+            self.pinned_snapshots.remove(&generation);
+        }
+        true
+    }
+
+    /// Kept for batch callers; the persistent maps allocate per node and
+    /// have nothing to reserve.
+    pub fn reserve_documents(&mut self, _additional: usize) {}
 
     pub fn put(&mut self, id: impl Into<String>, fields: DocumentFields) {
         let external_id = id.into();
@@ -474,6 +1744,7 @@ impl NativeQueryIndex {
         self.replace_document_fields(doc_id, fields);
         self.all_docs.insert(doc_id);
         self.generation += 1;
+        self.publish_subscription_diffs();
     }
 
     pub fn put_existing_unchecked(&mut self, id: &str, fields: DocumentFields) -> bool {
@@ -483,6 +1754,7 @@ impl NativeQueryIndex {
         self.replace_document_fields(doc_id, fields);
         self.all_docs.insert(doc_id);
         self.generation += 1;
+        self.publish_subscription_diffs();
         true
     }
 
@@ -496,7 +1768,9 @@ impl NativeQueryIndex {
         self.index_document(doc_id, &fields);
         self.documents.insert(doc_id, fields);
         self.all_docs.insert(doc_id);
+        self.attach_subscribed(doc_id);
         self.generation += 1;
+        self.publish_subscription_diffs();
     }
 
     pub fn delete(&mut self, id: impl Into<String>) {
@@ -507,6 +1781,7 @@ impl NativeQueryIndex {
     pub fn delete_id(&mut self, external_id: &str) {
         if self.remove_external(external_id) {
             self.generation += 1;
+            self.publish_subscription_diffs();
         }
     }
 
@@ -533,19 +1808,29 @@ impl NativeQueryIndex {
 
         if changed {
             self.generation += 1;
+            self.publish_subscription_diffs();
         }
         self.generation
     }
 
-    pub fn candidates(&self, query: &Query) -> RoaringBitmap {
+    /// Docs that may match `query`, which evaluation narrows. Answers that are
+    /// a whole bitmap of the index (all docs, one exact bucket) borrow it.
+    pub fn candidates(&self, query: &Query) -> Cow<'_, RoaringBitmap> {
         match query {
-            Query::All => self.all_docs.clone(),
+            Query::All | Query::IsNull { .. } => Cow::Borrowed(self.all_docs.bitmap()),
             Query::Exact { field, value } => self.exact_candidates(field, value),
+            Query::In { field, values } => Cow::Owned(self.in_candidates(field, values)),
+            Query::Between {
+                field,
+                low,
+                high,
+                inclusive,
+            } => Cow::Owned(self.between_candidates(field, low, high, *inclusive)),
             Query::Range {
                 field,
                 compare,
                 value,
-            } => self.range_candidates(field, *compare, value),
+            } => Cow::Owned(self.range_candidates(field, *compare, value)),
             Query::StringMatch {
                 field,
                 value,
@@ -555,12 +1840,33 @@ impl NativeQueryIndex {
                 .exact
                 .get(field)
                 .and_then(|values| values.get(&FieldValue::from(value.clone())))
-                .cloned()
-                .unwrap_or_default(),
-            Query::StringMatch { .. } | Query::IsNull { .. } => self.all_docs.clone(),
+                .map_or_else(
+                    || Cow::Owned(RoaringBitmap::new()),
+                    |docs| Cow::Borrowed(&**docs),
+                ),
+            Query::Text {
+                field,
+                value,
+                operator,
+            } => match self.text_indexes.get(field) {
+                Some(index) => Cow::Owned(index.candidates(
+                    &index.analyzer().terms(value),
+                    *operator == TextOperator::All,
+                )),
+                None => Cow::Borrowed(self.all_docs.bitmap()),
+            },
+            Query::StringMatch {
+                field,
+                value,
+                method,
+                ..
+            } => match self.trigram_indexes.get(field) {
+                Some(index) => Cow::Owned(index.candidates(value, *method)),
+                None => Cow::Borrowed(self.all_docs.bitmap()),
+            },
             Query::And(queries) => self.and_candidates(queries),
-            Query::Or(queries) => self.or_candidates(queries),
-            Query::Not(query) => &self.all_docs - &self.candidates(query),
+            Query::Or(queries) => Cow::Owned(self.or_candidates(queries)),
+            Query::Not(query) => Cow::Owned(self.all_docs.bitmap() - &*self.candidates(query)),
         }
     }
 
@@ -568,18 +1874,233 @@ impl NativeQueryIndex {
         self.matching_doc_ids(query).len()
     }
 
+    pub fn count_within(&self, query: &Query, budget: &QueryBudget) -> Result<u64, BudgetExceeded> {
+        let meter = BudgetMeter::new(budget);
+        let count = self.matching_doc_ids_metered(query, &meter).len();
+        meter.finish(count)
+    }
+
     pub fn sum(&self, query: &Query, field: impl Into<FieldPath>) -> Result<SumResult, String> {
-        let field = field.into();
+        self.sum_metered(query, &field.into(), &BudgetMeter::unlimited())
+    }
+
+    pub fn sum_within(
+        &self,
+        query: &Query,
+        field: impl Into<FieldPath>,
+        budget: &QueryBudget,
+    ) -> Result<SumResult, QueryError> {
+        let meter = BudgetMeter::new(budget);
+        let sum = self.sum_metered(query, &field.into(), &meter);
+        Ok(meter.finish(sum)??)
+    }
+
+    fn sum_metered(
+        &self,
+        query: &Query,
+        field: &FieldPath,
+        meter: &BudgetMeter,
+    ) -> Result<SumResult, String> {
         let mut result = SumResult::None;
-        for doc_id in self.matching_doc_ids(query).iter() {
-            let Some(value) = self.first_scalar(doc_id, &field) else {
+        for doc_id in self.matching_doc_ids_metered(query, meter).iter() {
+            let values = self.doc_scalars(doc_id, field);
+            if values.is_empty() {
                 continue;
-            };
-            result.add(value)?;
+            }
+            if !meter.materialize(1) {
+                break;
+            }
+            for value in values {
+                result.add(value)?;
+            }
         }
         Ok(result)
     }
 
+    pub fn aggregate(
+        &self,
+        query: &Query,
+        aggregation: &Aggregation,
+    ) -> Result<AggregationResult, String> {
+        Ok(match aggregation {
+            Aggregation::Count => AggregationResult::Count(self.count(query)),
+            Aggregation::Sum(field) => AggregationResult::Sum(self.sum(query, field.clone())?),
+            Aggregation::Min(field) => AggregationResult::Value(self.min(query, field.clone())),
+            Aggregation::Max(field) => AggregationResult::Value(self.max(query, field.clone())),
+            Aggregation::Average(field) => {
+                AggregationResult::Average(self.average(query, field.clone())?)
+            }
+            Aggregation::DistinctCount(field) => {
+                AggregationResult::Count(self.distinct_count(query, field.clone()))
+            }
+            Aggregation::GroupBy(field) => {
+                AggregationResult::Groups(self.group_by(query, field.clone()))
+            }
+            Aggregation::Histogram { field, interval } => {
+                AggregationResult::Histogram(self.histogram(query, field.clone(), *interval)?)
+            }
+        })
+    }
+
+    pub fn min(&self, query: &Query, field: impl Into<FieldPath>) -> Option<FieldValue> {
+        self.extreme_value(query, &field.into(), false)
+    }
+
+    pub fn max(&self, query: &Query, field: impl Into<FieldPath>) -> Option<FieldValue> {
+        self.extreme_value(query, &field.into(), true)
+    }
+
+    /// Mean of the numeric values of `field` across the matches; `None` when
+    /// no match has one.
+    pub fn average(
+        &self,
+        query: &Query,
+        field: impl Into<FieldPath>,
+    ) -> Result<Option<f64>, String> {
+        let field = field.into();
+        let mut sum = SumResult::None;
+        let mut count = 0_u64;
+        for doc_id in self.matching_doc_ids(query).iter() {
+            for value in self.doc_scalars(doc_id, &field) {
+                if matches!(
+                    value,
+                    FieldValue::I64(_)
+                        | FieldValue::U64(_)
+                        | FieldValue::BigUint(_)
+                        | FieldValue::F64(_)
+                ) {
+                    sum.add(value)?;
+                    count += 1;
+                }
+            }
+        }
+        Ok(match sum {
+            SumResult::None => None,
+            SumResult::I64(sum) => Some(sum as f64 / count as f64),
+            SumResult::U64(sum) => Some(sum as f64 / count as f64),
+            SumResult::BigUint(sum) => Some(sum.to_f64() / count as f64),
+            SumResult::F64(sum) => Some(sum / count as f64),
+        })
+    }
+
+    pub fn distinct_count(&self, query: &Query, field: impl Into<FieldPath>) -> u64 {
+        let field = field.into();
+        self.value_buckets(&self.matching_doc_ids(query), &field)
+            .len() as u64
+    }
+
+    /// Match counts per distinct value of `field`, in sort order.
+    pub fn group_by(&self, query: &Query, field: impl Into<FieldPath>) -> Vec<ValueBucket> {
+        let field = field.into();
+        let mut buckets: Vec<_> = self
+            .value_buckets(&self.matching_doc_ids(query), &field)
+            .into_iter()
+            .map(|(value, count)| ValueBucket { value, count })
+            .collect();
+        buckets.sort_by(|left, right| compare_field_values(&left.value, &right.value));
+        buckets
+    }
+
+    /// Match counts per non-empty numeric bucket of `field`, ascending.
+    pub fn histogram(
+        &self,
+        query: &Query,
+        field: impl Into<FieldPath>,
+        interval: u64,
+    ) -> Result<Vec<HistogramBucket>, String> {
+        if interval == 0 {
+            return Err("native histogram interval must be positive".to_string());
+        }
+        let field = field.into();
+        let interval = interval as i128;
+        let bucket_start = |value: i128| value.div_euclid(interval) * interval;
+        // Floats land in the integer-aligned bucket below them; infinities
+        // and NaN have no bucket.
+        let float_bucket_start = |value: f64| {
+            value
+                .is_finite()
+                .then(|| ((value / interval as f64).floor() as i128).saturating_mul(interval))
+        };
+        let matches = self.matching_doc_ids(query);
+        let range_i64 = self.range_i64.get(&field);
+        // Only wide values within i128 have a bucket start.
+        let big_bucket_start = |value: &BigUint| {
+            let value = i128::try_from(value.to_u128()?).ok()?;
+            Some(bucket_start(value))
+        };
+        let range_u64 = self.range_u64.get(&field);
+        let range_big_uint = self.range_big_uint.get(&field);
+        let range_f64 = self.range_f64.get(&field);
+        let indexed_values = range_i64.map_or(0, OrdMap::len)
+            + range_u64.map_or(0, OrdMap::len)
+            + range_big_uint.map_or(0, OrdMap::len)
+            + range_f64.map_or(0, OrdMap::len);
+
+        let mut buckets: BTreeMap<i128, RoaringBitmap> = BTreeMap::new();
+        if (indexed_values as u64) < matches.len() {
+            // Fewer distinct values than matches: walk the range indexes.
+            let values =
+                range_i64
+                    .into_iter()
+                    .flatten()
+                    .map(|(value, docs)| (bucket_start(*value as i128), docs))
+                    .chain(
+                        range_u64
+                            .into_iter()
+                            .flatten()
+                            .map(|(value, docs)| (bucket_start(*value as i128), docs)),
+                    )
+                    .chain(
+                        range_big_uint
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|(value, docs)| Some((big_bucket_start(value)?, docs))),
+                    )
+                    .chain(range_f64.into_iter().flatten().filter_map(|(value, docs)| {
+                        Some((float_bucket_start(value.get())?, docs))
+                    }));
+            for (start, docs) in values {
+                let docs = &**docs & &matches;
+                if !docs.is_empty() {
+                    *buckets.entry(start).or_default() |= docs;
+                }
+            }
+        } else {
+            for doc_id in matches.iter() {
+                let Some(values) = self
+                    .documents
+                    .get(&doc_id)
+                    .and_then(|document| document.scalar_values(&field))
+                else {
+                    continue;
+                };
+                for value in values {
+                    let start = match value {
+                        FieldValue::I64(value) => bucket_start(*value as i128),
+                        FieldValue::U64(value) => bucket_start(*value as i128),
+                        FieldValue::BigUint(value) => match big_bucket_start(value) {
+                            Some(start) => start,
+                            None => continue,
+                        },
+                        FieldValue::F64(value) => match float_bucket_start(value.get()) {
+                            Some(start) => start,
+                            None => continue,
+                        },
+                        _ => continue,
+                    };
+                    buckets.entry(start).or_default().insert(doc_id);
+                }
+            }
+        }
+        Ok(buckets
+            .into_iter()
+            .map(|(start, docs)| HistogramBucket {
+                start,
+                count: docs.len(),
+            })
+            .collect())
+    }
+
     pub fn delete_matching(&mut self, query: &Query) -> Vec<String> {
         let ids: Vec<_> = self
             .matching_doc_ids(query)
@@ -620,9 +2141,40 @@ impl NativeQueryIndex {
         sort: &[SortField],
         offset: usize,
         limit: Option<usize>,
+    ) -> Vec<String> {
+        self.search_page_metered(query, sort, offset, limit, &BudgetMeter::unlimited())
+    }
+
+    /// `search_page` that gives up once `budget` is spent. Index-sorted pages
+    /// stop at the page, so only the candidates walked to fill it count;
+    /// pages sorted in memory materialize every match.
+    pub fn search_page_within(
+        &self,
+        query: &Query,
+        sort: &[SortField],
+        offset: usize,
+        limit: Option<usize>,
+        budget: &QueryBudget,
+    ) -> Result<Vec<String>, BudgetExceeded> {
+        let meter = BudgetMeter::new(budget);
+        let page = self.search_page_metered(query, sort, offset, limit, &meter);
+        meter.finish(page)
+    }
+
+    fn search_page_metered(
+        &self,
+        query: &Query,
+        sort: &[SortField],
+        offset: usize,
+        limit: Option<usize>,
+        meter: &BudgetMeter,
     ) -> Vec<String> {
         if sort.is_empty() {
-            let matches = self.matching_doc_ids(query);
+            let matches = self.matching_doc_ids_metered(query, meter);
+            let returned = matches.len().saturating_sub(offset as u64);
+            if !meter.materialize(limit.map_or(returned, |limit| returned.min(limit as u64))) {
+                return Vec::new();
+            }
             let iter = matches.iter().skip(offset);
             if let Some(limit) = limit {
                 return iter
@@ -635,11 +2187,15 @@ impl NativeQueryIndex {
                 .collect();
         }
 
-        if let Some(page) = self.search_index_sorted_page(query, sort, offset, limit) {
+        if let Some(page) = self.search_index_sorted_page(query, sort, offset, limit, meter) {
             return page;
         }
 
-        let mut doc_ids: Vec<_> = self.matching_doc_ids(query).iter().collect();
+        let matches = self.matching_doc_ids_metered(query, meter);
+        if !meter.materialize(matches.len()) {
+            return Vec::new();
+        }
+        let mut doc_ids: Vec<_> = matches.iter().collect();
         doc_ids.sort_by(|left, right| self.compare_docs(*left, *right, sort));
         let doc_ids = doc_ids.into_iter().skip(offset);
         if let Some(limit) = limit {
@@ -653,9 +2209,202 @@ impl NativeQueryIndex {
             .collect()
     }
 
+    /// Keyset paging: the next `limit` matches ordered strictly after `after`
+    /// (from the start when `None`). Unlike offset paging, documents written
+    /// between pages cannot shift already-returned results into a later page.
+    /// With an empty `sort`, results order by id rather than insertion.
+    pub fn search_page_after(
+        &self,
+        query: &Query,
+        sort: &[SortField],
+        after: Option<&SearchCursor>,
+        limit: usize,
+    ) -> Result<SearchPage, String> {
+        if after.is_some_and(|cursor| cursor.values.len() != sort.len()) {
+            return Err("native search cursor does not match the sort fields".to_string());
+        }
+        let after = after.map(|cursor| {
+            (
+                cursor.values.as_slice(),
+                IdSortKey::from_store_key(&cursor.id),
+            )
+        });
+        let doc_ids = match self.search_index_sorted_after(query, sort, after.as_ref(), limit) {
+            Some(doc_ids) => doc_ids,
+            None => {
+                let compare = |left: &DocId, right: &DocId| self.compare_docs(*left, *right, sort);
+                let mut doc_ids: Vec<_> = self
+                    .matching_doc_ids(query)
+                    .iter()
+                    .filter(|doc_id| {
+                        after.as_ref().is_none_or(|(values, id)| {
+                            self.compare_doc_to_key(*doc_id, sort, values, id) == Ordering::Greater
+                        })
+                    })
+                    .collect();
+                if doc_ids.len() > limit {
+                    doc_ids.select_nth_unstable_by(limit, compare);
+                    doc_ids.truncate(limit);
+                }
+                doc_ids.sort_by(compare);
+                doc_ids
+            }
+        };
+
+        let cursor = match doc_ids.last() {
+            Some(last) if doc_ids.len() == limit => Some(SearchCursor {
+                values: sort
+                    .iter()
+                    .map(|field| self.first_scalar(*last, &field.field).cloned())
+                    .collect(),
+                id: self
+                    .internal_to_external
+                    .get(last)
+                    .cloned()
+                    .unwrap_or_default(),
+            }),
+            _ => None,
+        };
+        Ok(SearchPage {
+            ids: doc_ids
+                .into_iter()
+                .filter_map(|doc_id| self.internal_to_external.get(&doc_id).cloned())
+                .collect(),
+            cursor,
+            generation: self.generation,
+        })
+    }
+
+    pub fn explain(&self, query: &Query, sort: &[SortField], limit: Option<usize>) -> QueryPlan {
+        let root = self.explain_node(query);
+        let matched = self.count(query);
+        let sort = match sort {
+            [] => SortStrategy::DocumentOrder,
+            [sort] => match self.sort_source(query, &sort.field) {
+                Some(SortSource {
+                    composite: Some(index),
+                    ..
+                }) => SortStrategy::CompositeIndex {
+                    prefix: index.prefix().to_vec(),
+                    field: sort.field.clone(),
+                },
+                Some(_) => SortStrategy::SortIndex {
+                    field: sort.field.clone(),
+                },
+                None => SortStrategy::InMemory,
+            },
+            _ => SortStrategy::InMemory,
+        };
+        QueryPlan {
+            full_scan: root.scans_all_documents(),
+            documents_evaluated: root.actual_candidates,
+            matched,
+            returned: limit.map_or(matched, |limit| matched.min(limit as u64)),
+            root,
+            sort,
+        }
+    }
+
+    fn explain_node(&self, query: &Query) -> PlanNode {
+        let mut children = Vec::new();
+        let (predicate, field, access) = match query {
+            Query::All => ("all", None, PlanAccess::AllDocuments),
+            Query::Exact { field, value } => (
+                "exact",
+                Some(field),
+                if is_large_byte_value(value) {
+                    PlanAccess::LargeByteValues
+                } else {
+                    PlanAccess::ExactIndex
+                },
+            ),
+            Query::In { field, values } => (
+                "in",
+                Some(field),
+                if values.iter().any(is_large_byte_value) {
+                    PlanAccess::LargeByteValues
+                } else {
+                    PlanAccess::ExactIndex
+                },
+            ),
+            Query::Range { field, .. } => ("range", Some(field), PlanAccess::RangeIndex),
+            Query::Between { field, .. } => ("between", Some(field), PlanAccess::RangeIndex),
+            Query::StringMatch {
+                field,
+                method: StringMatchMethod::Exact,
+                case_insensitive: false,
+                ..
+            } => ("string_match", Some(field), PlanAccess::ExactIndex),
+            Query::StringMatch { field, .. } => (
+                "string_match",
+                Some(field),
+                if self.trigram_indexes.contains_key(field) {
+                    PlanAccess::TrigramIndex
+                } else {
+                    PlanAccess::FullScan
+                },
+            ),
+            Query::IsNull { field } => ("is_null", Some(field), PlanAccess::FullScan),
+            Query::Text { field, .. } => (
+                "text",
+                Some(field),
+                if self.text_indexes.contains_key(field) {
+                    PlanAccess::TextIndex
+                } else {
+                    PlanAccess::FullScan
+                },
+            ),
+            Query::And(queries) => {
+                children = queries
+                    .iter()
+                    .map(|query| self.explain_node(query))
+                    .collect();
+                let access = if self.composite_seed(queries).is_some() {
+                    PlanAccess::CompositeIndex
+                } else {
+                    queries
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, query)| self.estimated_candidate_len(query))
+                        .map_or(PlanAccess::AllDocuments, |(child, _)| PlanAccess::Seeded {
+                            child,
+                        })
+                };
+                ("and", None, access)
+            }
+            Query::Or(queries) => {
+                children = queries
+                    .iter()
+                    .map(|query| self.explain_node(query))
+                    .collect();
+                ("or", None, PlanAccess::Union)
+            }
+            Query::Not(query) => {
+                children.push(self.explain_node(query));
+                ("not", None, PlanAccess::Complement)
+            }
+        };
+        PlanNode {
+            predicate,
+            field: field.cloned(),
+            access,
+            estimated_candidates: self.estimated_candidate_len(query),
+            actual_candidates: self.candidates(query).len(),
+            children,
+        }
+    }
+
     fn matching_doc_ids(&self, query: &Query) -> RoaringBitmap {
+        self.matching_doc_ids_metered(query, &BudgetMeter::unlimited())
+    }
+
+    /// Matches verified until `meter` runs out.
+    fn matching_doc_ids_metered(&self, query: &Query, meter: &BudgetMeter) -> RoaringBitmap {
         let mut matches = RoaringBitmap::new();
         for doc_id in self.candidates(query).iter() {
+            if !meter.examine() {
+                break;
+            }
             if self.matches_doc(doc_id, query) {
                 matches.insert(doc_id);
             }
@@ -683,6 +2432,11 @@ impl NativeQueryIndex {
             Query::Exact { field, value } => {
                 self.matching_field_scopes(document, field, |field_value| field_value == value)
             }
+            Query::In { field, values } => {
+                self.matching_field_scopes(document, field, |field_value| {
+                    values.contains(field_value)
+                })
+            }
             Query::Range {
                 field,
                 compare,
@@ -690,6 +2444,18 @@ impl NativeQueryIndex {
             } => self.matching_field_scopes(document, field, |field_value| {
                 compare_range_values(field_value, *compare, value)
             }),
+            Query::Between {
+                field,
+                low,
+                high,
+                inclusive,
+            } => {
+                let (lower, upper) = between_compares(*inclusive);
+                self.matching_field_scopes(document, field, |field_value| {
+                    compare_range_values(field_value, lower, low)
+                        && compare_range_values(field_value, upper, high)
+                })
+            }
             Query::StringMatch {
                 field,
                 value,
@@ -698,6 +2464,11 @@ impl NativeQueryIndex {
             } => self.matching_field_scopes(document, field, |field_value| {
                 matches_string_value(field_value, value, *method, *case_insensitive)
             }),
+            Query::Text {
+                field,
+                value,
+                operator,
+            } => self.matching_text_scopes(document, field, value, *operator),
             Query::IsNull { field } => {
                 if document
                     .scoped_scalars
@@ -780,17 +2551,147 @@ impl NativeQueryIndex {
         }
     }
 
+    /// Terms are matched per document, across all of its values for `field`:
+    /// with `TextOperator::All` the terms may be spread over several array
+    /// items. The matched scopes are those of the values holding any term.
+    fn matching_text_scopes(
+        &self,
+        document: &DocumentFields,
+        field: &FieldPath,
+        value: &str,
+        operator: TextOperator,
+    ) -> QueryMatch {
+        let default_analyzer;
+        let analyzer = match self.text_indexes.get(field) {
+            Some(index) => index.analyzer(),
+            None => {
+                default_analyzer = TextAnalyzer::simple();
+                &default_analyzer
+            }
+        };
+        let terms = analyzer.terms(value);
+        let mut found = HashSet::new();
+        let mut scopes = RoaringBitmap::new();
+        let mut field_present = false;
+        for fact in &document.scoped_scalars {
+            if fact.path != *field {
+                continue;
+            }
+            field_present = true;
+            let FieldValue::String(text) = &fact.value else {
+                continue;
+            };
+            let tokens: HashSet<_> = analyzer.tokens(text).into_iter().collect();
+            for term in terms.iter().filter(|term| tokens.contains(*term)) {
+                found.insert(term);
+                scopes.insert(fact.scope);
+            }
+        }
+        if !field_present {
+            return QueryMatch::Undefined;
+        }
+        let complete = match operator {
+            TextOperator::Any => !found.is_empty(),
+            TextOperator::All => !terms.is_empty() && found.len() == terms.len(),
+        };
+        if complete {
+            QueryMatch::Matched(scopes)
+        } else {
+            QueryMatch::False
+        }
+    }
+
+    pub fn text_search(
+        &self,
+        filter: &Query,
+        text_sort: &TextSort,
+        limit: usize,
+    ) -> Result<Vec<ScoredDocument>, String> {
+        let index = self.text_indexes.get(&text_sort.field).ok_or_else(|| {
+            format!(
+                "native text search requires a text index on {:?}",
+                text_sort.field
+            )
+        })?;
+        let query = Query::Text {
+            field: text_sort.field.clone(),
+            value: text_sort.query.clone(),
+            operator: text_sort.operator,
+        };
+        let terms = index.analyzer().terms(&text_sort.query);
+        let mut candidates = self.candidates(&query);
+        if !matches!(filter, Query::All) {
+            *candidates.to_mut() &= &*self.candidates(filter);
+        }
+        let mut scored = Vec::new();
+        for doc_id in candidates.iter() {
+            if !self.matches_doc(doc_id, &query) || !self.matches_doc(doc_id, filter) {
+                continue;
+            }
+            let Some(id) = self.internal_to_external.get(&doc_id) else {
+                continue;
+            };
+            scored.push(ScoredDocument {
+                id: id.clone(),
+                score: index.score(doc_id, &terms),
+            });
+        }
+        scored.sort_by(|left, right| {
+            right
+                .score
+                .partial_cmp(&left.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.id.cmp(&right.id))
+        });
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
     pub fn vector_search(
         &self,
         filter: &Query,
         vector_sort: &VectorSort,
         limit: usize,
+    ) -> Vec<ScoredDocument> {
+        self.vector_search_metered(filter, vector_sort, limit, &BudgetMeter::unlimited())
+    }
+
+    /// `vector_search` that gives up once `budget` is spent; every scored
+    /// document counts as materialized.
+    pub fn vector_search_within(
+        &self,
+        filter: &Query,
+        vector_sort: &VectorSort,
+        limit: usize,
+        budget: &QueryBudget,
+    ) -> Result<Vec<ScoredDocument>, BudgetExceeded> {
+        let meter = BudgetMeter::new(budget);
+        let scored = self.vector_search_metered(filter, vector_sort, limit, &meter);
+        meter.finish(scored)
+    }
+
+    fn vector_search_metered(
+        &self,
+        filter: &Query,
+        vector_sort: &VectorSort,
+        limit: usize,
+        meter: &BudgetMeter,
     ) -> Vec<ScoredDocument> {
         let Some(vectors) = self.vectors.get(&vector_sort.field) else {
             return Vec::new();
         };
+        if let VectorSearchMode::Approximate { ef } = vector_sort.mode {
+            if let Some(scored) =
+                self.approximate_vector_search(filter, vector_sort, limit, ef, meter)
+            {
+                return scored;
+            }
+        }
         let mut scored = Vec::new();
         for doc_id in self.candidates(filter).iter() {
+            if !meter.examine() {
+                break;
+            }
             if !self.matches_doc(doc_id, filter) {
                 continue;
             }
@@ -803,6 +2704,9 @@ impl NativeQueryIndex {
             let Some(id) = self.internal_to_external.get(&doc_id) else {
                 continue;
             };
+            if !meter.materialize(1) {
+                break;
+            }
             scored.push(ScoredDocument {
                 id: id.clone(),
                 score: vector_distance(vector, &vector_sort.query, vector_sort.metric),
@@ -818,8 +2722,58 @@ impl NativeQueryIndex {
         scored
     }
 
+    /// Graph-backed search. Returns `None` when the request has to be served by
+    /// the exact scan instead (no graph for the field/metric, dimension
+    /// mismatch, or a filter selective enough that scoring every match is
+    /// cheaper than walking the graph).
+    fn approximate_vector_search(
+        &self,
+        filter: &Query,
+        vector_sort: &VectorSort,
+        limit: usize,
+        ef: usize,
+        meter: &BudgetMeter,
+    ) -> Option<Vec<ScoredDocument>> {
+        let index = self.vector_indexes.get(&vector_sort.field)?;
+        if index.config().metric != vector_sort.metric
+            || index.dimension() != Some(vector_sort.query.len())
+        {
+            return None;
+        }
+        let vectors = self.vectors.get(&vector_sort.field)?;
+        let ef = ef.max(limit);
+        let allowed = match filter {
+            Query::All => None,
+            filter => {
+                let allowed = self.matching_doc_ids_metered(filter, meter);
+                if allowed.len() <= ef as u64 {
+                    return None;
+                }
+                Some(allowed)
+            }
+        };
+        let mut scored: Vec<_> = index
+            .search(&vector_sort.query, limit, ef, allowed.as_ref(), vectors)
+            .into_iter()
+            .filter_map(|(doc_id, score)| {
+                Some(ScoredDocument {
+                    id: self.internal_to_external.get(&doc_id)?.clone(),
+                    score,
+                })
+            })
+            .collect();
+        meter.materialize(scored.len() as u64);
+        scored.sort_by(|left, right| {
+            left.score
+                .partial_cmp(&right.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.id.cmp(&right.id))
+        });
+        Some(scored)
+    }
+
     fn allocate_doc_id(&mut self, external_id: String) -> DocId {
-        let doc_id = match self.free_doc_ids.pop() {
+        let doc_id = match self.free_doc_ids.pop_back() {
             Some(doc_id) => {
                 debug_assert!(
                     !self.internal_to_external.contains_key(&doc_id)
@@ -854,11 +2808,12 @@ impl NativeQueryIndex {
         let Some(doc_id) = self.external_to_internal.remove(external_id) else {
             return false;
         };
+        self.detach_subscribed(doc_id);
         self.internal_to_external.remove(&doc_id);
         self.internal_to_sort_key.remove(&doc_id);
         self.remove_document_fields(doc_id);
         self.all_docs.remove(doc_id);
-        self.free_doc_ids.push(doc_id);
+        self.free_doc_ids.push_back(doc_id);
         true
     }
 
@@ -866,17 +2821,26 @@ impl NativeQueryIndex {
         let Some(fields) = self.documents.remove(&doc_id) else {
             return;
         };
+        if !self.composite_indexes.is_empty() {
+            for index in Arc::make_mut(&mut self.composite_indexes) {
+                index.remove(doc_id, &fields);
+            }
+        }
         for (path, values) in fields.scalars {
             self.remove_scalar_values(&path, values, doc_id);
         }
         for (path, _) in fields.vectors {
-            if let Some(values) = self.vectors.get_mut(&path) {
-                values.remove(&doc_id);
-            }
+            self.remove_vector(&path, doc_id);
         }
     }
 
     fn replace_document_fields(&mut self, doc_id: DocId, fields: DocumentFields) {
+        self.detach_subscribed(doc_id);
+        self.reindex_document_fields(doc_id, fields);
+        self.attach_subscribed(doc_id);
+    }
+
+    fn reindex_document_fields(&mut self, doc_id: DocId, fields: DocumentFields) {
         let Some(existing) = self.documents.get(&doc_id) else {
             self.index_document(doc_id, &fields);
             self.documents.insert(doc_id, fields);
@@ -886,6 +2850,18 @@ impl NativeQueryIndex {
             self.documents.insert(doc_id, fields);
             return;
         }
+        if self
+            .composite_indexes
+            .iter()
+            .any(|index| index.affected_by(existing, &fields))
+        {
+            for index in Arc::make_mut(&mut self.composite_indexes) {
+                if index.affected_by(existing, &fields) {
+                    index.remove(doc_id, existing);
+                    index.insert(doc_id, &fields);
+                }
+            }
+        }
 
         let removed_scalars = existing
             .scalars
@@ -916,68 +2892,221 @@ impl NativeQueryIndex {
             self.remove_scalar_values(&path, values, doc_id);
         }
         for path in removed_vectors {
-            if let Some(values) = self.vectors.get_mut(&path) {
-                values.remove(&doc_id);
-            }
+            self.remove_vector(&path, doc_id);
         }
         for (path, values) in inserted_scalars {
             self.index_scalar_values(&path, &values, doc_id);
         }
         for (path, vector) in inserted_vectors {
-            self.vectors.entry(path).or_default().insert(doc_id, vector);
+            self.insert_vector(path, vector, doc_id);
         }
         self.documents.insert(doc_id, fields);
     }
 
+    fn take_reader_state(&mut self) -> ReaderState {
+        ReaderState {
+            generation: self.generation,
+            subscriptions: std::mem::take(&mut self.subscriptions),
+            next_subscription_id: self.next_subscription_id,
+            subscription_diffs: std::mem::take(&mut self.subscription_diffs),
+            pinned_snapshots: std::mem::take(&mut self.pinned_snapshots),
+        }
+    }
+
+    /// Reinstalls readers after the documents were replaced wholesale,
+    /// re-running subscription queries once. The generation keeps increasing
+    /// so the replacement reads as a write and never reuses a pinned one.
+    fn restore_reader_state(&mut self, state: ReaderState) {
+        self.generation = self.generation.max(state.generation + 1);
+        let mut subscriptions = state.subscriptions;
+        for subscription in subscriptions.values_mut() {
+            self.populate_subscription(subscription);
+        }
+        self.subscriptions = subscriptions;
+        self.next_subscription_id = state.next_subscription_id;
+        self.subscription_diffs = state.subscription_diffs;
+        self.pinned_snapshots = state.pinned_snapshots;
+        self.publish_subscription_views(None);
+    }
+
+    fn populate_subscription(&self, subscription: &mut Subscription) {
+        subscription.members = self.matching_doc_ids(&subscription.query);
+        subscription.ordered = subscription.members.iter().collect();
+        subscription
+            .ordered
+            .sort_by(|left, right| self.compare_docs(*left, *right, &subscription.sort));
+    }
+
+    fn subscription_view(&self, subscription: &Subscription) -> Vec<String> {
+        subscription
+            .ordered
+            .iter()
+            .take(subscription.limit.unwrap_or(usize::MAX))
+            .filter_map(|doc_id| self.internal_to_external.get(doc_id).cloned())
+            .collect()
+    }
+
+    /// Takes `doc_id` out of every subscription before its fields change or it
+    /// is removed: its position is found with the values it was ordered by.
+    fn detach_subscribed(&mut self, doc_id: DocId) {
+        if self.subscriptions.is_empty() {
+            return;
+        }
+        if let Some(external_id) = self.internal_to_external.get(&doc_id) {
+            self.subscription_touched.insert(external_id.clone());
+        }
+        let mut subscriptions = std::mem::take(&mut self.subscriptions);
+        for subscription in subscriptions.values_mut() {
+            if !subscription.members.remove(doc_id) {
+                continue;
+            }
+            match subscription
+                .ordered
+                .binary_search_by(|probe| self.compare_docs(*probe, doc_id, &subscription.sort))
+            {
+                Ok(position) => {
+                    subscription.ordered.remove(position);
+                }
+                Err(_) => subscription.ordered.retain(|member| *member != doc_id),
+            }
+        }
+        self.subscriptions = subscriptions;
+    }
+
+    fn attach_subscribed(&mut self, doc_id: DocId) {
+        if self.subscriptions.is_empty() {
+            return;
+        }
+        if let Some(external_id) = self.internal_to_external.get(&doc_id) {
+            self.subscription_touched.insert(external_id.clone());
+        }
+        let mut subscriptions = std::mem::take(&mut self.subscriptions);
+        for subscription in subscriptions.values_mut() {
+            if !self.matches_doc(doc_id, &subscription.query) {
+                continue;
+            }
+            subscription.members.insert(doc_id);
+            let (Ok(position) | Err(position)) = subscription
+                .ordered
+                .binary_search_by(|probe| self.compare_docs(*probe, doc_id, &subscription.sort));
+            subscription.ordered.insert(position, doc_id);
+        }
+        self.subscriptions = subscriptions;
+    }
+
+    fn publish_subscription_diffs(&mut self) {
+        if self.subscription_touched.is_empty() {
+            return;
+        }
+        let touched = std::mem::take(&mut self.subscription_touched);
+        self.publish_subscription_views(Some(&touched));
+    }
+
+    /// Queues a diff for every subscription whose view changed. `touched` is
+    /// the set of rewritten ids; `None` treats every id as rewritten.
+    fn publish_subscription_views(&mut self, touched: Option<&HashSet<String>>) {
+        let mut subscriptions = std::mem::take(&mut self.subscriptions);
+        for (id, subscription) in subscriptions.iter_mut() {
+            let view = self.subscription_view(subscription);
+            let diff =
+                diff_subscription_views(*id, self.generation, &subscription.view, &view, touched);
+            subscription.view = view;
+            if !diff.is_empty() {
+                self.subscription_diffs.push(diff);
+            }
+        }
+        self.subscriptions = subscriptions;
+    }
+
     fn index_document(&mut self, doc_id: DocId, fields: &DocumentFields) {
+        if !self.composite_indexes.is_empty() {
+            for index in Arc::make_mut(&mut self.composite_indexes) {
+                index.insert(doc_id, fields);
+            }
+        }
         for (path, values) in &fields.scalars {
             self.index_scalar_values(path, values, doc_id);
         }
         for (path, vector) in &fields.vectors {
-            self.vectors
-                .entry(path.clone())
-                .or_default()
-                .insert(doc_id, vector.clone());
+            self.insert_vector(path.clone(), vector.clone(), doc_id);
         }
     }
 
+    fn insert_vector(&mut self, path: FieldPath, vector: Vec<f32>, doc_id: DocId) {
+        let vectors = self.vectors.entry(path.clone()).or_default();
+        vectors.insert(doc_id, vector);
+        if let Some(index) = self.vector_indexes.get_mut(&path) {
+            index.insert(doc_id, vectors);
+        }
+    }
+
+    fn remove_vector(&mut self, path: &FieldPath, doc_id: DocId) {
+        let Some(vectors) = self.vectors.get_mut(path) else {
+            return;
+        };
+        if let Some(index) = self.vector_indexes.get_mut(path) {
+            index.remove(doc_id, vectors);
+        }
+        vectors.remove(&doc_id);
+    }
+
     fn index_scalar_values(&mut self, path: &FieldPath, values: &[FieldValue], doc_id: DocId) {
+        if let Some(index) = self.text_indexes.get_mut(path) {
+            index.insert(doc_id, values);
+        }
+        if let Some(index) = self.trigram_indexes.get_mut(path) {
+            index.insert(doc_id, values);
+        }
         if let Some(value) = values.first() {
             self.insert_sort_value(path, value, doc_id);
         }
         for value in values {
             if is_large_byte_value(value) {
-                self.large_exact_bytes
-                    .entry(path.clone())
-                    .or_default()
+                Arc::make_mut(self.large_exact_bytes.entry(path.clone()).or_default())
                     .insert(doc_id);
             } else {
-                self.exact
-                    .entry(path.clone())
-                    .or_default()
-                    .entry(value.clone())
-                    .or_default()
-                    .insert(doc_id);
+                Arc::make_mut(
+                    self.exact
+                        .entry(path.clone())
+                        .or_default()
+                        .entry(value.clone())
+                        .or_default(),
+                )
+                .insert(doc_id);
             }
             if let Some(value) = value.as_i64() {
-                self.range_i64
-                    .entry(path.clone())
-                    .or_default()
-                    .entry(value)
-                    .or_default()
-                    .insert(doc_id);
+                Arc::make_mut(
+                    self.range_i64
+                        .entry(path.clone())
+                        .or_default()
+                        .entry(value)
+                        .or_default(),
+                )
+                .insert(doc_id);
             } else if let Some(value) = value.as_u64() {
-                self.range_u64
-                    .entry(path.clone())
-                    .or_default()
-                    .entry(value)
-                    .or_default()
-                    .insert(doc_id);
+                Arc::make_mut(
+                    self.range_u64
+                        .entry(path.clone())
+                        .or_default()
+                        .entry(value)
+                        .or_default(),
+                )
+                .insert(doc_id);
+            } else if let Some(value) = value.as_big_uint() {
+                insert_into_ordered_index(&mut self.range_big_uint, path, value.clone(), doc_id);
+            } else if let Some(value) = value.as_ordered_f64() {
+                insert_into_ordered_index(&mut self.range_f64, path, value, doc_id);
             }
         }
     }
 
     fn remove_scalar_values(&mut self, path: &FieldPath, values: Vec<FieldValue>, doc_id: DocId) {
+        if let Some(index) = self.text_indexes.get_mut(path) {
+            index.remove(doc_id, &values);
+        }
+        if let Some(index) = self.trigram_indexes.get_mut(path) {
+            index.remove(doc_id, &values);
+        }
         if let Some(value) = values.first() {
             self.remove_sort_value(path, value, doc_id);
         }
@@ -991,25 +3120,95 @@ impl NativeQueryIndex {
                 remove_from_range_i64(&mut self.range_i64, path, value, doc_id);
             } else if let Some(value) = value.as_u64() {
                 remove_from_range_u64(&mut self.range_u64, path, value, doc_id);
+            } else if let Some(value) = value.as_big_uint() {
+                remove_from_ordered_index(&mut self.range_big_uint, path, value, doc_id);
+            } else if let Some(value) = value.as_ordered_f64() {
+                remove_from_ordered_index(&mut self.range_f64, path, &value, doc_id);
             }
         }
     }
 
-    fn and_candidates(&self, queries: &[Query]) -> RoaringBitmap {
+    fn and_candidates(&self, queries: &[Query]) -> Cow<'_, RoaringBitmap> {
         if queries.is_empty() {
-            return self.all_docs.clone();
+            return Cow::Borrowed(self.all_docs.bitmap());
+        }
+        if let Some(candidates) = self.composite_seed(queries) {
+            return Cow::Owned(candidates);
+        }
+        queries
+            .iter()
+            .min_by_key(|query| self.estimated_candidate_len(query))
+            .map_or_else(
+                || Cow::Owned(RoaringBitmap::new()),
+                |query| self.candidates(query),
+            )
+    }
+
+    /// Candidates of an `And` from the composite index its exact matches pin,
+    /// when that is no larger than the best child's estimate.
+    fn composite_seed(&self, queries: &[Query]) -> Option<RoaringBitmap> {
+        let (index, group) = self.pinned_composite_group(queries, None)?;
+        let Some(group) = group else {
+            return Some(RoaringBitmap::new());
+        };
+        let mut candidates = group.docs().clone();
+        for query in queries {
+            if let Query::Range {
+                field,
+                compare,
+                value,
+            } = query
+            {
+                if field == index.suffix() {
+                    candidates &= group.range_candidates(*compare, value);
+                }
+            }
+        }
+        let best_child = queries
+            .iter()
+            .map(|query| self.estimated_candidate_len(query))
+            .min()
+            .unwrap_or(u64::MAX);
+        (candidates.len() <= best_child).then_some(candidates)
+    }
+
+    /// The composite index whose every prefix field is pinned by an `Exact`
+    /// among `conjuncts` (the longest such prefix; ordered by `suffix` when
+    /// given), with the group of the pinned values. The group is `None` when
+    /// no document holds that combination.
+    fn pinned_composite_group(
+        &self,
+        conjuncts: &[Query],
+        suffix: Option<&FieldPath>,
+    ) -> Option<(&CompositeIndex, Option<&CompositeGroup>)> {
+        if self.composite_indexes.is_empty() {
+            return None;
         }
-        queries
+        let pinned = |path: &FieldPath| {
+            conjuncts.iter().find_map(|query| match query {
+                Query::Exact { field, value } if field == path => Some(value.clone()),
+                _ => None,
+            })
+        };
+        self.composite_indexes
             .iter()
-            .min_by_key(|query| self.estimated_candidate_len(query))
-            .map(|query| self.candidates(query))
-            .unwrap_or_default()
+            .filter(|index| suffix.is_none_or(|suffix| index.suffix() == suffix))
+            .filter_map(|index| {
+                let key = index
+                    .prefix()
+                    .iter()
+                    .map(pinned)
+                    .collect::<Option<Vec<_>>>()?;
+                Some((index, key))
+            })
+            .max_by_key(|(index, _)| index.prefix().len())
+            .map(|(index, key)| (index, index.group(&key)))
     }
 
     fn or_candidates(&self, queries: &[Query]) -> RoaringBitmap {
         let mut result = RoaringBitmap::new();
         for query in queries {
-            result |= self.candidates(query);
+            result |= &*self.candidates(query);
         }
         result
     }
@@ -1023,16 +3222,105 @@ impl NativeQueryIndex {
         if let Some(value) = value.as_i64() {
             return range_i64_candidates(self.range_i64.get(field), compare, value);
         }
+        // Every `BigUint` is above every `U64`, so a bound of one kind either
+        // takes all of the other kind's values or none of them.
         if let Some(value) = value.as_u64() {
-            return range_u64_candidates(self.range_u64.get(field), compare, value);
+            let mut candidates = range_u64_candidates(self.range_u64.get(field), compare, value);
+            if matches!(
+                compare,
+                Compare::Greater | Compare::GreaterOrEqual | Compare::NotEqual
+            ) {
+                if let Some(index) = self.range_big_uint.get(field) {
+                    candidates |= union_bitmaps(index.values());
+                }
+            }
+            return candidates;
+        }
+        if let Some(value) = value.as_big_uint() {
+            let mut candidates =
+                ordered_range_candidates(self.range_big_uint.get(field), compare, value);
+            if matches!(
+                compare,
+                Compare::Less | Compare::LessOrEqual | Compare::NotEqual
+            ) {
+                if let Some(index) = self.range_u64.get(field) {
+                    candidates |= union_bitmaps(index.values());
+                }
+            }
+            return candidates;
+        }
+        if let Some(value) = value.as_ordered_f64() {
+            return ordered_range_candidates(self.range_f64.get(field), compare, &value);
         }
         RoaringBitmap::new()
     }
 
+    fn in_candidates(&self, field: &FieldPath, values: &[FieldValue]) -> RoaringBitmap {
+        let mut matches = RoaringBitmap::new();
+        if let Some(exact) = self.exact.get(field) {
+            for docs in values.iter().filter_map(|value| exact.get(value)) {
+                matches |= &**docs;
+            }
+        }
+        if values.iter().any(is_large_byte_value) {
+            if let Some(large_byte_docs) = self.large_exact_bytes.get(field) {
+                matches |= &**large_byte_docs;
+            }
+        }
+        matches
+    }
+
+    fn between_candidates(
+        &self,
+        field: &FieldPath,
+        low: &FieldValue,
+        high: &FieldValue,
+        inclusive: bool,
+    ) -> RoaringBitmap {
+        match (low, high) {
+            (FieldValue::I64(low), FieldValue::I64(high)) => {
+                bounded_range_candidates(self.range_i64.get(field), low, high, inclusive)
+            }
+            (FieldValue::U64(low), FieldValue::U64(high)) => {
+                bounded_range_candidates(self.range_u64.get(field), low, high, inclusive)
+            }
+            (FieldValue::BigUint(low), FieldValue::BigUint(high)) => {
+                bounded_range_candidates(self.range_big_uint.get(field), low, high, inclusive)
+            }
+            _ => match (low.as_ordered_f64(), high.as_ordered_f64()) {
+                (Some(low), Some(high)) => {
+                    bounded_range_candidates(self.range_f64.get(field), &low, &high, inclusive)
+                }
+                // Bounds of different kinds: intersect each side's candidates
+                // (a superset for multi-valued fields; evaluation narrows it).
+                _ => {
+                    let (lower, upper) = between_compares(inclusive);
+                    self.range_candidates(field, lower, low)
+                        & self.range_candidates(field, upper, high)
+                }
+            },
+        }
+    }
+
     fn estimated_candidate_len(&self, query: &Query) -> u64 {
         match query {
             Query::All => self.all_docs.len(),
             Query::Exact { field, value } => self.estimated_exact_candidate_len(field, value),
+            Query::In { field, values } => values
+                .iter()
+                .map(|value| self.estimated_exact_candidate_len(field, value))
+                .fold(0_u64, u64::saturating_add)
+                .min(self.all_docs.len()),
+            Query::Between {
+                field,
+                low,
+                high,
+                inclusive,
+            } => {
+                let (lower, upper) = between_compares(*inclusive);
+                self.estimated_range_candidate_len(field, lower, low)
+                    .min(self.estimated_range_candidate_len(field, upper, high))
+            }
             Query::Range {
                 field,
                 compare,
@@ -1047,13 +3335,27 @@ impl NativeQueryIndex {
                 .exact
                 .get(field)
                 .and_then(|values| values.get(&FieldValue::from(value.clone())))
-                .map(RoaringBitmap::len)
+                .map(|docs| docs.len())
                 .unwrap_or(0),
-            Query::And(queries) => queries
-                .iter()
-                .map(|query| self.estimated_candidate_len(query))
-                .min()
-                .unwrap_or_else(|| self.all_docs.len()),
+            Query::Text {
+                field,
+                value,
+                operator,
+            } => match self.text_indexes.get(field) {
+                Some(index) => index.estimated_candidate_len(
+                    &index.analyzer().terms(value),
+                    *operator == TextOperator::All,
+                ),
+                None => self.all_docs.len(),
+            },
+            Query::And(queries) => match self.composite_seed(queries) {
+                Some(candidates) => candidates.len(),
+                None => queries
+                    .iter()
+                    .map(|query| self.estimated_candidate_len(query))
+                    .min()
+                    .unwrap_or_else(|| self.all_docs.len()),
+            },
             Query::Or(queries) => queries
                 .iter()
                 .map(|query| self.estimated_candidate_len(query))
@@ -1063,7 +3365,16 @@ impl NativeQueryIndex {
                 .all_docs
                 .len()
                 .saturating_sub(self.estimated_candidate_len(query)),
-            Query::StringMatch { .. } | Query::IsNull { .. } => self.all_docs.len(),
+            Query::StringMatch {
+                field,
+                value,
+                method,
+                ..
+            } => match self.trigram_indexes.get(field) {
+                Some(index) => index.estimated_candidate_len(value, *method),
+                None => self.all_docs.len(),
+            },
+            Query::IsNull { .. } => self.all_docs.len(),
         }
     }
 
@@ -1076,25 +3387,61 @@ impl NativeQueryIndex {
         if let Some(value) = value.as_i64() {
             return estimate_i64_range_len(self.range_i64.get(field), compare, value);
         }
+        let big_uint_len = |index: Option<&OrdMap<BigUint, Arc<RoaringBitmap>>>| {
+            index.map_or(0, |index| index.values().map(|docs| docs.len()).sum())
+        };
         if let Some(value) = value.as_u64() {
-            return estimate_u64_range_len(self.range_u64.get(field), compare, value);
+            let mut len = estimate_u64_range_len(self.range_u64.get(field), compare, value);
+            if matches!(
+                compare,
+                Compare::Greater | Compare::GreaterOrEqual | Compare::NotEqual
+            ) {
+                len = len.saturating_add(big_uint_len(self.range_big_uint.get(field)));
+            }
+            return len;
+        }
+        if let Some(value) = value.as_big_uint() {
+            let mut len = estimate_interpolated_range_len(
+                self.range_big_uint.get(field),
+                compare,
+                value,
+                BigUint::to_f64,
+            );
+            if matches!(
+                compare,
+                Compare::Less | Compare::LessOrEqual | Compare::NotEqual
+            ) {
+                len = len.saturating_add(
+                    self.range_u64
+                        .get(field)
+                        .map_or(0, |index| index.values().map(|docs| docs.len()).sum()),
+                );
+            }
+            return len;
+        }
+        if let Some(value) = value.as_ordered_f64() {
+            return estimate_interpolated_range_len(
+                self.range_f64.get(field),
+                compare,
+                &value,
+                |value| value.get(),
+            );
         }
         0
     }
 
-    fn exact_candidates(&self, field: &FieldPath, value: &FieldValue) -> RoaringBitmap {
-        let mut matches = self
+    fn exact_candidates(&self, field: &FieldPath, value: &FieldValue) -> Cow<'_, RoaringBitmap> {
+        let matches = self
             .exact
             .get(field)
             .and_then(|values| values.get(value))
-            .cloned()
-            .unwrap_or_default();
+            .map(|docs| &**docs);
         if is_large_byte_value(value) {
             if let Some(large_byte_docs) = self.large_exact_bytes.get(field) {
-                matches |= large_byte_docs;
+                return Cow::Owned(matches.cloned().unwrap_or_default() | &**large_byte_docs);
             }
         }
-        matches
+        matches.map_or_else(|| Cow::Owned(RoaringBitmap::new()), Cow::Borrowed)
     }
 
     fn estimated_exact_candidate_len(&self, field: &FieldPath, value: &FieldValue) -> u64 {
@@ -1102,13 +3449,13 @@ impl NativeQueryIndex {
             .exact
             .get(field)
             .and_then(|values| values.get(value))
-            .map(RoaringBitmap::len)
+            .map(|docs| docs.len())
             .unwrap_or(0);
         if is_large_byte_value(value) {
             len = len.saturating_add(
                 self.large_exact_bytes
                     .get(field)
-                    .map(RoaringBitmap::len)
+                    .map(|docs| docs.len())
                     .unwrap_or(0),
             );
         }
@@ -1137,6 +3484,35 @@ impl NativeQueryIndex {
         self.compare_external_ids(left, right, tie_break_direction(sort))
     }
 
+    /// `compare_docs` against a cursor's sort key instead of a second document.
+    fn compare_doc_to_key(
+        &self,
+        doc_id: DocId,
+        sort: &[SortField],
+        values: &[Option<FieldValue>],
+        id: &IdSortKey,
+    ) -> Ordering {
+        for (field, value) in sort.iter().zip(values) {
+            let ordering =
+                compare_optional_values(self.first_scalar(doc_id, &field.field), value.as_ref());
+            let ordering = match field.direction {
+                SortDirection::Asc => ordering,
+                SortDirection::Desc => ordering.reverse(),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        let ordering = match self.internal_to_sort_key.get(&doc_id) {
+            Some(key) => key.cmp(id),
+            None => Ordering::Less,
+        };
+        match tie_break_direction(sort) {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        }
+    }
+
     /// Compare two documents by their primary-key id, honoring the tie-break
     /// `direction` (ascending id for `Asc`, descending id for `Desc`). Ids are
     /// compared in the id's natural typed order so ties match the default sqlite
@@ -1166,6 +3542,112 @@ impl NativeQueryIndex {
         }
     }
 
+    fn extreme_value(&self, query: &Query, field: &FieldPath, max: bool) -> Option<FieldValue> {
+        let matches = self.matching_doc_ids(query);
+        fn pick<'a>(
+            left: Option<&'a FieldValue>,
+            right: &'a FieldValue,
+            max: bool,
+        ) -> Option<&'a FieldValue> {
+            match left {
+                Some(left) if (compare_field_values(left, right) == Ordering::Greater) == max => {
+                    Some(left)
+                }
+                _ => Some(right),
+            }
+        }
+        let mut result = None;
+        let exact = self.exact.get(field);
+        if exact.map_or(0, im::HashMap::len) as u64 >= matches.len() {
+            for doc_id in matches.iter() {
+                result = self
+                    .doc_scalars(doc_id, field)
+                    .iter()
+                    .fold(result, |result, value| pick(result, value, max));
+            }
+            return result.cloned();
+        }
+
+        // Fewer distinct values than matches: walk the exact index. Values of
+        // different kinds order by `compare_field_values` like sorted search.
+        for (value, docs) in exact.into_iter().flatten() {
+            if !docs.is_disjoint(&matches) {
+                result = pick(result, value, max);
+            }
+        }
+        // Oversized byte values are not in the exact index.
+        if let Some(large) = self.large_exact_bytes.get(field) {
+            for doc_id in (&**large & &matches).iter() {
+                result = self
+                    .doc_scalars(doc_id, field)
+                    .iter()
+                    .filter(|value| is_large_byte_value(value))
+                    .fold(result, |result, value| pick(result, value, max));
+            }
+        }
+        result.cloned()
+    }
+
+    /// Per distinct value of `field`, the number of `matches` holding it.
+    fn value_buckets(
+        &self,
+        matches: &RoaringBitmap,
+        field: &FieldPath,
+    ) -> HashMap<FieldValue, u64> {
+        let mut buckets = HashMap::new();
+        let exact = self.exact.get(field);
+        if exact.map_or(0, im::HashMap::len) as u64 >= matches.len() {
+            for doc_id in matches.iter() {
+                let Some(values) = self
+                    .documents
+                    .get(&doc_id)
+                    .and_then(|document| document.scalar_values(field))
+                else {
+                    continue;
+                };
+                let distinct: HashSet<_> = values.iter().collect();
+                for value in distinct {
+                    *buckets.entry(value.clone()).or_default() += 1;
+                }
+            }
+            return buckets;
+        }
+
+        for (value, docs) in exact.into_iter().flatten() {
+            let count = docs.intersection_len(matches);
+            if count > 0 {
+                buckets.insert(value.clone(), count);
+            }
+        }
+        // Oversized byte values are not in the exact index.
+        if let Some(large) = self.large_exact_bytes.get(field) {
+            for doc_id in (&**large & matches).iter() {
+                let Some(values) = self
+                    .documents
+                    .get(&doc_id)
+                    .and_then(|document| document.scalar_values(field))
+                else {
+                    continue;
+                };
+                let distinct: HashSet<_> = values
+                    .iter()
+                    .filter(|value| is_large_byte_value(value))
+                    .collect();
+                for value in distinct {
+                    *buckets.entry(value.clone()).or_default() += 1;
+                }
+            }
+        }
+        buckets
+    }
+
+    fn doc_scalars(&self, doc_id: DocId, path: &FieldPath) -> &[FieldValue] {
+        self.documents
+            .get(&doc_id)
+            .and_then(|document| document.scalar_values(path))
+            .unwrap_or_default()
+    }
+
     fn first_scalar(&self, doc_id: DocId, path: &FieldPath) -> Option<&FieldValue> {
         self.documents
             .get(&doc_id)
@@ -1179,6 +3661,7 @@ impl NativeQueryIndex {
         sort: &[SortField],
         offset: usize,
         limit: Option<usize>,
+        meter: &BudgetMeter,
     ) -> Option<Vec<String>> {
         if sort.len() != 1 {
             return None;
@@ -1189,214 +3672,328 @@ impl NativeQueryIndex {
         if limit == 0 {
             return Some(Vec::new());
         }
-        if self
-            .large_exact_bytes
-            .get(&sort.field)
-            .is_some_and(|docs| !docs.is_empty())
-        {
-            return None;
-        }
-
-        let mut result = Vec::new();
-        let mut skipped = 0;
-        let mut seen = RoaringBitmap::new();
-        let mut has_ordered_index = false;
+        let source = self.sort_source(query, &sort.field)?;
+
+        let mut page = SortedPage {
+            query,
+            offset,
+            limit,
+            meter,
+            skipped: 0,
+            seen: RoaringBitmap::new(),
+            result: Vec::new(),
+        };
 
         if sort.direction == SortDirection::Desc
             && !self.collect_missing_sorted_docs(
                 &sort.field,
+                source.missing.as_deref(),
                 true,
-                query,
-                offset,
-                limit,
-                &mut skipped,
-                &mut seen,
-                &mut result,
+                &mut page,
             )
         {
-            return Some(result);
+            return Some(page.result);
         }
 
         match sort.direction {
             SortDirection::Asc => {
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_bool.get(&sort.field),
-                    false,
-                    query,
-                    offset,
-                    limit,
-                    &mut skipped,
-                    &mut seen,
-                    &mut result,
-                );
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_i64.get(&sort.field),
-                    false,
-                    query,
-                    offset,
-                    limit,
-                    &mut skipped,
-                    &mut seen,
-                    &mut result,
-                );
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_u64.get(&sort.field),
-                    false,
-                    query,
-                    offset,
-                    limit,
-                    &mut skipped,
-                    &mut seen,
-                    &mut result,
-                );
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_string.get(&sort.field),
-                    false,
-                    query,
-                    offset,
-                    limit,
-                    &mut skipped,
-                    &mut seen,
-                    &mut result,
-                );
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_bytes.get(&sort.field),
-                    false,
-                    query,
-                    offset,
-                    limit,
-                    &mut skipped,
-                    &mut seen,
-                    &mut result,
-                );
+                self.collect_sort_index_docs(source.bool, false, &mut page);
+                self.collect_sort_index_docs(source.i64, false, &mut page);
+                self.collect_sort_index_docs(source.u64, false, &mut page);
+                self.collect_sort_index_docs(source.big_uint, false, &mut page);
+                self.collect_sort_index_docs(source.f64, false, &mut page);
+                self.collect_sort_index_docs(source.string, false, &mut page);
+                self.collect_sort_index_docs(source.bytes, false, &mut page);
             }
             SortDirection::Desc => {
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_bytes.get(&sort.field),
-                    true,
-                    query,
-                    offset,
-                    limit,
-                    &mut skipped,
-                    &mut seen,
-                    &mut result,
-                );
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_string.get(&sort.field),
-                    true,
-                    query,
-                    offset,
-                    limit,
-                    &mut skipped,
-                    &mut seen,
-                    &mut result,
-                );
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_u64.get(&sort.field),
-                    true,
+                self.collect_sort_index_docs(source.bytes, true, &mut page);
+                self.collect_sort_index_docs(source.string, true, &mut page);
+                self.collect_sort_index_docs(source.f64, true, &mut page);
+                self.collect_sort_index_docs(source.big_uint, true, &mut page);
+                self.collect_sort_index_docs(source.u64, true, &mut page);
+                self.collect_sort_index_docs(source.i64, true, &mut page);
+                self.collect_sort_index_docs(source.bool, true, &mut page);
+            }
+        }
+
+        if page.result.len() >= limit {
+            return Some(page.result);
+        }
+
+        if sort.direction == SortDirection::Asc {
+            self.collect_missing_sorted_docs(
+                &sort.field,
+                source.missing.as_deref(),
+                false,
+                &mut page,
+            );
+        }
+
+        Some(page.result)
+    }
+
+    /// Seek variant of `search_index_sorted_page` for keyset paging: each typed
+    /// sort index is entered at the cursor's value instead of being skipped
+    /// through from the start, so a page costs O(limit + non-matching docs
+    /// passed over) rather than O(offset).
+    fn search_index_sorted_after(
+        &self,
+        query: &Query,
+        sort: &[SortField],
+        after: Option<&(&[Option<FieldValue>], IdSortKey)>,
+        limit: usize,
+    ) -> Option<Vec<DocId>> {
+        if sort.len() != 1 {
+            return None;
+        }
+        let field = &sort[0].field;
+        let reverse = sort[0].direction == SortDirection::Desc;
+        let source = self.sort_source(query, field)?;
+
+        // Value kinds in `field_value_rank` order, then the missing-value group;
+        // a descending scan visits them back to front.
+        const MISSING_RANK: u8 = 7;
+        let mut ranks: Vec<u8> = (0..=MISSING_RANK).collect();
+        if reverse {
+            ranks.reverse();
+        }
+        let after = after.map(|(values, id)| (values[0].as_ref(), id));
+        let after_position = after.map(|(value, _)| {
+            let rank = value.map_or(MISSING_RANK, field_value_rank);
+            ranks.iter().position(|candidate| *candidate == rank)
+        });
+
+        let mut result = Vec::new();
+        for (position, rank) in ranks.iter().enumerate() {
+            if result.len() >= limit {
+                break;
+            }
+            let start = match (after, after_position.flatten()) {
+                (Some(_), Some(after_position)) if position < after_position => continue,
+                (Some((value, id)), Some(after_position)) if position == after_position => {
+                    Some((value, id))
+                }
+                _ => None,
+            };
+            match rank {
+                0 => {
+                    self.collect_keyset_docs(source.bool, start, reverse, query, limit, &mut result)
+                }
+                1 => {
+                    self.collect_keyset_docs(source.i64, start, reverse, query, limit, &mut result)
+                }
+                2 => {
+                    self.collect_keyset_docs(source.u64, start, reverse, query, limit, &mut result)
+                }
+                3 => self.collect_keyset_docs(
+                    source.big_uint,
+                    start,
+                    reverse,
                     query,
-                    offset,
                     limit,
-                    &mut skipped,
-                    &mut seen,
                     &mut result,
-                );
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_i64.get(&sort.field),
-                    true,
+                ),
+                4 => {
+                    self.collect_keyset_docs(source.f64, start, reverse, query, limit, &mut result)
+                }
+                5 => self.collect_keyset_docs(
+                    source.string,
+                    start,
+                    reverse,
                     query,
-                    offset,
                     limit,
-                    &mut skipped,
-                    &mut seen,
                     &mut result,
-                );
-                has_ordered_index |= self.collect_sort_index_docs(
-                    self.sort_bool.get(&sort.field),
-                    true,
+                ),
+                6 => self.collect_keyset_docs(
+                    source.bytes,
+                    start,
+                    reverse,
                     query,
-                    offset,
                     limit,
-                    &mut skipped,
-                    &mut seen,
                     &mut result,
-                );
+                ),
+                _ => {
+                    let mut missing: Vec<DocId> = match source.missing.as_deref() {
+                        Some(missing) => missing.iter().collect(),
+                        None => self
+                            .all_docs
+                            .iter()
+                            .filter(|doc_id| self.first_scalar(*doc_id, field).is_none())
+                            .collect(),
+                    };
+                    missing.sort_by(|left, right| {
+                        self.compare_external_ids(*left, *right, sort[0].direction)
+                    });
+                    self.collect_keyset_tie_group(
+                        missing,
+                        start.map(|(_, id)| id),
+                        reverse,
+                        query,
+                        limit,
+                        &mut result,
+                    );
+                }
             }
         }
+        Some(result)
+    }
 
-        if !has_ordered_index {
-            return None;
-        }
-        if result.len() >= limit {
-            return Some(result);
+    /// Where a single-field sort on `field` reads ordered docs from: the
+    /// composite group `query` pins, else the `sort_*` indexes when usable.
+    fn sort_source(&self, query: &Query, field: &FieldPath) -> Option<SortSource<'_>> {
+        let conjuncts = match query {
+            Query::And(queries) => queries.as_slice(),
+            query => std::slice::from_ref(query),
+        };
+        if let Some((index, group)) = self.pinned_composite_group(conjuncts, Some(field)) {
+            let Some(group) = group else {
+                return Some(SortSource {
+                    composite: Some(index),
+                    bool: None,
+                    i64: None,
+                    u64: None,
+                    big_uint: None,
+                    f64: None,
+                    string: None,
+                    bytes: None,
+                    missing: Some(Cow::Owned(RoaringBitmap::new())),
+                });
+            };
+            return Some(SortSource {
+                composite: Some(index),
+                bool: Some(&group.sort_bool),
+                i64: Some(&group.sort_i64),
+                u64: Some(&group.sort_u64),
+                big_uint: Some(&group.sort_big_uint),
+                f64: Some(&group.sort_f64),
+                string: Some(&group.sort_string),
+                bytes: Some(&group.sort_bytes),
+                missing: Some(Cow::Borrowed(&group.missing)),
+            });
         }
-
-        if sort.direction == SortDirection::Asc {
-            self.collect_missing_sorted_docs(
-                &sort.field,
-                false,
-                query,
-                offset,
-                limit,
-                &mut skipped,
-                &mut seen,
-                &mut result,
-            );
+        if !self.sort_index_usable(field) {
+            return None;
         }
-
-        Some(result)
+        Some(SortSource {
+            composite: None,
+            bool: self.sort_bool.get(field),
+            i64: self.sort_i64.get(field),
+            u64: self.sort_u64.get(field),
+            big_uint: self.sort_big_uint.get(field),
+            f64: self.sort_f64.get(field),
+            string: self.sort_string.get(field),
+            bytes: self.sort_bytes.get(field),
+            missing: None,
+        })
+    }
+
+    /// Whether single-field sorts on `field` can be served from the `sort_*`
+    /// indexes (oversized byte values are not in them).
+    fn sort_index_usable(&self, field: &FieldPath) -> bool {
+        self.large_exact_bytes
+            .get(field)
+            .is_none_or(|docs| docs.is_empty())
+            && (self.sort_bool.contains_key(field)
+                || self.sort_i64.contains_key(field)
+                || self.sort_u64.contains_key(field)
+                || self.sort_big_uint.contains_key(field)
+                || self.sort_f64.contains_key(field)
+                || self.sort_string.contains_key(field)
+                || self.sort_bytes.contains_key(field))
     }
 
-    fn collect_sort_index_docs<T: Ord>(
+    fn collect_keyset_docs<T: SortIndexKey>(
         &self,
-        index: Option<&BTreeMap<T, RoaringBitmap>>,
+        index: Option<&OrdMap<T, Arc<RoaringBitmap>>>,
+        after: Option<(Option<&FieldValue>, &IdSortKey)>,
         reverse: bool,
         query: &Query,
-        offset: usize,
         limit: usize,
-        skipped: &mut usize,
-        seen: &mut RoaringBitmap,
-        result: &mut Vec<String>,
-    ) -> bool {
+        result: &mut Vec<DocId>,
+    ) {
         let Some(index) = index else {
-            return false;
+            return;
         };
-        if reverse {
-            self.collect_index_sorted_docs(
-                index.values().rev(),
+        let after = after.and_then(|(value, id)| Some((T::from_field_value(value?)?, id)));
+        let groups: Box<dyn Iterator<Item = (&T, &Arc<RoaringBitmap>)>> = match (after, reverse) {
+            (None, false) => Box::new(index.iter()),
+            (None, true) => Box::new(index.iter().rev()),
+            (Some((value, _)), false) => Box::new(index.range((Included(value), Unbounded))),
+            (Some((value, _)), true) => Box::new(index.range((Unbounded, Included(value))).rev()),
+        };
+        for (value, bitmap) in groups {
+            if result.len() >= limit {
+                return;
+            }
+            let after_id = after
+                .filter(|(after_value, _)| *after_value == value)
+                .map(|(_, id)| id);
+            self.collect_keyset_tie_group(
+                self.tie_ordered_doc_ids(bitmap, reverse),
+                after_id,
                 reverse,
                 query,
-                offset,
                 limit,
-                skipped,
-                seen,
                 result,
             );
+        }
+    }
+
+    /// Append matching docs of an id-ordered tie group, skipping those at or
+    /// before `after_id` in scan order.
+    fn collect_keyset_tie_group(
+        &self,
+        doc_ids: Vec<DocId>,
+        after_id: Option<&IdSortKey>,
+        reverse: bool,
+        query: &Query,
+        limit: usize,
+        result: &mut Vec<DocId>,
+    ) {
+        let past = if reverse {
+            Ordering::Less
         } else {
-            self.collect_index_sorted_docs(
-                index.values(),
-                reverse,
-                query,
-                offset,
-                limit,
-                skipped,
-                seen,
-                result,
-            );
+            Ordering::Greater
+        };
+        for doc_id in doc_ids {
+            if result.len() >= limit {
+                return;
+            }
+            if let Some(after_id) = after_id {
+                let Some(key) = self.internal_to_sort_key.get(&doc_id) else {
+                    continue;
+                };
+                if key.cmp(after_id) != past {
+                    continue;
+                }
+            }
+            if self.matches_doc(doc_id, query) {
+                result.push(doc_id);
+            }
+        }
+    }
+
+    fn collect_sort_index_docs<T: Ord>(
+        &self,
+        index: Option<&OrdMap<T, Arc<RoaringBitmap>>>,
+        reverse: bool,
+        page: &mut SortedPage<'_>,
+    ) {
+        let Some(index) = index else {
+            return;
+        };
+        if reverse {
+            self.collect_index_sorted_docs(index.values().rev(), reverse, page);
+        } else {
+            self.collect_index_sorted_docs(index.values(), reverse, page);
         }
-        true
     }
 
     fn collect_index_sorted_docs<'a>(
         &self,
-        bitmaps: impl Iterator<Item = &'a RoaringBitmap>,
+        bitmaps: impl Iterator<Item = &'a Arc<RoaringBitmap>>,
         reverse: bool,
-        query: &Query,
-        offset: usize,
-        limit: usize,
-        skipped: &mut usize,
-        seen: &mut RoaringBitmap,
-        result: &mut Vec<String>,
+        page: &mut SortedPage<'_>,
     ) -> bool {
         for bitmap in bitmaps {
             // Docs sharing the same sort value are a tie group. `bitmap.iter()`
@@ -1405,7 +4002,7 @@ impl NativeQueryIndex {
             // primary-key id (in its natural typed order) in the scan direction to
             // match the default backend.
             for doc_id in self.tie_ordered_doc_ids(bitmap, reverse) {
-                if !self.collect_sorted_doc(doc_id, query, offset, limit, skipped, seen, result) {
+                if !self.collect_sorted_doc(doc_id, page) {
                     return false;
                 }
             }
@@ -1433,23 +4030,22 @@ impl NativeQueryIndex {
     fn collect_missing_sorted_docs(
         &self,
         field: &FieldPath,
+        missing: Option<&RoaringBitmap>,
         reverse: bool,
-        query: &Query,
-        offset: usize,
-        limit: usize,
-        skipped: &mut usize,
-        seen: &mut RoaringBitmap,
-        result: &mut Vec<String>,
+        page: &mut SortedPage<'_>,
     ) -> bool {
         // Docs without a value for the sort field form one tie group (all "null").
         // Emit them ordered by primary-key id (in its natural typed order) in the
         // scan direction, matching the default backend, instead of raw DocId (local
         // insertion) order.
-        let mut missing: Vec<DocId> = self
-            .all_docs
-            .iter()
-            .filter(|doc_id| self.first_scalar(*doc_id, field).is_none())
-            .collect();
+        let mut missing: Vec<DocId> = match missing {
+            Some(missing) => missing.iter().collect(),
+            None => self
+                .all_docs
+                .iter()
+                .filter(|doc_id| self.first_scalar(*doc_id, field).is_none())
+                .collect(),
+        };
         if missing.len() > 1 {
             let direction = if reverse {
                 SortDirection::Desc
@@ -1459,37 +4055,37 @@ impl NativeQueryIndex {
             missing.sort_by(|left, right| self.compare_external_ids(*left, *right, direction));
         }
         for doc_id in missing {
-            if !self.collect_sorted_doc(doc_id, query, offset, limit, skipped, seen, result) {
+            if !self.collect_sorted_doc(doc_id, page) {
                 return false;
             }
         }
         true
     }
 
-    fn collect_sorted_doc(
-        &self,
-        doc_id: DocId,
-        query: &Query,
-        offset: usize,
-        limit: usize,
-        skipped: &mut usize,
-        seen: &mut RoaringBitmap,
-        result: &mut Vec<String>,
-    ) -> bool {
-        if result.len() >= limit {
+    fn collect_sorted_doc(&self, doc_id: DocId, page: &mut SortedPage<'_>) -> bool {
+        if page.result.len() >= page.limit {
+            return false;
+        }
+        if !page.seen.insert(doc_id) {
+            return true;
+        }
+        if !page.meter.examine() {
             return false;
         }
-        if !seen.insert(doc_id) || !self.matches_doc(doc_id, query) {
+        if !self.matches_doc(doc_id, page.query) {
             return true;
         }
-        if *skipped < offset {
-            *skipped += 1;
+        if page.skipped < page.offset {
+            page.skipped += 1;
             return true;
         }
         if let Some(id) = self.internal_to_external.get(&doc_id) {
-            result.push(id.clone());
+            if !page.meter.materialize(1) {
+                return false;
+            }
+            page.result.push(id.clone());
         }
-        result.len() < limit
+        page.result.len() < page.limit
     }
 
     fn insert_sort_value(&mut self, path: &FieldPath, value: &FieldValue, doc_id: DocId) {
@@ -1503,6 +4099,12 @@ impl NativeQueryIndex {
             FieldValue::U64(value) => {
                 insert_into_ordered_index(&mut self.sort_u64, path, *value, doc_id)
             }
+            FieldValue::BigUint(value) => {
+                insert_into_ordered_index(&mut self.sort_big_uint, path, value.clone(), doc_id)
+            }
+            FieldValue::F64(value) => {
+                insert_into_ordered_index(&mut self.sort_f64, path, *value, doc_id)
+            }
             FieldValue::String(value) => {
                 insert_into_ordered_index(&mut self.sort_string, path, Arc::clone(value), doc_id)
             }
@@ -1524,6 +4126,12 @@ impl NativeQueryIndex {
             FieldValue::U64(value) => {
                 remove_from_ordered_index(&mut self.sort_u64, path, value, doc_id)
             }
+            FieldValue::BigUint(value) => {
+                remove_from_ordered_index(&mut self.sort_big_uint, path, value, doc_id)
+            }
+            FieldValue::F64(value) => {
+                remove_from_ordered_index(&mut self.sort_f64, path, value, doc_id)
+            }
             FieldValue::String(value) => {
                 remove_from_ordered_index(&mut self.sort_string, path, value, doc_id)
             }
@@ -1540,10 +4148,41 @@ impl SumResult {
         match value {
             FieldValue::I64(value) => self.add_i64(*value as i128),
             FieldValue::U64(value) => self.add_u64(*value as u128),
+            FieldValue::BigUint(value) => self.add_big_uint(value),
+            FieldValue::F64(value) => {
+                self.add_f64(value.get());
+                Ok(())
+            }
             FieldValue::Bool(_) | FieldValue::String(_) | FieldValue::Bytes(_) => Ok(()),
         }
     }
 
+    fn add_f64(&mut self, value: f64) {
+        *self = Self::F64(match *self {
+            Self::None => value,
+            Self::I64(sum) => sum as f64 + value,
+            Self::U64(sum) => sum as f64 + value,
+            Self::BigUint(ref sum) => sum.to_f64() + value,
+            Self::F64(sum) => sum + value,
+        });
+    }
+
+    fn add_big_uint(&mut self, value: &BigUint) -> Result<(), String> {
+        match self {
+            Self::None => *self = Self::BigUint(value.clone()),
+            Self::U64(sum) => *self = Self::BigUint(value.add_be_bytes(&sum.to_be_bytes())),
+            Self::BigUint(sum) => *sum = sum.add_be_bytes(value.to_be_bytes()),
+            Self::I64(_) => {
+                let value = value.to_u128().ok_or_else(|| {
+                    "native sum cannot mix wide unsigned values with i64".to_string()
+                })?;
+                return self.add_u64(value);
+            }
+            Self::F64(sum) => *sum += value.to_f64(),
+        }
+        Ok(())
+    }
+
     fn add_i64(&mut self, value: i128) -> Result<(), String> {
         match self {
             Self::None => {
@@ -1556,6 +4195,24 @@ impl SumResult {
                     .ok_or_else(|| "native i64 sum overflow".to_string())?;
                 Ok(())
             }
+            Self::BigUint(sum) => {
+                let signed_sum = sum
+                    .to_u128()
+                    .and_then(|sum| i128::try_from(sum).ok())
+                    .ok_or_else(|| {
+                        "native sum cannot mix wide unsigned values with i64".to_string()
+                    })?;
+                *self = Self::I64(
+                    signed_sum
+                        .checked_add(value)
+                        .ok_or_else(|| "native mixed sum overflow".to_string())?,
+                );
+                Ok(())
+            }
+            Self::F64(sum) => {
+                *sum += value as f64;
+                Ok(())
+            }
             Self::U64(sum) => {
                 let signed_sum = i128::try_from(*sum)
                     .map_err(|_| "native sum cannot mix large u64 values with i64".to_string())?;
@@ -1576,9 +4233,22 @@ impl SumResult {
                 Ok(())
             }
             Self::U64(sum) => {
-                *sum = sum
-                    .checked_add(value)
-                    .ok_or_else(|| "native u64 sum overflow".to_string())?;
+                match sum.checked_add(value) {
+                    Some(next) => *sum = next,
+                    None => {
+                        *self = Self::BigUint(
+                            BigUint(sum.to_be_bytes().into()).add_be_bytes(&value.to_be_bytes()),
+                        )
+                    }
+                }
+                Ok(())
+            }
+            Self::BigUint(sum) => {
+                *sum = sum.add_be_bytes(&value.to_be_bytes());
+                Ok(())
+            }
+            Self::F64(sum) => {
+                *sum += value as f64;
                 Ok(())
             }
             Self::I64(sum) => {
@@ -1593,6 +4263,84 @@ impl SumResult {
     }
 }
 
+fn touched_field_ids<'a>(
+    fields: impl Iterator<Item = &'a FieldPath>,
+    diff: &SchemaDiff,
+) -> impl Iterator<Item = u32> {
+    let mut ids: Vec<u32> = fields
+        .filter_map(|field| match field {
+            FieldPath::Id(id) if diff.touches(field) => Some(*id),
+            _ => None,
+        })
+        .collect();
+    ids.sort_unstable();
+    ids.into_iter()
+}
+
+/// `moved` holds the retained ids that cannot stay in place: starting from the
+/// ids not rewritten (whose relative order a write never changes), a rewritten
+/// id stays when its nearest staying predecessor is the same in both views.
+fn diff_subscription_views(
+    subscription: SubscriptionId,
+    generation: u64,
+    old: &[String],
+    new: &[String],
+    touched: Option<&HashSet<String>>,
+) -> SubscriptionDiff {
+    let old_positions: HashMap<&str, usize> = old
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (id.as_str(), index))
+        .collect();
+    let new_positions: HashMap<&str, usize> = new
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (id.as_str(), index))
+        .collect();
+    let is_touched = |id: &str| touched.is_none_or(|touched| touched.contains(id));
+    let mut staying: HashSet<&str> = new
+        .iter()
+        .map(String::as_str)
+        .filter(|id| old_positions.contains_key(id) && !is_touched(id))
+        .collect();
+    let staying_predecessor = |view: &[String], index: usize, staying: &HashSet<&str>| {
+        view[..index]
+            .iter()
+            .rev()
+            .find(|id| staying.contains(id.as_str()))
+            .cloned()
+    };
+
+    let mut diff = SubscriptionDiff {
+        subscription,
+        generation,
+        added: Vec::new(),
+        removed: old
+            .iter()
+            .filter(|id| !new_positions.contains_key(id.as_str()))
+            .cloned()
+            .collect(),
+        moved: Vec::new(),
+    };
+    for (index, id) in new.iter().enumerate() {
+        let Some(&old_index) = old_positions.get(id.as_str()) else {
+            diff.added.push((id.clone(), index));
+            continue;
+        };
+        if staying.contains(id.as_str()) {
+            continue;
+        }
+        if staying_predecessor(old, old_index, &staying)
+            == staying_predecessor(new, index, &staying)
+        {
+            staying.insert(id);
+        } else {
+            diff.moved.push((id.clone(), index));
+        }
+    }
+    diff
+}
+
 fn root_scope() -> RoaringBitmap {
     let mut scopes = RoaringBitmap::new();
     scopes.insert(0);
@@ -1623,6 +4371,16 @@ fn compare_range_values(left: &FieldValue, compare: Compare, right: &FieldValue)
         (FieldValue::U64(left), FieldValue::U64(right)) => {
             compare_ordering(left.cmp(right), compare)
         }
+        (FieldValue::BigUint(left), FieldValue::BigUint(right)) => {
+            compare_ordering(left.cmp(right), compare)
+        }
+        (FieldValue::U64(_), FieldValue::BigUint(_)) => compare_ordering(Ordering::Less, compare),
+        (FieldValue::BigUint(_), FieldValue::U64(_)) => {
+            compare_ordering(Ordering::Greater, compare)
+        }
+        (FieldValue::F64(left), FieldValue::F64(right)) if !left.is_nan() && !right.is_nan() => {
+            compare_ordering(left.cmp(right), compare)
+        }
         _ => false,
     }
 }
@@ -1634,6 +4392,7 @@ fn compare_ordering(ordering: Ordering, compare: Compare) -> bool {
         Compare::LessOrEqual => ordering != Ordering::Greater,
         Compare::Greater => ordering == Ordering::Greater,
         Compare::GreaterOrEqual => ordering != Ordering::Less,
+        Compare::NotEqual => ordering != Ordering::Equal,
     }
 }
 
@@ -1647,9 +4406,7 @@ fn matches_string_value(
         return false;
     };
     if case_insensitive {
-        let value = value.to_lowercase();
-        let query = query.to_lowercase();
-        return matches_string(&value, &query, method);
+        return matches_string(&fold_case(value), &fold_case(query), method);
     }
     matches_string(value, query, method)
 }
@@ -1659,11 +4416,45 @@ fn matches_string(value: &str, query: &str, method: StringMatchMethod) -> bool {
         StringMatchMethod::Exact => value == query,
         StringMatchMethod::Prefix => value.starts_with(query),
         StringMatchMethod::Contains => value.contains(query),
+        StringMatchMethod::Suffix => value.ends_with(query),
+        StringMatchMethod::Glob => matches_glob(value, query),
+    }
+}
+
+fn matches_glob(value: &str, pattern: &str) -> bool {
+    let value: Vec<char> = value.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut value_at, mut pattern_at) = (0, 0);
+    // Last `*` seen and the value position it currently stops at; on a
+    // mismatch it swallows one more character.
+    let mut star: Option<(usize, usize)> = None;
+    while value_at < value.len() {
+        match pattern.get(pattern_at) {
+            Some('*') => {
+                star = Some((pattern_at, value_at));
+                pattern_at += 1;
+            }
+            Some(&character) if character == '?' || character == value[value_at] => {
+                value_at += 1;
+                pattern_at += 1;
+            }
+            _ => {
+                let Some((star_at, stopped_at)) = star else {
+                    return false;
+                };
+                star = Some((star_at, stopped_at + 1));
+                pattern_at = star_at + 1;
+                value_at = stopped_at + 1;
+            }
+        }
     }
+    pattern[pattern_at..]
+        .iter()
+        .all(|character| *character == '*')
 }
 
 fn range_i64_candidates(
-    index: Option<&BTreeMap<i64, RoaringBitmap>>,
+    index: Option<&OrdMap<i64, Arc<RoaringBitmap>>>,
     compare: Compare,
     value: i64,
 ) -> RoaringBitmap {
@@ -1671,7 +4462,9 @@ fn range_i64_candidates(
         return RoaringBitmap::new();
     };
     match compare {
-        Compare::Equal => index.get(&value).cloned().unwrap_or_default(),
+        Compare::Equal => index
+            .get(&value)
+            .map_or_else(RoaringBitmap::new, |docs| (**docs).clone()),
         Compare::Less => union_bitmaps(index.range(..value).map(|(_, bitmap)| bitmap)),
         Compare::LessOrEqual => union_bitmaps(index.range(..=value).map(|(_, bitmap)| bitmap)),
         Compare::Greater => union_bitmaps(
@@ -1680,11 +4473,17 @@ fn range_i64_candidates(
                 .map(|(_, bitmap)| bitmap),
         ),
         Compare::GreaterOrEqual => union_bitmaps(index.range(value..).map(|(_, bitmap)| bitmap)),
+        Compare::NotEqual => union_bitmaps(
+            index
+                .iter()
+                .filter(|(key, _)| **key != value)
+                .map(|(_, bitmap)| bitmap),
+        ),
     }
 }
 
 fn estimate_i64_range_len(
-    index: Option<&BTreeMap<i64, RoaringBitmap>>,
+    index: Option<&OrdMap<i64, Arc<RoaringBitmap>>>,
     compare: Compare,
     value: i64,
 ) -> u64 {
@@ -1692,7 +4491,10 @@ fn estimate_i64_range_len(
         return 0;
     };
     if compare == Compare::Equal {
-        return index.get(&value).map(RoaringBitmap::len).unwrap_or(0);
+        return index.get(&value).map(|docs| docs.len()).unwrap_or(0);
+    }
+    if compare == Compare::NotEqual {
+        return index.len() as u64;
     }
     let Some((&min, _)) = index.iter().next() else {
         return 0;
@@ -1710,7 +4512,7 @@ fn estimate_i64_range_len(
 }
 
 fn range_u64_candidates(
-    index: Option<&BTreeMap<u64, RoaringBitmap>>,
+    index: Option<&OrdMap<u64, Arc<RoaringBitmap>>>,
     compare: Compare,
     value: u64,
 ) -> RoaringBitmap {
@@ -1718,7 +4520,9 @@ fn range_u64_candidates(
         return RoaringBitmap::new();
     };
     match compare {
-        Compare::Equal => index.get(&value).cloned().unwrap_or_default(),
+        Compare::Equal => index
+            .get(&value)
+            .map_or_else(RoaringBitmap::new, |docs| (**docs).clone()),
         Compare::Less => union_bitmaps(index.range(..value).map(|(_, bitmap)| bitmap)),
         Compare::LessOrEqual => union_bitmaps(index.range(..=value).map(|(_, bitmap)| bitmap)),
         Compare::Greater => union_bitmaps(
@@ -1727,11 +4531,17 @@ fn range_u64_candidates(
                 .map(|(_, bitmap)| bitmap),
         ),
         Compare::GreaterOrEqual => union_bitmaps(index.range(value..).map(|(_, bitmap)| bitmap)),
+        Compare::NotEqual => union_bitmaps(
+            index
+                .iter()
+                .filter(|(key, _)| **key != value)
+                .map(|(_, bitmap)| bitmap),
+        ),
     }
 }
 
 fn estimate_u64_range_len(
-    index: Option<&BTreeMap<u64, RoaringBitmap>>,
+    index: Option<&OrdMap<u64, Arc<RoaringBitmap>>>,
     compare: Compare,
     value: u64,
 ) -> u64 {
@@ -1739,7 +4549,10 @@ fn estimate_u64_range_len(
         return 0;
     };
     if compare == Compare::Equal {
-        return index.get(&value).map(RoaringBitmap::len).unwrap_or(0);
+        return index.get(&value).map(|docs| docs.len()).unwrap_or(0);
+    }
+    if compare == Compare::NotEqual {
+        return index.len() as u64;
     }
     let Some((&min, _)) = index.iter().next() else {
         return 0;
@@ -1756,6 +4569,110 @@ fn estimate_u64_range_len(
     )
 }
 
+pub(crate) fn ordered_range_candidates<T: Ord>(
+    index: Option<&OrdMap<T, Arc<RoaringBitmap>>>,
+    compare: Compare,
+    value: &T,
+) -> RoaringBitmap {
+    let Some(index) = index else {
+        return RoaringBitmap::new();
+    };
+    match compare {
+        Compare::Equal => index
+            .get(value)
+            .map_or_else(RoaringBitmap::new, |docs| (**docs).clone()),
+        Compare::Less => union_bitmaps(index.range(..value).map(|(_, bitmap)| bitmap)),
+        Compare::LessOrEqual => union_bitmaps(index.range(..=value).map(|(_, bitmap)| bitmap)),
+        Compare::Greater => union_bitmaps(
+            index
+                .range((Excluded(value), Unbounded))
+                .map(|(_, bitmap)| bitmap),
+        ),
+        Compare::GreaterOrEqual => union_bitmaps(index.range(value..).map(|(_, bitmap)| bitmap)),
+        Compare::NotEqual => union_bitmaps(
+            index
+                .iter()
+                .filter(|(key, _)| *key != value)
+                .map(|(_, bitmap)| bitmap),
+        ),
+    }
+}
+
+/// Keys in `low..=high` (or the open interval), for `Query::Between` bounds
+/// of one kind.
+fn bounded_range_candidates<T: Ord>(
+    index: Option<&OrdMap<T, Arc<RoaringBitmap>>>,
+    low: &T,
+    high: &T,
+    inclusive: bool,
+) -> RoaringBitmap {
+    let Some(index) = index else {
+        return RoaringBitmap::new();
+    };
+    // `BTreeMap::range` panics on inverted or empty-exclusive bounds.
+    if low > high || (low == high && !inclusive) {
+        return RoaringBitmap::new();
+    }
+    let bounds = if inclusive {
+        (Included(low), Included(high))
+    } else {
+        (Excluded(low), Excluded(high))
+    };
+    union_bitmaps(index.range(bounds).map(|(_, bitmap)| bitmap))
+}
+
+fn between_compares(inclusive: bool) -> (Compare, Compare) {
+    if inclusive {
+        (Compare::GreaterOrEqual, Compare::LessOrEqual)
+    } else {
+        (Compare::Greater, Compare::Less)
+    }
+}
+
+/// Float and wide integer keys do not fit `estimate_ordered_range_len`'s
+/// i128 span, so interpolate the bound's position between the smallest and
+/// largest key as floats instead.
+fn estimate_interpolated_range_len<T: Ord>(
+    index: Option<&OrdMap<T, Arc<RoaringBitmap>>>,
+    compare: Compare,
+    value: &T,
+    to_f64: impl Fn(&T) -> f64,
+) -> u64 {
+    let Some(index) = index else {
+        return 0;
+    };
+    if compare == Compare::Equal {
+        return index.get(value).map(|docs| docs.len()).unwrap_or(0);
+    }
+    if compare == Compare::NotEqual {
+        return index.len() as u64;
+    }
+    let (Some((min, _)), Some((max, _))) = (index.iter().next(), index.iter().next_back()) else {
+        return 0;
+    };
+    let distinct_values = index.len() as u64;
+    if min == max {
+        return if compare_ordering(min.cmp(value), compare) {
+            distinct_values
+        } else {
+            0
+        };
+    }
+    let (min, max, value) = (to_f64(min), to_f64(max), to_f64(value));
+    let below = ((value - min) / (max - min)).clamp(0.0, 1.0);
+    let fraction = match compare {
+        Compare::Equal | Compare::NotEqual => {
+            unreachable!("equality estimates are resolved from exact keys")
+        }
+        Compare::Less | Compare::LessOrEqual => below,
+        Compare::Greater | Compare::GreaterOrEqual => 1.0 - below,
+    };
+    if !fraction.is_finite() {
+        return distinct_values;
+    }
+    (fraction * distinct_values as f64).round() as u64
+}
+
 fn estimate_ordered_range_len(
     distinct_values: u64,
     min: i128,
@@ -1777,7 +4694,9 @@ fn estimate_ordered_range_len(
     let span = (max - min + 1) as u128;
     let distinct_values = distinct_values as u128;
     let estimate = match compare {
-        Compare::Equal => unreachable!("equal range estimates are resolved from exact keys"),
+        Compare::Equal | Compare::NotEqual => {
+            unreachable!("equality estimates are resolved from exact keys")
+        }
         Compare::Less => {
             if value <= min {
                 0
@@ -1818,10 +4737,12 @@ fn estimate_ordered_range_len(
     estimate.min(u64::MAX as u128) as u64
 }
 
-fn union_bitmaps<'a>(bitmaps: impl Iterator<Item = &'a RoaringBitmap>) -> RoaringBitmap {
+pub(crate) fn union_bitmaps<'a, B: Borrow<RoaringBitmap> + 'a>(
+    bitmaps: impl Iterator<Item = &'a B>,
+) -> RoaringBitmap {
     let mut result = RoaringBitmap::new();
     for bitmap in bitmaps {
-        result |= bitmap;
+        result |= bitmap.borrow();
     }
     result
 }
@@ -1834,77 +4755,331 @@ fn is_large_byte_value(value: &FieldValue) -> bool {
 }
 
 fn remove_from_exact(
-    index: &mut HashMap<FieldPath, HashMap<FieldValue, RoaringBitmap>>,
+    index: &mut im::HashMap<FieldPath, im::HashMap<FieldValue, Arc<RoaringBitmap>>>,
     path: &FieldPath,
     value: &FieldValue,
     doc_id: DocId,
 ) {
     if let Some(values) = index.get_mut(path) {
         if let Some(bitmap) = values.get_mut(value) {
-            bitmap.remove(doc_id);
+            Arc::make_mut(bitmap).remove(doc_id);
         }
     }
 }
 
 fn remove_from_bitmap_map(
-    index: &mut HashMap<FieldPath, RoaringBitmap>,
+    index: &mut im::HashMap<FieldPath, Arc<RoaringBitmap>>,
     path: &FieldPath,
     doc_id: DocId,
 ) {
     if let Some(bitmap) = index.get_mut(path) {
-        bitmap.remove(doc_id);
+        Arc::make_mut(bitmap).remove(doc_id);
     }
 }
 
 fn remove_from_range_i64(
-    index: &mut HashMap<FieldPath, BTreeMap<i64, RoaringBitmap>>,
+    index: &mut OrderedIndex<i64>,
     path: &FieldPath,
     value: i64,
     doc_id: DocId,
 ) {
     if let Some(values) = index.get_mut(path) {
         if let Some(bitmap) = values.get_mut(&value) {
-            bitmap.remove(doc_id);
+            Arc::make_mut(bitmap).remove(doc_id);
         }
     }
 }
 
 fn remove_from_range_u64(
-    index: &mut HashMap<FieldPath, BTreeMap<u64, RoaringBitmap>>,
+    index: &mut OrderedIndex<u64>,
     path: &FieldPath,
     value: u64,
     doc_id: DocId,
 ) {
     if let Some(values) = index.get_mut(path) {
         if let Some(bitmap) = values.get_mut(&value) {
-            bitmap.remove(doc_id);
+            Arc::make_mut(bitmap).remove(doc_id);
+        }
+    }
+}
+
+fn write_snapshot_path(out: &mut Vec<u8>, path: &FieldPath) {
+    match path {
+        FieldPath::Id(id) => {
+            out.push(0);
+            write_u32(out, *id);
+        }
+        FieldPath::Name(name) => {
+            out.push(1);
+            write_string(out, name);
+        }
+    }
+}
+
+// Tags follow the query bridge's `FieldValueDto` order.
+fn write_snapshot_value(out: &mut Vec<u8>, value: &FieldValue) {
+    match value {
+        FieldValue::Bool(value) => out.extend_from_slice(&[0, u8::from(*value)]),
+        FieldValue::I64(value) => {
+            out.push(1);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        FieldValue::U64(value) => {
+            out.push(2);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        FieldValue::String(value) => {
+            out.push(3);
+            write_string(out, value);
+        }
+        FieldValue::Bytes(value) => {
+            out.push(4);
+            write_bytes(out, value);
+        }
+        FieldValue::F64(value) => {
+            out.push(5);
+            out.extend_from_slice(&value.get().to_le_bytes());
+        }
+        FieldValue::BigUint(value) => {
+            out.push(6);
+            write_bytes(out, value.to_be_bytes());
+        }
+    }
+}
+
+fn write_snapshot_bitmap(out: &mut Vec<u8>, bitmap: &RoaringBitmap) {
+    let mut bytes = Vec::with_capacity(bitmap.serialized_size());
+    bitmap
+        .serialize_into(&mut bytes)
+        .expect("serializing into a Vec cannot fail");
+    write_bytes(out, &bytes);
+}
+
+fn write_snapshot_document(out: &mut Vec<u8>, fields: &DocumentFields) {
+    // `scalars` is rebuilt from the scoped list, which holds every value in
+    // insertion order.
+    write_u32(out, fields.scoped_scalars.len() as u32);
+    for scalar in &fields.scoped_scalars {
+        write_u32(out, scalar.scope);
+        write_snapshot_path(out, &scalar.path);
+        write_snapshot_value(out, &scalar.value);
+    }
+    write_u32(out, fields.vectors.len() as u32);
+    for (path, vector) in &fields.vectors {
+        write_snapshot_path(out, path);
+        write_u32(out, vector.len() as u32);
+        for component in vector {
+            out.extend_from_slice(&component.to_le_bytes());
+        }
+    }
+}
+
+fn write_ordered_snapshot<K: Ord>(
+    out: &mut Vec<u8>,
+    index: &OrderedIndex<K>,
+    write_key: impl Fn(&mut Vec<u8>, &K),
+) {
+    write_u32(out, index.len() as u32);
+    for (path, keys) in index {
+        write_snapshot_path(out, path);
+        write_u32(out, keys.len() as u32);
+        for (key, docs) in keys {
+            write_key(out, key);
+            write_snapshot_bitmap(out, docs);
+        }
+    }
+}
+
+struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    fn fixed<const N: usize>(&mut self, label: &'static str) -> Result<[u8; N], DecodeError> {
+        let end = self
+            .offset
+            .checked_add(N)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(DecodeError::Truncated(label))?;
+        let value = self.bytes[self.offset..end]
+            .try_into()
+            .expect("slice length checked");
+        self.offset = end;
+        Ok(value)
+    }
+
+    fn u32(&mut self, label: &'static str) -> Result<u32, DecodeError> {
+        read_u32(self.bytes, &mut self.offset, label)
+    }
+
+    fn bytes(&mut self, label: &'static str) -> Result<&'a [u8], DecodeError> {
+        read_bytes(self.bytes, &mut self.offset, label)
+    }
+
+    fn string(&mut self, label: &'static str) -> Result<String, DecodeError> {
+        read_string(self.bytes, &mut self.offset, label)
+    }
+
+    fn path(&mut self) -> Result<FieldPath, DecodeError> {
+        match self.fixed("index snapshot field path")? {
+            [0] => Ok(FieldPath::Id(self.u32("index snapshot field id")?)),
+            [1] => Ok(FieldPath::Name(self.string("index snapshot field name")?)),
+            [tag] => Err(DecodeError::InvalidTag("index snapshot field path", tag)),
+        }
+    }
+
+    fn value(&mut self) -> Result<FieldValue, DecodeError> {
+        Ok(match self.fixed("index snapshot value")? {
+            [0] => match self.fixed("index snapshot bool")? {
+                [0] => FieldValue::Bool(false),
+                [1] => FieldValue::Bool(true),
+                [tag] => return Err(DecodeError::InvalidTag("index snapshot bool", tag)),
+            },
+            [1] => FieldValue::I64(i64::from_le_bytes(self.fixed("index snapshot i64")?)),
+            [2] => FieldValue::U64(u64::from_le_bytes(self.fixed("index snapshot u64")?)),
+            [3] => FieldValue::from(self.string("index snapshot string")?),
+            [4] => FieldValue::from(self.bytes("index snapshot bytes")?.to_vec()),
+            [5] => FieldValue::from(f64::from_le_bytes(self.fixed("index snapshot f64")?)),
+            [6] => FieldValue::from_unsigned_be_bytes(self.bytes("index snapshot big uint")?),
+            [tag] => return Err(DecodeError::InvalidTag("index snapshot value", tag)),
+        })
+    }
+
+    fn bitmap(&mut self) -> Result<RoaringBitmap, DecodeError> {
+        RoaringBitmap::deserialize_from(self.bytes("index snapshot bitmap")?)
+            .map_err(|_| DecodeError::InvalidBitmap)
+    }
+
+    fn document(&mut self) -> Result<DocumentFields, DecodeError> {
+        let mut fields = DocumentFields::new();
+        for _ in 0..self.u32("index snapshot scalars")? {
+            let scope = self.u32("index snapshot scope")?;
+            let path = self.path()?;
+            fields.insert_scoped_scalar(scope, path, self.value()?);
+        }
+        for _ in 0..self.u32("index snapshot vectors")? {
+            let path = self.path()?;
+            let mut vector = Vec::new();
+            for _ in 0..self.u32("index snapshot vector length")? {
+                vector.push(f32::from_le_bytes(self.fixed("index snapshot vector")?));
+            }
+            fields.insert_vector(path, vector);
+        }
+        Ok(fields)
+    }
+
+    fn ordered<K: Ord + Clone>(
+        &mut self,
+        read_key: impl Fn(&mut Self) -> Result<K, DecodeError>,
+    ) -> Result<OrderedIndex<K>, DecodeError> {
+        let mut index = im::HashMap::new();
+        for _ in 0..self.u32("index snapshot ordered fields")? {
+            let path = self.path()?;
+            let mut keys = OrdMap::new();
+            for _ in 0..self.u32("index snapshot ordered keys")? {
+                keys.insert(read_key(self)?, Arc::new(self.bitmap()?));
+            }
+            index.insert(path, keys);
         }
+        Ok(index)
     }
 }
 
-fn insert_into_ordered_index<T: Ord>(
-    index: &mut HashMap<FieldPath, BTreeMap<T, RoaringBitmap>>,
+fn insert_into_ordered_index<T: Ord + Clone>(
+    index: &mut OrderedIndex<T>,
     path: &FieldPath,
     value: T,
     doc_id: DocId,
 ) {
-    index
-        .entry(path.clone())
-        .or_default()
-        .entry(value)
-        .or_default()
-        .insert(doc_id);
+    Arc::make_mut(
+        index
+            .entry(path.clone())
+            .or_default()
+            .entry(value)
+            .or_default(),
+    )
+    .insert(doc_id);
 }
 
-fn remove_from_ordered_index<T: Ord>(
-    index: &mut HashMap<FieldPath, BTreeMap<T, RoaringBitmap>>,
+fn remove_from_ordered_index<T: Ord + Clone>(
+    index: &mut OrderedIndex<T>,
     path: &FieldPath,
     value: &T,
     doc_id: DocId,
 ) {
     if let Some(values) = index.get_mut(path) {
         if let Some(bitmap) = values.get_mut(value) {
-            bitmap.remove(doc_id);
+            Arc::make_mut(bitmap).remove(doc_id);
+        }
+    }
+}
+
+/// Typed key of one of the `sort_*` indexes, recoverable from a `FieldValue`
+/// of the matching kind.
+trait SortIndexKey: Ord {
+    fn from_field_value(value: &FieldValue) -> Option<&Self>;
+}
+
+impl SortIndexKey for bool {
+    fn from_field_value(value: &FieldValue) -> Option<&Self> {
+        match value {
+            FieldValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SortIndexKey for i64 {
+    fn from_field_value(value: &FieldValue) -> Option<&Self> {
+        match value {
+            FieldValue::I64(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SortIndexKey for u64 {
+    fn from_field_value(value: &FieldValue) -> Option<&Self> {
+        match value {
+            FieldValue::U64(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SortIndexKey for BigUint {
+    fn from_field_value(value: &FieldValue) -> Option<&Self> {
+        match value {
+            FieldValue::BigUint(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SortIndexKey for TotalF64 {
+    fn from_field_value(value: &FieldValue) -> Option<&Self> {
+        match value {
+            FieldValue::F64(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SortIndexKey for Arc<str> {
+    fn from_field_value(value: &FieldValue) -> Option<&Self> {
+        match value {
+            FieldValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SortIndexKey for Arc<[u8]> {
+    fn from_field_value(value: &FieldValue) -> Option<&Self> {
+        match value {
+            FieldValue::Bytes(value) => Some(value),
+            _ => None,
         }
     }
 }
@@ -1933,6 +5108,8 @@ fn compare_field_values(left: &FieldValue, right: &FieldValue) -> Ordering {
         (FieldValue::Bool(left), FieldValue::Bool(right)) => left.cmp(right),
         (FieldValue::I64(left), FieldValue::I64(right)) => left.cmp(right),
         (FieldValue::U64(left), FieldValue::U64(right)) => left.cmp(right),
+        (FieldValue::BigUint(left), FieldValue::BigUint(right)) => left.cmp(right),
+        (FieldValue::F64(left), FieldValue::F64(right)) => left.cmp(right),
         (FieldValue::String(left), FieldValue::String(right)) => left.cmp(right),
         (FieldValue::Bytes(left), FieldValue::Bytes(right)) => left.cmp(right),
         _ => field_value_rank(left).cmp(&field_value_rank(right)),
@@ -1944,12 +5121,14 @@ fn field_value_rank(value: &FieldValue) -> u8 {
         FieldValue::Bool(_) => 0,
         FieldValue::I64(_) => 1,
         FieldValue::U64(_) => 2,
-        FieldValue::String(_) => 3,
-        FieldValue::Bytes(_) => 4,
+        FieldValue::BigUint(_) => 3,
+        FieldValue::F64(_) => 4,
+        FieldValue::String(_) => 5,
+        FieldValue::Bytes(_) => 6,
     }
 }
 
-fn vector_distance(left: &[f32], right: &[f32], metric: VectorMetric) -> f32 {
+pub(crate) fn vector_distance(left: &[f32], right: &[f32], metric: VectorMetric) -> f32 {
     match metric {
         VectorMetric::L2 => left
             .iter()
@@ -1984,9 +5163,19 @@ fn vector_distance(left: &[f32], right: &[f32], metric: VectorMetric) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::{
-        Compare, DocumentFields, FieldValue, IndexBatch, NativeQueryIndex, Query, SortDirection,
-        SortField, SumResult, VectorMetric, VectorSort, MAX_EXACT_INDEXED_BYTE_FIELD_LENGTH,
+        Aggregation, AggregationResult, BudgetExceeded, BudgetLimit, Compare, Deadline, DocSet,
+        DocumentFields, FieldPath, FieldValue, HistogramBucket, IndexBatch, NativeQueryIndex,
+        PlanAccess, Query, QueryBudget, QueryError, SortDirection, SortField, SortStrategy,
+        StringMatchMethod, SubscriptionDiff, SubscriptionId, SumResult, TextOperator, TextSort,
+        ValueBucket, VectorMetric, VectorSearchMode, VectorSort,
+        MAX_EXACT_INDEXED_BYTE_FIELD_LENGTH,
     };
+    use crate::hnsw::VectorIndexConfig;
+    use crate::text::TextAnalyzer;
+    use roaring::RoaringBitmap;
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::sync::Arc;
 
     #[test]
     fn plans_multi_field_range_query_with_bitmaps() {
@@ -2200,6 +5389,554 @@ mod tests {
         );
     }
 
+    #[test]
+    fn explain_reports_index_choice_and_scans() {
+        let mut index = NativeQueryIndex::new();
+        for i in 0..20_u64 {
+            index.put(
+                format!("doc-{i}"),
+                DocumentFields::new()
+                    .with_scalar("kind", i % 4)
+                    .with_scalar("name", format!("name-{i}")),
+            );
+        }
+        let query = Query::And(vec![
+            Query::StringMatch {
+                field: "name".into(),
+                value: "1".into(),
+                method: StringMatchMethod::Contains,
+                case_insensitive: false,
+            },
+            Query::Exact {
+                field: "kind".into(),
+                value: FieldValue::U64(1),
+            },
+        ]);
+        let sort = [SortField {
+            field: "name".into(),
+            direction: SortDirection::Asc,
+        }];
+
+        let plan = index.explain(&query, &sort, Some(2));
+        assert_eq!(plan.root.access, PlanAccess::Seeded { child: 1 });
+        assert_eq!(plan.root.children[0].access, PlanAccess::FullScan);
+        assert_eq!(plan.root.children[0].actual_candidates, 20);
+        assert_eq!(plan.root.children[1].access, PlanAccess::ExactIndex);
+        assert_eq!(plan.root.children[1].estimated_candidates, 5);
+        assert_eq!(plan.documents_evaluated, 5);
+        assert!(!plan.full_scan);
+        // doc-1, doc-13, doc-17 (doc-5 and doc-9 do not contain "1").
+        assert_eq!(plan.matched, 3);
+        assert_eq!(plan.returned, 2);
+        assert_eq!(
+            plan.sort,
+            SortStrategy::SortIndex {
+                field: "name".into()
+            }
+        );
+
+        let plan = index.explain(
+            &Query::IsNull {
+                field: "missing".into(),
+            },
+            &[sort[0].clone(), sort[0].clone()],
+            None,
+        );
+        assert!(plan.full_scan);
+        assert_eq!(plan.root.predicate, "is_null");
+        assert_eq!(plan.sort, SortStrategy::InMemory);
+        assert_eq!(
+            index.explain(&Query::All, &[], None).sort,
+            SortStrategy::DocumentOrder
+        );
+
+        // Decided by the access path, not by how many candidates it yields.
+        let every_kind = Query::Range {
+            field: "kind".into(),
+            compare: Compare::GreaterOrEqual,
+            value: FieldValue::U64(0),
+        };
+        let plan = index.explain(&every_kind, &[], None);
+        assert_eq!(plan.root.access, PlanAccess::RangeIndex);
+        assert_eq!(plan.documents_evaluated, 20);
+        assert!(!plan.full_scan);
+        let no_kind = Query::Not(Box::new(Query::Exact {
+            field: "kind".into(),
+            value: FieldValue::U64(9),
+        }));
+        assert!(!index.explain(&no_kind, &[], None).full_scan);
+        let with_null = Query::Or(vec![
+            Query::Exact {
+                field: "kind".into(),
+                value: FieldValue::U64(1),
+            },
+            Query::IsNull {
+                field: "missing".into(),
+            },
+        ]);
+        assert!(index.explain(&with_null, &[], None).full_scan);
+        assert!(index.explain(&Query::All, &[], None).full_scan);
+        assert!(
+            NativeQueryIndex::new()
+                .explain(&Query::All, &[], None)
+                .full_scan
+        );
+    }
+
+    fn collect_keyset_pages(
+        index: &NativeQueryIndex,
+        query: &Query,
+        sort: &[SortField],
+        limit: usize,
+    ) -> Vec<String> {
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = index
+                .search_page_after(query, sort, cursor.as_ref(), limit)
+                .unwrap();
+            assert!(page.ids.len() <= limit);
+            ids.extend(page.ids);
+            match page.cursor {
+                Some(next) => cursor = Some(next),
+                None => return ids,
+            }
+        }
+    }
+
+    #[test]
+    fn keyset_pages_match_sorted_search() {
+        let mut index = NativeQueryIndex::new();
+        for i in 0..40_u64 {
+            let mut fields = DocumentFields::new()
+                .with_scalar("keep", i % 5 != 4)
+                .with_scalar("other", i % 3);
+            if i % 10 == 3 {
+                fields.insert_scalar("rank", format!("r{}", i % 4));
+            } else if i % 9 != 0 {
+                fields.insert_scalar("rank", i % 7);
+            }
+            index.put(format!("doc-{i:02}"), fields);
+        }
+        let keep = Query::Exact {
+            field: "keep".into(),
+            value: FieldValue::Bool(true),
+        };
+        let rank = |direction| SortField {
+            field: "rank".into(),
+            direction,
+        };
+        let sorts = [
+            vec![rank(SortDirection::Asc)],
+            vec![rank(SortDirection::Desc)],
+            vec![
+                rank(SortDirection::Asc),
+                SortField {
+                    field: "other".into(),
+                    direction: SortDirection::Desc,
+                },
+            ],
+        ];
+        for sort in &sorts {
+            for query in [&Query::All, &keep] {
+                for limit in [1, 4, 7, 100] {
+                    assert_eq!(
+                        collect_keyset_pages(&index, query, sort, limit),
+                        index.search(query, sort, None)
+                    );
+                }
+            }
+        }
+
+        let mut by_id = index.search(&keep, &[], None);
+        by_id.sort();
+        assert_eq!(collect_keyset_pages(&index, &keep, &[], 6), by_id);
+
+        let page = index
+            .search_page_after(&Query::All, &sorts[0], None, 3)
+            .unwrap();
+        assert!(index
+            .search_page_after(&Query::All, &sorts[2], page.cursor.as_ref(), 3)
+            .is_err());
+    }
+
+    #[test]
+    fn wide_unsigned_fields_range_sort_and_sum() {
+        let mut huge = [0_u8; 32];
+        huge[0] = 0x80;
+        let huge = FieldValue::from_unsigned_be_bytes(&huge);
+        let mut index = NativeQueryIndex::new();
+        for (id, balance) in [
+            ("small", FieldValue::from(5_u128)),
+            ("max", FieldValue::U64(u64::MAX)),
+            ("big", FieldValue::from(u64::MAX as u128 + 10)),
+            ("huge", huge.clone()),
+            ("u128", FieldValue::from(u128::MAX)),
+        ] {
+            index.put(id, DocumentFields::new().with_scalar("balance", balance));
+        }
+        assert_eq!(FieldValue::from(5_u128), FieldValue::U64(5));
+        let range = |compare, value: FieldValue| Query::Range {
+            field: "balance".into(),
+            compare,
+            value,
+        };
+        let sort = [SortField {
+            field: "balance".into(),
+            direction: SortDirection::Asc,
+        }];
+
+        assert_eq!(
+            index.search(&range(Compare::Greater, FieldValue::U64(100)), &sort, None),
+            vec!["max", "big", "u128", "huge"]
+        );
+        assert_eq!(
+            index.search(
+                &range(Compare::Less, FieldValue::from(u64::MAX as u128 + 11)),
+                &sort,
+                None
+            ),
+            vec!["small", "max", "big"]
+        );
+        assert_eq!(
+            index.search(
+                &range(Compare::GreaterOrEqual, FieldValue::from(u128::MAX)),
+                &sort,
+                None
+            ),
+            vec!["u128", "huge"]
+        );
+        for limit in [1, 2, 100] {
+            assert_eq!(
+                collect_keyset_pages(&index, &Query::All, &sort, limit),
+                vec!["small", "max", "big", "u128", "huge"]
+            );
+        }
+        assert_eq!(index.max(&Query::All, "balance"), Some(huge));
+
+        let SumResult::BigUint(sum) = index
+            .sum(
+                &range(Compare::Less, FieldValue::from(u64::MAX as u128 + 11)),
+                "balance",
+            )
+            .unwrap()
+        else {
+            panic!("expected a wide sum");
+        };
+        assert_eq!(
+            FieldValue::BigUint(sum),
+            FieldValue::from(2 * u64::MAX as u128 + 15)
+        );
+        let SumResult::BigUint(sum) = index
+            .sum(
+                &Query::Or(vec![
+                    range(Compare::Equal, FieldValue::U64(5)),
+                    range(Compare::Equal, FieldValue::from(u128::MAX)),
+                ]),
+                "balance",
+            )
+            .unwrap()
+        else {
+            panic!("expected a wide sum");
+        };
+        assert_eq!(sum.to_string(), "340282366920938463463374607431768211460");
+    }
+
+    #[test]
+    fn trigram_index_prunes_string_matches_without_changing_results() {
+        let names = [
+            "Straße.rs",
+            "STRASSE.md",
+            "main.rs",
+            "lib.RS",
+            "notes",
+            "rs",
+        ];
+        let build = |trigrams: bool| {
+            let mut index = NativeQueryIndex::new();
+            if trigrams {
+                index.configure_trigram_index("name");
+            }
+            for (position, name) in names.iter().enumerate() {
+                index.put(
+                    position.to_string(),
+                    DocumentFields::new().with_scalar("name", *name),
+                );
+            }
+            index.put("number", DocumentFields::new().with_scalar("name", 3_u64));
+            index
+        };
+        let indexed = build(true);
+        let scanned = build(false);
+        let string_match = |value: &str, method, case_insensitive| Query::StringMatch {
+            field: "name".into(),
+            value: value.into(),
+            method,
+            case_insensitive,
+        };
+        let sorted = |index: &NativeQueryIndex, query: &Query| {
+            let mut ids = index.search(query, &[], None);
+            ids.sort();
+            ids
+        };
+
+        let cases = [
+            (
+                string_match("strasse", StringMatchMethod::Prefix, true),
+                vec!["0", "1"],
+            ),
+            (
+                string_match("STRASSE", StringMatchMethod::Contains, false),
+                vec!["1"],
+            ),
+            (
+                string_match(".rs", StringMatchMethod::Suffix, true),
+                vec!["0", "2", "3"],
+            ),
+            (
+                string_match(".rs", StringMatchMethod::Suffix, false),
+                vec!["0", "2"],
+            ),
+            (
+                string_match("*a?n.*", StringMatchMethod::Glob, false),
+                vec!["2"],
+            ),
+            (
+                string_match("*.rs", StringMatchMethod::Glob, true),
+                vec!["0", "2", "3"],
+            ),
+            (
+                string_match("r*", StringMatchMethod::Glob, false),
+                vec!["5"],
+            ),
+            (
+                string_match("RS", StringMatchMethod::Exact, true),
+                vec!["5"],
+            ),
+        ];
+        for (query, expected) in &cases {
+            assert_eq!(&sorted(&indexed, query), expected, "{query:?}");
+            assert_eq!(sorted(&scanned, query), sorted(&indexed, query));
+        }
+
+        let suffix = string_match(".rs", StringMatchMethod::Suffix, false);
+        let plan = indexed.explain(&suffix, &[], None);
+        assert_eq!(plan.root.access, PlanAccess::TrigramIndex);
+        // The index is case-folded, so `lib.RS` is a candidate that fails
+        // verification.
+        assert_eq!(plan.documents_evaluated, 3);
+        assert_eq!(plan.matched, 2);
+        assert!(scanned.explain(&suffix, &[], None).full_scan);
+
+        let mut indexed = indexed;
+        indexed.put("2", DocumentFields::new().with_scalar("name", "main.py"));
+        indexed.delete("0");
+        assert!(indexed.search(&suffix, &[], None).is_empty());
+        indexed.clear();
+        indexed.put("a", DocumentFields::new().with_scalar("name", "x.rs"));
+        assert_eq!(
+            indexed.explain(&suffix, &[], None).root.access,
+            PlanAccess::TrigramIndex
+        );
+        assert_eq!(indexed.search(&suffix, &[], None), vec!["a"]);
+    }
+
+    #[test]
+    fn set_membership_between_and_not_equal_queries() {
+        let mut index = NativeQueryIndex::new();
+        for (id, score) in [("a", 1_u64), ("b", 5), ("c", 9)] {
+            index.put(id, DocumentFields::new().with_scalar("score", score));
+        }
+        index.put(
+            "multi",
+            DocumentFields::new()
+                .with_scalar("score", 2_u64)
+                .with_scalar("score", 12_u64),
+        );
+        index.put("signed", DocumentFields::new().with_scalar("score", -3_i64));
+        index.put("text", DocumentFields::new().with_scalar("score", "x"));
+        index.put("none", DocumentFields::new());
+        let sorted = |query: &Query| {
+            let mut ids = index.search(query, &[], None);
+            ids.sort();
+            ids
+        };
+        let between = |low: u64, high: u64, inclusive| Query::Between {
+            field: "score".into(),
+            low: FieldValue::U64(low),
+            high: FieldValue::U64(high),
+            inclusive,
+        };
+
+        let values = vec![
+            FieldValue::U64(5),
+            FieldValue::U64(12),
+            FieldValue::from("x"),
+        ];
+        let in_query = Query::In {
+            field: "score".into(),
+            values: values.clone(),
+        };
+        let or_query = Query::Or(
+            values
+                .into_iter()
+                .map(|value| Query::Exact {
+                    field: "score".into(),
+                    value,
+                })
+                .collect(),
+        );
+        assert_eq!(sorted(&in_query), vec!["b", "multi", "text"]);
+        assert_eq!(sorted(&in_query), sorted(&or_query));
+        assert_eq!(
+            index.explain(&in_query, &[], None).root.access,
+            PlanAccess::ExactIndex
+        );
+
+        // One value has to sit inside both bounds, unlike an `And` of ranges.
+        assert_eq!(sorted(&between(3, 9, true)), vec!["b", "c"]);
+        assert_eq!(sorted(&between(1, 9, false)), vec!["b", "multi"]);
+        assert_eq!(
+            sorted(&Query::And(vec![
+                Query::Range {
+                    field: "score".into(),
+                    compare: Compare::GreaterOrEqual,
+                    value: FieldValue::U64(3),
+                },
+                Query::Range {
+                    field: "score".into(),
+                    compare: Compare::LessOrEqual,
+                    value: FieldValue::U64(9),
+                },
+            ])),
+            vec!["b", "c", "multi"]
+        );
+        assert_eq!(index.count(&between(9, 3, true)), 0);
+        assert_eq!(index.count(&between(5, 5, false)), 0);
+        assert_eq!(sorted(&between(5, 5, true)), vec!["b"]);
+        let plan = index.explain(&between(3, 9, true), &[], None);
+        assert_eq!(plan.root.predicate, "between");
+        assert!(!plan.full_scan);
+
+        let not_equal = Query::Range {
+            field: "score".into(),
+            compare: Compare::NotEqual,
+            value: FieldValue::U64(5),
+        };
+        assert_eq!(sorted(&not_equal), vec!["a", "c", "multi"]);
+        assert_eq!(
+            sorted(&Query::And(vec![Query::All, not_equal.clone()])),
+            sorted(&not_equal)
+        );
+    }
+
+    #[test]
+    fn float_fields_range_sort_and_aggregate() {
+        let mut index = NativeQueryIndex::new();
+        for (id, price) in [
+            ("neg", -1.5),
+            ("zero", 0.0),
+            ("neg-zero", -0.0),
+            ("mid", 2.25),
+            ("nan", f64::NAN),
+            ("inf", f64::INFINITY),
+        ] {
+            index.put(id, DocumentFields::new().with_scalar("price", price));
+        }
+        index.put("int", DocumentFields::new().with_scalar("price", 3_u64));
+        index.put("none", DocumentFields::new());
+        let range = |compare, value: f64| Query::Range {
+            field: "price".into(),
+            compare,
+            value: FieldValue::from(value),
+        };
+
+        let mut cheap = index.search(&range(Compare::Less, 1.0), &[], None);
+        cheap.sort();
+        assert_eq!(cheap, vec!["neg", "neg-zero", "zero"]);
+        assert_eq!(
+            index.search(&range(Compare::Greater, 2.25), &[], None),
+            vec!["inf"]
+        );
+        assert_eq!(index.count(&range(Compare::Equal, f64::NAN)), 0);
+        assert_eq!(
+            index.count(&Query::Exact {
+                field: "price".into(),
+                value: FieldValue::from(-0.0),
+            }),
+            2
+        );
+
+        let sort = [SortField {
+            field: "price".into(),
+            direction: SortDirection::Asc,
+        }];
+        assert_eq!(
+            index.search(&Query::All, &sort, None),
+            vec!["int", "neg", "neg-zero", "zero", "mid", "inf", "nan", "none"]
+        );
+        for limit in [1, 3, 100] {
+            assert_eq!(
+                collect_keyset_pages(&index, &Query::All, &sort, limit),
+                index.search(&Query::All, &sort, None)
+            );
+        }
+
+        let finite = range(Compare::LessOrEqual, 2.25);
+        assert_eq!(index.sum(&finite, "price").unwrap(), SumResult::F64(0.75));
+        assert_eq!(index.average(&finite, "price").unwrap(), Some(0.1875));
+        assert_eq!(index.min(&Query::All, "price"), Some(FieldValue::U64(3)));
+        assert_eq!(index.max(&finite, "price"), Some(FieldValue::from(2.25)));
+        assert_eq!(
+            index.histogram(&Query::All, "price", 2).unwrap(),
+            vec![
+                HistogramBucket {
+                    start: -2,
+                    count: 1
+                },
+                HistogramBucket { start: 0, count: 2 },
+                HistogramBucket { start: 2, count: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn keyset_pages_do_not_repeat_or_skip_under_concurrent_writes() {
+        let mut index = NativeQueryIndex::new();
+        for i in 0..20_u64 {
+            index.put(
+                format!("doc-{i:02}"),
+                DocumentFields::new().with_scalar("rank", i / 2),
+            );
+        }
+        let sort = [SortField {
+            field: "rank".into(),
+            direction: SortDirection::Asc,
+        }];
+        let first = index
+            .search_page_after(&Query::All, &sort, None, 5)
+            .unwrap();
+        assert_eq!(first.ids.len(), 5);
+
+        // A write before the cursor would shift an offset page by one.
+        index.put("early", DocumentFields::new().with_scalar("rank", 0_u64));
+        index.delete("doc-19");
+        index.put("late", DocumentFields::new().with_scalar("rank", 100_u64));
+
+        let second = index
+            .search_page_after(&Query::All, &sort, first.cursor.as_ref(), 100)
+            .unwrap();
+        assert!(second.generation > first.generation);
+        assert!(second.cursor.is_none());
+        let mut seen: Vec<_> = first.ids.iter().chain(&second.ids).cloned().collect();
+        assert!(!seen.contains(&"early".to_string()));
+        assert!(!seen.contains(&"doc-19".to_string()));
+        assert_eq!(seen.last().map(String::as_str), Some("late"));
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 20);
+    }
+
     #[test]
     fn ties_break_by_external_id_not_insertion_order() {
         // Insert in an order where the local insertion counter (DocId) is the
@@ -2503,12 +6240,226 @@ mod tests {
 
         let query = Query::Exact {
             field: "group".into(),
-            value: FieldValue::from("left"),
+            value: FieldValue::from("left"),
+        };
+
+        assert_eq!(index.sum(&query, "value").unwrap(), SumResult::U64(4));
+        assert_eq!(index.delete_matching(&query), vec!["a", "c"]);
+        assert_eq!(index.search(&Query::All, &[], None), vec!["b"]);
+    }
+
+    #[test]
+    fn aggregates_match_between_index_and_scan_paths() {
+        let mut index = NativeQueryIndex::new();
+        for i in 0..40_u64 {
+            let mut fields = DocumentFields::new()
+                .with_scalar("group", format!("g{}", i % 3))
+                .with_scalar("value", i)
+                .with_scalar("value", 1000_u64)
+                .with_scalar("even", i % 2 == 0);
+            if i == 7 {
+                fields = DocumentFields::new()
+                    .with_scalar("group", "g1")
+                    .with_scalar("value", -5_i64)
+                    .with_scalar("even", false);
+            }
+            index.put(format!("doc-{i}"), fields);
+        }
+        for i in 0..60 {
+            index.put(
+                format!("dup-{i}"),
+                DocumentFields::new().with_scalar("value", 5_u64),
+            );
+        }
+        let even = Query::Exact {
+            field: "even".into(),
+            value: FieldValue::Bool(true),
+        };
+        let few = Query::Range {
+            field: "value".into(),
+            compare: Compare::Less,
+            value: FieldValue::U64(3),
+        };
+
+        assert_eq!(index.min(&Query::All, "value"), Some(FieldValue::I64(-5)));
+        // The trailing 1000 counts as much as the first value.
+        assert_eq!(index.max(&Query::All, "value"), Some(FieldValue::U64(1000)));
+        assert_eq!(index.min(&even, "value"), Some(FieldValue::U64(0)));
+        assert_eq!(index.max(&few, "value"), Some(FieldValue::U64(1000)));
+        assert_eq!(index.max(&few, "missing"), None);
+        assert_eq!(
+            index.max(&Query::All, "group"),
+            Some(FieldValue::from("g2"))
+        );
+
+        assert_eq!(index.average(&few, "value").unwrap(), Some(500.5));
+        assert_eq!(index.average(&few, "group").unwrap(), None);
+
+        assert_eq!(index.distinct_count(&Query::All, "group"), 3);
+        assert_eq!(index.distinct_count(&few, "group"), 3);
+        // 0..40 without 7, -5 and the shared trailing 1000.
+        assert_eq!(index.distinct_count(&Query::All, "value"), 41);
+
+        let groups = index.group_by(&even, "group");
+        assert_eq!(
+            groups
+                .iter()
+                .map(|bucket| (bucket.value.clone(), bucket.count))
+                .collect::<Vec<_>>(),
+            vec![
+                (FieldValue::from("g0"), 7),
+                (FieldValue::from("g1"), 6),
+                (FieldValue::from("g2"), 7),
+            ]
+        );
+        assert_eq!(
+            index.group_by(&few, "group"),
+            vec![
+                ValueBucket {
+                    value: FieldValue::from("g0"),
+                    count: 1
+                },
+                ValueBucket {
+                    value: FieldValue::from("g1"),
+                    count: 1
+                },
+                ValueBucket {
+                    value: FieldValue::from("g2"),
+                    count: 1
+                },
+            ]
+        );
+
+        let expected = vec![
+            HistogramBucket {
+                start: -10,
+                count: 1,
+            },
+            HistogramBucket {
+                start: 0,
+                count: 69,
+            },
+            HistogramBucket {
+                start: 10,
+                count: 10,
+            },
+            HistogramBucket {
+                start: 20,
+                count: 10,
+            },
+            HistogramBucket {
+                start: 30,
+                count: 10,
+            },
+            HistogramBucket {
+                start: 1000,
+                count: 39,
+            },
+        ];
+        // Fewer distinct values than matches: served from the range index.
+        assert_eq!(
+            index.aggregate(
+                &Query::All,
+                &Aggregation::Histogram {
+                    field: "value".into(),
+                    interval: 10,
+                }
+            ),
+            Ok(AggregationResult::Histogram(expected))
+        );
+        assert_eq!(
+            index
+                .histogram(&even, "value", 10)
+                .unwrap()
+                .iter()
+                .map(|bucket| (bucket.start, bucket.count))
+                .collect::<Vec<_>>(),
+            vec![(0, 5), (10, 5), (20, 5), (30, 5), (1000, 20)]
+        );
+        assert_eq!(
+            index.histogram(&few, "value", 1000).unwrap(),
+            vec![
+                HistogramBucket { start: 0, count: 3 },
+                HistogramBucket {
+                    start: 1000,
+                    count: 3,
+                },
+            ]
+        );
+        assert!(index.histogram(&few, "value", 0).is_err());
+        assert_eq!(
+            index.aggregate(&even, &Aggregation::Count),
+            Ok(AggregationResult::Count(20))
+        );
+    }
+
+    #[test]
+    fn aggregations_read_every_value_of_multi_valued_fields() {
+        let mut index = NativeQueryIndex::new();
+        for (id, values) in [("a", [3_u64, 10]), ("b", [7, 7]), ("c", [20, 1])] {
+            index.put(
+                id,
+                DocumentFields::new()
+                    .with_scalar("group", "tagged")
+                    .with_scalar("value", values[0])
+                    .with_scalar("value", values[1]),
+            );
+        }
+        for i in 0..20 {
+            index.put(
+                format!("pad-{i}"),
+                DocumentFields::new()
+                    .with_scalar("group", "pad")
+                    .with_scalar("value", 50_u64)
+                    .with_scalar("value", 5_u64),
+            );
+        }
+        let tagged = Query::Exact {
+            field: "group".into(),
+            value: FieldValue::from("tagged"),
         };
-
-        assert_eq!(index.sum(&query, "value").unwrap(), SumResult::U64(4));
-        assert_eq!(index.delete_matching(&query), vec!["a", "c"]);
-        assert_eq!(index.search(&Query::All, &[], None), vec!["b"]);
+        let aggregate =
+            |query: &Query, aggregation: Aggregation| index.aggregate(query, &aggregation).unwrap();
+
+        // Few matches scan the documents; many matches over few distinct
+        // values walk the indexes. Both read every value.
+        for (query, values, sum, min, max, histogram) in [
+            (&tagged, 6, 48, 1, 20, vec![(0, 3), (10, 1), (20, 1)]),
+            (
+                &Query::All,
+                46,
+                48 + 20 * 55,
+                1,
+                50,
+                vec![(0, 23), (10, 1), (20, 1), (50, 20)],
+            ),
+        ] {
+            assert_eq!(
+                aggregate(query, Aggregation::Sum("value".into())),
+                AggregationResult::Sum(SumResult::U64(sum))
+            );
+            assert_eq!(
+                aggregate(query, Aggregation::Min("value".into())),
+                AggregationResult::Value(Some(FieldValue::U64(min)))
+            );
+            assert_eq!(
+                aggregate(query, Aggregation::Max("value".into())),
+                AggregationResult::Value(Some(FieldValue::U64(max)))
+            );
+            assert_eq!(
+                aggregate(query, Aggregation::Average("value".into())),
+                AggregationResult::Average(Some(sum as f64 / values as f64))
+            );
+            assert_eq!(
+                index
+                    .histogram(query, "value", 10)
+                    .unwrap()
+                    .iter()
+                    .map(|bucket| (bucket.start, bucket.count))
+                    .collect::<Vec<_>>(),
+                histogram
+            );
+        }
     }
 
     #[test]
@@ -2587,6 +6538,7 @@ mod tests {
                     field: "embedding".into(),
                     query: vec![1.0, 0.0],
                     metric: VectorMetric::Cosine,
+                    mode: VectorSearchMode::Exact,
                 },
                 10,
             ),
@@ -2858,6 +6810,7 @@ mod tests {
                 field: "embedding".into(),
                 query: vec![1.0, 0.0],
                 metric: VectorMetric::Cosine,
+                mode: VectorSearchMode::Exact,
             },
             2,
         );
@@ -2865,4 +6818,718 @@ mod tests {
         assert_eq!(results[0].id, "a");
         assert_eq!(results[1].id, "c");
     }
+
+    fn replay_subscription_diffs(
+        index: &mut NativeQueryIndex,
+        views: &mut HashMap<SubscriptionId, Vec<String>>,
+    ) -> Vec<SubscriptionDiff> {
+        let diffs = index.take_subscription_diffs();
+        for diff in &diffs {
+            assert_eq!(diff.generation, index.generation());
+            let view = views.get_mut(&diff.subscription).unwrap();
+            view.retain(|id| {
+                !diff.removed.contains(id) && !diff.moved.iter().any(|(moved, _)| moved == id)
+            });
+            let mut inserted: Vec<_> = diff.added.iter().chain(&diff.moved).collect();
+            inserted.sort_by_key(|(_, index)| *index);
+            for (id, index) in inserted {
+                view.insert(*index, id.clone());
+            }
+        }
+        diffs
+    }
+
+    #[test]
+    fn subscriptions_track_top_n_views_through_writes() {
+        let score = |value: u64| DocumentFields::new().with_scalar("score", value);
+        let mut index = NativeQueryIndex::new();
+        for (id, value) in [("a", 5_u64), ("b", 3), ("c", 8), ("d", 1), ("e", 6)] {
+            index.put(id, score(value));
+        }
+        let query = Query::Range {
+            field: "score".into(),
+            compare: Compare::GreaterOrEqual,
+            value: FieldValue::U64(2),
+        };
+        let sort = vec![SortField {
+            field: "score".into(),
+            direction: SortDirection::Desc,
+        }];
+        let (top, top_view) = index.subscribe(query.clone(), sort.clone(), Some(3));
+        let (all, all_view) = index.subscribe(Query::All, Vec::new(), None);
+        assert_eq!(top_view, vec!["c", "e", "a"]);
+        let mut views = HashMap::from([(top, top_view), (all, all_view)]);
+        let check = |index: &mut NativeQueryIndex, views: &mut HashMap<_, Vec<String>>| {
+            replay_subscription_diffs(index, views);
+            assert_eq!(views[&top], index.search(&query, &sort, Some(3)));
+            if let Some(view) = views.get(&all) {
+                assert_eq!(*view, index.search(&Query::All, &[], None));
+            }
+        };
+
+        index.put("f", score(7));
+        let diffs = replay_subscription_diffs(&mut index, &mut views);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].added, vec![("f".to_string(), 1)]);
+        assert_eq!(diffs[0].removed, vec!["a".to_string()]);
+        assert!(diffs[0].moved.is_empty());
+        check(&mut index, &mut views);
+
+        // Rewriting a member to the end of the window moves it; removing one
+        // refills the window from past its end.
+        index.put("c", score(6));
+        let diffs = replay_subscription_diffs(&mut index, &mut views);
+        assert_eq!(diffs[0].moved, vec![("c".to_string(), 2)]);
+        check(&mut index, &mut views);
+        index.put("c", score(9));
+        check(&mut index, &mut views);
+        index.delete("c");
+        check(&mut index, &mut views);
+        index.put("b", score(0));
+        check(&mut index, &mut views);
+        index.apply_batch(
+            IndexBatch::new()
+                .delete("f")
+                .put("g", score(4))
+                .put("a", score(10)),
+        );
+        check(&mut index, &mut views);
+        index.delete_matching(&Query::Range {
+            field: "score".into(),
+            compare: Compare::Less,
+            value: FieldValue::U64(5),
+        });
+        check(&mut index, &mut views);
+
+        // Writes that leave the views unchanged publish nothing.
+        index.put("a", score(10));
+        assert!(index.take_subscription_diffs().is_empty());
+
+        assert!(index.unsubscribe(all));
+        views.remove(&all);
+        index.clear();
+        check(&mut index, &mut views);
+        assert!(views[&top].is_empty());
+        index.put("h", score(2));
+        check(&mut index, &mut views);
+        assert_eq!(views[&top], vec!["h"]);
+    }
+
+    #[test]
+    fn query_budgets_stop_expensive_queries_with_partial_statistics() {
+        let mut index = NativeQueryIndex::new();
+        for doc in 0..1_000_u64 {
+            index.put(
+                format!("doc-{doc:04}"),
+                DocumentFields::new()
+                    .with_scalar("name", format!("name-{doc}"))
+                    .with_scalar("score", doc)
+                    .with_vector("embedding", vec![doc as f32, 1.0]),
+            );
+        }
+        // Without a trigram index every document is verified.
+        let scan = Query::StringMatch {
+            field: "name".into(),
+            value: "NAME-".to_string(),
+            method: StringMatchMethod::Contains,
+            case_insensitive: true,
+        };
+        let budget = |max_candidates, max_materialized| QueryBudget {
+            max_candidates,
+            max_materialized,
+            deadline: None,
+        };
+
+        assert_eq!(
+            index.count_within(&scan, &budget(Some(100), None)),
+            Err(BudgetExceeded {
+                limit: BudgetLimit::Candidates,
+                candidates_examined: 101,
+                documents_materialized: 0,
+            })
+        );
+        assert_eq!(
+            index.count_within(&scan, &budget(Some(1_000), None)),
+            Ok(1_000)
+        );
+
+        // An index-sorted page only walks as far as the page needs.
+        let top = [SortField {
+            field: "score".into(),
+            direction: SortDirection::Desc,
+        }];
+        assert_eq!(
+            index.search_page_within(&scan, &top, 5, Some(5), &budget(Some(10), Some(5))),
+            Ok(index.search_page(&scan, &top, 5, Some(5)))
+        );
+        assert_eq!(
+            index
+                .search_page_within(&scan, &[], 0, None, &budget(None, Some(10)))
+                .unwrap_err()
+                .limit,
+            BudgetLimit::Materialized
+        );
+
+        let expired = QueryBudget {
+            deadline: Some(Deadline {
+                at: 0.0,
+                clock: || 1.0,
+            }),
+            ..QueryBudget::default()
+        };
+        assert_eq!(
+            index.search_page_within(&scan, &top, 0, Some(5), &expired),
+            Err(BudgetExceeded {
+                limit: BudgetLimit::Deadline,
+                candidates_examined: 1,
+                documents_materialized: 0,
+            })
+        );
+
+        assert!(matches!(
+            index.sum_within(&scan, "score", &budget(None, Some(10))),
+            Err(QueryError::BudgetExceeded(BudgetExceeded {
+                limit: BudgetLimit::Materialized,
+                ..
+            }))
+        ));
+        assert_eq!(
+            index.sum_within(&Query::All, "score", &budget(None, None)),
+            Ok(SumResult::U64(499_500))
+        );
+
+        let nearest = VectorSort {
+            field: "embedding".into(),
+            query: vec![0.0, 1.0],
+            metric: VectorMetric::L2,
+            mode: VectorSearchMode::Exact,
+        };
+        assert_eq!(
+            index
+                .vector_search_within(&scan, &nearest, 3, &budget(None, Some(100)))
+                .unwrap_err()
+                .documents_materialized,
+            101
+        );
+        assert_eq!(
+            index.vector_search_within(&scan, &nearest, 3, &QueryBudget::default()),
+            Ok(index.vector_search(&scan, &nearest, 3))
+        );
+    }
+
+    #[test]
+    fn composite_index_serves_pinned_prefix_sorts_and_ranges() {
+        let mut plain = NativeQueryIndex::new();
+        let mut composite = NativeQueryIndex::new();
+        composite.configure_composite_index(vec!["owner".into()], "timestamp");
+        for doc in 0..60_u64 {
+            let mut fields =
+                DocumentFields::new().with_scalar("owner", format!("owner-{}", doc % 3));
+            match doc % 10 {
+                // No timestamp: sorts after every value ascending.
+                0 => {}
+                // Several timestamps: ordered by the first, ranged by any.
+                1 => {
+                    fields.insert_scalar("timestamp", 1_000 - doc);
+                    fields.insert_scalar("timestamp", doc);
+                }
+                _ => fields.insert_scalar("timestamp", (doc * 7) % 50),
+            }
+            plain.put(format!("doc-{doc:02}"), fields.clone());
+            composite.put(format!("doc-{doc:02}"), fields);
+        }
+        // Moving a document to another owner moves it between groups.
+        for index in [&mut plain, &mut composite] {
+            index.put(
+                "doc-02",
+                DocumentFields::new()
+                    .with_scalar("owner", "owner-1")
+                    .with_scalar("timestamp", 3_u64),
+            );
+            index.delete("doc-05");
+        }
+
+        let owner = Query::Exact {
+            field: "owner".into(),
+            value: FieldValue::from("owner-1"),
+        };
+        let recent = Query::And(vec![
+            owner.clone(),
+            Query::Range {
+                field: "timestamp".into(),
+                compare: Compare::GreaterOrEqual,
+                value: FieldValue::U64(30),
+            },
+        ]);
+        for direction in [SortDirection::Asc, SortDirection::Desc] {
+            let sort = [SortField {
+                field: "timestamp".into(),
+                direction,
+            }];
+            for query in [&owner, &recent] {
+                let expected = plain.search(query, &sort, None);
+                assert_eq!(composite.search(query, &sort, None), expected);
+                assert_eq!(
+                    composite.search_page(query, &sort, 2, Some(5)),
+                    expected[2..7]
+                );
+                let mut paged = Vec::new();
+                let mut cursor = None;
+                loop {
+                    let page = composite
+                        .search_page_after(query, &sort, cursor.as_ref(), 4)
+                        .unwrap();
+                    paged.extend(page.ids);
+                    cursor = page.cursor;
+                    if cursor.is_none() {
+                        break;
+                    }
+                }
+                assert_eq!(paged, expected);
+            }
+            assert_eq!(
+                composite.explain(&owner, &sort, Some(5)).sort,
+                SortStrategy::CompositeIndex {
+                    prefix: vec!["owner".into()],
+                    field: "timestamp".into(),
+                }
+            );
+        }
+
+        let plan = composite.explain(&recent, &[], None);
+        assert_eq!(plan.root.access, PlanAccess::CompositeIndex);
+        assert!(plan.documents_evaluated < plain.explain(&recent, &[], None).documents_evaluated);
+        assert_eq!(composite.count(&recent), plain.count(&recent));
+
+        let nobody = Query::Exact {
+            field: "owner".into(),
+            value: FieldValue::from("nobody"),
+        };
+        let sort = [SortField {
+            field: "timestamp".into(),
+            direction: SortDirection::Desc,
+        }];
+        assert!(composite.search(&nobody, &sort, Some(3)).is_empty());
+
+        assert!(composite.remove_composite_index(&["owner".into()], &"timestamp".into()));
+        assert_eq!(
+            composite.explain(&owner, &sort, Some(5)).sort,
+            SortStrategy::SortIndex {
+                field: "timestamp".into()
+            }
+        );
+    }
+
+    #[test]
+    fn pinned_snapshots_read_one_generation_under_writes() {
+        let score = |value: u64| DocumentFields::new().with_scalar("score", value);
+        let mut index = NativeQueryIndex::new();
+        for value in 0..10_u64 {
+            index.put(format!("doc-{value}"), score(value));
+        }
+        let sort = [SortField {
+            field: "score".into(),
+            direction: SortDirection::Asc,
+        }];
+        let generation = index.pin_snapshot();
+        let expected = index.search(&Query::All, &sort, None);
+
+        let mut pages = Vec::new();
+        let mut cursor = None;
+        for round in 0..5_u64 {
+            let snapshot = index.pinned_snapshot(generation).unwrap();
+            let page = snapshot
+                .search_page_after(&Query::All, &sort, cursor.as_ref(), 3)
+                .unwrap();
+            assert_eq!(page.generation, generation);
+            pages.extend(page.ids);
+            cursor = page.cursor;
+            index.apply_batch(
+                IndexBatch::new()
+                    .delete(format!("doc-{round}"))
+                    .put(format!("new-{round}"), score(round + 100)),
+            );
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pages, expected);
+
+        let snapshot = index.pinned_snapshot(generation).unwrap().clone();
+        assert_eq!(snapshot.count(&Query::All), 10);
+        assert_eq!(
+            snapshot.sum(&Query::All, "score").unwrap(),
+            SumResult::U64(45)
+        );
+        assert_ne!(index.sum(&Query::All, "score").unwrap(), SumResult::U64(45));
+
+        index.clear();
+        assert!(index.generation() > generation);
+        assert_eq!(index.pinned_snapshot(generation).unwrap().len(), 10);
+        assert!(index.release_snapshot(generation));
+        assert!(index.pinned_snapshot(generation).is_none());
+        assert!(!index.release_snapshot(generation));
+        // An owned snapshot outlives the pin.
+        assert_eq!(snapshot.search(&Query::All, &sort, None), expected);
+    }
+
+    #[test]
+    fn writes_under_a_pin_copy_only_what_they_touch() {
+        let mut index = NativeQueryIndex::new();
+        for value in 0..100_u64 {
+            index.put(
+                format!("doc-{value}"),
+                DocumentFields::new()
+                    .with_scalar("score", value)
+                    .with_scalar("tag", format!("tag-{}", value % 10)),
+            );
+        }
+        let generation = index.pin_snapshot();
+        index.put("new", DocumentFields::new().with_scalar("score", 1000_u64));
+
+        let pinned = index.pinned_snapshot(generation).unwrap();
+        let (score, tag) = (FieldPath::from("score"), FieldPath::from("tag"));
+        // The untouched field's maps are still shared with the snapshot...
+        assert!(index.exact[&tag].ptr_eq(&pinned.exact[&tag]));
+        assert!(index.sort_string[&tag].ptr_eq(&pinned.sort_string[&tag]));
+        // ...while the written one diverged without touching the snapshot.
+        assert!(!index.exact[&score].ptr_eq(&pinned.exact[&score]));
+        assert!(!index.range_u64[&score].ptr_eq(&pinned.range_u64[&score]));
+        assert_eq!((pinned.len(), index.len()), (100, 101));
+        // ...and within it, so are the postings of the values not written.
+        let (five, new) = (FieldValue::from(5_u64), FieldValue::from(1000_u64));
+        assert!(Arc::ptr_eq(
+            &index.exact[&score][&five],
+            &pinned.exact[&score][&five]
+        ));
+        assert!(Arc::ptr_eq(
+            &index.range_u64[&score][&5],
+            &pinned.range_u64[&score][&5]
+        ));
+        assert!(!pinned.exact[&score].contains_key(&new));
+        // Whole-index candidates are borrowed, not copied.
+        assert!(matches!(index.candidates(&Query::All), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn doc_set_writes_copy_one_chunk() {
+        let mut docs = DocSet::default();
+        for doc_id in [0, 1, 1 << 16, (1 << 16) + 1] {
+            docs.insert(doc_id);
+        }
+        let snapshot = docs.clone();
+        assert!(docs.insert(2));
+        assert!(docs.remove(1 << 16));
+        assert!(!docs.remove(1 << 16));
+        assert!(!docs.insert(0));
+
+        assert_eq!(
+            snapshot.iter().collect::<Vec<_>>(),
+            vec![0, 1, 65536, 65537]
+        );
+        assert_eq!(docs.iter().collect::<Vec<_>>(), vec![0, 1, 2, 65537]);
+        assert_eq!((snapshot.len(), docs.len()), (4, 4));
+        assert!(!docs.contains(1 << 16) && snapshot.contains(1 << 16));
+
+        // The bitmap view is shared with a snapshot until the next write...
+        let bitmap = docs.bitmap() as *const RoaringBitmap;
+        let snapshot = docs.clone();
+        assert!(std::ptr::eq(snapshot.bitmap(), bitmap));
+        docs.insert(3);
+        assert!(docs.bitmap().iter().eq([0, 1, 2, 3, 65537]));
+        assert!(snapshot.bitmap().iter().eq([0, 1, 2, 65537]));
+        // ...and kept up to date in place once nothing else holds it.
+        drop(snapshot);
+        let bitmap = docs.bitmap() as *const RoaringBitmap;
+        docs.remove(65537);
+        assert!(std::ptr::eq(docs.bitmap(), bitmap));
+        assert!(docs.bitmap().iter().eq([0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn snapshot_reloads_the_built_index() {
+        let mut index = NativeQueryIndex::new();
+        for i in 0..40_u64 {
+            let mut fields = DocumentFields::new()
+                .with_scalar("n", i)
+                .with_scalar("signed", i as i64 - 20)
+                .with_scalar("price", i as f64 / 4.0)
+                .with_scalar("name", format!("item {i}"))
+                .with_scalar("flag", i % 3 == 0)
+                .with_scalar("tag", vec![i as u8; if i % 5 == 0 { 200 } else { 4 }])
+                .with_vector("embedding", vec![i as f32, 1.0]);
+            fields.insert_scoped_scalar(1, "tags", format!("t{}", i % 4));
+            fields.insert_scoped_scalar(2, "tags", format!("t{}", i % 7));
+            if i % 2 == 0 {
+                fields.insert_scalar("wide", FieldValue::from(u128::MAX - i as u128));
+            }
+            index.put(format!("number:{i}"), fields);
+        }
+        for i in [3, 17] {
+            index.delete(format!("number:{i}"));
+        }
+        let snapshot = index.encode_snapshot(7);
+
+        let mut loaded = NativeQueryIndex::new();
+        loaded.configure_text_index("name", TextAnalyzer::simple());
+        assert!(!loaded.load_snapshot(&snapshot, 8).unwrap());
+        assert!(loaded.is_empty());
+        assert!(loaded.load_snapshot(&snapshot, 7).unwrap());
+        assert_eq!(loaded.len(), index.len());
+
+        let range = |field: &str, compare, value: FieldValue| Query::Range {
+            field: field.into(),
+            compare,
+            value,
+        };
+        let queries = [
+            Query::All,
+            range("n", Compare::GreaterOrEqual, FieldValue::U64(30)),
+            range("signed", Compare::Less, FieldValue::I64(-15)),
+            range("price", Compare::Greater, FieldValue::from(8.0)),
+            range("wide", Compare::Less, FieldValue::from(u128::MAX - 30)),
+            Query::Exact {
+                field: "tag".into(),
+                value: FieldValue::from(vec![10_u8; 200]),
+            },
+            Query::And(vec![
+                Query::Exact {
+                    field: "tags".into(),
+                    value: FieldValue::from("t1"),
+                },
+                Query::Exact {
+                    field: "flag".into(),
+                    value: FieldValue::Bool(true),
+                },
+            ]),
+        ];
+        for field in ["n", "price", "name", "wide", "flag", "tag"] {
+            for direction in [SortDirection::Asc, SortDirection::Desc] {
+                let sort = [SortField {
+                    field: field.into(),
+                    direction,
+                }];
+                for query in &queries {
+                    assert_eq!(
+                        loaded.search(query, &sort, None),
+                        index.search(query, &sort, None),
+                        "{query:?} by {field}"
+                    );
+                }
+            }
+        }
+        let vector_sort = VectorSort {
+            field: "embedding".into(),
+            query: vec![10.0, 1.0],
+            metric: VectorMetric::L2,
+            mode: VectorSearchMode::Exact,
+        };
+        assert_eq!(
+            loaded.vector_search(&Query::All, &vector_sort, 3),
+            index.vector_search(&Query::All, &vector_sort, 3)
+        );
+        let text = Query::Text {
+            field: "name".into(),
+            value: "item 12".into(),
+            operator: TextOperator::All,
+        };
+        assert_eq!(
+            loaded.explain(&text, &[], None).root.access,
+            PlanAccess::TextIndex
+        );
+        assert_eq!(loaded.search(&text, &[], None), vec!["number:12"]);
+
+        // Freed doc ids are still recycled after the reload.
+        for target in [&mut index, &mut loaded] {
+            target.put(
+                "number:100",
+                DocumentFields::new().with_scalar("n", 100_u64),
+            );
+        }
+        let sort = [SortField {
+            field: "n".into(),
+            direction: SortDirection::Desc,
+        }];
+        assert_eq!(
+            loaded.search(&Query::All, &sort, Some(2)),
+            index.search(&Query::All, &sort, Some(2))
+        );
+        assert_eq!(
+            loaded.encode_snapshot(7).len(),
+            index.encode_snapshot(7).len()
+        );
+
+        let mut corrupt = snapshot.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 1;
+        assert!(NativeQueryIndex::new().load_snapshot(&corrupt, 7).is_err());
+        assert!(NativeQueryIndex::new().load_snapshot(&[], 7).is_err());
+    }
+
+    #[test]
+    fn approximate_vector_search_tracks_puts_deletes_and_filters() {
+        let mut index = NativeQueryIndex::new();
+        index.configure_vector_index(
+            "embedding",
+            VectorIndexConfig {
+                max_connections: 8,
+                ef_construction: 32,
+                ..VectorIndexConfig::new(VectorMetric::L2)
+            },
+        );
+        for i in 0..400_u64 {
+            let angle = i as f32 * 0.05;
+            index.put(
+                format!("doc-{i}"),
+                DocumentFields::new()
+                    .with_scalar("even", i % 2 == 0)
+                    .with_vector("embedding", vec![angle.cos() * i as f32, angle.sin()]),
+            );
+        }
+        let sort = |mode| VectorSort {
+            field: "embedding".into(),
+            query: vec![100.0, 0.0],
+            metric: VectorMetric::L2,
+            mode,
+        };
+        let even = Query::Exact {
+            field: "even".into(),
+            value: FieldValue::Bool(true),
+        };
+
+        let exact = index.vector_search(&even, &sort(VectorSearchMode::Exact), 5);
+        let approximate =
+            index.vector_search(&even, &sort(VectorSearchMode::Approximate { ef: 64 }), 5);
+        assert_eq!(approximate, exact);
+
+        index.delete_id(&exact[0].id);
+        index.put(
+            "moved",
+            DocumentFields::new()
+                .with_scalar("even", true)
+                .with_vector("embedding", vec![100.0, 0.0]),
+        );
+        let approximate =
+            index.vector_search(&even, &sort(VectorSearchMode::Approximate { ef: 64 }), 5);
+        assert_eq!(approximate[0].id, "moved");
+        assert!(approximate.iter().all(|result| result.id != exact[0].id));
+        assert_eq!(
+            approximate,
+            index.vector_search(&even, &sort(VectorSearchMode::Exact), 5)
+        );
+
+        // A metric the graph was not built for is served by the exact scan.
+        let cosine = index.vector_search(
+            &Query::All,
+            &VectorSort {
+                metric: VectorMetric::Cosine,
+                ..sort(VectorSearchMode::Approximate { ef: 64 })
+            },
+            3,
+        );
+        assert_eq!(cosine.len(), 3);
+
+        index.clear();
+        assert!(index.vector_index_config(&"embedding".into()).is_some());
+    }
+
+    #[test]
+    fn text_queries_match_and_rank_with_or_without_index() {
+        let mut index = NativeQueryIndex::new();
+        index.put(
+            "a",
+            DocumentFields::new()
+                .with_scalar("body", "Running rust on the web")
+                .with_scalar("published", true),
+        );
+        index.put(
+            "b",
+            DocumentFields::new()
+                .with_scalar("body", "rust runs rust everywhere, rust")
+                .with_scalar("published", true),
+        );
+        index.put(
+            "c",
+            DocumentFields::new()
+                .with_scalar("body", "Typescript on the web")
+                .with_scalar("published", false),
+        );
+        let all_terms = Query::Text {
+            field: "body".into(),
+            value: "RUST web".into(),
+            operator: TextOperator::All,
+        };
+        let any_terms = Query::Text {
+            field: "body".into(),
+            value: "RUST web".into(),
+            operator: TextOperator::Any,
+        };
+
+        assert_eq!(index.search(&all_terms, &[], None), vec!["a"]);
+        assert_eq!(index.count(&any_terms), 3);
+        assert!(index
+            .text_search(
+                &Query::All,
+                &TextSort {
+                    field: "body".into(),
+                    query: "rust".into(),
+                    operator: TextOperator::Any,
+                },
+                10,
+            )
+            .is_err());
+
+        index.configure_text_index("body", TextAnalyzer::english());
+        assert_eq!(index.search(&all_terms, &[], None), vec!["a"]);
+        assert_eq!(index.count(&Query::Not(Box::new(all_terms))), 2);
+        // Stemming conflates "running" and "runs" once the index analyzer applies.
+        assert_eq!(
+            index.count(&Query::Text {
+                field: "body".into(),
+                value: "run".into(),
+                operator: TextOperator::Any,
+            }),
+            2
+        );
+
+        let sort = TextSort {
+            field: "body".into(),
+            query: "rust web".into(),
+            operator: TextOperator::Any,
+        };
+        let ranked: Vec<_> = index
+            .text_search(&Query::All, &sort, 10)
+            .unwrap()
+            .into_iter()
+            .map(|result| result.id)
+            .collect();
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[2], "c");
+        let filtered = index
+            .text_search(
+                &Query::Exact {
+                    field: "published".into(),
+                    value: FieldValue::Bool(false),
+                },
+                &sort,
+                10,
+            )
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "c");
+
+        index.delete("b");
+        index.put(
+            "c",
+            DocumentFields::new().with_scalar("body", "now about rust only"),
+        );
+        let ranked = index.text_search(&Query::All, &sort, 10).unwrap();
+        assert_eq!(
+            ranked.iter().map(|result| &result.id).collect::<Vec<_>>(),
+            vec!["a", "c"]
+        );
+
+        index.clear();
+        assert!(index.text_analyzer(&"body".into()).is_some());
+    }
 }
@@ -1,4 +1,5 @@
 use crate::planner::{DocumentFields, FieldPath, FieldValue};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 const BRIDGE_VERSION: u8 = 1;
@@ -6,6 +7,7 @@ const BRIDGE_VERSION: u8 = 1;
 #[derive(Clone, Debug)]
 pub struct NativeSchemaIr {
     root: NativeSchemaNode,
+    fingerprint: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -30,6 +32,8 @@ enum NativeSchemaNode {
     I16,
     I32,
     I64,
+    F32,
+    F64,
     String,
     Uint8Array,
     Object {
@@ -44,6 +48,9 @@ enum NativeSchemaNode {
     },
     Generic,
     PublicSignKey,
+    /// Field typed by an abstract class: one `Object` per concrete variant,
+    /// told apart by their (non-empty) variant prefixes.
+    Polymorphic(Vec<NativeSchemaNode>),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -62,6 +69,8 @@ pub enum SchemaError {
     VariantPrefixMismatch,
     MissingFieldMetadata,
     GenericNode,
+    InvalidVariantTable,
+    UnknownVariant,
     ScopeOverflow,
     OffsetOverflow,
     UnexpectedEof,
@@ -94,6 +103,14 @@ impl fmt::Display for SchemaError {
                 formatter,
                 "native schema IR contains a generic node that cannot be extracted from Borsh"
             ),
+            Self::InvalidVariantTable => write!(
+                formatter,
+                "native schema polymorphic variants must be objects with a variant prefix"
+            ),
+            Self::UnknownVariant => write!(
+                formatter,
+                "Borsh variant prefix did not match any native schema variant"
+            ),
             Self::ScopeOverflow => write!(formatter, "native schema extraction scope overflow"),
             Self::OffsetOverflow => write!(formatter, "bridge payload offset overflow"),
             Self::UnexpectedEof => write!(formatter, "unexpected end of bridge payload"),
@@ -112,6 +129,12 @@ impl fmt::Display for SchemaError {
 impl std::error::Error for SchemaError {}
 
 impl NativeSchemaIr {
+    /// Hash of the encoded IR. Index snapshots record it so they are only
+    /// reused with the schema their fields were extracted under.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
     pub fn stats(&self) -> NativeSchemaIrStats {
         NativeSchemaIrStats {
             root_fields: match &self.root {
@@ -126,9 +149,93 @@ impl NativeSchemaIr {
     fn scalar_capacity(&self, byte_element_index_limit: usize) -> usize {
         self.root.scalar_capacity(byte_element_index_limit)
     }
+
+    /// Shape of every field id, as the set of encoded node signatures it is
+    /// declared with (one per polymorphic variant declaring it). Nested
+    /// objects contribute their variant prefix but not their fields, which
+    /// have ids of their own.
+    pub(crate) fn field_shapes(&self) -> BTreeMap<u32, BTreeSet<Vec<u8>>> {
+        let mut shapes = BTreeMap::new();
+        self.root.collect_field_shapes(&mut shapes);
+        shapes
+    }
 }
 
 impl NativeSchemaNode {
+    fn collect_field_shapes(&self, shapes: &mut BTreeMap<u32, BTreeSet<Vec<u8>>>) {
+        match self {
+            NativeSchemaNode::Object { fields, .. } => {
+                for field in fields {
+                    let mut shape = field.array_field.to_le_bytes().to_vec();
+                    field.node.write_shape(&mut shape);
+                    shapes.entry(field.field).or_default().insert(shape);
+                    field.node.collect_field_shapes(shapes);
+                }
+            }
+            NativeSchemaNode::Option(node) | NativeSchemaNode::Vec(node) => {
+                node.collect_field_shapes(shapes)
+            }
+            NativeSchemaNode::FixedArray { element, .. } => element.collect_field_shapes(shapes),
+            NativeSchemaNode::Polymorphic(variants) => {
+                for variant in variants {
+                    variant.collect_field_shapes(shapes);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn write_shape(&self, out: &mut Vec<u8>) {
+        out.push(self.tag());
+        match self {
+            NativeSchemaNode::Object { variant_prefix, .. } => {
+                out.extend_from_slice(&(variant_prefix.len() as u32).to_le_bytes());
+                out.extend_from_slice(variant_prefix);
+            }
+            NativeSchemaNode::Option(node) | NativeSchemaNode::Vec(node) => node.write_shape(out),
+            NativeSchemaNode::FixedArray { length, element } => {
+                out.extend_from_slice(&length.to_le_bytes());
+                element.write_shape(out);
+            }
+            NativeSchemaNode::Polymorphic(variants) => {
+                out.extend_from_slice(&(variants.len() as u32).to_le_bytes());
+                for variant in variants {
+                    variant.write_shape(out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The node's tag in the encoded IR.
+    fn tag(&self) -> u8 {
+        match self {
+            NativeSchemaNode::Bool => 0,
+            NativeSchemaNode::U8 => 1,
+            NativeSchemaNode::U16 => 2,
+            NativeSchemaNode::U32 => 3,
+            NativeSchemaNode::U64 => 4,
+            NativeSchemaNode::U128 => 5,
+            NativeSchemaNode::U256 => 6,
+            NativeSchemaNode::U512 => 7,
+            NativeSchemaNode::I8 => 8,
+            NativeSchemaNode::I16 => 9,
+            NativeSchemaNode::I32 => 10,
+            NativeSchemaNode::I64 => 11,
+            NativeSchemaNode::String => 12,
+            NativeSchemaNode::Uint8Array => 13,
+            NativeSchemaNode::Object { .. } => 14,
+            NativeSchemaNode::Option(_) => 15,
+            NativeSchemaNode::Vec(_) => 16,
+            NativeSchemaNode::FixedArray { .. } => 17,
+            NativeSchemaNode::Generic => 18,
+            NativeSchemaNode::PublicSignKey => 19,
+            NativeSchemaNode::F32 => 20,
+            NativeSchemaNode::F64 => 21,
+            NativeSchemaNode::Polymorphic(_) => 22,
+        }
+    }
+
     fn node_count(&self) -> usize {
         match self {
             NativeSchemaNode::Object { fields, .. } => {
@@ -145,6 +252,9 @@ impl NativeSchemaNode {
                 let _ = length;
                 1 + element.node_count()
             }
+            NativeSchemaNode::Polymorphic(variants) => {
+                1 + variants.iter().map(Self::node_count).sum::<usize>()
+            }
             NativeSchemaNode::Bool
             | NativeSchemaNode::U8
             | NativeSchemaNode::U16
@@ -157,6 +267,8 @@ impl NativeSchemaNode {
             | NativeSchemaNode::I16
             | NativeSchemaNode::I32
             | NativeSchemaNode::I64
+            | NativeSchemaNode::F32
+            | NativeSchemaNode::F64
             | NativeSchemaNode::String
             | NativeSchemaNode::Uint8Array
             | NativeSchemaNode::PublicSignKey
@@ -172,6 +284,9 @@ impl NativeSchemaNode {
                 .sum::<usize>(),
             NativeSchemaNode::Option(node) | NativeSchemaNode::Vec(node) => node.generic_count(),
             NativeSchemaNode::FixedArray { element, .. } => element.generic_count(),
+            NativeSchemaNode::Polymorphic(variants) => {
+                variants.iter().map(Self::generic_count).sum::<usize>()
+            }
             NativeSchemaNode::Generic => 1,
             NativeSchemaNode::Bool
             | NativeSchemaNode::U8
@@ -185,6 +300,8 @@ impl NativeSchemaNode {
             | NativeSchemaNode::I16
             | NativeSchemaNode::I32
             | NativeSchemaNode::I64
+            | NativeSchemaNode::F32
+            | NativeSchemaNode::F64
             | NativeSchemaNode::String
             | NativeSchemaNode::Uint8Array
             | NativeSchemaNode::PublicSignKey => 0,
@@ -212,6 +329,11 @@ impl NativeSchemaNode {
                 }
                 node => (*length as usize) * (1 + node.scalar_capacity(byte_element_index_limit)),
             },
+            NativeSchemaNode::Polymorphic(variants) => variants
+                .iter()
+                .map(|variant| variant.scalar_capacity(byte_element_index_limit))
+                .max()
+                .unwrap_or(0),
             NativeSchemaNode::Bool
             | NativeSchemaNode::U8
             | NativeSchemaNode::U16
@@ -224,6 +346,8 @@ impl NativeSchemaNode {
             | NativeSchemaNode::I16
             | NativeSchemaNode::I32
             | NativeSchemaNode::I64
+            | NativeSchemaNode::F32
+            | NativeSchemaNode::F64
             | NativeSchemaNode::String
             | NativeSchemaNode::Uint8Array
             | NativeSchemaNode::PublicSignKey => 1,
@@ -237,7 +361,20 @@ pub fn decode_native_schema_ir(schema_ir_bytes: &[u8]) -> Result<NativeSchemaIr,
     ensure_bridge_version(reader.read_u8()?)?;
     let root = read_native_schema_node(&mut reader)?;
     reader.finish()?;
-    Ok(NativeSchemaIr { root })
+    Ok(NativeSchemaIr {
+        root,
+        fingerprint: fnv1a_64(schema_ir_bytes),
+    })
+}
+
+// 64-bit FNV-1a: a stable fingerprint, not a security boundary.
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325_u64;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
 }
 
 pub fn extract_encoded_document_fields(
@@ -329,6 +466,16 @@ impl<'a> BridgeReader<'a> {
         }
     }
 
+    /// Whether the unread bytes start with `prefix`, without consuming them.
+    fn starts_with(&mut self, prefix: &[u8]) -> bool {
+        let offset = self.offset;
+        let matched = self
+            .read_exact(prefix.len())
+            .is_ok_and(|actual| actual == prefix);
+        self.offset = offset;
+        matched
+    }
+
     fn read_u8(&mut self) -> Result<u8, SchemaError> {
         let bytes = self.read_exact(1)?;
         Ok(bytes[0])
@@ -378,6 +525,11 @@ impl<'a> BridgeReader<'a> {
                 let len = self.read_u32()? as usize;
                 FieldValue::from(self.read_exact(len)?.to_vec())
             }
+            5 => FieldValue::from(f64::from_bits(self.read_u64()?)),
+            6 => {
+                let len = self.read_u32()? as usize;
+                FieldValue::from_unsigned_be_bytes(self.read_exact(len)?)
+            }
             tag => return Err(SchemaError::InvalidFieldValueTag(tag)),
         })
     }
@@ -461,6 +613,26 @@ fn read_native_schema_node(reader: &mut BridgeReader) -> Result<NativeSchemaNode
         },
         18 => NativeSchemaNode::Generic,
         19 => NativeSchemaNode::PublicSignKey,
+        20 => NativeSchemaNode::F32,
+        21 => NativeSchemaNode::F64,
+        22 => {
+            let variant_count = reader.read_u32()? as usize;
+            // Every variant takes at least its tag, prefix length and field
+            // count (9 bytes).
+            let remaining = reader.len.saturating_sub(reader.offset);
+            let mut variants = Vec::with_capacity(variant_count.min(remaining / 9));
+            for _ in 0..variant_count {
+                let variant = read_native_schema_node(reader)?;
+                if !matches!(
+                    &variant,
+                    NativeSchemaNode::Object { variant_prefix, .. } if !variant_prefix.is_empty()
+                ) {
+                    return Err(SchemaError::InvalidVariantTable);
+                }
+                variants.push(variant);
+            }
+            NativeSchemaNode::Polymorphic(variants)
+        }
         tag => return Err(SchemaError::UnknownSchemaNode(tag)),
     })
 }
@@ -566,7 +738,7 @@ fn extract_schema_node(
                 fields,
                 scope,
                 required_field(field)?,
-                FieldValue::U64(read_le_u64_saturating(reader, 16)?),
+                FieldValue::from_unsigned_le_bytes(reader.read_exact(16)?),
             );
         }
         NativeSchemaNode::U256 => {
@@ -574,7 +746,7 @@ fn extract_schema_node(
                 fields,
                 scope,
                 required_field(field)?,
-                FieldValue::U64(read_le_u64_saturating(reader, 32)?),
+                FieldValue::from_unsigned_le_bytes(reader.read_exact(32)?),
             );
         }
         NativeSchemaNode::U512 => {
@@ -582,7 +754,7 @@ fn extract_schema_node(
                 fields,
                 scope,
                 required_field(field)?,
-                FieldValue::U64(read_le_u64_saturating(reader, 64)?),
+                FieldValue::from_unsigned_le_bytes(reader.read_exact(64)?),
             );
         }
         NativeSchemaNode::I8 => {
@@ -617,6 +789,22 @@ fn extract_schema_node(
                 FieldValue::I64(reader.read_i64()?),
             );
         }
+        NativeSchemaNode::F32 => {
+            insert_scalar(
+                fields,
+                scope,
+                required_field(field)?,
+                FieldValue::from(f32::from_bits(reader.read_u32()?) as f64),
+            );
+        }
+        NativeSchemaNode::F64 => {
+            insert_scalar(
+                fields,
+                scope,
+                required_field(field)?,
+                FieldValue::from(f64::from_bits(reader.read_u64()?)),
+            );
+        }
         NativeSchemaNode::String => {
             let value = reader.read_string()?;
             insert_scalar(
@@ -705,6 +893,23 @@ fn extract_schema_node(
             let bytes = reader.read_exact(len)?;
             insert_bytes_facts(fields, state, scope, required_field(field)?, bytes)?;
         }
+        NativeSchemaNode::Polymorphic(variants) => {
+            // A subclass's prefix extends its parent's, so the longest match
+            // is the most derived variant.
+            let mut matched: Option<(&NativeSchemaNode, usize)> = None;
+            for variant in variants {
+                let NativeSchemaNode::Object { variant_prefix, .. } = variant else {
+                    continue;
+                };
+                if matched.is_none_or(|(_, len)| variant_prefix.len() > len)
+                    && reader.starts_with(variant_prefix)
+                {
+                    matched = Some((variant, variant_prefix.len()));
+                }
+            }
+            let (variant, _) = matched.ok_or(SchemaError::UnknownVariant)?;
+            extract_schema_node(variant, reader, fields, scope, state, field)?;
+        }
         NativeSchemaNode::Generic => return Err(SchemaError::GenericNode),
     }
     Ok(())
@@ -751,23 +956,6 @@ fn insert_bytes_facts(
     Ok(())
 }
 
-// u128/u256/u512 values above u64::MAX saturate to u64::MAX so the field
-// keeps a presence fact (IsNull must not match) and Greater/GreaterOrEqual
-// ranges stay correct; Equal/Less comparisons against u64::MAX itself are
-// approximate. Query values are capped at u64 by the interface and the TS
-// sqlite engine rejects these schemas outright, so there is no narrower
-// observable behavior to match.
-fn read_le_u64_saturating(reader: &mut BridgeReader, width: usize) -> Result<u64, SchemaError> {
-    let bytes = reader.read_exact(width)?;
-    if bytes.iter().skip(8).any(|byte| *byte != 0) {
-        return Ok(u64::MAX);
-    }
-    let low_width = width.min(8);
-    let mut low = [0u8; 8];
-    low[..low_width].copy_from_slice(&bytes[..low_width]);
-    Ok(u64::from_le_bytes(low))
-}
-
 fn read_le_i64_with_width(reader: &mut BridgeReader, width: usize) -> Result<i64, SchemaError> {
     let bytes = reader.read_exact(width)?;
     let mut out = if bytes.last().is_some_and(|byte| byte & 0x80 != 0) {
@@ -792,7 +980,7 @@ mod tests {
     use super::{
         decode_native_schema_ir, extract_encoded_document_fields,
         extract_encoded_document_fields_from_parts,
-        extract_encoded_document_fields_from_parts_with_byte_limits,
+        extract_encoded_document_fields_from_parts_with_byte_limits, SchemaError,
     };
     use crate::planner::{Compare, FieldPath, FieldValue, NativeQueryIndex, Query};
 
@@ -856,6 +1044,21 @@ mod tests {
         out
     }
 
+    fn schema_with_float_fields() -> Vec<u8> {
+        let mut out = vec![1, 14];
+        write_u32(&mut out, 0);
+        write_u32(&mut out, 2);
+        write_string(&mut out, "f32");
+        write_u32(&mut out, 1);
+        write_u32(&mut out, 301);
+        out.push(20);
+        write_string(&mut out, "f64");
+        write_u32(&mut out, 2);
+        write_u32(&mut out, 302);
+        out.push(21);
+        out
+    }
+
     fn schema_with_public_sign_key() -> Vec<u8> {
         let mut out = vec![1, 14];
         write_u32(&mut out, 0);
@@ -932,13 +1135,51 @@ mod tests {
     }
 
     #[test]
-    fn saturates_wide_unsigned_values_above_u64_max() {
+    fn extracts_float_fields_for_range_queries() {
+        let schema = decode_native_schema_ir(&schema_with_float_fields()).unwrap();
+        let mut index = NativeQueryIndex::new();
+        for (id, single, double) in [("a", 1.5_f32, -0.0_f64), ("b", -2.25, 9.75)] {
+            let mut encoded = Vec::new();
+            encoded.extend_from_slice(&single.to_le_bytes());
+            encoded.extend_from_slice(&double.to_le_bytes());
+            let fields = extract_encoded_document_fields(&schema, &encoded, 0).unwrap();
+            index.put(id, fields);
+        }
+
+        assert_eq!(schema.stats().generic_nodes, 0);
+        assert_eq!(
+            index.search(
+                &Query::Range {
+                    field: FieldPath::Id(1),
+                    compare: Compare::Less,
+                    value: FieldValue::from(0.0),
+                },
+                &[],
+                None,
+            ),
+            vec!["b"]
+        );
+        assert_eq!(
+            index.search(
+                &Query::Exact {
+                    field: FieldPath::Id(2),
+                    value: FieldValue::from(0.0),
+                },
+                &[],
+                None,
+            ),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn keeps_wide_unsigned_values_above_u64_max() {
         let schema = decode_native_schema_ir(&schema_with_wide_unsigned_fields()).unwrap();
         let mut encoded = Vec::new();
         encoded.extend_from_slice(&1_u16.to_le_bytes());
         encoded.extend_from_slice(&(u64::MAX as u128 + 1).to_le_bytes());
         let mut u256 = [0u8; 32];
-        u256[31] = 1;
+        u256[31] = 0x80;
         encoded.extend_from_slice(&u256);
         let mut u512 = [0u8; 64];
         u512[0] = 3;
@@ -947,10 +1188,17 @@ mod tests {
 
         let fields = extract_encoded_document_fields(&schema, &encoded, 0).unwrap();
 
-        for field in [2, 3, 4] {
+        let mut u256 = [0u8; 32];
+        u256[0] = 0x80;
+        for (field, value) in [
+            (2, FieldValue::from(u64::MAX as u128 + 1)),
+            (3, FieldValue::from_unsigned_be_bytes(&u256)),
+            (4, FieldValue::from(u64::MAX as u128 + 4)),
+        ] {
+            assert!(matches!(value, FieldValue::BigUint(_)));
             assert_eq!(
                 fields.scalar_values(&FieldPath::Id(field)),
-                Some([FieldValue::U64(u64::MAX)].as_slice()),
+                Some([value].as_slice()),
                 "field {field}",
             );
         }
@@ -1007,6 +1255,113 @@ mod tests {
         assert_eq!(schema.stats().generic_nodes, 0);
     }
 
+    fn schema_with_polymorphic_shape() -> Vec<u8> {
+        let mut out = vec![1, 14];
+        write_u32(&mut out, 0);
+        write_u32(&mut out, 1);
+        write_string(&mut out, "shape");
+        write_u32(&mut out, 5);
+        write_u32(&mut out, 105);
+        out.push(16);
+        out.push(22);
+        write_u32(&mut out, 3);
+        // Circle, variant 0: radius.
+        out.push(14);
+        write_u32(&mut out, 1);
+        out.push(0);
+        write_u32(&mut out, 1);
+        write_string(&mut out, "radius");
+        write_u32(&mut out, 6);
+        write_u32(&mut out, 106);
+        out.push(3);
+        // Rect, variant 1: width.
+        out.push(14);
+        write_u32(&mut out, 1);
+        out.push(1);
+        write_u32(&mut out, 1);
+        write_string(&mut out, "width");
+        write_u32(&mut out, 7);
+        write_u32(&mut out, 107);
+        out.push(3);
+        // Square extends Rect with variant 2: width and label.
+        out.push(14);
+        write_u32(&mut out, 2);
+        out.extend_from_slice(&[1, 2]);
+        write_u32(&mut out, 2);
+        write_string(&mut out, "width");
+        write_u32(&mut out, 7);
+        write_u32(&mut out, 107);
+        out.push(3);
+        write_string(&mut out, "label");
+        write_u32(&mut out, 8);
+        write_u32(&mut out, 108);
+        out.push(12);
+        out
+    }
+
+    #[test]
+    fn extracts_polymorphic_fields_by_variant_prefix() {
+        let schema = decode_native_schema_ir(&schema_with_polymorphic_shape()).unwrap();
+        assert_eq!(schema.stats().generic_nodes, 0);
+
+        let mut encoded = Vec::new();
+        write_u32(&mut encoded, 3);
+        encoded.push(0);
+        write_u32(&mut encoded, 4);
+        encoded.push(1);
+        write_u32(&mut encoded, 9);
+        encoded.extend_from_slice(&[1, 2]);
+        write_u32(&mut encoded, 5);
+        write_string(&mut encoded, "five");
+        let fields = extract_encoded_document_fields(&schema, &encoded, 0).unwrap();
+
+        assert_eq!(
+            fields.scalar_values(&FieldPath::Id(6)),
+            Some([FieldValue::U64(4)].as_slice())
+        );
+        assert_eq!(
+            fields.scalar_values(&FieldPath::Id(7)),
+            Some([FieldValue::U64(9), FieldValue::U64(5)].as_slice())
+        );
+        assert_eq!(
+            fields.scalar_values(&FieldPath::Id(8)),
+            Some([FieldValue::from("five")].as_slice())
+        );
+
+        let mut index = NativeQueryIndex::new();
+        index.put("doc", fields);
+        assert_eq!(
+            index.search(
+                &Query::Range {
+                    field: FieldPath::Id(7),
+                    compare: Compare::Greater,
+                    value: FieldValue::U64(8),
+                },
+                &[],
+                None,
+            ),
+            vec!["doc".to_string()]
+        );
+
+        let mut unknown = Vec::new();
+        write_u32(&mut unknown, 1);
+        unknown.push(3);
+        assert_eq!(
+            extract_encoded_document_fields(&schema, &unknown, 0).unwrap_err(),
+            SchemaError::UnknownVariant
+        );
+
+        let mut unprefixed = vec![1, 22];
+        write_u32(&mut unprefixed, 1);
+        unprefixed.push(14);
+        write_u32(&mut unprefixed, 0);
+        write_u32(&mut unprefixed, 0);
+        assert_eq!(
+            decode_native_schema_ir(&unprefixed).unwrap_err(),
+            SchemaError::InvalidVariantTable
+        );
+    }
+
     #[test]
     fn extracts_fields_across_encoded_parts() {
         let schema = decode_native_schema_ir(&schema_with_id_score_and_bytes()).unwrap();
@@ -0,0 +1,359 @@
+use crate::planner::{DocId, FieldValue};
+use im::HashMap;
+use roaring::RoaringBitmap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+/// How field text and query text are turned into terms. Documents and queries
+/// always go through the same analyzer, so any combination is self-consistent.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextAnalyzer {
+    pub lowercase: bool,
+    pub stemming: bool,
+    pub stop_words: HashSet<String>,
+}
+
+impl TextAnalyzer {
+    /// Lowercasing only; every word is a term.
+    pub fn simple() -> Self {
+        Self {
+            lowercase: true,
+            ..Self::default()
+        }
+    }
+
+    /// Lowercasing, light English suffix stemming and the common English stop
+    /// word list.
+    pub fn english() -> Self {
+        Self {
+            lowercase: true,
+            stemming: true,
+            stop_words: ENGLISH_STOP_WORDS
+                .iter()
+                .map(|word| word.to_string())
+                .collect(),
+        }
+    }
+
+    /// Split `text` into terms. Words are maximal runs of Unicode alphanumeric
+    /// characters, except that ideographic and kana characters (which are not
+    /// space-delimited) each form their own term.
+    pub fn tokens(&self, text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        for character in text.chars() {
+            if is_ideographic(character) {
+                self.push_token(&mut tokens, std::mem::take(&mut word));
+                self.push_token(&mut tokens, character.to_string());
+            } else if character.is_alphanumeric() {
+                word.push(character);
+            } else {
+                self.push_token(&mut tokens, std::mem::take(&mut word));
+            }
+        }
+        self.push_token(&mut tokens, word);
+        tokens
+    }
+
+    fn push_token(&self, tokens: &mut Vec<String>, word: String) {
+        if word.is_empty() {
+            return;
+        }
+        let word = if self.lowercase {
+            word.to_lowercase()
+        } else {
+            word
+        };
+        if self.stop_words.contains(&word) {
+            return;
+        }
+        tokens.push(if self.stemming { stem(&word) } else { word });
+    }
+
+    /// Distinct terms of `text`, in first-occurrence order.
+    pub fn terms(&self, text: &str) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.tokens(text)
+            .into_iter()
+            .filter(|token| seen.insert(token.clone()))
+            .collect()
+    }
+}
+
+fn is_ideographic(character: char) -> bool {
+    matches!(
+        character as u32,
+        0x3040..=0x30ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xf900..=0xfaff | 0x20000..=0x2fa1f
+    )
+}
+
+/// Light English stemmer: plural (Harman "S" stemmer) followed by a few
+/// common inflectional suffixes, keeping at least three characters of stem.
+/// It only has to conflate obvious variants consistently, not produce words.
+fn stem(word: &str) -> String {
+    if !word.is_ascii() || word.len() <= 3 {
+        return word.to_string();
+    }
+    let mut stem = word.to_string();
+    if stem.ends_with("ies") && !stem.ends_with("eies") && !stem.ends_with("aies") {
+        stem.truncate(stem.len() - 3);
+        stem.push('y');
+    } else if stem.ends_with('s')
+        && !["aes", "ees", "oes", "us", "ss"]
+            .iter()
+            .any(|suffix| stem.ends_with(suffix))
+    {
+        stem.truncate(stem.len() - 1);
+    }
+    for suffix in ["ing", "ed", "ly"] {
+        if stem.len() >= suffix.len() + 3 && stem.ends_with(suffix) {
+            stem.truncate(stem.len() - suffix.len());
+            if suffix != "ly" {
+                undouble_final_consonant(&mut stem);
+            }
+            break;
+        }
+    }
+    stem
+}
+
+/// "running" -> "runn" -> "run", but "falling" -> "fall".
+fn undouble_final_consonant(stem: &mut String) {
+    let bytes = stem.as_bytes();
+    let [.., previous, last] = bytes else {
+        return;
+    };
+    if previous == last && !matches!(last, b'a' | b'e' | b'i' | b'o' | b'u' | b'l' | b's' | b'z') {
+        stem.pop();
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct TextPosting {
+    docs: RoaringBitmap,
+    frequencies: HashMap<DocId, u32>,
+}
+
+/// Inverted index over the string values of one field, with the collection
+/// statistics BM25 needs (document lengths in terms and their total).
+#[derive(Clone, Debug)]
+pub struct TextIndex {
+    analyzer: TextAnalyzer,
+    postings: HashMap<String, Arc<TextPosting>>,
+    doc_lengths: HashMap<DocId, u32>,
+    total_length: u64,
+}
+
+impl TextIndex {
+    pub fn new(analyzer: TextAnalyzer) -> Self {
+        Self {
+            analyzer,
+            postings: HashMap::new(),
+            doc_lengths: HashMap::new(),
+            total_length: 0,
+        }
+    }
+
+    pub fn analyzer(&self) -> &TextAnalyzer {
+        &self.analyzer
+    }
+
+    pub fn len(&self) -> usize {
+        self.doc_lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_lengths.is_empty()
+    }
+
+    pub fn insert(&mut self, doc_id: DocId, values: &[FieldValue]) {
+        let tokens = self.value_tokens(values);
+        if tokens.is_empty() {
+            return;
+        }
+        self.doc_lengths.insert(doc_id, tokens.len() as u32);
+        self.total_length += tokens.len() as u64;
+        for token in tokens {
+            let posting = Arc::make_mut(self.postings.entry(token).or_default());
+            posting.docs.insert(doc_id);
+            *posting.frequencies.entry(doc_id).or_default() += 1;
+        }
+    }
+
+    pub fn remove(&mut self, doc_id: DocId, values: &[FieldValue]) {
+        let Some(length) = self.doc_lengths.remove(&doc_id) else {
+            return;
+        };
+        self.total_length = self.total_length.saturating_sub(length as u64);
+        for token in self.value_tokens(values) {
+            let Some(posting) = self.postings.get_mut(&token) else {
+                continue;
+            };
+            let posting = Arc::make_mut(posting);
+            posting.docs.remove(doc_id);
+            posting.frequencies.remove(&doc_id);
+            if posting.docs.is_empty() {
+                self.postings.remove(&token);
+            }
+        }
+    }
+
+    /// Docs containing any (`match_all == false`) or every term.
+    pub fn candidates(&self, terms: &[String], match_all: bool) -> RoaringBitmap {
+        if terms.is_empty() {
+            return RoaringBitmap::new();
+        }
+        let mut postings = terms.iter().map(|term| {
+            self.postings
+                .get(term)
+                .map(|posting| &posting.docs)
+                .cloned()
+                .unwrap_or_default()
+        });
+        let first = postings.next().unwrap_or_default();
+        postings.fold(first, |result, docs| {
+            if match_all {
+                result & docs
+            } else {
+                result | docs
+            }
+        })
+    }
+
+    pub fn estimated_candidate_len(&self, terms: &[String], match_all: bool) -> u64 {
+        let lens = terms.iter().map(|term| {
+            self.postings
+                .get(term)
+                .map(|posting| posting.docs.len())
+                .unwrap_or(0)
+        });
+        if match_all {
+            lens.min().unwrap_or(0)
+        } else {
+            lens.fold(0_u64, u64::saturating_add)
+        }
+    }
+
+    /// Okapi BM25 relevance of `doc_id` for the (distinct) query `terms`.
+    pub fn score(&self, doc_id: DocId, terms: &[String]) -> f32 {
+        let Some(&length) = self.doc_lengths.get(&doc_id) else {
+            return 0.0;
+        };
+        let doc_count = self.doc_lengths.len() as f32;
+        let average_length = self.total_length as f32 / doc_count;
+        let length_norm = 1.0 - BM25_B + BM25_B * length as f32 / average_length;
+        terms
+            .iter()
+            .filter_map(|term| {
+                let posting = self.postings.get(term)?;
+                let frequency = *posting.frequencies.get(&doc_id)? as f32;
+                let containing = posting.docs.len() as f32;
+                let idf = (1.0 + (doc_count - containing + 0.5) / (containing + 0.5)).ln();
+                Some(idf * frequency * (BM25_K1 + 1.0) / (frequency + BM25_K1 * length_norm))
+            })
+            .sum()
+    }
+
+    fn value_tokens(&self, values: &[FieldValue]) -> Vec<String> {
+        values
+            .iter()
+            .filter_map(|value| match value {
+                FieldValue::String(value) => Some(self.analyzer.tokens(value)),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TextAnalyzer, TextIndex};
+    use crate::planner::FieldValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn analyzers_segment_fold_and_stem() {
+        assert_eq!(
+            TextAnalyzer::simple().tokens("Hello, Wörld! 東京 rocks"),
+            vec!["hello", "wörld", "東", "京", "rocks"]
+        );
+        assert_eq!(
+            TextAnalyzer::english().tokens("The ponies were running quickly to the houses"),
+            vec!["pony", "were", "run", "quick", "house"]
+        );
+        assert_eq!(
+            TextAnalyzer::english().terms("cats and the cat"),
+            vec!["cat"]
+        );
+    }
+
+    #[test]
+    fn bm25_prefers_rarer_terms_and_shorter_documents() {
+        let mut index = TextIndex::new(TextAnalyzer::simple());
+        index.insert(0, &[FieldValue::from("rust wasm rust")]);
+        index.insert(1, &[FieldValue::from("rust")]);
+        index.insert(
+            2,
+            &[FieldValue::from("typescript wasm with a much longer body")],
+        );
+        index.insert(
+            3,
+            &[FieldValue::from("rust appears once in this longer body")],
+        );
+
+        let terms = TextAnalyzer::simple().terms("rust");
+        assert_eq!(
+            index.candidates(&terms, false).iter().collect::<Vec<_>>(),
+            vec![0, 1, 3]
+        );
+        assert!(index.score(1, &terms) > index.score(3, &terms));
+        assert!(index.score(0, &terms) > index.score(3, &terms));
+
+        let terms = TextAnalyzer::simple().terms("typescript wasm");
+        assert!(index.score(2, &terms) > index.score(0, &terms));
+        assert_eq!(
+            index.candidates(&terms, true).iter().collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        index.remove(
+            2,
+            &[FieldValue::from("typescript wasm with a much longer body")],
+        );
+        assert!(index.candidates(&terms, false).iter().eq([0]));
+        assert_eq!(index.len(), 3);
+    }
+
+    #[test]
+    fn writes_after_a_clone_copy_only_touched_postings() {
+        let mut index = TextIndex::new(TextAnalyzer::simple());
+        index.insert(0, &[FieldValue::from("rust wasm")]);
+        index.insert(1, &[FieldValue::from("typescript")]);
+        let snapshot = index.clone();
+
+        index.insert(2, &[FieldValue::from("rust")]);
+        assert!(Arc::ptr_eq(
+            &index.postings["typescript"],
+            &snapshot.postings["typescript"]
+        ));
+        assert!(!Arc::ptr_eq(
+            &index.postings["rust"],
+            &snapshot.postings["rust"]
+        ));
+
+        let terms = TextAnalyzer::simple().terms("rust");
+        assert!(snapshot.candidates(&terms, false).iter().eq([0]));
+        assert_eq!((snapshot.len(), index.len()), (2, 3));
+        assert_eq!(snapshot.score(2, &terms), 0.0);
+    }
+}
@@ -13,6 +13,7 @@ export enum Compare {
 	GreaterOrEqual = 2,
 	Less = 3,
 	LessOrEqual = 4,
+	NotEqual = 5,
 }
 export const compare = (
 	test: bigint | number,
@@ -31,6 +32,9 @@ export const compare = (
 			return test < value;
 		case Compare.LessOrEqual:
 			return test <= value;
+		case Compare.NotEqual:
+			// eslint-disable-next-line eqeqeq
+			return test != value;
 		default:
 			// eslint-disable-next-line no-console
 			console.warn("Unexpected compare");
@@ -186,8 +190,39 @@ export enum StringMatchMethod {
 	"exact" = 0,
 	"prefix" = 1,
 	"contains" = 2,
+	"suffix" = 3,
+	"glob" = 4,
 }
 
+/**
+ * `*` matches any run of characters and `?` any single character
+ */
+export const matchesGlob = (value: string, pattern: string): boolean => {
+	const chars = [...value];
+	const glob = [...pattern];
+	let valueAt = 0;
+	let patternAt = 0;
+	// last `*` seen and the value position it currently stops at
+	let star: [number, number] | undefined;
+	while (valueAt < chars.length) {
+		const next = glob[patternAt];
+		if (next === "*") {
+			star = [patternAt, valueAt];
+			patternAt++;
+		} else if (next === "?" || (next != null && next === chars[valueAt])) {
+			valueAt++;
+			patternAt++;
+		} else if (star) {
+			star = [star[0], star[1] + 1];
+			patternAt = star[0] + 1;
+			valueAt = star[1];
+		} else {
+			return false;
+		}
+	}
+	return glob.slice(patternAt).every((char) => char === "*");
+};
+
 @variant(2)
 export class StringMatch extends StateFieldQuery {
 	@field({ type: "string" })
@@ -223,7 +258,7 @@ export class IntegerCompare extends StateFieldQuery {
 	constructor(props: {
 		key: string[] | string;
 		value: bigint | number | IntegerValue;
-		compare: "eq" | "gt" | "gte" | "lt" | "lte" | Compare;
+		compare: "eq" | "gt" | "gte" | "lt" | "lte" | "neq" | Compare;
 	}) {
 		super(props);
 		if (props.value instanceof IntegerValue) {
@@ -247,6 +282,8 @@ export class IntegerCompare extends StateFieldQuery {
 				this.compare = Compare.Less;
 			} else if (props.compare === "lte") {
 				this.compare = Compare.LessOrEqual;
+			} else if (props.compare === "neq") {
+				this.compare = Compare.NotEqual;
 			} else {
 				throw new Error("Invalid compare string");
 			}
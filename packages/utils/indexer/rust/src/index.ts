@@ -6,6 +6,8 @@ import {
 	StringType,
 	VecKind,
 	deserialize,
+	getDependencies,
+	getOffset,
 	getSchemasBottomUp,
 	serialize,
 } from "@dao-xyz/borsh";
@@ -29,8 +31,30 @@ export type RustIndexerOptions = {
 	byteElementIndexLimit?: number;
 };
 
+export type TextIndexOptions = {
+	/**
+	 * "english" lowercases, drops stop words and stems; "simple" only
+	 * lowercases and splits on non-alphanumerics. Defaults to "english".
+	 */
+	analyzer?: "english" | "simple";
+};
+
+export type TextSearchOptions = {
+	/** String field to search; see `configureTextIndex`. */
+	key: string | string[];
+	text: string;
+	/** Whether a match needs any (default) or all of the analyzed terms. */
+	operator?: "any" | "all";
+	/** Filter the matches must also satisfy. */
+	query?: types.QueryLike;
+	limit?: number;
+};
+
+export type TextSearchResult<T> = types.IndexedValue<T> & { score: number };
+
 type NativeRustIndex<T extends Record<string, any>> = {
 	configure_schema_ir: (schemaIr: Uint8Array) => [number, number, number];
+	diff_schema_ir?: (schemaIr: Uint8Array) => NativeSchemaIrDiff;
 	put: (key: string, id: types.IdKey, value: T, fields: Uint8Array) => void;
 	put_encoded: (
 		key: string,
@@ -213,16 +237,45 @@ type NativeRustIndex<T extends Record<string, any>> = {
 		offset: number,
 		limit: number,
 	) => Array<[types.IdKey, T]>;
+	query_page_within?: (
+		query: Uint8Array,
+		sort: Uint8Array,
+		offset: number,
+		limit: number,
+		maxCandidates?: number,
+		maxMaterialized?: number,
+		deadline?: number,
+	) => Array<[types.IdKey, T]>;
 	query_exact_string_first_batch?: (
 		field: number,
 		values: string[],
 	) => Array<[types.IdKey, T] | undefined>;
 	count: (query: Uint8Array) => number;
+	count_within?: (
+		query: Uint8Array,
+		maxCandidates?: number,
+		deadline?: number,
+	) => number;
 	sum: (query: Uint8Array, field: number) => [NativeSumKind, string];
+	sum_within?: (
+		query: Uint8Array,
+		field: number,
+		maxCandidates?: number,
+		maxMaterialized?: number,
+		deadline?: number,
+	) => [NativeSumKind, string];
 	delete_matching: (query: Uint8Array) => Array<[types.IdKey, T]>;
 	delete_keys: (keys: string[]) => Array<[types.IdKey, T]>;
 	delete_keys_void?: (keys: string[]) => void;
 	delete_keys_count?: (keys: string[]) => number;
+	configure_text_index?: (field: number, english: boolean) => void;
+	query_text?: (
+		query: Uint8Array,
+		field: number,
+		text: string,
+		matchAll: boolean,
+		limit: number,
+	) => Array<[[types.IdKey, T], number]>;
 };
 
 type NativeBackboneDocumentIndexTarget = {
@@ -304,28 +357,47 @@ type NativeValue =
 	| { type: "bool"; value: boolean }
 	| { type: "i64"; value: number | bigint }
 	| { type: "u64"; value: number | bigint }
+	| { type: "biguint"; value: bigint }
 	| { type: "string"; value: string }
 	| { type: "bytes"; value: Uint8Array };
 type NativeIntegerValue = Extract<
 	NativeValue,
-	{ type: "i64" } | { type: "u64" }
+	{ type: "i64" } | { type: "u64" } | { type: "biguint" }
 >;
 
 type NativeFieldCursor = {
 	field: string;
 	fieldId: number;
 	arrayFieldId: number;
+	repeated: boolean;
 };
 
+type NativeStringMatchMethod =
+	| "exact"
+	| "prefix"
+	| "contains"
+	| "suffix"
+	| "glob";
+
+type NativeCompare = "eq" | "gt" | "gte" | "lt" | "lte" | "neq";
+
 type NativeQuerySpec =
 	| { op: "all" }
 	| { op: "exact"; field: number; value: NativeValue }
 	| {
 			op: "range";
 			field: number;
-			compare: "eq" | "gt" | "gte" | "lt" | "lte";
+			compare: NativeCompare;
 			value: NativeValue;
 	  }
+	| { op: "in"; field: number; values: NativeValue[] }
+	| {
+			op: "between";
+			field: number;
+			low: NativeValue;
+			high: NativeValue;
+			inclusive: boolean;
+	  }
 	| { op: "and"; queries: NativeQuerySpec[] }
 	| { op: "or"; queries: NativeQuerySpec[] }
 	| { op: "not"; query: NativeQuerySpec }
@@ -333,10 +405,11 @@ type NativeQuerySpec =
 			op: "string";
 			field: number;
 			value: string;
-			method: "exact" | "prefix" | "contains";
+			method: NativeStringMatchMethod;
 			caseInsensitive: boolean;
 	  }
-	| { op: "is_null"; field: number };
+	| { op: "is_null"; field: number }
+	| { op: "text"; field: number; value: string; operator: "any" | "all" };
 
 type NativeQueryCompileResult = {
 	spec: NativeQuerySpec;
@@ -348,11 +421,12 @@ type NativeSortField = {
 	direction: "asc" | "desc";
 };
 
-type NativeSumKind = "none" | "i64" | "u64";
+type NativeSumKind = "none" | "i64" | "u64" | "f64";
 type NativeFieldValueRow =
 	| ["bool", boolean]
 	| ["i64", string]
 	| ["u64", string]
+	| ["f64", number]
 	| ["string", string]
 	| ["bytes", Uint8Array];
 
@@ -364,6 +438,9 @@ type NativeCandidatePage = {
 };
 
 const BRIDGE_VERSION = 1;
+// Query payloads carry their own version: the Rust decoder accepts every
+// version up to its own, and rejects variants newer than the payload claims.
+const QUERY_BRIDGE_VERSION = 4;
 const DEFAULT_JOURNAL_COMPACT_AFTER_OPERATIONS = 64 * 1024;
 const DEFAULT_BYTE_ELEMENT_INDEX_LIMIT = 0;
 const MAX_NATIVE_BYTE_ELEMENT_INDEX_LIMIT = 0xffffffff;
@@ -375,6 +452,8 @@ const enum NativeValueTag {
 	U64 = 2,
 	String = 3,
 	Bytes = 4,
+	F64 = 5,
+	BigUint = 6,
 }
 
 const enum NativeQueryTag {
@@ -386,6 +465,9 @@ const enum NativeQueryTag {
 	Not = 5,
 	StringMatch = 6,
 	IsNull = 7,
+	Text = 8,
+	In = 9,
+	Between = 10,
 }
 
 const enum NativeCompareTag {
@@ -394,12 +476,20 @@ const enum NativeCompareTag {
 	GreaterOrEqual = 2,
 	Less = 3,
 	LessOrEqual = 4,
+	NotEqual = 5,
 }
 
 const enum NativeStringMatchMethodTag {
 	Exact = 0,
 	Prefix = 1,
 	Contains = 2,
+	Suffix = 3,
+	Glob = 4,
+}
+
+const enum NativeTextOperatorTag {
+	Any = 0,
+	All = 1,
 }
 
 const enum NativeSortDirectionTag {
@@ -428,6 +518,9 @@ const enum NativeSchemaNodeTag {
 	FixedArray = 17,
 	Generic = 18,
 	PublicSignKey = 19,
+	F32 = 20,
+	F64 = 21,
+	Polymorphic = 22,
 }
 
 const textEncoder = new TextEncoder();
@@ -535,6 +628,10 @@ const nativeArrayElementFieldKeyFromFieldKey = (field: string): string =>
 
 type NativeFieldDictionary = {
 	id: (field: string) => number;
+	// The compiled schema writer reports every integer field it writes, so
+	// queries can tell fields holding one value per document from repeated ones.
+	markInteger: (field: number, repeated: boolean) => void;
+	isSingleInteger: (field: number) => boolean;
 };
 
 const nativeFieldHash = (field: string): number => {
@@ -548,6 +645,8 @@ const nativeFieldHash = (field: string): number => {
 const createNativeFieldDictionary = (): NativeFieldDictionary => {
 	const ids = new Map<string, number>();
 	const fields = new Map<number, string>();
+	const singleIntegers = new Set<number>();
+	const repeatedIntegers = new Set<number>();
 	return {
 		id: (field: string) => {
 			let id = ids.get(field);
@@ -564,16 +663,23 @@ const createNativeFieldDictionary = (): NativeFieldDictionary => {
 			}
 			return id;
 		},
+		markInteger: (field: number, repeated: boolean) => {
+			(repeated ? repeatedIntegers : singleIntegers).add(field);
+		},
+		isSingleInteger: (field: number) =>
+			singleIntegers.has(field) && !repeatedIntegers.has(field),
 	};
 };
 
 const nativeFieldCursor = (
 	dictionary: NativeFieldDictionary,
 	field: string,
+	repeated: boolean,
 ): NativeFieldCursor => ({
 	field,
 	fieldId: dictionary.id(field),
 	arrayFieldId: dictionary.id(nativeArrayElementFieldKeyFromFieldKey(field)),
+	repeated,
 });
 
 const appendNativeFieldCursor = (
@@ -581,7 +687,20 @@ const appendNativeFieldCursor = (
 	parent: NativeFieldCursor | undefined,
 	key: string,
 ): NativeFieldCursor =>
-	nativeFieldCursor(dictionary, appendNativeFieldKey(parent?.field, key));
+	nativeFieldCursor(
+		dictionary,
+		appendNativeFieldKey(parent?.field, key),
+		parent?.repeated ?? false,
+	);
+
+// Big-endian without leading zeros, matching `FieldValue::BigUint`.
+const bigUintBytes = (value: bigint): Uint8Array => {
+	const bytes: number[] = [];
+	for (let rest = value; rest > 0n; rest >>= 8n) {
+		bytes.push(Number(rest & 0xffn));
+	}
+	return Uint8Array.from(bytes.reverse());
+};
 
 const nativeIntegerValue = (
 	value: number | bigint,
@@ -593,6 +712,9 @@ const nativeIntegerValue = (
 		if (value >= -9223372036854775808n && value <= 9223372036854775807n) {
 			return { type: "i64", value };
 		}
+		if (value > 0n) {
+			return { type: "biguint", value };
+		}
 		return undefined;
 	}
 	if (!Number.isSafeInteger(value)) {
@@ -618,9 +740,7 @@ const markNativeFieldSeen = (
 	return true;
 };
 
-const compareToNative = (
-	compare: types.Compare,
-): "eq" | "gt" | "gte" | "lt" | "lte" => {
+const compareToNative = (compare: types.Compare): NativeCompare => {
 	switch (compare) {
 		case types.Compare.Equal:
 			return "eq";
@@ -632,12 +752,14 @@ const compareToNative = (
 			return "lt";
 		case types.Compare.LessOrEqual:
 			return "lte";
+		case types.Compare.NotEqual:
+			return "neq";
 		default:
 			throw new Error("Unexpected compare");
 	}
 };
 
-const nativeCompareTag = (compare: "eq" | "gt" | "gte" | "lt" | "lte") => {
+const nativeCompareTag = (compare: NativeCompare) => {
 	switch (compare) {
 		case "eq":
 			return NativeCompareTag.Equal;
@@ -649,12 +771,12 @@ const nativeCompareTag = (compare: "eq" | "gt" | "gte" | "lt" | "lte") => {
 			return NativeCompareTag.Less;
 		case "lte":
 			return NativeCompareTag.LessOrEqual;
+		case "neq":
+			return NativeCompareTag.NotEqual;
 	}
 };
 
-const nativeStringMatchMethodTag = (
-	method: "exact" | "prefix" | "contains",
-) => {
+const nativeStringMatchMethodTag = (method: NativeStringMatchMethod) => {
 	switch (method) {
 		case "exact":
 			return NativeStringMatchMethodTag.Exact;
@@ -662,6 +784,29 @@ const nativeStringMatchMethodTag = (
 			return NativeStringMatchMethodTag.Prefix;
 		case "contains":
 			return NativeStringMatchMethodTag.Contains;
+		case "suffix":
+			return NativeStringMatchMethodTag.Suffix;
+		case "glob":
+			return NativeStringMatchMethodTag.Glob;
+	}
+};
+
+const stringMatchMethodToNative = (
+	method: types.StringMatchMethod,
+): NativeStringMatchMethod => {
+	switch (method) {
+		case types.StringMatchMethod.exact:
+			return "exact";
+		case types.StringMatchMethod.prefix:
+			return "prefix";
+		case types.StringMatchMethod.contains:
+			return "contains";
+		case types.StringMatchMethod.suffix:
+			return "suffix";
+		case types.StringMatchMethod.glob:
+			return "glob";
+		default:
+			throw new Error("Unexpected string match method");
 	}
 };
 
@@ -688,6 +833,15 @@ const writeNativeValue = (writer: BinaryWriter, value: NativeValue): void => {
 			writer.u8(NativeValueTag.U64);
 			writer.u64(BigInt(value.value));
 			return;
+		case "biguint": {
+			const bytes = bigUintBytes(value.value);
+			writer.u8(NativeValueTag.BigUint);
+			writer.u32(bytes.byteLength);
+			for (const byte of bytes) {
+				writer.u8(byte);
+			}
+			return;
+		}
 		case "string":
 			writer.u8(NativeValueTag.String);
 			writer.string(value.value);
@@ -721,6 +875,21 @@ const writeNativeQuerySpec = (
 			writer.u8(nativeCompareTag(query.compare));
 			writeNativeValue(writer, query.value);
 			return;
+		case "in":
+			writer.u8(NativeQueryTag.In);
+			writer.u32(query.field);
+			writer.u32(query.values.length);
+			for (const value of query.values) {
+				writeNativeValue(writer, value);
+			}
+			return;
+		case "between":
+			writer.u8(NativeQueryTag.Between);
+			writer.u32(query.field);
+			writeNativeValue(writer, query.low);
+			writeNativeValue(writer, query.high);
+			writer.bool(query.inclusive);
+			return;
 		case "and":
 			writer.u8(NativeQueryTag.And);
 			writer.u32(query.queries.length);
@@ -750,12 +919,22 @@ const writeNativeQuerySpec = (
 			writer.u8(NativeQueryTag.IsNull);
 			writer.u32(query.field);
 			return;
+		case "text":
+			writer.u8(NativeQueryTag.Text);
+			writer.u32(query.field);
+			writer.string(query.value);
+			writer.u8(
+				query.operator === "all"
+					? NativeTextOperatorTag.All
+					: NativeTextOperatorTag.Any,
+			);
+			return;
 	}
 };
 
 const encodeNativeQuerySpec = (query: NativeQuerySpec): Uint8Array => {
 	const writer = new BinaryWriter();
-	writer.u8(BRIDGE_VERSION);
+	writer.u8(QUERY_BRIDGE_VERSION);
 	writeNativeQuerySpec(writer, query);
 	return writer.finalize();
 };
@@ -792,6 +971,8 @@ const nativeFieldValueRowToJsValue = (
 		case "i64":
 		case "u64":
 			return typeof value === "string" ? BigInt(value) : undefined;
+		case "f64":
+			return typeof value === "number" ? value : undefined;
 		case "string":
 			return typeof value === "string" ? value : undefined;
 		case "bytes":
@@ -866,6 +1047,23 @@ class NativeFieldWriter {
 		this.offset = writeUint64(this.view, this.offset, value);
 	}
 
+	writeBigUint(scope: number, fieldId: number, value: bigint): void {
+		const bytes = bigUintBytes(value);
+		this.writeHeader(
+			scope,
+			fieldId,
+			NativeValueTag.BigUint,
+			4 + bytes.byteLength,
+		);
+		this.offset = writeBytes(this.output, this.view, this.offset, bytes);
+	}
+
+	writeF64(scope: number, fieldId: number, value: number): void {
+		this.writeHeader(scope, fieldId, NativeValueTag.F64, 8);
+		this.view.setFloat64(this.offset, value, true);
+		this.offset += 8;
+	}
+
 	writeString(scope: number, fieldId: number, value: string): void {
 		this.writeStringBytes(scope, fieldId, textEncoder.encode(value));
 	}
@@ -992,6 +1190,17 @@ type NativeSchemaIrStats = {
 	nodeCount: number;
 	genericNodes: number;
 };
+
+type NativeSchemaIrDiff = {
+	added: number[];
+	removed: number[];
+	retyped: number[];
+	invalidated: Array<{
+		kind: "field" | "text" | "trigram" | "composite";
+		field: number | string;
+		prefix?: Array<number | string>;
+	}>;
+};
 type SharedLogCoordinateNativeFields = {
 	hash: string;
 	hashNumber: number | bigint;
@@ -1135,6 +1344,8 @@ const writeNativeIntegerFact = (
 	}
 	if (nativeValue.type === "u64") {
 		writer.writeU64(scope, fieldId, nativeValue.value);
+	} else if (nativeValue.type === "biguint") {
+		writer.writeBigUint(scope, fieldId, nativeValue.value);
 	} else {
 		writer.writeI64(scope, fieldId, nativeValue.value);
 	}
@@ -1292,7 +1503,7 @@ const integerFieldTypes = new Set([
 	"i64",
 ]);
 
-const nativeSchemaIntegerNodeTag = (
+const nativeSchemaNumberNodeTag = (
 	fieldType: string,
 ): NativeSchemaNodeTag | undefined => {
 	switch (fieldType) {
@@ -1318,6 +1529,10 @@ const nativeSchemaIntegerNodeTag = (
 			return NativeSchemaNodeTag.I32;
 		case "i64":
 			return NativeSchemaNodeTag.I64;
+		case "f32":
+			return NativeSchemaNodeTag.F32;
+		case "f64":
+			return NativeSchemaNodeTag.F64;
 		default:
 			return undefined;
 	}
@@ -1393,6 +1608,80 @@ const writeNativeSchemaObjectNode = (
 	}
 
 	active.add(ctor);
+	const variants = nativeSchemaVariants(ctor, schemas);
+	if (variants?.length === 0) {
+		writer.u8(NativeSchemaNodeTag.Generic);
+	} else if (variants) {
+		writer.u8(NativeSchemaNodeTag.Polymorphic);
+		writer.u32(variants.length);
+		for (const variantSchemas of variants) {
+			writeNativeSchemaObjectFields(
+				writer,
+				variantSchemas,
+				dictionary,
+				cursor,
+				active,
+			);
+		}
+	} else {
+		writeNativeSchemaObjectFields(writer, schemas, dictionary, cursor, active);
+	}
+	active.delete(ctor);
+};
+
+/**
+ * Schemas of every class a field typed `ctor` may deserialize to, when
+ * subclasses are registered for it. Borsh tells them apart by their variant
+ * prefixes, so classes without one (an abstract base) are left out. Empty
+ * when a subclass cannot be described, which keeps the whole field generic.
+ */
+const nativeSchemaVariants = (
+	ctor: Function,
+	schemas: ReturnType<typeof getSchemasBottomUp>,
+): ReturnType<typeof getSchemasBottomUp>[] | undefined => {
+	const classes: Function[] = [];
+	const visit = (next: Function) => {
+		for (const dependency of getDependencies(next, getOffset(next)) ?? []) {
+			if (!classes.includes(dependency)) {
+				classes.push(dependency);
+				visit(dependency);
+			}
+		}
+	};
+	visit(ctor);
+	if (classes.length === 0) {
+		return undefined;
+	}
+
+	const variants: ReturnType<typeof getSchemasBottomUp>[] = [];
+	if (encodeNativeSchemaVariantPrefix(schemas).byteLength > 0) {
+		variants.push(schemas);
+	}
+	for (const next of classes) {
+		let nextSchemas: ReturnType<typeof getSchemasBottomUp>;
+		try {
+			nextSchemas = getSchemasBottomUp(next);
+		} catch {
+			return [];
+		}
+		if (
+			!nextSchemas?.length ||
+			encodeNativeSchemaVariantPrefix(nextSchemas).byteLength === 0
+		) {
+			return [];
+		}
+		variants.push(nextSchemas);
+	}
+	return variants;
+};
+
+const writeNativeSchemaObjectFields = (
+	writer: NativeSchemaIrWriter,
+	schemas: ReturnType<typeof getSchemasBottomUp>,
+	dictionary: NativeFieldDictionary,
+	cursor: NativeFieldCursor | undefined,
+	active: Set<Function>,
+): void => {
 	const fields = schemas.flatMap((nextSchema) => nextSchema.fields);
 	const variantPrefix = encodeNativeSchemaVariantPrefix(schemas);
 	writer.u8(NativeSchemaNodeTag.Object);
@@ -1406,7 +1695,6 @@ const writeNativeSchemaObjectNode = (
 		writer.u32(fieldCursor.arrayFieldId);
 		writeNativeSchemaNode(writer, field.type, dictionary, fieldCursor, active);
 	}
-	active.delete(ctor);
 };
 
 const writeNativeSchemaNode = (
@@ -1470,7 +1758,7 @@ const writeNativeSchemaNode = (
 			return;
 		}
 		writer.u8(
-			nativeSchemaIntegerNodeTag(fieldType) ?? NativeSchemaNodeTag.Generic,
+			nativeSchemaNumberNodeTag(fieldType) ?? NativeSchemaNodeTag.Generic,
 		);
 		return;
 	}
@@ -1504,7 +1792,7 @@ const compileNativeFieldEncoder = <T extends Record<string, any>>(
 			byPrefix = new Map();
 			objectWriterCache.set(ctor, byPrefix);
 		}
-		const prefix = cursor?.field ?? "";
+		const prefix = cursor ? `${cursor.field}${cursor.repeated ? "*" : ""}` : "";
 		if (byPrefix.has(prefix)) {
 			return byPrefix.get(prefix);
 		}
@@ -1613,7 +1901,7 @@ const compileFieldValueWriter = (
 		}
 		const writeElement = compileFieldValueWriter(
 			fieldType.elementType,
-			cursor,
+			{ ...cursor, repeated: true },
 			getObjectWriter,
 			dictionary,
 			byteElementIndexLimit,
@@ -1670,12 +1958,20 @@ const compileFieldValueWriter = (
 			});
 		}
 		if (integerFieldTypes.has(fieldType)) {
+			dictionary.markInteger(cursor.fieldId, cursor.repeated);
 			return nativeFieldValueWriter((value, writer, _state, scope) => {
 				if (typeof value === "number" || typeof value === "bigint") {
 					writeNativeIntegerFact(writer, scope, cursor.fieldId, value);
 				}
 			});
 		}
+		if (fieldType === "f32" || fieldType === "f64") {
+			return nativeFieldValueWriter((value, writer, _state, scope) => {
+				if (typeof value === "number") {
+					writer.writeF64(scope, cursor.fieldId, value);
+				}
+			});
+		}
 		return nativeFieldValueWriter((value, writer, _state, scope) =>
 			writeNativeScalarFact(writer, scope, cursor.fieldId, value),
 		);
@@ -1730,6 +2026,9 @@ const decodeNativeSum = ([kind, value]: [NativeSumKind, string]):
 	if (kind === "none") {
 		return 0;
 	}
+	if (kind === "f64") {
+		return Number(value);
+	}
 	const sum = BigInt(value);
 	if (
 		sum >= BigInt(Number.MIN_SAFE_INTEGER) &&
@@ -1766,13 +2065,74 @@ const compileNativeAnd = (
 		compiled.push(next.spec);
 	}
 
-	if (compiled.length === 0) {
+	const folded = foldNativeBetween(compiled, dictionary);
+	if (folded.length === 0) {
 		return { spec: { op: "all" }, exact: true };
 	}
-	if (compiled.length === 1) {
-		return { spec: compiled[0], exact: true };
+	if (folded.length === 1) {
+		return { spec: folded[0], exact: true };
+	}
+	return { spec: { op: "and", queries: folded }, exact: true };
+};
+
+// A lower and an upper bound on a single-valued integer field select one
+// interval, which the native planner answers with a single range probe.
+// Repeated fields keep both bounds: each may be met by a different element.
+const foldNativeBetween = (
+	queries: NativeQuerySpec[],
+	dictionary: NativeFieldDictionary,
+): NativeQuerySpec[] => {
+	const folded = [...queries];
+	for (let i = 0; i < folded.length; i++) {
+		const low = folded[i];
+		if (
+			low.op !== "range" ||
+			(low.compare !== "gt" && low.compare !== "gte") ||
+			!dictionary.isSingleInteger(low.field)
+		) {
+			continue;
+		}
+		const field = low.field;
+		const upper = low.compare === "gte" ? "lte" : "lt";
+		const j = folded.findIndex(
+			(high) =>
+				high.op === "range" && high.field === field && high.compare === upper,
+		);
+		if (j === -1) {
+			continue;
+		}
+		const high = folded[j] as Extract<NativeQuerySpec, { op: "range" }>;
+		folded[i] = {
+			op: "between",
+			field,
+			low: low.value,
+			high: high.value,
+			inclusive: low.compare === "gte",
+		};
+		folded.splice(j, 1);
+		if (j < i) {
+			i--;
+		}
+	}
+	return folded;
+};
+
+// Equalities on one field form a set lookup.
+const foldNativeIn = (
+	queries: NativeQuerySpec[],
+): NativeQuerySpec | undefined => {
+	const [first] = queries;
+	if (queries.length < 2 || first.op !== "exact") {
+		return;
+	}
+	const values: NativeValue[] = [];
+	for (const query of queries) {
+		if (query.op !== "exact" || query.field !== first.field) {
+			return;
+		}
+		values.push(query.value);
 	}
-	return { spec: { op: "and", queries: compiled }, exact: true };
+	return { op: "in", field: first.field, values };
 };
 
 const compileNativeQuery = (
@@ -1792,7 +2152,10 @@ const compileNativeQuery = (
 			}
 			compiled.push(next.spec);
 		}
-		return { spec: { op: "or", queries: compiled }, exact: true };
+		return {
+			spec: foldNativeIn(compiled) ?? { op: "or", queries: compiled },
+			exact: true,
+		};
 	}
 	if (query instanceof types.Not) {
 		const child = compileNativeQuery(query.not, dictionary, prefix);
@@ -1836,12 +2199,7 @@ const compileNativeQuery = (
 				op: "string",
 				field: nativeFieldId(dictionary, [...prefix, ...query.key]),
 				value: query.value,
-				method:
-					query.method === types.StringMatchMethod.exact
-						? "exact"
-						: query.method === types.StringMatchMethod.prefix
-							? "prefix"
-							: "contains",
+				method: stringMatchMethodToNative(query.method),
 				caseInsensitive: query.caseInsensitive,
 			},
 			exact: true,
@@ -3311,6 +3669,83 @@ export class RustIndex<T extends Record<string, any>, NestedType = any>
 		);
 	}
 
+	/**
+	 * Maintain a BM25 text index over the string field `key`, built from the
+	 * documents already indexed. The configuration is not persisted, so call
+	 * it again after every `init`.
+	 */
+	configureTextIndex(key: string | string[], options?: TextIndexOptions): void {
+		this.assertOpen();
+		const configure = this.getNative().configure_text_index;
+		if (typeof configure !== "function") {
+			throw new Error("Native text indexes are not supported by this build");
+		}
+		configure.call(
+			this.getNative(),
+			this.nativeTextFieldId(key),
+			(options?.analyzer ?? "english") === "english",
+		);
+	}
+
+	/** Documents matching `options.text`, ordered by BM25 relevance. */
+	async searchText(options: TextSearchOptions): Promise<TextSearchResult<T>[]> {
+		if (this.isClosing()) {
+			return [];
+		}
+		this.assertOpen();
+		const queryText = this.getNative().query_text;
+		if (typeof queryText !== "function") {
+			throw new Error("Native text search is not supported by this build");
+		}
+		const compiled = this.requireNativePlan(types.toQuery(options.query), {
+			allowAll: true,
+		});
+		const results = queryText.call(
+			this.getNative(),
+			encodeNativeQuerySpec(compiled.spec),
+			this.nativeTextFieldId(options.key),
+			options.text,
+			options.operator === "all",
+			options.limit ?? 0xffffffff,
+		);
+		return results.map(([[id, value], score]) => ({
+			id,
+			value: this.decodeNativeStoredValue(value),
+			score,
+		}));
+	}
+
+	/** Number of documents matching `options.text` and `options.query`. */
+	async countText(options: Omit<TextSearchOptions, "limit">): Promise<number> {
+		if (this.isClosing()) {
+			return 0;
+		}
+		this.assertOpen();
+		const compiled = this.requireNativePlan(types.toQuery(options.query), {
+			allowAll: true,
+		});
+		const text: NativeQuerySpec = {
+			op: "text",
+			field: this.nativeTextFieldId(options.key),
+			value: options.text,
+			operator: options.operator ?? "any",
+		};
+		return this.countNativePlan({
+			spec:
+				compiled.spec.op === "all"
+					? text
+					: { op: "and", queries: [compiled.spec, text] },
+			exact: true,
+		});
+	}
+
+	private nativeTextFieldId(key: string | string[]): number {
+		return nativeFieldId(
+			this.fieldDictionary,
+			Array.isArray(key) ? key : [key],
+		);
+	}
+
 	iterate<S extends types.Shape | undefined>(
 		query?: types.IterateOptions,
 		properties?: { shape?: S; reference?: boolean },
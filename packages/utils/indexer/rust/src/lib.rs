@@ -1,10 +1,17 @@
 use indexmap::IndexMap;
-use js_sys::{Array, Uint8Array};
+use js_sys::{Array, Date, Error, Object, Reflect, Uint8Array};
 use peerbit_indexer_core::codec::{
-    decode_query as decode_core_query, decode_sort as decode_core_sort,
+    decode_aggregation as decode_core_aggregation, decode_query as decode_core_query,
+    decode_search_cursor, decode_sort as decode_core_sort, encode_aggregation_result,
+    encode_search_cursor,
 };
+use peerbit_indexer_core::hnsw::VectorIndexConfig;
+use peerbit_indexer_core::migration::{diff_schema_ir, InvalidatedIndex};
 use peerbit_indexer_core::planner::{
-    DocumentFields, FieldPath, FieldValue, NativeQueryIndex, Query, SortField, SumResult,
+    BudgetExceeded, BudgetLimit, Deadline, DocumentFields, FieldPath, FieldValue, IndexSnapshot,
+    NativeQueryIndex, PlanAccess, PlanNode, Query, QueryBudget, QueryError, QueryPlan,
+    ScoredDocument, SearchPage, SortField, SortStrategy, SubscriptionDiff, SumResult, TextOperator,
+    TextSort, VectorMetric, VectorSearchMode, VectorSort,
 };
 use peerbit_indexer_core::schema::{
     decode_document_fields as decode_core_document_fields,
@@ -13,9 +20,10 @@ use peerbit_indexer_core::schema::{
     extract_encoded_document_fields_from_parts as extract_core_encoded_document_fields_from_parts,
     NativeSchemaIr as CoreNativeSchemaIr,
 };
+use peerbit_indexer_core::text::TextAnalyzer;
 use wasm_bindgen::prelude::*;
 
-pub use peerbit_indexer_core::{planner, storage};
+pub use peerbit_indexer_core::{planner, storage, text};
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod native_fs;
@@ -102,6 +110,19 @@ impl NativeIndexStore {
         entries
     }
 
+    fn scored_entries(&self, scored: &[ScoredDocument]) -> Array {
+        let entries = Array::new();
+        for result in scored {
+            if let Some(entry) = self.entries.get(&result.id) {
+                let scored_entry = Array::new();
+                scored_entry.push(&entry_to_js(entry));
+                scored_entry.push(&JsValue::from_f64(result.score as f64));
+                entries.push(&scored_entry);
+            }
+        }
+        entries
+    }
+
     fn delete_keys_void(&mut self, keys: &[String]) {
         for key in keys {
             self.entries.shift_remove(key);
@@ -185,11 +206,64 @@ impl NativeQueryPlanner {
         Ok(ids_to_js(ids))
     }
 
+    /// `query_page` bounded by a budget: candidates verified, documents
+    /// materialized and a `Date.now()` deadline, each optional. Throws an
+    /// error named `BudgetExceeded` carrying the work done so far instead of
+    /// running on.
+    #[allow(clippy::too_many_arguments)]
+    pub fn query_page_within(
+        &self,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        offset: usize,
+        limit: usize,
+        max_candidates: Option<f64>,
+        max_materialized: Option<f64>,
+        deadline: Option<f64>,
+    ) -> Result<Array, JsValue> {
+        let query = decode_query(&query_bytes)?;
+        let sort = decode_sort(&sort_bytes)?;
+        let budget = query_budget(max_candidates, max_materialized, deadline);
+        let ids = self
+            .index
+            .search_page_within(&query, &sort, offset, Some(limit), &budget)
+            .map_err(budget_error)?;
+        Ok(ids_to_js(ids))
+    }
+
+    /// Keyset page as `[ids, cursor | undefined, generation]`; pass the cursor
+    /// back to fetch the next page.
+    pub fn query_after(
+        &self,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        cursor_bytes: Option<Vec<u8>>,
+        limit: usize,
+    ) -> Result<Array, JsValue> {
+        let page = self.search_page_after(&query_bytes, &sort_bytes, cursor_bytes, limit)?;
+        Ok(page_to_js(ids_to_js(page.ids.clone()), &page))
+    }
+
     pub fn count(&self, query_bytes: Vec<u8>) -> Result<usize, JsValue> {
         let query = decode_query(&query_bytes)?;
         Ok(self.index.count(&query) as usize)
     }
 
+    pub fn count_within(
+        &self,
+        query_bytes: Vec<u8>,
+        max_candidates: Option<f64>,
+        deadline: Option<f64>,
+    ) -> Result<usize, JsValue> {
+        let query = decode_query(&query_bytes)?;
+        let budget = query_budget(max_candidates, None, deadline);
+        let count = self
+            .index
+            .count_within(&query, &budget)
+            .map_err(budget_error)?;
+        Ok(count as usize)
+    }
+
     pub fn sum(&self, query_bytes: Vec<u8>, field: u32) -> Result<Array, JsValue> {
         let query = decode_query(&query_bytes)?;
         let sum = self
@@ -199,11 +273,285 @@ impl NativeQueryPlanner {
         Ok(sum_to_js(sum))
     }
 
+    pub fn sum_within(
+        &self,
+        query_bytes: Vec<u8>,
+        field: u32,
+        max_candidates: Option<f64>,
+        max_materialized: Option<f64>,
+        deadline: Option<f64>,
+    ) -> Result<Array, JsValue> {
+        let query = decode_query(&query_bytes)?;
+        let budget = query_budget(max_candidates, max_materialized, deadline);
+        let sum = self
+            .index
+            .sum_within(&query, FieldPath::Id(field), &budget)
+            .map_err(|error| match error {
+                QueryError::BudgetExceeded(exceeded) => budget_error(exceeded),
+                QueryError::Failed(message) => js_error(message),
+            })?;
+        Ok(sum_to_js(sum))
+    }
+
+    pub fn explain(
+        &self,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        limit: Option<usize>,
+    ) -> Result<JsValue, JsValue> {
+        let query = decode_query(&query_bytes)?;
+        let sort = decode_sort(&sort_bytes)?;
+        plan_to_js(&self.index.explain(&query, &sort, limit))
+    }
+
+    pub fn aggregate(&self, request_bytes: Vec<u8>) -> Result<Vec<u8>, JsValue> {
+        let (query, aggregation) = decode_core_aggregation(&request_bytes).map_err(js_error)?;
+        let result = self
+            .index
+            .aggregate(&query, &aggregation)
+            .map_err(js_error)?;
+        Ok(encode_aggregation_result(result))
+    }
+
     pub fn delete_matching(&mut self, query_bytes: Vec<u8>) -> Result<Array, JsValue> {
         let query = decode_query(&query_bytes)?;
         let ids = self.index.delete_matching(&query);
         Ok(ids_to_js(ids))
     }
+
+    pub fn configure_text_index(&mut self, field: u32, english: bool) {
+        let analyzer = if english {
+            TextAnalyzer::english()
+        } else {
+            TextAnalyzer::simple()
+        };
+        self.index
+            .configure_text_index(FieldPath::Id(field), analyzer);
+    }
+
+    pub fn configure_trigram_index(&mut self, field: u32) {
+        self.index.configure_trigram_index(FieldPath::Id(field));
+    }
+
+    pub fn configure_composite_index(&mut self, prefix: Vec<u32>, suffix: u32) {
+        self.index.configure_composite_index(
+            prefix.into_iter().map(FieldPath::Id).collect(),
+            FieldPath::Id(suffix),
+        );
+    }
+
+    /// Maintain an HNSW graph over the vectors of `field` for approximate
+    /// `vector_search_within` calls using `metric` (`cosine`, `dot` or `l2`).
+    /// `max_connections` (`M`) and `ef_construction` default to 16 and 200.
+    pub fn configure_vector_index(
+        &mut self,
+        field: u32,
+        metric: &str,
+        max_connections: Option<usize>,
+        ef_construction: Option<usize>,
+    ) -> Result<(), JsValue> {
+        let defaults = VectorIndexConfig::new(parse_vector_metric(metric).map_err(js_error)?);
+        self.index.configure_vector_index(
+            FieldPath::Id(field),
+            VectorIndexConfig {
+                max_connections: max_connections.unwrap_or(defaults.max_connections),
+                ef_construction: ef_construction.unwrap_or(defaults.ef_construction),
+                ..defaults
+            },
+        );
+        Ok(())
+    }
+
+    /// Live view of a query as `[subscription, ids]`; later writes queue
+    /// diffs for `take_subscription_diffs`.
+    pub fn subscribe(
+        &mut self,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        limit: Option<usize>,
+    ) -> Result<Array, JsValue> {
+        let query = decode_query(&query_bytes)?;
+        let sort = decode_sort(&sort_bytes)?;
+        let (subscription, ids) = self.index.subscribe(query, sort, limit);
+        let out = Array::new();
+        out.push(&JsValue::from_f64(subscription as f64));
+        out.push(&ids_to_js(ids));
+        Ok(out)
+    }
+
+    pub fn unsubscribe(&mut self, subscription: u32) -> bool {
+        self.index.unsubscribe(subscription)
+    }
+
+    /// Pins the current generation for the `*_at` reads until
+    /// `release_snapshot`.
+    pub fn pin_snapshot(&mut self) -> f64 {
+        self.index.pin_snapshot() as f64
+    }
+
+    pub fn release_snapshot(&mut self, generation: f64) -> bool {
+        self.index.release_snapshot(generation as u64)
+    }
+
+    /// `query_after` against the snapshot pinned at `generation`.
+    pub fn query_after_at(
+        &self,
+        generation: f64,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        cursor_bytes: Option<Vec<u8>>,
+        limit: usize,
+    ) -> Result<Array, JsValue> {
+        let snapshot = self.pinned_snapshot(generation)?;
+        let query = decode_query(&query_bytes)?;
+        let sort = decode_sort(&sort_bytes)?;
+        let cursor = cursor_bytes
+            .map(|bytes| decode_search_cursor(&bytes))
+            .transpose()
+            .map_err(js_error)?;
+        let page = snapshot
+            .search_page_after(&query, &sort, cursor.as_ref(), limit)
+            .map_err(js_error)?;
+        Ok(page_to_js(ids_to_js(page.ids.clone()), &page))
+    }
+
+    pub fn count_at(&self, generation: f64, query_bytes: Vec<u8>) -> Result<usize, JsValue> {
+        let snapshot = self.pinned_snapshot(generation)?;
+        let query = decode_query(&query_bytes)?;
+        Ok(snapshot.count(&query) as usize)
+    }
+
+    pub fn sum_at(
+        &self,
+        generation: f64,
+        query_bytes: Vec<u8>,
+        field: u32,
+    ) -> Result<Array, JsValue> {
+        let snapshot = self.pinned_snapshot(generation)?;
+        let query = decode_query(&query_bytes)?;
+        let sum = snapshot
+            .sum(&query, FieldPath::Id(field))
+            .map_err(js_error)?;
+        Ok(sum_to_js(sum))
+    }
+
+    pub fn take_subscription_diffs(&mut self) -> Result<Array, JsValue> {
+        let out = Array::new();
+        for diff in self.index.take_subscription_diffs() {
+            out.push(&subscription_diff_to_js(&diff)?);
+        }
+        Ok(out)
+    }
+
+    pub fn query_text(
+        &self,
+        query_bytes: Vec<u8>,
+        field: u32,
+        text: String,
+        match_all: bool,
+        limit: usize,
+    ) -> Result<Array, JsValue> {
+        let scored = self.text_search(&query_bytes, field, text, match_all, limit)?;
+        Ok(scored_ids_to_js(scored))
+    }
+
+    /// Nearest `limit` documents to `vector` among those matching
+    /// `query_bytes`, as `[id, score]` pairs, bounded like
+    /// `query_page_within`. `metric` is `cosine`, `dot` or `l2`; an `ef`
+    /// searches the field's HNSW graph instead of scoring every candidate.
+    #[allow(clippy::too_many_arguments)]
+    pub fn vector_search_within(
+        &self,
+        query_bytes: Vec<u8>,
+        field: u32,
+        vector: Vec<f32>,
+        metric: &str,
+        ef: Option<usize>,
+        limit: usize,
+        max_candidates: Option<f64>,
+        max_materialized: Option<f64>,
+        deadline: Option<f64>,
+    ) -> Result<Array, JsValue> {
+        let budget = query_budget(max_candidates, max_materialized, deadline);
+        let scored = self.vector_search(&query_bytes, field, vector, metric, ef, limit, &budget)?;
+        Ok(scored_ids_to_js(scored))
+    }
+}
+
+impl NativeQueryPlanner {
+    fn pinned_snapshot(&self, generation: f64) -> Result<&IndexSnapshot, JsValue> {
+        self.index
+            .pinned_snapshot(generation as u64)
+            .ok_or_else(|| js_error(format!("native snapshot {generation} is not pinned")))
+    }
+
+    fn search_page_after(
+        &self,
+        query_bytes: &[u8],
+        sort_bytes: &[u8],
+        cursor_bytes: Option<Vec<u8>>,
+        limit: usize,
+    ) -> Result<SearchPage, JsValue> {
+        let query = decode_query(query_bytes)?;
+        let sort = decode_sort(sort_bytes)?;
+        let cursor = cursor_bytes
+            .map(|bytes| decode_search_cursor(&bytes))
+            .transpose()
+            .map_err(js_error)?;
+        self.index
+            .search_page_after(&query, &sort, cursor.as_ref(), limit)
+            .map_err(js_error)
+    }
+
+    fn text_search(
+        &self,
+        query_bytes: &[u8],
+        field: u32,
+        text: String,
+        match_all: bool,
+        limit: usize,
+    ) -> Result<Vec<ScoredDocument>, JsValue> {
+        let query = decode_query(query_bytes)?;
+        let text_sort = TextSort {
+            field: FieldPath::Id(field),
+            query: text,
+            operator: if match_all {
+                TextOperator::All
+            } else {
+                TextOperator::Any
+            },
+        };
+        self.index
+            .text_search(&query, &text_sort, limit)
+            .map_err(js_error)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn vector_search(
+        &self,
+        query_bytes: &[u8],
+        field: u32,
+        vector: Vec<f32>,
+        metric: &str,
+        ef: Option<usize>,
+        limit: usize,
+        budget: &QueryBudget,
+    ) -> Result<Vec<ScoredDocument>, JsValue> {
+        let query = decode_query(query_bytes)?;
+        let metric = parse_vector_metric(metric).map_err(js_error)?;
+        let vector_sort = VectorSort {
+            field: FieldPath::Id(field),
+            query: vector,
+            metric,
+            mode: match ef {
+                Some(ef) => VectorSearchMode::Approximate { ef },
+                None => VectorSearchMode::Exact,
+            },
+        };
+        self.index
+            .vector_search_within(&query, &vector_sort, limit, budget)
+            .map_err(budget_error)
+    }
 }
 
 #[wasm_bindgen]
@@ -240,6 +588,51 @@ impl NativeRustIndex {
         Ok(out)
     }
 
+    /// Compare `schema_ir_bytes` with the configured schema IR without
+    /// switching to it: `{ added, removed, retyped, invalidated }`, field ids
+    /// plus the indexes left stale until stored documents are reindexed.
+    pub fn diff_schema_ir(&self, schema_ir_bytes: Vec<u8>) -> Result<JsValue, JsValue> {
+        let next = decode_core_native_schema_ir(&schema_ir_bytes).map_err(js_error)?;
+        let current = self
+            .schema_ir
+            .as_ref()
+            .ok_or_else(|| js_error("Native schema IR has not been configured"))?;
+        let diff = diff_schema_ir(current, &next);
+        let field_ids = |ids: &[u32]| {
+            let out = Array::new();
+            for id in ids {
+                out.push(&JsValue::from_f64(*id as f64));
+            }
+            out
+        };
+        let invalidated = Array::new();
+        for index in self.planner.index.indexes_invalidated_by(&diff) {
+            let entry = Object::new();
+            let (kind, field) = match &index {
+                InvalidatedIndex::Field(field) => ("field", field),
+                InvalidatedIndex::Text(field) => ("text", field),
+                InvalidatedIndex::Trigram(field) => ("trigram", field),
+                InvalidatedIndex::Composite { prefix, suffix } => {
+                    let paths = Array::new();
+                    for path in prefix {
+                        paths.push(&field_path_to_js(path));
+                    }
+                    set_js(&entry, "prefix", paths.into())?;
+                    ("composite", suffix)
+                }
+            };
+            set_js(&entry, "kind", kind.into())?;
+            set_js(&entry, "field", field_path_to_js(field))?;
+            invalidated.push(&entry);
+        }
+        let out = Object::new();
+        set_js(&out, "added", field_ids(&diff.added).into())?;
+        set_js(&out, "removed", field_ids(&diff.removed).into())?;
+        set_js(&out, "retyped", field_ids(&diff.retyped).into())?;
+        set_js(&out, "invalidated", invalidated.into())?;
+        Ok(out.into())
+    }
+
     pub fn len(&self) -> usize {
         self.store.len()
     }
@@ -823,6 +1216,41 @@ impl NativeRustIndex {
         Ok(self.store.entries_for_keys(&keys))
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn query_page_within(
+        &self,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        offset: usize,
+        limit: usize,
+        max_candidates: Option<f64>,
+        max_materialized: Option<f64>,
+        deadline: Option<f64>,
+    ) -> Result<Array, JsValue> {
+        let query = decode_query(&query_bytes)?;
+        let sort = decode_sort(&sort_bytes)?;
+        let budget = query_budget(max_candidates, max_materialized, deadline);
+        let keys = self
+            .planner
+            .index
+            .search_page_within(&query, &sort, offset, Some(limit), &budget)
+            .map_err(budget_error)?;
+        Ok(self.store.entries_for_keys(&keys))
+    }
+
+    pub fn query_after(
+        &self,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        cursor_bytes: Option<Vec<u8>>,
+        limit: usize,
+    ) -> Result<Array, JsValue> {
+        let page =
+            self.planner
+                .search_page_after(&query_bytes, &sort_bytes, cursor_bytes, limit)?;
+        Ok(page_to_js(self.store.entries_for_keys(&page.ids), &page))
+    }
+
     pub fn query_exact_string_first_batch(&self, field: u32, values: Array) -> Array {
         let out = Array::new();
         let field = FieldPath::Id(field);
@@ -845,15 +1273,136 @@ impl NativeRustIndex {
         self.planner.count(query_bytes)
     }
 
+    pub fn count_within(
+        &self,
+        query_bytes: Vec<u8>,
+        max_candidates: Option<f64>,
+        deadline: Option<f64>,
+    ) -> Result<usize, JsValue> {
+        self.planner
+            .count_within(query_bytes, max_candidates, deadline)
+    }
+
     pub fn sum(&self, query_bytes: Vec<u8>, field: u32) -> Result<Array, JsValue> {
         self.planner.sum(query_bytes, field)
     }
 
+    pub fn sum_within(
+        &self,
+        query_bytes: Vec<u8>,
+        field: u32,
+        max_candidates: Option<f64>,
+        max_materialized: Option<f64>,
+        deadline: Option<f64>,
+    ) -> Result<Array, JsValue> {
+        self.planner.sum_within(
+            query_bytes,
+            field,
+            max_candidates,
+            max_materialized,
+            deadline,
+        )
+    }
+
+    pub fn aggregate(&self, request_bytes: Vec<u8>) -> Result<Vec<u8>, JsValue> {
+        self.planner.aggregate(request_bytes)
+    }
+
+    /// Plan tree for a query as `{ root, sort, fullScan, documentsEvaluated,
+    /// matched, returned }`; each node is `{ predicate, field, access,
+    /// estimated, actual, children }`.
+    pub fn explain(
+        &self,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        limit: Option<usize>,
+    ) -> Result<JsValue, JsValue> {
+        self.planner.explain(query_bytes, sort_bytes, limit)
+    }
+
     pub fn delete_matching(&mut self, query_bytes: Vec<u8>) -> Result<Array, JsValue> {
         let query = decode_query(&query_bytes)?;
         let keys = self.planner.index.delete_matching(&query);
         Ok(self.store.delete_keys(&keys))
     }
+
+    pub fn configure_text_index(&mut self, field: u32, english: bool) {
+        self.planner.configure_text_index(field, english);
+    }
+
+    pub fn configure_trigram_index(&mut self, field: u32) {
+        self.planner.configure_trigram_index(field);
+    }
+
+    pub fn configure_composite_index(&mut self, prefix: Vec<u32>, suffix: u32) {
+        self.planner.configure_composite_index(prefix, suffix);
+    }
+
+    pub fn configure_vector_index(
+        &mut self,
+        field: u32,
+        metric: &str,
+        max_connections: Option<usize>,
+        ef_construction: Option<usize>,
+    ) -> Result<(), JsValue> {
+        self.planner
+            .configure_vector_index(field, metric, max_connections, ef_construction)
+    }
+
+    pub fn subscribe(
+        &mut self,
+        query_bytes: Vec<u8>,
+        sort_bytes: Vec<u8>,
+        limit: Option<usize>,
+    ) -> Result<Array, JsValue> {
+        self.planner.subscribe(query_bytes, sort_bytes, limit)
+    }
+
+    pub fn unsubscribe(&mut self, subscription: u32) -> bool {
+        self.planner.unsubscribe(subscription)
+    }
+
+    pub fn take_subscription_diffs(&mut self) -> Result<Array, JsValue> {
+        self.planner.take_subscription_diffs()
+    }
+
+    /// Entries matching `query_bytes` and `text`, as `[[id, value], score]`
+    /// pairs ordered by BM25 relevance.
+    pub fn query_text(
+        &self,
+        query_bytes: Vec<u8>,
+        field: u32,
+        text: String,
+        match_all: bool,
+        limit: usize,
+    ) -> Result<Array, JsValue> {
+        let scored = self
+            .planner
+            .text_search(&query_bytes, field, text, match_all, limit)?;
+        Ok(self.store.scored_entries(&scored))
+    }
+
+    /// Entries nearest to `vector`, as `[[id, value], score]` pairs; see
+    /// `NativeQueryPlanner::vector_search_within`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn vector_search_within(
+        &self,
+        query_bytes: Vec<u8>,
+        field: u32,
+        vector: Vec<f32>,
+        metric: &str,
+        ef: Option<usize>,
+        limit: usize,
+        max_candidates: Option<f64>,
+        max_materialized: Option<f64>,
+        deadline: Option<f64>,
+    ) -> Result<Array, JsValue> {
+        let budget = query_budget(max_candidates, max_materialized, deadline);
+        let scored =
+            self.planner
+                .vector_search(&query_bytes, field, vector, metric, ef, limit, &budget)?;
+        Ok(self.store.scored_entries(&scored))
+    }
 }
 
 impl NativeRustIndex {
@@ -898,6 +1447,118 @@ fn ids_to_js(ids: Vec<String>) -> Array {
     out
 }
 
+fn plan_to_js(plan: &QueryPlan) -> Result<JsValue, JsValue> {
+    let out = Object::new();
+    let sort = Object::new();
+    match &plan.sort {
+        SortStrategy::DocumentOrder => set_js(&sort, "strategy", "document_order".into())?,
+        SortStrategy::SortIndex { field } => {
+            set_js(&sort, "strategy", "sort_index".into())?;
+            set_js(&sort, "field", field_path_to_js(field))?;
+        }
+        SortStrategy::CompositeIndex { prefix, field } => {
+            set_js(&sort, "strategy", "composite_index".into())?;
+            set_js(&sort, "field", field_path_to_js(field))?;
+            let paths = Array::new();
+            for path in prefix {
+                paths.push(&field_path_to_js(path));
+            }
+            set_js(&sort, "prefix", paths.into())?;
+        }
+        SortStrategy::InMemory => set_js(&sort, "strategy", "in_memory".into())?,
+    }
+    set_js(&out, "root", plan_node_to_js(&plan.root)?)?;
+    set_js(&out, "sort", sort.into())?;
+    set_js(&out, "fullScan", plan.full_scan.into())?;
+    set_js(
+        &out,
+        "documentsEvaluated",
+        (plan.documents_evaluated as f64).into(),
+    )?;
+    set_js(&out, "matched", (plan.matched as f64).into())?;
+    set_js(&out, "returned", (plan.returned as f64).into())?;
+    Ok(out.into())
+}
+
+fn plan_node_to_js(node: &PlanNode) -> Result<JsValue, JsValue> {
+    let out = Object::new();
+    let access = match node.access {
+        PlanAccess::AllDocuments => "all_documents",
+        PlanAccess::ExactIndex => "exact_index",
+        PlanAccess::LargeByteValues => "large_byte_values",
+        PlanAccess::RangeIndex => "range_index",
+        PlanAccess::TextIndex => "text_index",
+        PlanAccess::TrigramIndex => "trigram_index",
+        PlanAccess::CompositeIndex => "composite_index",
+        PlanAccess::FullScan => "full_scan",
+        PlanAccess::Seeded { child } => {
+            set_js(&out, "seedChild", (child as f64).into())?;
+            "seeded"
+        }
+        PlanAccess::Union => "union",
+        PlanAccess::Complement => "complement",
+    };
+    let children = Array::new();
+    for child in &node.children {
+        children.push(&plan_node_to_js(child)?);
+    }
+    set_js(&out, "predicate", node.predicate.into())?;
+    set_js(
+        &out,
+        "field",
+        node.field
+            .as_ref()
+            .map_or(JsValue::UNDEFINED, field_path_to_js),
+    )?;
+    set_js(&out, "access", access.into())?;
+    set_js(&out, "estimated", (node.estimated_candidates as f64).into())?;
+    set_js(&out, "actual", (node.actual_candidates as f64).into())?;
+    set_js(&out, "children", children.into())?;
+    Ok(out.into())
+}
+
+fn subscription_diff_to_js(diff: &SubscriptionDiff) -> Result<JsValue, JsValue> {
+    let positioned = |ids: &[(String, usize)]| {
+        let out = Array::new();
+        for (id, index) in ids {
+            let pair = Array::new();
+            pair.push(&JsValue::from_str(id));
+            pair.push(&JsValue::from_f64(*index as f64));
+            out.push(&pair);
+        }
+        out
+    };
+    let out = Object::new();
+    set_js(&out, "subscription", (diff.subscription as f64).into())?;
+    set_js(&out, "generation", (diff.generation as f64).into())?;
+    set_js(&out, "added", positioned(&diff.added).into())?;
+    set_js(&out, "removed", ids_to_js(diff.removed.clone()).into())?;
+    set_js(&out, "moved", positioned(&diff.moved).into())?;
+    Ok(out.into())
+}
+
+fn field_path_to_js(field: &FieldPath) -> JsValue {
+    match field {
+        FieldPath::Id(id) => JsValue::from_f64(*id as f64),
+        FieldPath::Name(name) => JsValue::from_str(name),
+    }
+}
+
+fn set_js(target: &Object, key: &str, value: JsValue) -> Result<(), JsValue> {
+    Reflect::set(target, &JsValue::from_str(key), &value).map(|_| ())
+}
+
+fn page_to_js(results: Array, page: &SearchPage) -> Array {
+    let out = Array::new();
+    out.push(&results);
+    match &page.cursor {
+        Some(cursor) => out.push(&Uint8Array::from(encode_search_cursor(cursor).as_slice())),
+        None => out.push(&JsValue::UNDEFINED),
+    };
+    out.push(&JsValue::from_f64(page.generation as f64));
+    out
+}
+
 fn sum_to_js(sum: SumResult) -> Array {
     let out = Array::new();
     match sum {
@@ -913,6 +1574,15 @@ fn sum_to_js(sum: SumResult) -> Array {
             out.push(&JsValue::from_str("u64"));
             out.push(&JsValue::from_str(&value.to_string()));
         }
+        // Decimal strings decode as BigInt on the JS side whatever the width.
+        SumResult::BigUint(value) => {
+            out.push(&JsValue::from_str("u64"));
+            out.push(&JsValue::from_str(&value.to_string()));
+        }
+        SumResult::F64(value) => {
+            out.push(&JsValue::from_str("f64"));
+            out.push(&JsValue::from_str(&value.to_string()));
+        }
     }
     out
 }
@@ -1077,6 +1747,110 @@ fn decode_sort(sort_bytes: &[u8]) -> Result<Vec<SortField>, JsValue> {
     decode_core_sort(sort_bytes).map_err(js_error)
 }
 
+fn scored_ids_to_js(scored: Vec<ScoredDocument>) -> Array {
+    let out = Array::new();
+    for result in scored {
+        let pair = Array::new();
+        pair.push(&JsValue::from_str(&result.id));
+        pair.push(&JsValue::from_f64(result.score as f64));
+        out.push(&pair);
+    }
+    out
+}
+
+fn parse_vector_metric(metric: &str) -> Result<VectorMetric, String> {
+    match metric {
+        "cosine" => Ok(VectorMetric::Cosine),
+        "dot" => Ok(VectorMetric::Dot),
+        "l2" => Ok(VectorMetric::L2),
+        other => Err(format!("unknown vector metric {other}")),
+    }
+}
+
 fn js_error(error: impl ToString) -> JsValue {
     JsValue::from_str(&error.to_string())
 }
+
+fn query_budget(
+    max_candidates: Option<f64>,
+    max_materialized: Option<f64>,
+    deadline: Option<f64>,
+) -> QueryBudget {
+    QueryBudget {
+        max_candidates: max_candidates.map(|max| max as u64),
+        max_materialized: max_materialized.map(|max| max as u64),
+        deadline: deadline.map(|at| Deadline {
+            at,
+            clock: Date::now,
+        }),
+    }
+}
+
+/// An `Error` named `BudgetExceeded`, so callers can tell it from a failed
+/// query, with `limit`, `candidatesExamined` and `documentsMaterialized`.
+fn budget_error(exceeded: BudgetExceeded) -> JsValue {
+    let error = Error::new(&exceeded.to_string());
+    error.set_name("BudgetExceeded");
+    let limit = match exceeded.limit {
+        BudgetLimit::Candidates => "candidates",
+        BudgetLimit::Materialized => "materialized",
+        BudgetLimit::Deadline => "deadline",
+    };
+    let _ = set_js(&error, "limit", limit.into());
+    let _ = set_js(
+        &error,
+        "candidatesExamined",
+        (exceeded.candidates_examined as f64).into(),
+    );
+    let _ = set_js(
+        &error,
+        "documentsMaterialized",
+        (exceeded.documents_materialized as f64).into(),
+    );
+    error.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_vector_metric, NativeQueryPlanner, NativeRustIndex};
+    use peerbit_indexer_core::hnsw::VectorIndexConfig;
+    use peerbit_indexer_core::planner::{FieldPath, VectorMetric};
+
+    #[test]
+    fn configure_vector_index_builds_the_requested_graph() {
+        let mut planner = NativeQueryPlanner::new();
+        planner
+            .configure_vector_index(3, "dot", Some(8), Some(64))
+            .unwrap();
+        assert_eq!(
+            planner.index.vector_index_config(&FieldPath::Id(3)),
+            Some(VectorIndexConfig {
+                metric: VectorMetric::Dot,
+                max_connections: 8,
+                ef_construction: 64,
+            })
+        );
+
+        let mut index = NativeRustIndex::new();
+        index
+            .configure_vector_index(5, "cosine", None, None)
+            .unwrap();
+        assert_eq!(
+            index.planner.index.vector_index_config(&FieldPath::Id(5)),
+            Some(VectorIndexConfig::new(VectorMetric::Cosine))
+        );
+        assert_eq!(
+            index.planner.index.vector_index_config(&FieldPath::Id(3)),
+            None
+        );
+    }
+
+    #[test]
+    fn vector_metrics_parse_by_name() {
+        assert_eq!(parse_vector_metric("l2"), Ok(VectorMetric::L2));
+        assert_eq!(
+            parse_vector_metric("hamming"),
+            Err("unknown vector metric hamming".to_string())
+        );
+    }
+}
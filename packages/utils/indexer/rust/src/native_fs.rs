@@ -1,7 +1,17 @@
 use crate::storage::{ByteStorage, MemoryByteStorage};
-use std::fs;
-use std::io::{self, ErrorKind};
+use peerbit_indexer_core::persistence::{
+    decode_journal_prefix, decode_key_value_snapshot, encode_journal_delete_record,
+    encode_journal_put_record, encode_key_value_snapshot, JournalOperation, JOURNAL_MAGIC,
+};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+
+const SEGMENT_EXTENSION: &str = "wal";
+const CHECKPOINT_EXTENSION: &str = "snapshot";
+const TEMP_EXTENSION: &str = "tmp";
+const DEFAULT_CHECKPOINT_BYTES: u64 = 4 * 1024 * 1024;
 
 pub struct NativeFsSnapshotStorage {
     path: PathBuf,
@@ -61,6 +71,377 @@ impl ByteStorage for NativeFsSnapshotStorage {
     }
 }
 
+/// Crash-safe `ByteStorage` backed by a directory of write-ahead journal
+/// segments (`<seq>.wal`, `PBRIDXW1` records) and key-value checkpoints
+/// (`<seq>.snapshot`, `PBRIDXK1`). Checkpoint `seq` holds the state before
+/// segment `seq`, so opening loads the newest checkpoint and replays the
+/// segments from its sequence on.
+///
+/// Writes are buffered until `flush`, which appends them to the current
+/// segment and fsyncs it. Once a segment outgrows the checkpoint threshold the
+/// journal rotates to a new segment and a background thread rebuilds the
+/// checkpoint from the files on disk (temp file, fsync, rename) and deletes
+/// what it supersedes. A write is durable once it is in a synced segment, so a
+/// failed checkpoint only delays compaction; its error is returned by the next
+/// `flush` or `checkpoint`.
+///
+/// This is an opt-in backend for native hosts: the wasm index keeps its
+/// in-memory storage and persists through the JS journal.
+pub struct NativeFsLogStorage {
+    dir: PathBuf,
+    memory: MemoryByteStorage,
+    segment: File,
+    segment_seq: u64,
+    /// Length of the segment up to its last synced append.
+    segment_len: u64,
+    /// Set when an append failed and its partial bytes past `segment_len`
+    /// could not be truncated yet; the next append retries the truncation.
+    torn: bool,
+    /// Sequence of the newest checkpoint on disk, if any.
+    checkpoint_seq: Option<u64>,
+    pending: Vec<u8>,
+    /// Set by `clear`, which the journal cannot express: the next flush
+    /// checkpoints instead of appending.
+    needs_checkpoint: bool,
+    checkpoint_bytes: u64,
+    compaction: Option<(u64, JoinHandle<io::Result<()>>)>,
+}
+
+impl NativeFsLogStorage {
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut checkpoints = Vec::new();
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            match log_file_kind(&path) {
+                Some((CHECKPOINT_EXTENSION, seq)) => checkpoints.push(seq),
+                Some((SEGMENT_EXTENSION, seq)) => segments.push(seq),
+                // Leftover of a checkpoint interrupted before its rename.
+                _ if path
+                    .extension()
+                    .is_some_and(|extension| extension == TEMP_EXTENSION) =>
+                {
+                    fs::remove_file(&path)?
+                }
+                _ => {}
+            }
+        }
+        checkpoints.sort_unstable();
+        segments.sort_unstable();
+
+        let checkpoint_seq = checkpoints.last().copied();
+        let base_seq = checkpoint_seq.unwrap_or(0);
+        let mut memory = load_checkpoint(&dir, checkpoint_seq)?;
+        let segments: Vec<u64> = segments
+            .into_iter()
+            .filter(|seq| *seq >= base_seq)
+            .collect();
+        let mut segment_len = 0;
+        for seq in &segments {
+            let path = log_path(&dir, *seq, SEGMENT_EXTENSION);
+            let bytes = fs::read(&path)?;
+            let intact = replay_segment(&mut memory, &bytes)?;
+            if intact < bytes.len() {
+                // Torn tail of an append that never finished its fsync.
+                OpenOptions::new()
+                    .write(true)
+                    .open(&path)?
+                    .set_len(intact as u64)?;
+            }
+            segment_len = intact as u64;
+        }
+        remove_superseded(&dir, base_seq)?;
+
+        let (segment, segment_seq) = match segments.last() {
+            Some(&seq) => (open_segment(&dir, seq)?, seq),
+            None => {
+                segment_len = JOURNAL_MAGIC.len() as u64;
+                (create_segment(&dir, base_seq)?, base_seq)
+            }
+        };
+        Ok(Self {
+            dir,
+            memory,
+            segment,
+            segment_seq,
+            segment_len,
+            torn: false,
+            checkpoint_seq,
+            pending: Vec::new(),
+            needs_checkpoint: false,
+            checkpoint_bytes: DEFAULT_CHECKPOINT_BYTES,
+            compaction: None,
+        })
+    }
+
+    /// Segment size (in bytes) past which a flush rotates the journal and
+    /// checkpoints.
+    pub fn with_checkpoint_bytes(mut self, checkpoint_bytes: u64) -> Self {
+        self.checkpoint_bytes = checkpoint_bytes;
+        self
+    }
+
+    /// Make every write so far durable.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self
+            .compaction
+            .as_ref()
+            .is_some_and(|(_, handle)| handle.is_finished())
+        {
+            self.wait_for_compaction()?;
+        }
+        if self.needs_checkpoint {
+            return self.checkpoint();
+        }
+        self.append_pending()?;
+        if self.segment_len >= self.checkpoint_bytes {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    /// Rotate to a new segment and checkpoint everything before it.
+    ///
+    /// Buffered writes are appended and synced to the closing segment first,
+    /// so they are durable when this returns and the checkpoint can be built
+    /// from disk in the background. After a `clear` the journal cannot
+    /// describe the state, so the checkpoint is written from memory before
+    /// returning instead.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        self.wait_for_compaction()?;
+        if self.needs_checkpoint {
+            self.pending.clear();
+        } else {
+            self.append_pending()?;
+        }
+        let seq = self.segment_seq + 1;
+        self.segment = create_segment(&self.dir, seq)?;
+        self.segment_seq = seq;
+        self.segment_len = JOURNAL_MAGIC.len() as u64;
+        self.torn = false;
+
+        if self.needs_checkpoint {
+            let snapshot = encode_key_value_snapshot(self.memory.entries());
+            write_atomically(&log_path(&self.dir, seq, CHECKPOINT_EXTENSION), &snapshot)?;
+            self.needs_checkpoint = false;
+            self.checkpoint_seq = Some(seq);
+            return remove_superseded(&self.dir, seq);
+        }
+
+        let dir = self.dir.clone();
+        let base = self.checkpoint_seq;
+        let handle = thread::spawn(move || {
+            let mut memory = load_checkpoint(&dir, base)?;
+            for segment_seq in base.unwrap_or(0)..seq {
+                match fs::read(log_path(&dir, segment_seq, SEGMENT_EXTENSION)) {
+                    Ok(bytes) => {
+                        replay_segment(&mut memory, &bytes)?;
+                    }
+                    Err(error) if error.kind() == ErrorKind::NotFound => {}
+                    Err(error) => return Err(error),
+                }
+            }
+            let snapshot = encode_key_value_snapshot(memory.entries());
+            write_atomically(&log_path(&dir, seq, CHECKPOINT_EXTENSION), &snapshot)?;
+            remove_superseded(&dir, seq)
+        });
+        self.compaction = Some((seq, handle));
+        Ok(())
+    }
+
+    /// Block until the background checkpoint (if any) has been written and
+    /// the files it supersedes are removed, returning its error if it failed.
+    pub fn wait_for_compaction(&mut self) -> io::Result<()> {
+        let Some((seq, handle)) = self.compaction.take() else {
+            return Ok(());
+        };
+        handle
+            .join()
+            .map_err(|_| io::Error::other("log storage compaction panicked"))??;
+        self.checkpoint_seq = Some(seq);
+        Ok(())
+    }
+
+    fn append_pending(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        if self.torn {
+            self.truncate_torn_tail()?;
+        }
+        if let Err(error) = self
+            .segment
+            .write_all(&self.pending)
+            .and_then(|()| self.segment.sync_data())
+        {
+            // Part of the append may be on disk. Left there, the next append
+            // would land after a torn record and be dropped on replay, so cut
+            // the segment back to its last good length; the writes stay
+            // pending for the next flush.
+            self.torn = true;
+            let _ = self.truncate_torn_tail();
+            return Err(error);
+        }
+        self.segment_len += self.pending.len() as u64;
+        self.pending.clear();
+        Ok(())
+    }
+
+    fn truncate_torn_tail(&mut self) -> io::Result<()> {
+        self.segment.set_len(self.segment_len)?;
+        self.segment.seek(SeekFrom::Start(self.segment_len))?;
+        self.segment.sync_data()?;
+        self.torn = false;
+        Ok(())
+    }
+
+    pub fn remove_dir(mut self) -> io::Result<()> {
+        self.wait_for_compaction()?;
+        match fs::remove_dir_all(&self.dir) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl Drop for NativeFsLogStorage {
+    fn drop(&mut self) {
+        let _ = self.wait_for_compaction();
+    }
+}
+
+impl ByteStorage for NativeFsLogStorage {
+    fn get(&self, key: &str) -> Option<&[u8]> {
+        self.memory.get(key)
+    }
+
+    fn put(&mut self, key: String, value: Vec<u8>) {
+        if !self.needs_checkpoint {
+            self.pending
+                .extend_from_slice(&encode_journal_put_record(&key, &value));
+        }
+        self.memory.put(key, value);
+    }
+
+    fn delete(&mut self, key: &str) -> bool {
+        let deleted = self.memory.delete(key);
+        if deleted && !self.needs_checkpoint {
+            self.pending
+                .extend_from_slice(&encode_journal_delete_record(key));
+        }
+        deleted
+    }
+
+    fn clear(&mut self) {
+        self.memory.clear();
+        self.pending.clear();
+        self.needs_checkpoint = true;
+    }
+
+    fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    fn entries(&self) -> Vec<(&str, &[u8])> {
+        self.memory.entries()
+    }
+}
+
+fn load_checkpoint(dir: &Path, seq: Option<u64>) -> io::Result<MemoryByteStorage> {
+    let mut memory = MemoryByteStorage::new();
+    let Some(seq) = seq else {
+        return Ok(memory);
+    };
+    let bytes = fs::read(log_path(dir, seq, CHECKPOINT_EXTENSION))?;
+    let entries = decode_key_value_snapshot(&bytes)
+        .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+    memory.reserve(entries.len());
+    for (key, value) in entries {
+        memory.put(key, value);
+    }
+    Ok(memory)
+}
+
+/// Apply a segment's intact records, returning the length of that prefix.
+fn replay_segment(memory: &mut MemoryByteStorage, bytes: &[u8]) -> io::Result<usize> {
+    let (records, intact) = decode_journal_prefix(bytes)
+        .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+    for record in records {
+        match (record.operation, record.value) {
+            (JournalOperation::Put, Some(value)) => memory.put(record.key, value),
+            _ => {
+                memory.delete(&record.key);
+            }
+        }
+    }
+    Ok(intact)
+}
+
+fn log_path(dir: &Path, seq: u64, extension: &str) -> PathBuf {
+    dir.join(format!("{seq:020}.{extension}"))
+}
+
+fn log_file_kind(path: &Path) -> Option<(&str, u64)> {
+    let extension = path.extension()?.to_str()?;
+    let seq = path.file_stem()?.to_str()?.parse().ok()?;
+    Some((extension, seq))
+}
+
+fn create_segment(dir: &Path, seq: u64) -> io::Result<File> {
+    let mut segment = OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(log_path(dir, seq, SEGMENT_EXTENSION))?;
+    segment.write_all(JOURNAL_MAGIC)?;
+    segment.sync_all()?;
+    sync_dir(dir)?;
+    Ok(segment)
+}
+
+fn open_segment(dir: &Path, seq: u64) -> io::Result<File> {
+    OpenOptions::new()
+        .append(true)
+        .open(log_path(dir, seq, SEGMENT_EXTENSION))
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let temp = path.with_extension(TEMP_EXTENSION);
+    let mut file = File::create(&temp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    sync_dir(path.parent().unwrap_or(Path::new(".")))
+}
+
+/// Delete checkpoints and segments older than checkpoint `seq`.
+fn remove_superseded(dir: &Path, seq: u64) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if let Some((CHECKPOINT_EXTENSION | SEGMENT_EXTENSION, file_seq)) = log_file_kind(&path) {
+            if file_seq < seq {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+// Directories cannot be opened for syncing elsewhere; renames are still
+// atomic, only their durability on power loss is left to the OS.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
 fn encode_snapshot(storage: &impl ByteStorage) -> Vec<u8> {
     let entries = storage.entries();
     let mut out = Vec::new();
@@ -116,19 +497,36 @@ fn read_bytes<'a>(bytes: &'a [u8], offset: &mut usize, length: usize) -> io::Res
 
 #[cfg(test)]
 mod tests {
-    use super::NativeFsSnapshotStorage;
+    use super::{
+        log_path, open_segment, NativeFsLogStorage, NativeFsSnapshotStorage, SEGMENT_EXTENSION,
+    };
     use crate::storage::ByteStorage;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::Write;
+    use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    #[test]
-    fn persists_across_reopen() {
-        let path = std::env::temp_dir().join(format!(
-            "peerbit-indexer-rust-{}.bin",
+    fn temp_path(extension: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "peerbit-indexer-rust-{}.{extension}",
             SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_nanos()
-        ));
+        ))
+    }
+
+    fn entries(storage: &impl ByteStorage) -> Vec<(String, Vec<u8>)> {
+        storage
+            .entries()
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn persists_across_reopen() {
+        let path = temp_path("bin");
 
         let mut storage = NativeFsSnapshotStorage::open(&path).unwrap();
         storage.put("a".to_string(), vec![1, 2, 3]);
@@ -138,4 +536,158 @@ mod tests {
         assert_eq!(reopened.get("a"), Some([1, 2, 3].as_slice()));
         reopened.remove_file().unwrap();
     }
+
+    #[test]
+    fn log_storage_recovers_from_checkpoint_and_journal() {
+        let dir = temp_path("log");
+        let mut storage = NativeFsLogStorage::open(&dir)
+            .unwrap()
+            .with_checkpoint_bytes(64);
+        for index in 0..10_u8 {
+            storage.put(format!("key-{index}"), vec![index; 8]);
+            storage.flush().unwrap();
+        }
+        storage.delete("key-3");
+        storage.put("key-0".to_string(), vec![42]);
+        storage.flush().unwrap();
+        storage.put("unflushed".to_string(), vec![1]);
+        let expected: Vec<_> = entries(&storage)
+            .into_iter()
+            .filter(|(key, _)| key != "unflushed")
+            .collect();
+        storage.wait_for_compaction().unwrap();
+        drop(storage);
+
+        // Rotation left one checkpoint and the segments after it.
+        let mut files: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files
+                .iter()
+                .filter(|path| path.extension().unwrap() == "snapshot")
+                .count(),
+            1
+        );
+        let last_segment = files.last().unwrap().clone();
+        assert_eq!(last_segment.extension().unwrap(), "wal");
+
+        // A torn append is dropped and truncated away.
+        OpenOptions::new()
+            .append(true)
+            .open(&last_segment)
+            .unwrap()
+            .write_all(&[9, 0, 0, 0, 1, 2])
+            .unwrap();
+        let mut reopened = NativeFsLogStorage::open(&dir).unwrap();
+        let mut recovered = entries(&reopened);
+        recovered.sort();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(recovered, expected);
+
+        reopened.put("after".to_string(), vec![7]);
+        reopened.flush().unwrap();
+        drop(reopened);
+        let reopened = NativeFsLogStorage::open(&dir).unwrap();
+        assert_eq!(reopened.get("after"), Some([7].as_slice()));
+        assert_eq!(reopened.len(), expected.len() + 1);
+        reopened.remove_dir().unwrap();
+    }
+
+    #[test]
+    fn log_storage_checkpoint_errors_surface_without_losing_writes() {
+        let dir = temp_path("log");
+        let mut storage = NativeFsLogStorage::open(&dir).unwrap();
+        storage.put("a".to_string(), vec![1]);
+        storage.flush().unwrap();
+        storage.put("b".to_string(), vec![2]);
+
+        // A directory in the way of the checkpoint's temp file fails it.
+        let blocked = dir.join(format!("{:020}.tmp", 1));
+        fs::create_dir(&blocked).unwrap();
+        storage.checkpoint().unwrap();
+        assert!(storage.wait_for_compaction().is_err());
+        drop(storage);
+        fs::remove_dir(&blocked).unwrap();
+
+        // The buffered write went to the closed segment before rotating.
+        let mut reopened = NativeFsLogStorage::open(&dir).unwrap();
+        let mut recovered = entries(&reopened);
+        recovered.sort();
+        assert_eq!(
+            recovered,
+            vec![("a".to_string(), vec![1]), ("b".to_string(), vec![2])]
+        );
+
+        // The next checkpoint rebuilds from the files it failed to compact.
+        reopened.put("c".to_string(), vec![3]);
+        reopened.checkpoint().unwrap();
+        reopened.wait_for_compaction().unwrap();
+        drop(reopened);
+        let reopened = NativeFsLogStorage::open(&dir).unwrap();
+        assert_eq!(reopened.len(), 3);
+        assert_eq!(reopened.get("c"), Some([3].as_slice()));
+        reopened.remove_dir().unwrap();
+    }
+
+    #[test]
+    fn log_storage_clear_is_durable_after_flush() {
+        let dir = temp_path("log");
+        let mut storage = NativeFsLogStorage::open(&dir).unwrap();
+        storage.put("a".to_string(), vec![1]);
+        storage.put("b".to_string(), vec![2]);
+        storage.flush().unwrap();
+        storage.clear();
+        storage.put("c".to_string(), vec![3]);
+        storage.flush().unwrap();
+        drop(storage);
+
+        let reopened = NativeFsLogStorage::open(&dir).unwrap();
+        assert_eq!(entries(&reopened), vec![("c".to_string(), vec![3])]);
+        reopened.remove_dir().unwrap();
+    }
+
+    #[test]
+    fn log_storage_failed_append_is_truncated_before_the_next_one() {
+        let dir = temp_path("log");
+        let mut storage = NativeFsLogStorage::open(&dir).unwrap();
+        storage.put("a".to_string(), vec![1]);
+        storage.flush().unwrap();
+
+        // An append that got part of its bytes out before failing, through a
+        // handle that can neither write nor truncate.
+        let segment_path = log_path(&dir, storage.segment_seq, SEGMENT_EXTENSION);
+        OpenOptions::new()
+            .append(true)
+            .open(&segment_path)
+            .unwrap()
+            .write_all(&[9, 0, 0, 0, 1, 2])
+            .unwrap();
+        storage.segment = File::open(&segment_path).unwrap();
+        storage.put("b".to_string(), vec![2]);
+        assert!(storage.flush().is_err());
+
+        // Once the segment is writable again the torn bytes are cut away
+        // before the retried append, so replay reaches it.
+        storage.segment = open_segment(&dir, storage.segment_seq).unwrap();
+        storage.put("c".to_string(), vec![3]);
+        storage.flush().unwrap();
+        drop(storage);
+
+        let reopened = NativeFsLogStorage::open(&dir).unwrap();
+        let mut recovered = entries(&reopened);
+        recovered.sort();
+        assert_eq!(
+            recovered,
+            vec![
+                ("a".to_string(), vec![1]),
+                ("b".to_string(), vec![2]),
+                ("c".to_string(), vec![3])
+            ]
+        );
+        reopened.remove_dir().unwrap();
+    }
 }
@@ -7,6 +7,7 @@ import {
 	IntegerCompare,
 	Nested,
 	Or,
+	type Query,
 	Sort,
 	SortDirection,
 	StringMatch,
@@ -16,7 +17,7 @@ import {
 } from "@peerbit/indexer-interface";
 import { tests } from "@peerbit/indexer-tests";
 import { expect } from "chai";
-import { create } from "../src/index.js";
+import { type RustIndex, create } from "../src/index.js";
 
 class BridgeDocument {
 	@id({ type: "string" })
@@ -388,6 +389,57 @@ describe("native planner bridge", () => {
 		await indices.drop();
 	});
 
+	it("searches and counts text through the native bm25 index", async () => {
+		const indices = create();
+		await indices.start();
+		const index = (await indices.init({
+			schema: BridgeDocument,
+		})) as unknown as RustIndex<BridgeDocument>;
+		await index.put(
+			new BridgeDocument("a", "peerbit", "running native indexes"),
+		);
+		await index.put(
+			new BridgeDocument(
+				"b",
+				"peerbit",
+				"the native index runs the native query",
+			),
+		);
+		await index.put(new BridgeDocument("c", "other", "typescript fallback"));
+		index.configureTextIndex("title");
+		await index.put(new BridgeDocument("d", "other", "native runner"));
+
+		const ranked = await index.searchText({
+			key: "title",
+			text: "native index",
+		});
+		expect(ranked.map((result) => result.value.id)).to.deep.equal([
+			"b",
+			"d",
+			"a",
+		]);
+		expect(ranked[0].score).to.be.greaterThan(ranked[1].score);
+
+		const filtered = await index.searchText({
+			key: "title",
+			text: "native index",
+			operator: "all",
+			query: new StringMatch({ key: "tag", value: "peerbit" }),
+			limit: 1,
+		});
+		expect(filtered.map((result) => result.value.id)).to.deep.equal(["b"]);
+
+		expect(await index.countText({ key: "title", text: "native" })).to.equal(3);
+		expect(
+			await index.countText({
+				key: "title",
+				text: "native",
+				query: new StringMatch({ key: "tag", value: "peerbit" }),
+			}),
+		).to.equal(2);
+		await indices.drop();
+	});
+
 	it("applies puts in a native batch", async () => {
 		const indices = create();
 		await indices.start();
@@ -1728,6 +1780,51 @@ describe("native planner bridge", () => {
 		await indices.drop();
 	});
 
+	it("evaluates set, interval and not-equal predicates in native rust", async () => {
+		const indices = create();
+		await indices.start();
+		const index = await indices.init({ schema: BridgeMetricDocument });
+		await index.put(new BridgeMetricDocument("a", "peerbit", 1));
+		await index.put(new BridgeMetricDocument("b", "other", 2));
+		await index.put(new BridgeMetricDocument("c", "peerbit", 3));
+
+		const ids = async (query: Query) => {
+			const results = await index
+				.iterate({ query, sort: new Sort({ key: "id" }) })
+				.all();
+			return results.map((result) => result.value.id);
+		};
+		const notTwo = new IntegerCompare({ key: "value", compare: "neq", value: 2 });
+
+		expect(
+			await ids(
+				new Or([
+					new IntegerCompare({ key: "value", compare: "eq", value: 1 }),
+					new IntegerCompare({ key: "value", compare: "eq", value: 3 }),
+				]),
+			),
+		).to.deep.equal(["a", "c"]);
+		expect(
+			await ids(
+				new And([
+					new IntegerCompare({ key: "value", compare: "lte", value: 3 }),
+					new IntegerCompare({ key: "value", compare: "gte", value: 2 }),
+				]),
+			),
+		).to.deep.equal(["b", "c"]);
+		expect(
+			await ids(
+				new And([
+					new IntegerCompare({ key: "value", compare: "gt", value: 1 }),
+					new IntegerCompare({ key: "value", compare: "lt", value: 3 }),
+				]),
+			),
+		).to.deep.equal(["b"]);
+		expect(await ids(notTwo)).to.deep.equal(["a", "c"]);
+		expect(await index.count({ query: notTwo })).to.equal(2);
+		await indices.drop();
+	});
+
 	it("keeps array and predicates scoped to the same native element", async () => {
 		const indices = create();
 		await indices.start();
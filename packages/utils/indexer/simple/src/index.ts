@@ -594,6 +594,12 @@ export class HashmapIndex<T extends Record<string, any>, NestedType = any>
 		if (f.method === types.StringMatchMethod.contains) {
 			return fv.includes(compare);
 		}
+		if (f.method === types.StringMatchMethod.suffix) {
+			return fv.endsWith(compare);
+		}
+		if (f.method === types.StringMatchMethod.glob) {
+			return types.matchesGlob(fv, compare);
+		}
 		throw new Error("Unsupported");
 	}
 
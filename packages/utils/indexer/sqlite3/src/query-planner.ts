@@ -472,11 +472,17 @@ const getIndexableQueryColumns = (
 		let target: IndexColumn[] | undefined;
 		let collation: IndexColumn["collation"] | undefined;
 		if (item instanceof IntegerCompare) {
+			if (item.compare === Compare.NotEqual) {
+				return;
+			}
 			key = item.key;
 			target = item.compare === Compare.Equal ? equality : range;
 		} else if (item instanceof StringMatch) {
 			key = item.key;
-			if (item.method === StringMatchMethod.contains) {
+			if (
+				item.method !== StringMatchMethod.exact &&
+				item.method !== StringMatchMethod.prefix
+			) {
 				return;
 			}
 			target = item.method === StringMatchMethod.exact ? equality : range;
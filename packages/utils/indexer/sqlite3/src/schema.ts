@@ -2372,11 +2372,25 @@ const convertStateFieldQuery = (
 		} else if (query.method === types.StringMatchMethod.prefix) {
 			statement = `${keyWithTable} LIKE ? `;
 			bindable.push(`${query.value}%`);
+		} else if (query.method === types.StringMatchMethod.suffix) {
+			statement = `${keyWithTable} LIKE ? `;
+			bindable.push(`%${query.value}`);
 		} else if (query.method === types.StringMatchMethod.exact) {
 			statement = `${keyWithTable} = ?`;
 			bindable.push(`${query.value}`);
 		}
-		if (query.caseInsensitive) {
+		if (query.method === types.StringMatchMethod.glob) {
+			// GLOB ignores collations, and `[` opens a character class that
+			// the portable `*`/`?` syntax does not have
+			const pattern = query.value.replace(/\[/g, "[[]");
+			if (query.caseInsensitive) {
+				statement = `lower(${keyWithTable}) GLOB ? `;
+				bindable.push(pattern.toLowerCase());
+			} else {
+				statement = `${keyWithTable} GLOB ? `;
+				bindable.push(pattern);
+			}
+		} else if (query.caseInsensitive) {
 			statement += " COLLATE NOCASE";
 		}
 		where = statement;
@@ -2404,6 +2418,8 @@ const convertStateFieldQuery = (
 				where = `${keyWithTable} >= ? `;
 			} else if (query.compare === types.Compare.LessOrEqual) {
 				where = `${keyWithTable} <= ? `;
+			} else if (query.compare === types.Compare.NotEqual) {
+				where = `${keyWithTable} != ? `;
 			} else {
 				throw new Error(`Unsupported compare type: ${query.compare} `);
 			}
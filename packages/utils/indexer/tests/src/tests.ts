@@ -746,6 +746,50 @@ export const tests = (
 						]);
 					});
 
+					it("suffix", async () => {
+						const responses = await search(store, {
+							query: [
+								new StringMatch({
+									key: "name",
+									value: "WORLD",
+									method: StringMatchMethod.suffix,
+									caseInsensitive: true,
+								}),
+							],
+						});
+						expect(responses.map((x) => x.id.primitive)).to.have.members([
+							"1",
+							"2",
+						]);
+					});
+
+					it("glob", async () => {
+						let responses = await search(store, {
+							query: [
+								new StringMatch({
+									key: "name",
+									value: "h?llo *",
+									method: StringMatchMethod.glob,
+									caseInsensitive: true,
+								}),
+							],
+						});
+						expect(responses.map((x) => x.id.primitive)).to.have.members([
+							"1",
+							"2",
+						]);
+						responses = await search(store, {
+							query: [
+								new StringMatch({
+									key: "name",
+									value: "H*d",
+									method: StringMatchMethod.glob,
+								}),
+							],
+						});
+						expect(responses.map((x) => x.id.primitive)).to.deep.equal(["2"]);
+					});
+
 					describe("arr", () => {
 						it("arr", async () => {
 							const responses = await search(store, {
@@ -1869,6 +1913,24 @@ export const tests = (
 						expect(response[1].value.number).to.be.oneOf([2n, 2]);
 					});
 
+					it("neq", async () => {
+						const response = await search(store, {
+							query: [
+								new IntegerCompare({
+									key: "number",
+									compare: Compare.NotEqual,
+									value: 2,
+								}),
+							],
+						});
+						response.sort((a, b) =>
+							bigIntSort(a.value.number as bigint, b.value.number as bigint),
+						);
+						expect(response).to.have.length(2);
+						expect(response[0].value.number).to.be.oneOf([1n, 1]);
+						expect(response[1].value.number).to.be.oneOf([3n, 3]);
+					});
+
 					it("bigint as compare value", async () => {
 						const response = await search(store, {
 							query: [
@@ -294,6 +294,14 @@ fn field_value_to_row(value: &FieldValue) -> JsValue {
             row.set(0, JsValue::from_str("u64"));
             row.set(1, JsValue::from_str(&value.to_string()));
         }
+        FieldValue::BigUint(value) => {
+            row.set(0, JsValue::from_str("u64"));
+            row.set(1, JsValue::from_str(&value.to_string()));
+        }
+        FieldValue::F64(value) => {
+            row.set(0, JsValue::from_str("f64"));
+            row.set(1, JsValue::from_f64(value.get()));
+        }
         FieldValue::String(value) => {
             row.set(0, JsValue::from_str("string"));
             row.set(1, JsValue::from_str(value));
@@ -321,6 +329,15 @@ fn sum_to_js(sum: SumResult) -> Array {
             out.push(&JsValue::from_str("u64"));
             out.push(&JsValue::from_str(&value.to_string()));
         }
+        // Decimal strings decode as BigInt on the JS side whatever the width.
+        SumResult::BigUint(value) => {
+            out.push(&JsValue::from_str("u64"));
+            out.push(&JsValue::from_str(&value.to_string()));
+        }
+        SumResult::F64(value) => {
+            out.push(&JsValue::from_str("f64"));
+            out.push(&JsValue::from_str(&value.to_string()));
+        }
     }
     out
 }
@@ -1190,45 +1207,40 @@ impl NativePeerbitBackbone {
         encode_key_value_snapshot(self.document_values.entries())
     }
 
+    /// Snapshot of the built document index, to be written alongside
+    /// `document_snapshot` and handed back to
+    /// `load_document_snapshot_index_and_journal`. Empty until a schema IR is
+    /// configured.
+    pub fn document_index_snapshot(&self) -> Vec<u8> {
+        match self.document_index_fingerprint() {
+            Some(fingerprint) => self.document_index.encode_snapshot(fingerprint),
+            None => Vec::new(),
+        }
+    }
+
     pub fn load_document_snapshot_and_journal(
         &mut self,
         snapshot: Uint8Array,
         journal: Uint8Array,
     ) -> Result<usize, JsValue> {
-        let mut entries = if snapshot.length() == 0 {
-            Default::default()
-        } else {
-            decode_key_value_snapshot(&snapshot.to_vec()).map_err(BackboneError::from)?
-        };
-        let journal_records = if journal.length() == 0 {
-            Vec::new()
-        } else {
-            decode_journal(&journal.to_vec()).map_err(BackboneError::from)?
-        };
-        let operations = journal_records.len();
-        for record in journal_records {
-            match record {
-                JournalRecord {
-                    key,
-                    value: Some(value),
-                    ..
-                } => {
-                    entries.insert(key, value);
-                }
-                JournalRecord { key, .. } => {
-                    entries.shift_remove(&key);
-                }
-            }
-        }
+        Ok(self.load_document_state(&snapshot.to_vec(), &[], &journal.to_vec())?)
+    }
 
-        self.document_values.clear();
-        for (key, value) in entries {
-            self.document_values.put(key, value);
-        }
-        self.rebuild_document_index_from_values()?;
-        self.document_journal.clear();
-        self.document_journal_record_count = 0;
-        Ok(operations)
+    /// `load_document_snapshot_and_journal` that reuses `index_snapshot` (taken
+    /// with `snapshot`) instead of re-extracting every document: only journal
+    /// records are extracted. An index snapshot from another schema IR or byte
+    /// limit, over other documents, or damaged, falls back to the full rebuild.
+    pub fn load_document_snapshot_index_and_journal(
+        &mut self,
+        snapshot: Uint8Array,
+        index_snapshot: Uint8Array,
+        journal: Uint8Array,
+    ) -> Result<usize, JsValue> {
+        Ok(self.load_document_state(
+            &snapshot.to_vec(),
+            &index_snapshot.to_vec(),
+            &journal.to_vec(),
+        )?)
     }
 
     pub fn document_signer_pending_journal_len(&self) -> usize {
@@ -1624,6 +1636,112 @@ impl NativePeerbitBackbone {
         Ok(())
     }
 
+    fn load_document_state(
+        &mut self,
+        snapshot: &[u8],
+        index_snapshot: &[u8],
+        journal: &[u8],
+    ) -> Result<usize, BackboneError> {
+        let mut entries = if snapshot.is_empty() {
+            Default::default()
+        } else {
+            decode_key_value_snapshot(snapshot)?
+        };
+        let journal_records = if journal.is_empty() {
+            Vec::new()
+        } else {
+            decode_journal(journal)?
+        };
+        let operations = journal_records.len();
+        let reuse_index = !index_snapshot.is_empty()
+            && self.load_document_index_snapshot(index_snapshot, entries.keys());
+        for record in journal_records {
+            match record {
+                JournalRecord {
+                    key,
+                    value: Some(value),
+                    ..
+                } => {
+                    if reuse_index {
+                        let fields = self.extract_stored_document_fields(&value)?;
+                        self.document_index.put(key.clone(), fields);
+                    }
+                    entries.insert(key, value);
+                }
+                JournalRecord { key, .. } => {
+                    if reuse_index {
+                        self.document_index.delete(key.clone());
+                    }
+                    entries.shift_remove(&key);
+                }
+            }
+        }
+
+        self.document_values.clear();
+        for (key, value) in entries {
+            self.document_values.put(key, value);
+        }
+        if reuse_index {
+            self.rebuild_document_head_keys();
+        } else {
+            self.rebuild_document_index_from_values()?;
+        }
+        self.document_journal.clear();
+        self.document_journal_record_count = 0;
+        Ok(operations)
+    }
+
+    /// Extraction depends on the schema IR and the byte limits, so an index
+    /// snapshot is only reusable when all of them are unchanged.
+    fn document_index_fingerprint(&self) -> Option<u64> {
+        let schema_ir = self.document_schema_ir.as_ref()?;
+        let limits = ((self.document_byte_element_index_limit as u64) << 32)
+            | NATIVE_BACKBONE_BYTE_EXACT_INDEX_LIMIT as u64;
+        Some(schema_ir.fingerprint() ^ limits.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    fn load_document_index_snapshot<'a>(
+        &mut self,
+        index_snapshot: &[u8],
+        mut keys: impl ExactSizeIterator<Item = &'a String>,
+    ) -> bool {
+        let Some(fingerprint) = self.document_index_fingerprint() else {
+            return false;
+        };
+        // The index is derived data: a damaged or stale snapshot is rebuilt
+        // from the values rather than failing the load.
+        if !matches!(
+            self.document_index
+                .load_snapshot(index_snapshot, fingerprint),
+            Ok(true)
+        ) {
+            return false;
+        }
+        let covers_entries = self.document_index.len() == keys.len()
+            && keys.all(|key| self.document_index.document_fields_by_id(key).is_some());
+        if !covers_entries {
+            self.document_index.clear();
+        }
+        covers_entries
+    }
+
+    fn extract_stored_document_fields(
+        &self,
+        value: &[u8],
+    ) -> Result<DocumentFields, BackboneError> {
+        let schema_ir = self
+            .document_schema_ir
+            .as_ref()
+            .ok_or(BackboneError::DocumentSchemaIrNotConfigured)?;
+        Ok(extract_encoded_document_fields_from_parts_with_byte_limits(
+            schema_ir,
+            value,
+            &[],
+            self.document_byte_element_index_limit,
+            NATIVE_BACKBONE_BYTE_EXACT_INDEX_LIMIT,
+        )?)
+    }
+
     fn rebuild_document_head_keys(&mut self) {
         let Some(fields) = self.document_context_fields else {
             self.document_key_by_head.clear();
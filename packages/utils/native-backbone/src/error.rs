@@ -33,6 +33,7 @@ pub enum BackboneError {
     ExpectedReservedBytes,
     WireSyncStashFrameTaken,
     WireSyncPinnedEntryMissing,
+    WireSyncEncryptionKeypairMissing,
     CoordinateCountOverflow,
     Truncated(&'static str),
     InvalidBool(&'static str),
@@ -115,6 +116,9 @@ impl std::fmt::Display for BackboneError {
             BackboneError::WireSyncPinnedEntryMissing => {
                 f.write_str("wire sync pinned stash entry missing")
             }
+            BackboneError::WireSyncEncryptionKeypairMissing => {
+                f.write_str("wire sync session has no encryption keypair")
+            }
             BackboneError::CoordinateCountOverflow => f.write_str("Coordinate count overflow"),
             BackboneError::Truncated(label) => write!(f, "Truncated {label}"),
             BackboneError::InvalidBool(label) => write!(f, "Invalid bool {label}"),
@@ -144,7 +144,7 @@ type NativePeerbitBackboneHandle = {
 	document_sum: (
 		queryBytes: Uint8Array,
 		field: number,
-	) => ["none" | "i64" | "u64", string];
+	) => ["none" | "i64" | "u64" | "f64", string];
 	put_document_encoded_parts_stored: (
 		key: string,
 		valuePrefixBytes: Uint8Array,
@@ -173,10 +173,16 @@ type NativePeerbitBackboneHandle = {
 		recordCount: number,
 	) => void;
 	document_snapshot: () => Uint8Array;
+	document_index_snapshot: () => Uint8Array;
 	load_document_snapshot_and_journal: (
 		snapshot: Uint8Array,
 		journal: Uint8Array,
 	) => number;
+	load_document_snapshot_index_and_journal: (
+		snapshot: Uint8Array,
+		indexSnapshot: Uint8Array,
+		journal: Uint8Array,
+	) => number;
 	coordinate_journal_header: () => Uint8Array;
 	coordinate_pending_journal_len: () => number;
 	coordinate_pending_journal_byte_len: () => number;
@@ -596,6 +602,15 @@ type NativePeerbitBackboneHandle = {
 		gidRefrences: string[][],
 		reserved: Uint8Array,
 	) => Uint8Array | undefined;
+	encode_encrypted_raw_exchange_sync_payload?: (
+		session: NativeWireSyncSessionHandle,
+		topic: string,
+		strict: boolean,
+		hashes: string[],
+		gidRefrences: string[][],
+		reserved: Uint8Array,
+		recipients: Uint8Array[],
+	) => Uint8Array | undefined;
 	clear: () => void;
 	clear_shared_log: () => void;
 	clear_entry_coordinates: () => void;
@@ -2096,6 +2111,11 @@ type NativeWireSyncSessionHandle = {
 	register_topic: (topic: string) => void;
 	unregister_topic: (topic: string) => boolean;
 	topic_count: () => number;
+	set_encryption_keypair: (
+		secretKey: Uint8Array,
+		publicKey: Uint8Array,
+	) => void;
+	clear_encryption_keypair: () => void;
 	decode_and_verify_batch: (frames: Uint8Array[], nowMs: number) => Uint32Array;
 	stashed_meta: (
 		id: Uint8Array,
@@ -2199,11 +2219,31 @@ export class NativeBackboneWireSyncSession {
 
 	static async create(options: {
 		selfHash: string;
+		/**
+		 * This node's X25519 keypair: sync payloads encrypted to it are
+		 * stashed too, and `encodeEncryptedRawExchangeSyncPayload` seals
+		 * from it.
+		 */
+		encryptionKeypair?: { secretKey: Uint8Array; publicKey: Uint8Array };
 	}): Promise<NativeBackboneWireSyncSession> {
 		const wasm = await loadWasm();
-		return new NativeBackboneWireSyncSession(
-			new wasm.NativeWireSyncSession(options.selfHash),
-		);
+		const handle = new wasm.NativeWireSyncSession(options.selfHash);
+		if (options.encryptionKeypair) {
+			handle.set_encryption_keypair(
+				options.encryptionKeypair.secretKey,
+				options.encryptionKeypair.publicKey,
+			);
+		}
+		return new NativeBackboneWireSyncSession(handle);
+	}
+
+	/** Throws when `publicKey` does not belong to `secretKey`. */
+	setEncryptionKeypair(secretKey: Uint8Array, publicKey: Uint8Array): void {
+		this.handle.set_encryption_keypair(secretKey, publicKey);
+	}
+
+	clearEncryptionKeypair(): void {
+		this.handle.clear_encryption_keypair();
 	}
 
 	registerTopic(topic: string): void {
@@ -5902,6 +5942,37 @@ export class NativePeerbitBackbone {
 		);
 	}
 
+	/**
+	 * `encodeRawExchangeSyncPayload` with the RawExchangeHeadsMessage sealed
+	 * in an EncryptedThing for `recipients` (X25519 public keys) from the
+	 * session's encryption keypair, using fresh CSPRNG keys and nonces.
+	 * Throws when the session has no keypair.
+	 */
+	encodeEncryptedRawExchangeSyncPayload(
+		session: NativeBackboneWireSyncSessionLike,
+		properties: {
+			topic: string;
+			strict?: boolean;
+			hashes: string[];
+			gidRefrences: string[][];
+			reserved?: Uint8Array;
+			recipients: Uint8Array[];
+		},
+	): Uint8Array | undefined {
+		if (!this.native.encode_encrypted_raw_exchange_sync_payload) {
+			return undefined;
+		}
+		return this.native.encode_encrypted_raw_exchange_sync_payload(
+			session.handle as NativeWireSyncSessionHandle,
+			properties.topic,
+			properties.strict ?? true,
+			properties.hashes,
+			properties.gidRefrences,
+			properties.reserved ?? SYNC_SEND_DEFAULT_RESERVED,
+			properties.recipients,
+		);
+	}
+
 	prepareRawReceiveBatch(
 		blocks: Uint8Array[],
 	): NativeBackboneRawReceivePreparedFacts[] {
@@ -6613,7 +6684,7 @@ export class NativePeerbitBackbone {
 	documentSum(
 		queryBytes: Uint8Array,
 		field: number,
-	): ["none" | "i64" | "u64", string] {
+	): ["none" | "i64" | "u64" | "f64", string] {
 		return this.native.document_sum(queryBytes, field);
 	}
 
@@ -6719,10 +6790,22 @@ export class NativePeerbitBackbone {
 		return this.native.document_snapshot();
 	}
 
+	documentIndexSnapshot(): Uint8Array {
+		return this.native.document_index_snapshot();
+	}
+
 	loadDocumentSnapshotAndJournal(
 		snapshot?: Uint8Array,
 		journal?: Uint8Array,
+		indexSnapshot?: Uint8Array,
 	): number {
+		if (indexSnapshot && indexSnapshot.length > 0) {
+			return this.native.load_document_snapshot_index_and_journal(
+				snapshot ?? new Uint8Array(),
+				indexSnapshot,
+				journal ?? new Uint8Array(),
+			);
+		}
 		return this.native.load_document_snapshot_and_journal(
 			snapshot ?? new Uint8Array(),
 			journal ?? new Uint8Array(),
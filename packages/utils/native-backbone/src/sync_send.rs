@@ -7,34 +7,39 @@
 //! append/receive paths already committed them to. The only boundary crossing
 //! is the single finished payload buffer handed to JS to become the
 //! `DataMessage` payload — entry block bytes never materialize as JS values on
-//! the send path.
+//! the send path. When the payload is addressed to known recipients it is
+//! sealed in an `EncryptedThing` from the session's X25519 keypair, with key
+//! and nonces drawn from the OS CSPRNG (`crypto.getRandomValues` in wasm).
 //!
 //! The store-reading core is `JsValue`-free so host `cargo test` covers it.
 
 use js_sys::{Array, Uint32Array, Uint8Array};
 use peerbit_log_rust::NativeLogBlockStore;
-use peerbit_wire::sync_payload::{encode_raw_exchange_sync_payload_refs, SyncPayloadHeadRef};
+use peerbit_wire::encryption::X25519_KEY_LENGTH;
+use peerbit_wire::sync_payload::{
+    encode_encrypted_raw_exchange_sync_payload_refs, encode_raw_exchange_sync_payload_refs,
+    SyncPayloadEncryption, SyncPayloadHeadRef,
+};
+use rand_core::OsRng;
 use wasm_bindgen::prelude::*;
 
 use crate::error::BackboneError;
-use crate::js_interop::{ensure_same_len, string_batches_from_array, strings_from_array};
+use crate::js_interop::{
+    bytes_vec_from_array, ensure_same_len, string_batches_from_array, strings_from_array,
+};
+use crate::wire_sync::NativeWireSyncSession;
 use crate::NativePeerbitBackbone;
 
 /// Marks a missing block in `block_byte_lengths` results (a real entry block
 /// cannot reach this size: message payloads are bounded far below it).
 pub(crate) const SYNC_SEND_MISSING_BLOCK: u32 = u32::MAX;
 
-/// Encode the outbound raw exchange sync payload for `hashes`, resolving each
-/// head's block bytes from `blocks`. Returns `None` when any block is missing
-/// (callers fall back to the TS send path).
-pub(crate) fn encode_sync_payload_from_store(
-    blocks: &NativeLogBlockStore,
-    topic: &str,
-    strict: bool,
-    hashes: &[String],
-    gid_refrences: &[Vec<String>],
-    reserved: [u8; 4],
-) -> Option<Vec<u8>> {
+/// Resolve each head's block bytes from `blocks`; `None` when any is missing.
+fn heads_from_store<'a>(
+    blocks: &'a NativeLogBlockStore,
+    hashes: &'a [String],
+    gid_refrences: &'a [Vec<String>],
+) -> Option<Vec<SyncPayloadHeadRef<'a>>> {
     const EMPTY_REFS: &[String] = &[];
     let mut heads = Vec::with_capacity(hashes.len());
     for (index, hash) in hashes.iter().enumerate() {
@@ -48,6 +53,21 @@ pub(crate) fn encode_sync_payload_from_store(
                 .unwrap_or(EMPTY_REFS),
         });
     }
+    Some(heads)
+}
+
+/// Encode the outbound raw exchange sync payload for `hashes`, resolving each
+/// head's block bytes from `blocks`. Returns `None` when any block is missing
+/// (callers fall back to the TS send path).
+pub(crate) fn encode_sync_payload_from_store(
+    blocks: &NativeLogBlockStore,
+    topic: &str,
+    strict: bool,
+    hashes: &[String],
+    gid_refrences: &[Vec<String>],
+    reserved: [u8; 4],
+) -> Option<Vec<u8>> {
+    let heads = heads_from_store(blocks, hashes, gid_refrences)?;
     Some(encode_raw_exchange_sync_payload_refs(
         &[topic.to_string()],
         strict,
@@ -56,6 +76,45 @@ pub(crate) fn encode_sync_payload_from_store(
     ))
 }
 
+/// [`encode_sync_payload_from_store`] sealed for `encryption.recipients`,
+/// with the payload key and nonces from `OsRng`. `Ok(None)` when any block is
+/// missing; fails on a low-order recipient key.
+pub(crate) fn encode_encrypted_sync_payload_from_store(
+    blocks: &NativeLogBlockStore,
+    topic: &str,
+    strict: bool,
+    hashes: &[String],
+    gid_refrences: &[Vec<String>],
+    reserved: [u8; 4],
+    encryption: SyncPayloadEncryption<'_>,
+) -> Result<Option<Vec<u8>>, BackboneError> {
+    let Some(heads) = heads_from_store(blocks, hashes, gid_refrences) else {
+        return Ok(None);
+    };
+    encode_encrypted_raw_exchange_sync_payload_refs(
+        &[topic.to_string()],
+        strict,
+        &heads,
+        reserved,
+        encryption,
+        &mut OsRng,
+    )
+    .map(Some)
+    .map_err(BackboneError::Message)
+}
+
+pub(crate) fn recipient_public_keys(
+    recipients: Array,
+) -> Result<Vec<[u8; X25519_KEY_LENGTH]>, BackboneError> {
+    bytes_vec_from_array(recipients)?
+        .into_iter()
+        .map(|key| {
+            key.try_into()
+                .map_err(|_| BackboneError::ExpectedBytes("32-byte X25519 recipient key"))
+        })
+        .collect()
+}
+
 pub(crate) fn reserved_bytes(reserved: &[u8]) -> Result<[u8; 4], BackboneError> {
     reserved
         .try_into()
@@ -114,13 +173,56 @@ impl NativePeerbitBackbone {
             None => Ok(JsValue::UNDEFINED),
         }
     }
+
+    /// [`Self::encode_raw_exchange_sync_payload`] with the
+    /// `RawExchangeHeadsMessage` sealed for `recipients` (X25519 public keys)
+    /// from `session`'s encryption keypair. Throws when the session has no
+    /// keypair or a recipient key is invalid.
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode_encrypted_raw_exchange_sync_payload(
+        &self,
+        session: &NativeWireSyncSession,
+        topic: &str,
+        strict: bool,
+        hashes: Array,
+        gid_refrences: Array,
+        reserved: &[u8],
+        recipients: Array,
+    ) -> Result<JsValue, JsValue> {
+        let sender = session
+            .core
+            .keypair()
+            .ok_or(BackboneError::WireSyncEncryptionKeypairMissing)?;
+        let hashes = strings_from_array(hashes)?;
+        let gid_refrences = string_batches_from_array(gid_refrences, "sync send gid references")?;
+        ensure_same_len(hashes.len(), gid_refrences.len(), "sync send heads")?;
+        let reserved = reserved_bytes(reserved)?;
+        let recipients = recipient_public_keys(recipients)?;
+        match encode_encrypted_sync_payload_from_store(
+            &self.blocks,
+            topic,
+            strict,
+            &hashes,
+            &gid_refrences,
+            reserved,
+            SyncPayloadEncryption {
+                sender,
+                recipients: &recipients,
+            },
+        )? {
+            Some(payload) => Ok(Uint8Array::from(payload.as_slice()).into()),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use peerbit_wire::encryption::X25519Keypair;
     use peerbit_wire::sync_payload::{
-        encode_raw_exchange_sync_payload, parse_pubsub_data, parse_raw_exchange_rpc_request,
+        decrypt_raw_exchange_rpc_request, encode_raw_exchange_sync_payload, parse_pubsub_data,
+        parse_raw_exchange_rpc_request,
     };
 
     fn store_with(entries: &[(&str, Vec<u8>)]) -> NativeLogBlockStore {
@@ -169,6 +271,81 @@ mod tests {
         assert_eq!(parsed.heads.len(), 2);
     }
 
+    #[test]
+    fn encrypted_store_payloads_open_to_the_plaintext_transport() {
+        let store = store_with(&[("zb2AA", vec![0xde, 0xad]), ("zb2BB", vec![1, 2, 3])]);
+        let hashes = vec!["zb2AA".to_string(), "zb2BB".to_string()];
+        let gid_refrences = vec![vec!["g1".to_string()], Vec::new()];
+        let sender = X25519Keypair::from_secret_key([1; 32]);
+        let recipient = X25519Keypair::from_secret_key([2; 32]);
+        let encryption = SyncPayloadEncryption {
+            sender: &sender,
+            recipients: &[recipient.public_key()],
+        };
+        let encrypt = || {
+            encode_encrypted_sync_payload_from_store(
+                &store,
+                "topic",
+                true,
+                &hashes,
+                &gid_refrences,
+                [1, 0, 0, 0],
+                encryption,
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let payload = encrypt();
+        assert_ne!(payload, encrypt(), "fresh key and nonces per payload");
+
+        let plain = encode_sync_payload_from_store(
+            &store,
+            "topic",
+            true,
+            &hashes,
+            &gid_refrences,
+            [1, 0, 0, 0],
+        )
+        .unwrap();
+
+        let pubsub = parse_pubsub_data(&payload).unwrap();
+        assert_eq!(pubsub.topics, vec!["topic".to_string()]);
+        let data = &payload[pubsub.data_offset..pubsub.data_offset + pubsub.data_length];
+        let decrypted = decrypt_raw_exchange_rpc_request(data, &recipient).unwrap();
+        // The plaintext encoder ends with the same `RawExchangeHeadsMessage`.
+        assert!(plain.ends_with(&decrypted.transport));
+        assert_eq!(decrypted.payload.heads.len(), 2);
+
+        let missing = vec!["zb2AA".to_string(), "zb2MISSING".to_string()];
+        assert!(encode_encrypted_sync_payload_from_store(
+            &store,
+            "topic",
+            true,
+            &missing,
+            &gid_refrences,
+            [0; 4],
+            encryption,
+        )
+        .unwrap()
+        .is_none());
+
+        // u = 0 is low-order: libsodium refuses the key agreement.
+        let low_order = SyncPayloadEncryption {
+            sender: &sender,
+            recipients: &[[0; 32]],
+        };
+        assert!(encode_encrypted_sync_payload_from_store(
+            &store,
+            "topic",
+            true,
+            &hashes,
+            &gid_refrences,
+            [0; 4],
+            low_order,
+        )
+        .is_err());
+    }
+
     #[test]
     fn reserved_bytes_require_exactly_four() {
         assert_eq!(reserved_bytes(&[1, 2, 3, 4]).unwrap(), [1, 2, 3, 4]);
@@ -14,16 +14,22 @@
 //! `decode_and_verify_batch` and its shared-log programs register their topics
 //! and consume stashed payloads by message id.
 //!
+//! With an X25519 keypair set, `RequestV0`s carrying an `EncryptedThing`
+//! addressed to this node are opened natively as well; their stash entry keeps
+//! the decrypted `RawExchangeHeadsMessage` instead of the frame.
+//!
 //! The stash core is `JsValue`-free so host `cargo test` covers the decision
 //! and eviction logic.
 
 use js_sys::{Array, Uint32Array, Uint8Array};
+use peerbit_wire::encryption::X25519Keypair;
 use peerbit_wire::sync_payload::{
-    parse_pubsub_data, parse_raw_exchange_rpc_request, SyncPayloadHead,
+    decrypt_raw_exchange_rpc_request, parse_pubsub_data, parse_raw_exchange_rpc_request,
+    SyncPayloadHead,
 };
 use peerbit_wire::wire::{
-    decode_and_verify_frames, decode_frame_delivery_meta, DeliveryMode, FrameRecord, VerifyStatus,
-    ID_LENGTH, VARIANT_DATA,
+    decode_and_verify_frames, decode_frame_delivery_meta_ref, DeliveryModeRef, FrameRecord,
+    VerifyStatus, ID_LENGTH, VARIANT_DATA,
 };
 use peerbit_wire::{record_to_words, RECORD_FLAG_SYNC_STASHED, RECORD_WORDS};
 use std::collections::{HashMap, VecDeque};
@@ -46,8 +52,9 @@ pub(crate) const WIRE_SYNC_MAX_STASHED_MESSAGES: usize = 512;
 pub(crate) const WIRE_SYNC_MAX_STASHED_BYTES: usize = 64 * 1024 * 1024;
 
 pub(crate) struct StashedSyncMessage {
-    frame: Vec<u8>,
-    /// Head byte offsets are absolute within `frame`.
+    /// The frame, or the decrypted `RawExchangeHeadsMessage` of an encrypted
+    /// payload. Head byte offsets are absolute within it.
+    bytes: Vec<u8>,
     heads: Vec<SyncPayloadHead>,
     reserved: [u8; 4],
     payload_length: usize,
@@ -72,6 +79,7 @@ pub(crate) struct WireSyncCore {
     stash: HashMap<[u8; ID_LENGTH], StashedSyncMessage>,
     order: VecDeque<[u8; ID_LENGTH]>,
     stashed_bytes: usize,
+    keypair: Option<X25519Keypair>,
     pub(crate) counters: WireSyncCounters,
 }
 
@@ -105,21 +113,33 @@ impl WireSyncCore {
         self.topic_refs.len()
     }
 
-    fn delivered_locally(&self, mode: Option<&DeliveryMode>) -> bool {
+    pub(crate) fn set_keypair(&mut self, keypair: Option<X25519Keypair>) {
+        self.keypair = keypair;
+    }
+
+    pub(crate) fn keypair(&self) -> Option<&X25519Keypair> {
+        self.keypair.as_ref()
+    }
+
+    fn delivered_locally(&self, mode: Option<&DeliveryModeRef<'_>>) -> bool {
         // Mirrors TopicControlPlane.onDataMessage: explicit receivers must
         // include this node; AnyWhere modes are always delivered locally.
         match mode {
-            Some(DeliveryMode::Silent { to, .. }) | Some(DeliveryMode::Acknowledge { to, .. }) => {
-                to.iter().any(|hash| hash == &self.self_hash)
+            Some(DeliveryModeRef::Silent { to, .. })
+            | Some(DeliveryModeRef::Acknowledge { to, .. }) => {
+                to.iter().any(|hash| hash == self.self_hash)
+            }
+            Some(DeliveryModeRef::AnyWhere) | Some(DeliveryModeRef::AcknowledgeAnyWhere { .. }) => {
+                true
             }
-            Some(DeliveryMode::AnyWhere) | Some(DeliveryMode::AcknowledgeAnyWhere { .. }) => true,
-            Some(DeliveryMode::Traced { .. }) | None => false,
+            Some(DeliveryModeRef::Traced { .. }) | None => false,
         }
     }
 
     /// Try to stash a decoded-and-verified DataMessage frame. Returns
     /// `Ok(true)` when the frame carried a raw exchange sync payload for a
-    /// registered topic addressed to this node. The only error is the
+    /// registered topic addressed to this node, either in plaintext or
+    /// encrypted to the session keypair. The only error is the
     /// (unreachable-by-construction) invariant breach of the frame buffer
     /// disappearing between the checks and the take — previously a panic.
     pub(crate) fn try_stash(
@@ -151,21 +171,20 @@ impl WireSyncCore {
         else {
             return Ok(false);
         };
-        let Ok(parsed) = parse_raw_exchange_rpc_request(data) else {
+        let plaintext = parse_raw_exchange_rpc_request(data).ok();
+        if plaintext.is_none() && self.keypair.is_none() {
             return Ok(false);
-        };
-        let Ok(meta) = decode_frame_delivery_meta(frame_bytes) else {
+        }
+        let Ok(meta) = decode_frame_delivery_meta_ref(frame_bytes) else {
             return Ok(false);
         };
         if meta.variant != VARIANT_DATA || !self.delivered_locally(meta.mode.as_ref()) {
             return Ok(false);
         }
+        // `meta` borrows the frame, which is taken below.
+        let id = meta.id;
 
-        if self
-            .stash
-            .get(&meta.id)
-            .is_some_and(|existing| existing.pinned)
-        {
+        if self.stash.get(&id).is_some_and(|existing| existing.pinned) {
             // Duplicate delivery of a message that is mid-processing: the id
             // is part of the signed header, so the stashed frame is
             // byte-identical. Keep the pinned entry (replacing it would reset
@@ -174,34 +193,53 @@ impl WireSyncCore {
             return Ok(true);
         }
 
-        let heads = parsed
-            .heads
-            .into_iter()
-            .map(|head| SyncPayloadHead {
-                // Translate payload-relative offsets to frame-absolute ones.
-                bytes_offset: data_offset + pubsub.data_offset + head.bytes_offset,
-                ..head
-            })
-            .collect();
-        let Some(frame) = frame.take() else {
-            return Err(BackboneError::WireSyncStashFrameTaken);
+        let (bytes, heads, reserved) = match plaintext {
+            Some(parsed) => {
+                let heads = parsed
+                    .heads
+                    .into_iter()
+                    .map(|head| SyncPayloadHead {
+                        // Translate payload-relative offsets to frame-absolute ones.
+                        bytes_offset: data_offset + pubsub.data_offset + head.bytes_offset,
+                        ..head
+                    })
+                    .collect();
+                let Some(frame) = frame.take() else {
+                    return Err(BackboneError::WireSyncStashFrameTaken);
+                };
+                (frame, heads, parsed.reserved)
+            }
+            // Decrypted head offsets already address the transport bytes. A
+            // payload that is not for this keypair is left to the TS path.
+            None => match self
+                .keypair
+                .as_ref()
+                .map(|keypair| decrypt_raw_exchange_rpc_request(data, keypair))
+            {
+                Some(Ok(decrypted)) => (
+                    decrypted.transport,
+                    decrypted.payload.heads,
+                    decrypted.payload.reserved,
+                ),
+                _ => return Ok(false),
+            },
         };
-        let frame_length = frame.len();
+        let stashed_length = bytes.len();
         if let Some(previous) = self.stash.insert(
-            meta.id,
+            id,
             StashedSyncMessage {
-                frame,
+                bytes,
                 heads,
-                reserved: parsed.reserved,
+                reserved,
                 payload_length: data_length,
                 pinned: false,
             },
         ) {
-            self.stashed_bytes -= previous.frame.len();
-            self.order.retain(|id| id != &meta.id);
+            self.stashed_bytes -= previous.bytes.len();
+            self.order.retain(|queued| queued != &id);
         }
-        self.stashed_bytes += frame_length;
-        self.order.push_back(meta.id);
+        self.stashed_bytes += stashed_length;
+        self.order.push_back(id);
         self.counters.stashed += 1;
         while self.stash.len() > WIRE_SYNC_MAX_STASHED_MESSAGES
             || self.stashed_bytes > WIRE_SYNC_MAX_STASHED_BYTES
@@ -210,7 +248,7 @@ impl WireSyncCore {
                 break;
             };
             if let Some(evicted) = self.stash.remove(&oldest) {
-                self.stashed_bytes -= evicted.frame.len();
+                self.stashed_bytes -= evicted.bytes.len();
                 self.counters.evicted += 1;
             }
         }
@@ -265,7 +303,7 @@ impl WireSyncCore {
             return false;
         };
         if let Some(removed) = self.stash.remove(id) {
-            self.stashed_bytes -= removed.frame.len();
+            self.stashed_bytes -= removed.bytes.len();
             self.order.retain(|entry| entry != id);
             self.counters.released += 1;
             true
@@ -285,7 +323,7 @@ impl WireSyncCore {
         let stashed = self.get(id)?;
         let select = |head: &SyncPayloadHead| {
             stashed
-                .frame
+                .bytes
                 .get(head.bytes_offset..head.bytes_offset + head.bytes_length)
                 .map(<[u8]>::to_vec)
         };
@@ -342,6 +380,24 @@ impl NativeWireSyncSession {
         self.core.topic_count()
     }
 
+    /// Open raw exchange sync payloads encrypted to this X25519 keypair when
+    /// stashing, and seal outbound ones from it
+    /// (`encode_encrypted_raw_exchange_sync_payload`). Fails when
+    /// `public_key` does not belong to `secret_key`.
+    pub fn set_encryption_keypair(
+        &mut self,
+        secret_key: &[u8],
+        public_key: &[u8],
+    ) -> Result<(), JsValue> {
+        let keypair = X25519Keypair::new(secret_key, public_key).map_err(BackboneError::Message)?;
+        self.core.set_keypair(Some(keypair));
+        Ok(())
+    }
+
+    pub fn clear_encryption_keypair(&mut self) {
+        self.core.set_keypair(None);
+    }
+
     /// Drop-in replacement for `peerbit_wire`'s `decode_and_verify_batch`
     /// (same flat u32 record layout) that additionally stashes raw exchange
     /// sync payloads for registered topics, flagging their records with
@@ -560,7 +616,11 @@ impl NativePeerbitBackbone {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use peerbit_wire::sync_payload::encode_raw_exchange_sync_payload;
+    use peerbit_wire::sync_payload::{
+        encode_encrypted_raw_exchange_sync_payload, encode_raw_exchange_sync_payload,
+        SyncPayloadEncryption,
+    };
+    use peerbit_wire::wire::DeliveryMode;
     use peerbit_wire::wire::{encode_frame, MessageHeader, WireMessage};
 
     fn sync_frame(
@@ -571,6 +631,14 @@ mod tests {
     ) -> (Vec<u8>, usize, usize) {
         let payload =
             encode_raw_exchange_sync_payload(&[topic.to_string()], true, heads, [0, 0, 0, 0]);
+        data_frame(id_byte, mode, payload)
+    }
+
+    fn data_frame(
+        id_byte: u8,
+        mode: Option<DeliveryMode>,
+        payload: Vec<u8>,
+    ) -> (Vec<u8>, usize, usize) {
         let message = WireMessage::Data {
             header: MessageHeader {
                 id: [id_byte; ID_LENGTH],
@@ -668,6 +736,70 @@ mod tests {
             .unwrap());
     }
 
+    #[test]
+    fn stashes_payloads_encrypted_to_the_session_keypair() {
+        let sender = X25519Keypair::from_secret_key([1; 32]);
+        let recipient = X25519Keypair::from_secret_key([2; 32]);
+        let outsider = X25519Keypair::from_secret_key([3; 32]);
+        let encrypted_frame = |id_byte: u8| {
+            let payload = encode_encrypted_raw_exchange_sync_payload(
+                &["topic".to_string()],
+                true,
+                &heads(),
+                [1, 0, 0, 0],
+                Some(SyncPayloadEncryption {
+                    sender: &sender,
+                    recipients: &[recipient.public_key()],
+                }),
+                &mut rand_core::OsRng,
+            )
+            .unwrap();
+            data_frame(id_byte, silent_to_self(), payload)
+        };
+        let mut core = WireSyncCore::new("self-hash".to_string());
+        core.register_topic("topic".to_string());
+
+        // Without a keypair the encrypted frame is left to the TS path.
+        let (frame, data_offset, data_length) = encrypted_frame(1);
+        let mut buffer = Some(frame);
+        assert!(!core
+            .try_stash(&mut buffer, data_offset, data_length)
+            .unwrap());
+
+        core.set_keypair(Some(outsider));
+        assert!(!core
+            .try_stash(&mut buffer, data_offset, data_length)
+            .unwrap());
+        assert!(buffer.is_some());
+
+        core.set_keypair(Some(recipient.clone()));
+        assert!(core
+            .try_stash(&mut buffer, data_offset, data_length)
+            .unwrap());
+        let stashed = core.get(&[1u8; ID_LENGTH]).unwrap();
+        assert_eq!(stashed.head_count(), 2);
+        assert_eq!(
+            stashed.head(1).unwrap().gid_refrences,
+            vec!["g".to_string()]
+        );
+        assert_eq!(stashed.reserved(), [1, 0, 0, 0]);
+        assert_eq!(
+            core.blocks(&[1u8; ID_LENGTH], None).unwrap(),
+            vec![vec![1, 2, 3], vec![4, 5]]
+        );
+
+        // Plaintext payloads still stash from the frame.
+        let (frame, data_offset, data_length) = sync_frame(2, "topic", silent_to_self(), &heads());
+        let mut buffer = Some(frame);
+        assert!(core
+            .try_stash(&mut buffer, data_offset, data_length)
+            .unwrap());
+        assert!(buffer.is_none());
+        assert!(core.release(&[1u8; ID_LENGTH]));
+        assert!(core.release(&[2u8; ID_LENGTH]));
+        assert_eq!(core.stashed_bytes, 0);
+    }
+
     #[test]
     fn topic_registration_is_refcounted() {
         let mut core = WireSyncCore::new("self-hash".to_string());